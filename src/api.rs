@@ -0,0 +1,187 @@
+//! A read-only JSON API over the state of a running crawl, for dashboards.
+//!
+//! The server shares the crawler's counters, visited URLs and errors, and
+//! serves the full results once the crawl is done. It keeps answering until
+//! the process exits, so the results can be fetched after the crawl.
+
+use crate::progress::{Counters, Progress, ProgressHandle};
+use crate::results::{CrawlError, Results};
+use dashmap::DashMap;
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use serde::Serialize;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::net::TcpListener;
+use tracing::{debug, info};
+use url::form_urlencoded;
+
+/// Items per page of `/urls` and `/errors` when no limit is given.
+const DEFAULT_LIMIT: usize = 100;
+
+/// Most items one page can hold.
+const MAX_LIMIT: usize = 1000;
+
+/// The crawl state the API reads.
+pub(crate) struct ApiState {
+    pub progress: Arc<Counters>,
+    pub visited: Arc<DashMap<String, usize>>,
+    pub errors: Arc<Mutex<Vec<CrawlError>>>,
+    /// The progress when the crawl ended and the results as JSON, once the
+    /// crawl is done
+    pub results: OnceLock<(Progress, String)>,
+    /// Required as a bearer token, when set
+    pub token: Option<String>,
+}
+
+impl ApiState {
+    /// Makes the results available at `/results`.
+    pub fn finish(&self, results: &Results) {
+        let progress = ProgressHandle { counters: self.progress.clone() }.snapshot();
+        let _ = self.results.set((progress, serde_json::to_string(results).unwrap_or_default()));
+    }
+}
+
+#[derive(Serialize)]
+struct Status {
+    finished: bool,
+    elapsed_ms: u64,
+    fetched: usize,
+    frontier: usize,
+    errors: usize,
+    discovered: usize,
+    requests_per_sec: f64,
+}
+
+#[derive(Serialize)]
+struct Page<T> {
+    total: usize,
+    offset: usize,
+    limit: usize,
+    items: Vec<T>,
+}
+
+/// Starts answering requests on `listener` until the process exits.
+pub(crate) fn start(listener: TcpListener, state: Arc<ApiState>) {
+    if let Ok(addr) = listener.local_addr() {
+        info!(address = %addr, "serving the crawl API");
+    }
+    tokio::spawn(async move {
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(err) => {
+                    debug!(error = %err, "API connection failed");
+                    continue;
+                }
+            };
+            let state = state.clone();
+            let service = service_fn(move |request| {
+                let response = respond(&request, &state);
+                async move { Ok::<_, Infallible>(response) }
+            });
+            tokio::spawn(async move {
+                if let Err(err) = http1::Builder::new().serve_connection(TokioIo::new(stream), service).await {
+                    debug!(error = %err, "API connection failed");
+                }
+            });
+        }
+    });
+}
+
+fn respond(request: &Request<Incoming>, state: &ApiState) -> Response<Full<Bytes>> {
+    if let Some(token) = &state.token {
+        let presented = request
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        if !presented.is_some_and(|presented| same_token(presented.trim(), token)) {
+            return error(StatusCode::UNAUTHORIZED, "missing or wrong bearer token");
+        }
+    }
+    if request.method() != Method::GET && request.method() != Method::HEAD {
+        return error(StatusCode::METHOD_NOT_ALLOWED, "only GET is supported");
+    }
+
+    let (offset, limit) = pagination(request.uri().query().unwrap_or_default());
+    match request.uri().path() {
+        "/status" => {
+            // A finished crawl keeps its final elapsed time and rate
+            let progress = match state.results.get() {
+                Some((progress, _)) => *progress,
+                None => ProgressHandle { counters: state.progress.clone() }.snapshot(),
+            };
+            json(&Status {
+                finished: state.results.get().is_some(),
+                elapsed_ms: progress.elapsed.as_millis() as u64,
+                fetched: progress.fetched,
+                frontier: progress.frontier,
+                errors: progress.errors,
+                discovered: state.visited.len(),
+                requests_per_sec: progress.requests_per_sec(),
+            })
+        }
+        "/urls" => {
+            // Sorted, so pages stay consistent while URLs are added
+            let mut urls: Vec<String> = state.visited.iter().map(|entry| entry.key().clone()).collect();
+            urls.sort();
+            json(&paginate(urls, offset, limit))
+        }
+        "/errors" => {
+            // Errors are only appended, so their order is stable
+            let errors = state.errors.lock().unwrap().clone();
+            json(&paginate(errors, offset, limit))
+        }
+        "/results" => match state.results.get() {
+            Some((_, results)) => body(StatusCode::OK, "application/json", results.clone()),
+            None => error(StatusCode::SERVICE_UNAVAILABLE, "the crawl is still running"),
+        },
+        _ => error(StatusCode::NOT_FOUND, "unknown endpoint (try /status, /urls, /errors or /results)"),
+    }
+}
+
+/// The `offset` and `limit` query parameters.
+fn pagination(query: &str) -> (usize, usize) {
+    let mut offset = 0;
+    let mut limit = DEFAULT_LIMIT;
+    for (name, value) in form_urlencoded::parse(query.as_bytes()) {
+        match name.as_ref() {
+            "offset" => offset = value.parse().unwrap_or(offset),
+            "limit" => limit = value.parse().unwrap_or(limit),
+            _ => {}
+        }
+    }
+    (offset, limit.min(MAX_LIMIT))
+}
+
+fn paginate<T>(items: Vec<T>, offset: usize, limit: usize) -> Page<T> {
+    let total = items.len();
+    Page { total, offset, limit, items: items.into_iter().skip(offset).take(limit).collect() }
+}
+
+/// Compares tokens in time independent of where they differ.
+fn same_token(presented: &str, token: &str) -> bool {
+    presented.len() == token.len()
+        && presented.bytes().zip(token.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+fn json<T: Serialize>(value: &T) -> Response<Full<Bytes>> {
+    body(StatusCode::OK, "application/json", serde_json::to_string(value).unwrap_or_default())
+}
+
+fn error(status: StatusCode, message: &str) -> Response<Full<Bytes>> {
+    body(status, "application/json", serde_json::json!({ "error": message }).to_string())
+}
+
+fn body(status: StatusCode, content_type: &'static str, body: String) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::new(Bytes::from(body)));
+    *response.status_mut() = status;
+    response.headers_mut().insert(CONTENT_TYPE, hyper::header::HeaderValue::from_static(content_type));
+    response
+}
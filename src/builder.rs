@@ -0,0 +1,1426 @@
+use crate::crawler::SilentCrawler;
+use crate::dns::IpFamily;
+use crate::error::CrawlerError;
+use crate::extract::LinkSource;
+use crate::frontier::CrawlStrategy;
+use crate::language::language_code;
+use crate::scope::{Scope, ScopeVerdict, UrlFilter};
+use crate::visited::VisitedBackend;
+use crate::webhook::WebhookEvent;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Default User-Agent sent when none is configured.
+pub const DEFAULT_USER_AGENT: &str =
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36";
+
+/// Credentials sent in the `Authorization` header of every request.
+#[derive(Clone)]
+pub(crate) enum Auth {
+    Basic { username: String, password: String },
+    Bearer(String),
+}
+
+// Keep credentials out of debug output
+impl fmt::Debug for Auth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Auth::Basic { username, .. } => write!(f, "Basic({}:***)", username),
+            Auth::Bearer(_) => f.write_str("Bearer(***)"),
+        }
+    }
+}
+
+/// A login form submitted before the crawl starts.
+#[derive(Debug, Clone)]
+pub(crate) struct Login {
+    pub url: String,
+    /// URL-encoded form body, e.g. `user=x&pass=y`
+    pub data: String,
+    /// Regex that the page after logging in must match
+    pub check: Option<String>,
+    /// CSS selector of a token field on the login page to add to the form
+    pub token_selector: Option<String>,
+}
+
+/// Settings shared between the builder and the running crawler.
+#[derive(Debug, Clone)]
+pub(crate) struct Config {
+    pub base_url: String,
+    /// Start URLs besides the base URL
+    pub seeds: Vec<String>,
+    /// URLs treated as already visited, never fetched
+    pub known_urls: Vec<String>,
+    pub max_depth: usize,
+    pub max_pages: Option<usize>,
+    pub max_consecutive_challenges: Option<usize>,
+    pub max_time: Option<Duration>,
+    /// Body bytes per second, across all requests
+    pub max_bandwidth: Option<u64>,
+    pub max_total_bytes: Option<u64>,
+    pub slow_threshold: Option<Duration>,
+    pub metrics_addr: Option<SocketAddr>,
+    pub webhook_url: Option<String>,
+    pub serve_addr: Option<SocketAddr>,
+    pub serve_token: Option<String>,
+    pub webhook_events: Vec<WebhookEvent>,
+    pub delay: Duration,
+    pub timeout: Duration,
+    pub connect_timeout: Option<Duration>,
+    pub read_timeout: Option<Duration>,
+    pub pool_idle_timeout: Option<Duration>,
+    pub pool_max_idle_per_host: Option<usize>,
+    /// Addresses used for these hosts instead of resolving them
+    pub resolve: Vec<(String, IpAddr)>,
+    pub dns_server: Option<SocketAddr>,
+    pub ip_family: IpFamily,
+    pub user_agent: String,
+    /// User agents rotated across requests; `user_agent` stays the one
+    /// used for robots.txt matching
+    pub user_agents: Vec<String>,
+    pub user_agent_per_host: bool,
+    pub send_referer: bool,
+    /// Whether responses may be compressed with gzip, deflate, brotli or zstd
+    pub compression: bool,
+    /// Extra headers sent with every request, overriding the defaults
+    pub headers: Vec<(String, String)>,
+    /// `Cookie`-style strings applied to the base URL's host
+    pub cookies: Vec<String>,
+    pub cookies_file: Option<PathBuf>,
+    pub auth: Option<Auth>,
+    pub login: Option<Login>,
+    pub respect_robots: bool,
+    pub respect_meta_robots: bool,
+    pub volatile_selectors: Vec<String>,
+    pub near_duplicates: bool,
+    pub respect_crawl_delay: bool,
+    pub concurrency: usize,
+    pub per_host_concurrency: usize,
+    pub retries: u32,
+    pub retry_backoff: Duration,
+    pub adaptive_throttle: bool,
+    pub max_redirects: usize,
+    pub use_sitemap: bool,
+    pub stream_path: Option<PathBuf>,
+    pub shutdown_grace: Duration,
+    pub state_path: Option<PathBuf>,
+    pub resume: bool,
+    pub db_path: Option<PathBuf>,
+    /// Directory fetched pages are mirrored into
+    pub save_dir: Option<PathBuf>,
+    pub convert_links: bool,
+    pub save_non_html: bool,
+    /// Directory of the HTTP cache
+    pub cache_dir: Option<PathBuf>,
+    pub cache_max_age: Duration,
+    /// Bytes of cached bodies kept at most
+    pub cache_max_size: u64,
+    pub refresh: bool,
+    pub warc_path: Option<PathBuf>,
+    pub har_path: Option<PathBuf>,
+    /// Bytes of each body kept in the HAR file; `None` keeps whole bodies
+    pub har_max_body: Option<usize>,
+    pub checkpoint_interval: Duration,
+    pub include_patterns: Vec<String>,
+    pub strip_params: Vec<String>,
+    pub strategy: CrawlStrategy,
+    pub visited_backend: VisitedBackend,
+    pub bloom_capacity: usize,
+    pub bloom_false_positive_rate: f64,
+    pub priority_patterns: Vec<String>,
+    pub max_per_directory: Option<usize>,
+    pub max_per_subdomain: Option<usize>,
+    pub trap_detection: bool,
+    pub max_segment_repeats: usize,
+    pub max_path_depth: usize,
+    pub max_query_params: usize,
+    pub max_future_years: i32,
+    pub sort_params: bool,
+    pub fold_www: bool,
+    pub exclude_patterns: Vec<String>,
+    pub allowed_domains: Vec<String>,
+    /// Every port of in-scope hosts is in scope, not only the seeds' ports
+    pub any_port: bool,
+    /// Include and exclude rules that decide the scope instead of the domains
+    pub scope_file: Option<PathBuf>,
+    pub collect_external: bool,
+    pub head_first: bool,
+    pub head_only: bool,
+    pub prefer_https: bool,
+    /// Bytes of a response body read at most
+    pub max_body_size: u64,
+    pub check_external: bool,
+    pub follow_canonical: bool,
+    /// Hreflang languages crawled; empty for all
+    pub languages: Vec<String>,
+    /// Languages pages are expected in, as ISO 639-1 codes; the language of
+    /// every page is detected when there are any
+    pub content_languages: Vec<String>,
+    pub skip_other_languages: bool,
+    pub structured_data: bool,
+    /// Bytes of a JSON-LD block parsed at most
+    pub max_json_ld_size: usize,
+    pub scan_js: bool,
+    pub deep_extract: bool,
+    /// Links are read from JSON responses as well as HTML
+    pub follow_json: bool,
+    /// Next and previous pages followed at most from where a chain of them
+    /// was entered
+    pub max_pagination: usize,
+    /// Links are read from PDF responses as well as HTML
+    pub parse_pdf: bool,
+    /// Bytes of a PDF read at most
+    pub max_pdf_size: u64,
+    pub harvest_emails: bool,
+    pub grep_patterns: Vec<String>,
+    pub grep_ignore_case: bool,
+    pub max_grep_snippets: usize,
+    pub scan_secrets: bool,
+    /// Extra secret rules as (name, regex)
+    pub secret_rules: Vec<(String, String)>,
+    /// Scripts fetched at most when scanning JavaScript
+    pub max_js_files: usize,
+    pub max_parameters: usize,
+    /// Paths to try in every crawled directory after the crawl
+    pub wordlist: Vec<String>,
+    pub bruteforce_forbidden: bool,
+    pub common_files: bool,
+    /// Bytes of each script read at most
+    pub max_js_size: u64,
+    pub find_sourcemaps: bool,
+    pub probe_sourcemaps: bool,
+    /// Bytes of a source map read at most
+    pub max_sourcemap_size: u64,
+    pub capture_headers: bool,
+    pub security_audit: bool,
+    pub record_graph: bool,
+    pub link_sources: Vec<LinkSource>,
+    pub fetch_assets: bool,
+    /// HTML pages are loaded in headless Chromium before links are extracted
+    pub render: bool,
+    /// Browser tabs open at once
+    pub render_concurrency: usize,
+    /// Only matching URLs are rendered
+    pub render_pattern: Option<String>,
+    /// CSS selector waited for instead of network idle
+    pub render_wait_for: Option<String>,
+    pub render_timeout: Duration,
+    /// Chromium executable, instead of the one found on the system
+    pub browser_path: Option<PathBuf>,
+    pub proxy: Option<String>,
+    pub proxies: Vec<String>,
+    pub proxy_max_failures: usize,
+    pub proxy_insecure: bool,
+    pub accept_invalid_certs: bool,
+    /// Extra PEM CA bundles to trust
+    pub ca_certs: Vec<PathBuf>,
+    /// PEM certificate and PKCS#8 key for mutual TLS
+    pub client_identity: Option<(PathBuf, PathBuf)>,
+}
+
+/// Configures and constructs a [`SilentCrawler`].
+///
+/// ```no_run
+/// # async fn run() -> Result<(), silent_crawler::CrawlerError> {
+/// use silent_crawler::CrawlerBuilder;
+///
+/// let results = CrawlerBuilder::new("https://example.com")
+///     .depth(2)
+///     .build()
+///     .await?
+///     .crawl()
+///     .await;
+/// println!("{} URLs", results.urls.len());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CrawlerBuilder {
+    config: Config,
+}
+
+impl CrawlerBuilder {
+    /// Starts a builder for crawling `base_url`. A missing scheme defaults to `http://`.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        CrawlerBuilder {
+            config: Config {
+                base_url: base_url.into(),
+                seeds: Vec::new(),
+                known_urls: Vec::new(),
+                max_depth: 3,
+                max_pages: None,
+                max_consecutive_challenges: None,
+                max_time: None,
+                max_bandwidth: None,
+                max_total_bytes: None,
+                slow_threshold: None,
+                metrics_addr: None,
+                webhook_url: None,
+                serve_addr: None,
+                serve_token: None,
+                webhook_events: vec![WebhookEvent::Finish],
+                delay: Duration::from_millis(500),
+                timeout: Duration::from_secs(10),
+                connect_timeout: None,
+                read_timeout: None,
+                pool_idle_timeout: None,
+                pool_max_idle_per_host: None,
+                resolve: Vec::new(),
+                dns_server: None,
+                ip_family: IpFamily::Any,
+                user_agent: DEFAULT_USER_AGENT.to_string(),
+                user_agents: Vec::new(),
+                user_agent_per_host: false,
+                send_referer: true,
+                compression: true,
+                headers: Vec::new(),
+                cookies: Vec::new(),
+                cookies_file: None,
+                auth: None,
+                login: None,
+                respect_robots: true,
+                respect_meta_robots: true,
+                volatile_selectors: Vec::new(),
+                near_duplicates: false,
+                respect_crawl_delay: true,
+                concurrency: 10,
+                per_host_concurrency: 2,
+                retries: 2,
+                retry_backoff: Duration::from_secs(1),
+                adaptive_throttle: false,
+                max_redirects: 10,
+                use_sitemap: false,
+                stream_path: None,
+                shutdown_grace: Duration::from_secs(5),
+                state_path: None,
+                resume: false,
+                db_path: None,
+                save_dir: None,
+                convert_links: false,
+                save_non_html: false,
+                cache_dir: None,
+                cache_max_age: Duration::from_secs(7 * 24 * 60 * 60),
+                cache_max_size: 1024 * 1024 * 1024,
+                refresh: false,
+                warc_path: None,
+                har_path: None,
+                har_max_body: None,
+                checkpoint_interval: Duration::from_secs(30),
+                include_patterns: Vec::new(),
+                strip_params: Vec::new(),
+                strategy: CrawlStrategy::Bfs,
+                visited_backend: VisitedBackend::Memory,
+                bloom_capacity: 10_000_000,
+                bloom_false_positive_rate: 0.001,
+                priority_patterns: Vec::new(),
+                max_per_directory: None,
+                max_per_subdomain: None,
+                trap_detection: true,
+                max_segment_repeats: 3,
+                max_path_depth: 15,
+                max_query_params: 10,
+                max_future_years: 2,
+                sort_params: false,
+                fold_www: false,
+                exclude_patterns: Vec::new(),
+                allowed_domains: Vec::new(),
+                scope_file: None,
+                any_port: false,
+                collect_external: true,
+                head_first: false,
+                head_only: false,
+                prefer_https: false,
+                max_body_size: 10 * 1024 * 1024,
+                check_external: false,
+                follow_canonical: false,
+                languages: Vec::new(),
+                content_languages: Vec::new(),
+                skip_other_languages: false,
+                structured_data: false,
+                max_json_ld_size: 512 * 1024,
+                scan_js: false,
+                deep_extract: false,
+                follow_json: false,
+                max_pagination: 200,
+                parse_pdf: false,
+                max_pdf_size: 20 * 1024 * 1024,
+                harvest_emails: false,
+                grep_patterns: Vec::new(),
+                grep_ignore_case: false,
+                max_grep_snippets: 3,
+                scan_secrets: false,
+                secret_rules: Vec::new(),
+                max_js_files: 200,
+                max_parameters: 1000,
+                wordlist: Vec::new(),
+                bruteforce_forbidden: false,
+                common_files: false,
+                max_js_size: 2 * 1024 * 1024,
+                find_sourcemaps: false,
+                probe_sourcemaps: false,
+                max_sourcemap_size: 20 * 1024 * 1024,
+                capture_headers: false,
+                security_audit: false,
+                record_graph: false,
+                link_sources: LinkSource::ALL.to_vec(),
+                fetch_assets: false,
+                render: false,
+                render_concurrency: 2,
+                render_pattern: None,
+                render_wait_for: None,
+                render_timeout: Duration::from_secs(30),
+                browser_path: None,
+                proxy: None,
+                proxies: Vec::new(),
+                proxy_max_failures: 3,
+                proxy_insecure: false,
+                accept_invalid_certs: false,
+                ca_certs: Vec::new(),
+                client_identity: None,
+            },
+        }
+    }
+
+    /// Maximum recursion depth (default: 3).
+    pub fn depth(mut self, depth: usize) -> Self {
+        self.config.max_depth = depth;
+        self
+    }
+
+    /// Stop fetching new URLs once this many have been fetched; requests
+    /// already in flight still finish (default: unlimited).
+    pub fn max_pages(mut self, max_pages: usize) -> Self {
+        self.config.max_pages = Some(max_pages);
+        self
+    }
+
+    /// Stop the crawl once more than this many responses in a row are bot
+    /// challenge or block pages, which means the site is blocking the
+    /// crawler (default: never).
+    pub fn stop_on_challenge(mut self, max_consecutive: usize) -> Self {
+        self.config.max_consecutive_challenges = Some(max_consecutive);
+        self
+    }
+
+    /// Stop the crawl once it has run this long, as if [`crate::StopHandle::stop`]
+    /// had been called (default: unlimited).
+    pub fn max_time(mut self, max_time: Duration) -> Self {
+        self.config.max_time = Some(max_time);
+        self
+    }
+
+    /// Read response bodies at most this many bytes per second in total,
+    /// across all concurrent requests (default: unlimited).
+    pub fn max_bandwidth(mut self, bytes_per_sec: u64) -> Self {
+        self.config.max_bandwidth = Some(bytes_per_sec);
+        self
+    }
+
+    /// Stop the crawl, as if [`crate::StopHandle::stop`] had been called,
+    /// once the response bodies read exceed this many bytes (default:
+    /// unlimited).
+    pub fn max_total_bytes(mut self, bytes: u64) -> Self {
+        self.config.max_total_bytes = Some(bytes);
+        self
+    }
+
+    /// List the pages that took at least this long to load in
+    /// [`Results::slow_pages`](crate::Results::slow_pages) (default: none).
+    pub fn slow_threshold(mut self, threshold: Duration) -> Self {
+        self.config.slow_threshold = Some(threshold);
+        self
+    }
+
+    /// Serve Prometheus metrics of the running crawl at `/metrics` on this
+    /// address, until the crawl finishes (default: off).
+    pub fn metrics_addr(mut self, addr: SocketAddr) -> Self {
+        self.config.metrics_addr = Some(addr);
+        self
+    }
+
+    /// Serve a read-only JSON API over the crawl's progress on this address:
+    /// `/status`, `/urls`, `/errors` and, once the crawl is done, `/results`.
+    /// It keeps serving until the process exits (default: off).
+    pub fn serve(mut self, addr: SocketAddr) -> Self {
+        self.config.serve_addr = Some(addr);
+        self
+    }
+
+    /// Require this bearer token on every API request (default: none).
+    pub fn serve_token(mut self, token: &str) -> Self {
+        self.config.serve_token = Some(token.to_string());
+        self
+    }
+
+    /// POST a JSON notification to this URL on the events chosen with
+    /// [`Self::webhook_on`] (default: none).
+    pub fn webhook(mut self, url: &str) -> Self {
+        self.config.webhook_url = Some(url.to_string());
+        self
+    }
+
+    /// Which events the webhook is notified of. Findings are batched, one
+    /// payload per kind at most every few seconds (default: finish only).
+    pub fn webhook_on(mut self, events: impl IntoIterator<Item = WebhookEvent>) -> Self {
+        self.config.webhook_events = events.into_iter().collect();
+        self
+    }
+
+    /// Minimum spacing between requests to the same host, before jitter is
+    /// added (default: 500ms).
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.config.delay = delay;
+        self
+    }
+
+    /// Per-request timeout (default: 10s).
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.config.timeout = timeout;
+        self
+    }
+
+    /// Time allowed to establish a connection, TLS handshake included
+    /// (default: only the request timeout applies). Failures are reported
+    /// as [`ErrorKind::ConnectTimeout`](crate::ErrorKind::ConnectTimeout).
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.config.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Time allowed between two reads of a response, so a stalled transfer
+    /// fails while a slow but steady one can take the whole request timeout
+    /// (default: only the request timeout applies).
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.config.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Close kept-alive connections after they have been idle this long
+    /// (default: 90s).
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.config.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Keep at most this many idle connections per host; 0 disables
+    /// keep-alive (default: unlimited).
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.config.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// Connect to `ip` for `host` instead of resolving it, on every port
+    /// (repeatable; several addresses for one host are tried in order).
+    pub fn resolve(mut self, host: impl Into<String>, ip: IpAddr) -> Self {
+        self.config.resolve.push((host.into(), ip));
+        self
+    }
+
+    /// Ask this DNS server instead of the system resolver. The hosts file
+    /// isn't consulted then.
+    pub fn dns_server(mut self, server: SocketAddr) -> Self {
+        self.config.dns_server = Some(server);
+        self
+    }
+
+    /// Only connect over IPv4 or IPv6 (default: [`IpFamily::Any`]). Hosts
+    /// without an address of that family fail with
+    /// [`ErrorKind::Dns`](crate::ErrorKind::Dns).
+    pub fn ip_family(mut self, family: IpFamily) -> Self {
+        self.config.ip_family = family;
+        self
+    }
+
+    /// User-Agent header sent with every request.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.config.user_agent = user_agent.into();
+        self
+    }
+
+    /// Send every request through this proxy (`http://`, `https://` or
+    /// `socks5://`, optionally with `user:pass@`). Without one, the
+    /// `HTTP_PROXY`/`HTTPS_PROXY`/`NO_PROXY` environment variables apply.
+    pub fn proxy(mut self, proxy: impl Into<String>) -> Self {
+        self.config.proxy = Some(proxy.into());
+        self
+    }
+
+    /// Spread requests round-robin over these proxies instead of using a
+    /// single one. Takes precedence over [`Self::proxy`].
+    pub fn proxies<I, S>(mut self, proxies: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.proxies = proxies.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Consecutive failures after which a proxy from the pool is taken out
+    /// of rotation (default: 3).
+    pub fn proxy_max_failures(mut self, max_failures: usize) -> Self {
+        self.config.proxy_max_failures = max_failures.max(1);
+        self
+    }
+
+    /// Skip TLS certificate verification, for proxies that intercept TLS
+    /// (default: false).
+    pub fn proxy_insecure(mut self, insecure: bool) -> Self {
+        self.config.proxy_insecure = insecure;
+        self
+    }
+
+    /// Rotate through these User-Agent strings instead of always sending
+    /// [`Self::user_agent`], which is still used to pick robots.txt rules.
+    pub fn user_agents<I, S>(mut self, agents: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.user_agents = agents.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Rotate through [`crate::BUILTIN_USER_AGENTS`], a list of current
+    /// desktop and mobile browsers.
+    pub fn random_user_agent(self) -> Self {
+        self.user_agents(crate::BUILTIN_USER_AGENTS.iter().copied())
+    }
+
+    /// When rotating user agents, keep one per host instead of choosing a
+    /// new one for every request (default: false).
+    pub fn user_agent_per_host(mut self, per_host: bool) -> Self {
+        self.config.user_agent_per_host = per_host;
+        self
+    }
+
+    /// Send the page a URL was found on as its `Referer` (default: true).
+    /// A `Referer` set with [`Self::header`] is sent unchanged instead.
+    pub fn send_referer(mut self, send_referer: bool) -> Self {
+        self.config.send_referer = send_referer;
+        self
+    }
+
+    /// Ask for compressed responses (default: true). When disabled,
+    /// `Accept-Encoding: identity` is sent instead; responses compressed
+    /// anyway are still decoded.
+    pub fn compression(mut self, compression: bool) -> Self {
+        self.config.compression = compression;
+        self
+    }
+
+    /// Sends `name: value` with every request. A header that is also set by
+    /// default (such as `Accept-Language`) replaces the default; giving the
+    /// same name several times sends every value.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.config.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Seeds the cookie jar with `name=value; other=value` pairs, sent only
+    /// to the base URL's host. Cookies set by responses are kept either way.
+    pub fn cookie(mut self, cookies: impl Into<String>) -> Self {
+        self.config.cookies.push(cookies.into());
+        self
+    }
+
+    /// Seeds the cookie jar from a Netscape cookies.txt file, such as one
+    /// exported from a browser.
+    pub fn cookies_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.cookies_file = Some(path.into());
+        self
+    }
+
+    /// Authenticate every request with HTTP Basic credentials.
+    pub fn basic_auth(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.config.auth = Some(Auth::Basic { username: username.into(), password: password.into() });
+        self
+    }
+
+    /// Authenticate every request with `Authorization: Bearer <token>`.
+    pub fn bearer_auth(mut self, token: impl Into<String>) -> Self {
+        self.config.auth = Some(Auth::Bearer(token.into()));
+        self
+    }
+
+    /// Log in before crawling by POSTing the URL-encoded form `data` to
+    /// `url`. The session cookies it sets are used for the whole crawl.
+    pub fn login(mut self, url: impl Into<String>, data: impl Into<String>) -> Self {
+        self.config.login = Some(Login {
+            url: url.into(),
+            data: data.into(),
+            check: None,
+            token_selector: None,
+        });
+        self
+    }
+
+    /// Regex that the page reached after logging in must match for the
+    /// login to count as successful. Requires [`Self::login`].
+    pub fn login_check(mut self, pattern: impl Into<String>) -> Self {
+        if let Some(login) = &mut self.config.login {
+            login.check = Some(pattern.into());
+        }
+        self
+    }
+
+    /// CSS selector of a token (such as a CSRF field) on the login page.
+    /// The login page is fetched first and the element's `name` and
+    /// `value` (or `content`) are added to the form. Requires [`Self::login`].
+    pub fn login_token_selector(mut self, selector: impl Into<String>) -> Self {
+        if let Some(login) = &mut self.config.login {
+            login.token_selector = Some(selector.into());
+        }
+        self
+    }
+
+    /// Accept invalid or self-signed TLS certificates (default: false).
+    pub fn accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.config.accept_invalid_certs = accept;
+        self
+    }
+
+    /// Trust the CA certificates in this PEM file in addition to the system
+    /// roots. May be given several times.
+    pub fn ca_cert(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.ca_certs.push(path.into());
+        self
+    }
+
+    /// Present this client certificate (PEM) and private key (PKCS#8 PEM)
+    /// to servers that require mutual TLS.
+    pub fn client_cert(mut self, cert: impl Into<PathBuf>, key: impl Into<PathBuf>) -> Self {
+        self.config.client_identity = Some((cert.into(), key.into()));
+        self
+    }
+
+    /// Whether to honor robots.txt (default: true).
+    pub fn respect_robots(mut self, respect_robots: bool) -> Self {
+        self.config.respect_robots = respect_robots;
+        self
+    }
+
+    /// Whether to honor `rel="nofollow"` links, `<meta name="robots">` tags
+    /// and `X-Robots-Tag` headers like a search engine (default: true).
+    /// Nofollow links and the links on nofollow pages aren't followed, and
+    /// noindex pages are listed in [`crate::Results::noindex_pages`].
+    pub fn respect_meta_robots(mut self, respect_meta_robots: bool) -> Self {
+        self.config.respect_meta_robots = respect_meta_robots;
+        self
+    }
+
+    /// Leave elements matching the CSS `selector`, such as CSRF token
+    /// inputs, out of the content hash that duplicate pages are found by.
+    /// May be given several times.
+    pub fn volatile_selector(mut self, selector: impl Into<String>) -> Self {
+        self.config.volatile_selectors.push(selector.into());
+        self
+    }
+
+    /// Also group pages whose visible text is nearly the same, by simhash
+    /// (default: false).
+    pub fn near_duplicates(mut self, near_duplicates: bool) -> Self {
+        self.config.near_duplicates = near_duplicates;
+        self
+    }
+
+    /// Maximum number of concurrent requests to any single host (default: 2).
+    pub fn per_host_concurrency(mut self, per_host_concurrency: usize) -> Self {
+        self.config.per_host_concurrency = per_host_concurrency.max(1);
+        self
+    }
+
+    /// How many times a request is retried after a timeout, connection
+    /// failure, 429 or 5xx response (default: 2).
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.config.retries = retries;
+        self
+    }
+
+    /// Base delay before the first retry; each further retry doubles it
+    /// (default: 1s).
+    pub fn retry_backoff(mut self, backoff: Duration) -> Self {
+        self.config.retry_backoff = backoff;
+        self
+    }
+
+    /// Permanently increase a host's delay each time it answers 429 Too Many
+    /// Requests (default: false).
+    pub fn adaptive_throttle(mut self, adaptive: bool) -> Self {
+        self.config.adaptive_throttle = adaptive;
+        self
+    }
+
+    /// Maximum redirect hops followed per URL (default: 10).
+    pub fn max_redirects(mut self, max_redirects: usize) -> Self {
+        self.config.max_redirects = max_redirects;
+        self
+    }
+
+    /// Whether a robots.txt `Crawl-delay` may raise the configured delay
+    /// (default: true). Has no effect when robots.txt is ignored.
+    pub fn respect_crawl_delay(mut self, respect_crawl_delay: bool) -> Self {
+        self.config.respect_crawl_delay = respect_crawl_delay;
+        self
+    }
+
+    /// Maximum number of concurrent requests (default: 10).
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.config.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Seed the frontier with URLs from `/sitemap.xml` and any `Sitemap:`
+    /// entries in robots.txt (default: false).
+    pub fn use_sitemap(mut self, use_sitemap: bool) -> Self {
+        self.config.use_sitemap = use_sitemap;
+        self
+    }
+
+    /// Append a JSON line to `path` for every URL as soon as it is finished,
+    /// so partial results survive a crash.
+    pub fn stream_to(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.stream_path = Some(path.into());
+        self
+    }
+
+    /// How long in-flight requests may keep running after a stop is
+    /// requested before they are abandoned (default: 5s).
+    pub fn shutdown_grace(mut self, grace: Duration) -> Self {
+        self.config.shutdown_grace = grace;
+        self
+    }
+
+    /// Periodically checkpoint crawl progress to `path`, and once more when
+    /// the crawl finishes or is stopped.
+    pub fn state_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.state_path = Some(path.into());
+        self
+    }
+
+    /// Write pages, links, errors and the frontier to a SQLite database at
+    /// `path` as the crawl runs (requires the `sqlite` feature). An existing
+    /// database is emptied unless the crawl resumes from it.
+    pub fn database(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.db_path = Some(path.into());
+        self
+    }
+
+    /// Save every fetched HTML page under `dir`, in a `host/path` layout
+    /// with a `manifest.json` mapping files to URLs.
+    pub fn save_to(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.config.save_dir = Some(dir.into());
+        self
+    }
+
+    /// Once the crawl ends, rewrite links between saved pages to relative
+    /// paths so the mirror can be browsed offline (default: false).
+    pub fn convert_links(mut self, convert_links: bool) -> Self {
+        self.config.convert_links = convert_links;
+        self
+    }
+
+    /// Also save successful non-HTML responses, such as linked PDFs and
+    /// images (default: false).
+    pub fn save_non_html(mut self, save_non_html: bool) -> Self {
+        self.config.save_non_html = save_non_html;
+        self
+    }
+
+    /// Keep successful responses with an `ETag` or `Last-Modified` in an
+    /// HTTP cache under `dir`, and fetch them again with conditional
+    /// requests: a `304 Not Modified` is answered from the cache, and the
+    /// page is marked `from_cache`.
+    pub fn cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.config.cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Drop cached responses stored longer ago than `max_age` (default: 7
+    /// days).
+    pub fn cache_max_age(mut self, max_age: Duration) -> Self {
+        self.config.cache_max_age = max_age;
+        self
+    }
+
+    /// Once the crawl ends, evict the least recently used responses until
+    /// the cached bodies take at most `bytes` (default: 1 GiB).
+    pub fn cache_max_size(mut self, bytes: u64) -> Self {
+        self.config.cache_max_size = bytes;
+        self
+    }
+
+    /// Fetch everything in full, without conditional requests, and replace
+    /// the cached responses (default: false).
+    pub fn refresh(mut self, refresh: bool) -> Self {
+        self.config.refresh = refresh;
+        self
+    }
+
+    /// Append a request and a response record for every fetch, redirects
+    /// and error responses included, to a gzipped WARC/1.1 file at `path`.
+    pub fn warc(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.warc_path = Some(path.into());
+        self
+    }
+
+    /// Record every fetch, with headers, sizes and timings, and write them
+    /// to a HAR 1.2 file at `path` when the crawl ends.
+    pub fn har(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.har_path = Some(path.into());
+        self
+    }
+
+    /// Keep at most `bytes` of each response body in the HAR file; 0 leaves
+    /// bodies out (default: whole bodies).
+    pub fn har_max_body(mut self, bytes: usize) -> Self {
+        self.config.har_max_body = Some(bytes);
+        self
+    }
+
+    /// Continue from the checkpoint in the database or state file instead of
+    /// starting over (default: false). Requires [`Self::database`] or
+    /// [`Self::state_file`]; the database is preferred when both are set.
+    pub fn resume(mut self, resume: bool) -> Self {
+        self.config.resume = resume;
+        self
+    }
+
+    /// How often the state file is rewritten and the frontier saved to the
+    /// database (default: 30s).
+    pub fn checkpoint_interval(mut self, interval: Duration) -> Self {
+        self.config.checkpoint_interval = interval;
+        self
+    }
+
+    /// Record links to out-of-scope sites in the results without fetching
+    /// them (default: true).
+    pub fn collect_external(mut self, collect_external: bool) -> Self {
+        self.config.collect_external = collect_external;
+        self
+    }
+
+    /// Send a HEAD request before each GET and skip the GET when the
+    /// response isn't HTML or is declared larger than
+    /// [`Self::max_body_size`] (default: false). Servers that reject HEAD
+    /// get a plain GET.
+    pub fn head_first(mut self, head_first: bool) -> Self {
+        self.config.head_first = head_first;
+        self
+    }
+
+    /// Send only HEAD requests, recording each URL's status and declared
+    /// size without reading bodies or following links (default: false).
+    /// Servers that reject HEAD get a GET.
+    pub fn head_only(mut self, head_only: bool) -> Self {
+        self.config.head_only = head_only;
+        self
+    }
+
+    /// Fetch http:// URLs on the default port over HTTPS when their host
+    /// serves it, checked with one HEAD request per host, and treat both
+    /// schemes as the same page (default: false). Hosts where the check
+    /// fails are crawled over plain HTTP.
+    pub fn prefer_https(mut self, prefer_https: bool) -> Self {
+        self.config.prefer_https = prefer_https;
+        self
+    }
+
+    /// Stop reading a response body after this many bytes; the page is
+    /// marked truncated and only its start is parsed (default: 10 MiB).
+    pub fn max_body_size(mut self, bytes: u64) -> Self {
+        self.config.max_body_size = bytes;
+        self
+    }
+
+    /// After crawling, check the collected external links with slow HEAD
+    /// requests and list the broken ones with their referrers (default:
+    /// false). Needs [`Self::collect_external`].
+    pub fn check_external(mut self, check_external: bool) -> Self {
+        self.config.check_external = check_external;
+        self
+    }
+
+    /// Treat a page whose `<link rel="canonical">` is another in-scope URL
+    /// as a copy of it: the canonical URL is queued instead of the page's
+    /// links (default: false). Parameterized variants of a page then add
+    /// one request each rather than a crawl of their own.
+    pub fn follow_canonical(mut self, follow_canonical: bool) -> Self {
+        self.config.follow_canonical = follow_canonical;
+        self
+    }
+
+    /// Only crawl the `hreflang` alternates in this language, such as `en`,
+    /// which includes its regional variants like `en-GB`. May be given
+    /// several times; by default alternates in every language are crawled.
+    /// URLs a page declares as an alternate in another language aren't
+    /// crawled, however they're linked; `x-default` alternates always are.
+    pub fn language(mut self, language: impl Into<String>) -> Self {
+        self.config.languages.push(language.into().trim().to_ascii_lowercase());
+        self
+    }
+
+    /// Detect the language of every HTML page from its visible text and
+    /// record it, expecting pages in this one, such as `en` or `eng`. May be
+    /// given several times. Pages with too little text to tell are left
+    /// undetected.
+    pub fn content_language(mut self, language: impl Into<String>) -> Self {
+        self.config.content_languages.push(language_code(&language.into()));
+        self
+    }
+
+    /// Don't follow the links of pages detected to be in a language other
+    /// than the [`Self::content_language`] ones (default: false). The pages
+    /// are still recorded, and undetected ones are followed.
+    pub fn skip_other_languages(mut self, skip_other_languages: bool) -> Self {
+        self.config.skip_other_languages = skip_other_languages;
+        self
+    }
+
+    /// Record the structured data of every HTML page: its JSON-LD blocks,
+    /// OpenGraph and Twitter card meta tags, and microdata items (default:
+    /// false). Invalid JSON-LD is flagged, and the schema.org types used
+    /// are counted in [`Results::schema_types`](crate::Results::schema_types).
+    pub fn structured_data(mut self, structured_data: bool) -> Self {
+        self.config.structured_data = structured_data;
+        self
+    }
+
+    /// Leave JSON-LD blocks larger than this many bytes unparsed, flagged
+    /// as invalid (default: 512 KiB).
+    pub fn max_json_ld_size(mut self, bytes: usize) -> Self {
+        self.config.max_json_ld_size = bytes;
+        self
+    }
+
+    /// Fetch the same-domain `.js` files referenced by crawled pages and
+    /// crawl the in-scope URLs and API-looking paths found in their string
+    /// literals (default: false).
+    pub fn scan_js(mut self, scan_js: bool) -> Self {
+        self.config.scan_js = scan_js;
+        self
+    }
+
+    /// Also crawl the in-scope URLs found in HTML comments and inline
+    /// scripts (default: false). Expect more false positives than from
+    /// links.
+    pub fn deep_extract(mut self, deep_extract: bool) -> Self {
+        self.config.deep_extract = deep_extract;
+        self
+    }
+
+    /// Also crawl the in-scope URLs of JSON responses: URL-valued fields,
+    /// HAL and JSON:API links, and `next` links, which stay at the depth of
+    /// the page that lists them so long pagination chains are followed to
+    /// the end (default: false).
+    pub fn follow_json(mut self, follow_json: bool) -> Self {
+        self.config.follow_json = follow_json;
+        self
+    }
+
+    /// Follow at most this many next and previous links in a row from the
+    /// page a paginated listing was entered at (default: 200). Pages linked
+    /// with `rel="next"` or `rel="prev"`, and the next pages of followed
+    /// JSON, stay at the depth of the page that links to them, so listings
+    /// are crawled to their end whatever the depth limit; this caps them
+    /// instead.
+    pub fn max_pagination(mut self, max_pagination: usize) -> Self {
+        self.config.max_pagination = max_pagination;
+        self
+    }
+
+    /// Also crawl the in-scope URLs of PDF responses: the targets of their
+    /// link annotations and the URLs in their text (default: false). PDFs
+    /// are listed in [`Results::documents`](crate::Results::documents) with
+    /// their title; encrypted and corrupt ones are skipped.
+    pub fn parse_pdf(mut self, parse_pdf: bool) -> Self {
+        self.config.parse_pdf = parse_pdf;
+        self
+    }
+
+    /// Read at most this many bytes of each PDF; larger ones are listed but
+    /// not parsed (default: 20 MiB).
+    pub fn max_pdf_size(mut self, bytes: u64) -> Self {
+        self.config.max_pdf_size = bytes;
+        self
+    }
+
+    /// Collect the email addresses on crawled pages, including `mailto:`
+    /// links (default: false).
+    pub fn harvest_emails(mut self, harvest_emails: bool) -> Self {
+        self.config.harvest_emails = harvest_emails;
+        self
+    }
+
+    /// Record the crawled HTML pages whose visible text matches this regex.
+    /// May be given several times.
+    pub fn grep(mut self, pattern: impl Into<String>) -> Self {
+        self.config.grep_patterns.push(pattern.into());
+        self
+    }
+
+    /// Match the grep patterns without regard to case (default: false).
+    pub fn grep_ignore_case(mut self, ignore_case: bool) -> Self {
+        self.config.grep_ignore_case = ignore_case;
+        self
+    }
+
+    /// Keep the context of at most this many matches per page and pattern
+    /// (default: 3).
+    pub fn max_grep_snippets(mut self, max_snippets: usize) -> Self {
+        self.config.max_grep_snippets = max_snippets;
+        self
+    }
+
+    /// Look for API keys, tokens and private keys in crawled HTML pages
+    /// and, with [`scan_js`](Self::scan_js), in scanned scripts (default:
+    /// false).
+    pub fn scan_secrets(mut self, scan_secrets: bool) -> Self {
+        self.config.scan_secrets = scan_secrets;
+        self
+    }
+
+    /// Adds a secret rule named `name` to the built-in ones. If `pattern`
+    /// has a capture group, the first one is the secret and the rest of
+    /// the match is context. Implies [`scan_secrets`](Self::scan_secrets).
+    pub fn secret_rule(mut self, name: impl Into<String>, pattern: impl Into<String>) -> Self {
+        self.config.scan_secrets = true;
+        self.config.secret_rules.push((name.into(), pattern.into()));
+        self
+    }
+
+    /// Track at most this many distinct query parameter names (default:
+    /// 1000). Sites generating random names would otherwise grow the
+    /// inventory without bound.
+    pub fn max_parameters(mut self, max_parameters: usize) -> Self {
+        self.config.max_parameters = max_parameters;
+        self
+    }
+
+    /// After the crawl, request each of these paths in every crawled
+    /// directory and report the ones that exist. Much noisier than
+    /// crawling, so off unless a wordlist is given.
+    pub fn wordlist<I, S>(mut self, words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.wordlist = words.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Also report wordlist paths answering 401 or 403 (default: false).
+    pub fn bruteforce_forbidden(mut self, forbidden: bool) -> Self {
+        self.config.bruteforce_forbidden = forbidden;
+        self
+    }
+
+    /// After the crawl, check every crawled host for a short list of files
+    /// that shouldn't be public, like `/.git/HEAD` and `/.env` (default:
+    /// false).
+    pub fn common_files(mut self, common_files: bool) -> Self {
+        self.config.common_files = common_files;
+        self
+    }
+
+    /// Scan at most this many scripts (default: 200).
+    pub fn max_js_files(mut self, max_js_files: usize) -> Self {
+        self.config.max_js_files = max_js_files;
+        self
+    }
+
+    /// Read at most this many bytes of each script (default: 2 MiB).
+    pub fn max_js_size(mut self, bytes: u64) -> Self {
+        self.config.max_js_size = bytes;
+        self
+    }
+
+    /// After crawling, fetch every same-domain script referenced by crawled
+    /// pages and record the source map its `sourceMappingURL` comment or
+    /// `SourceMap` header points to, with the original file names it lists
+    /// (default: false). Scripts are read up to [`Self::max_js_size`].
+    pub fn find_sourcemaps(mut self, find_sourcemaps: bool) -> Self {
+        self.config.find_sourcemaps = find_sourcemaps;
+        self
+    }
+
+    /// Also try `<script>.map` for scripts that don't name a map (default:
+    /// false). Implies [`Self::find_sourcemaps`].
+    pub fn probe_sourcemaps(mut self, probe_sourcemaps: bool) -> Self {
+        self.config.probe_sourcemaps = probe_sourcemaps;
+        self
+    }
+
+    /// Leave source maps larger than this many bytes unread; they are
+    /// still reported (default: 20 MiB).
+    pub fn max_sourcemap_size(mut self, bytes: u64) -> Self {
+        self.config.max_sourcemap_size = bytes;
+        self
+    }
+
+    /// Keep every response header of each page in its [`PageRecord`]
+    /// (default: false).
+    ///
+    /// [`PageRecord`]: crate::PageRecord
+    pub fn capture_headers(mut self, capture_headers: bool) -> Self {
+        self.config.capture_headers = capture_headers;
+        self
+    }
+
+    /// Count the HTML pages missing `Content-Security-Policy`,
+    /// `X-Frame-Options`, `Strict-Transport-Security` or
+    /// `X-Content-Type-Options`, and tally `Server` and `X-Powered-By`
+    /// values (default: false). Pages keep the audited headers even without
+    /// [`Self::capture_headers`].
+    pub fn security_audit(mut self, security_audit: bool) -> Self {
+        self.config.security_audit = security_audit;
+        self
+    }
+
+    /// Which HTML elements links are followed from (default: all of
+    /// [`LinkSource::ALL`]).
+    pub fn link_sources(mut self, sources: impl IntoIterator<Item = LinkSource>) -> Self {
+        self.config.link_sources = sources.into_iter().collect();
+        self
+    }
+
+    /// After crawling, send a HEAD request to every referenced script,
+    /// stylesheet and image and record the ones that are broken.
+    pub fn fetch_assets(mut self, enabled: bool) -> Self {
+        self.config.fetch_assets = enabled;
+        self
+    }
+
+    /// Load HTML pages in headless Chromium and extract links, titles and
+    /// forms from the rendered DOM, for sites that build their navigation
+    /// in JavaScript (requires the `render` feature; default: false). Pages
+    /// are still fetched normally first, so only successful HTML responses
+    /// reach the browser.
+    pub fn render(mut self, enabled: bool) -> Self {
+        self.config.render = enabled;
+        self
+    }
+
+    /// Pages rendered at once, each in its own browser tab (default: 2).
+    pub fn render_concurrency(mut self, tabs: usize) -> Self {
+        self.config.render_concurrency = tabs.max(1);
+        self
+    }
+
+    /// Only render URLs matching this regex; the others are crawled from
+    /// their HTML as fetched (default: every page).
+    pub fn render_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.config.render_pattern = Some(pattern.into());
+        self
+    }
+
+    /// Consider a page rendered once an element matches this CSS selector
+    /// (default: once the network has been idle for half a second).
+    pub fn render_wait_for(mut self, selector: impl Into<String>) -> Self {
+        self.config.render_wait_for = Some(selector.into());
+        self
+    }
+
+    /// Time allowed to load and render a page, after which the DOM is taken
+    /// as it is (default: 30 seconds).
+    pub fn render_timeout(mut self, timeout: Duration) -> Self {
+        self.config.render_timeout = timeout;
+        self
+    }
+
+    /// The Chromium or Chrome executable to render with (default: found on
+    /// the system, or through the `CHROME` environment variable).
+    pub fn browser_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.browser_path = Some(path.into());
+        self
+    }
+
+    /// Record every link between pages, including off-site ones, in
+    /// [`crate::Results::graph`] (default: false).
+    pub fn record_graph(mut self, record_graph: bool) -> Self {
+        self.config.record_graph = record_graph;
+        self
+    }
+
+    /// The order queued URLs are fetched in (default: breadth-first).
+    pub fn strategy(mut self, strategy: CrawlStrategy) -> Self {
+        self.config.strategy = strategy;
+        self
+    }
+
+    /// Where URLs seen so far are kept for deduplication (default: memory).
+    pub fn visited_backend(mut self, backend: VisitedBackend) -> Self {
+        self.config.visited_backend = backend;
+        self
+    }
+
+    /// Size the bloom filter of [`VisitedBackend::Bloom`] for this many URLs
+    /// (default: 10 million) at this false-positive rate (default: 0.001).
+    pub fn bloom_filter(mut self, capacity: usize, false_positive_rate: f64) -> Self {
+        self.config.bloom_capacity = capacity;
+        self.config.bloom_false_positive_rate = false_positive_rate;
+        self
+    }
+
+    /// With [`CrawlStrategy::Priority`], fetch URLs matching this regex
+    /// before all others. May be given several times; earlier patterns win.
+    pub fn prioritize(mut self, pattern: impl Into<String>) -> Self {
+        self.config.priority_patterns.push(pattern.into());
+        self
+    }
+
+    /// Queue at most this many URLs per first-level directory of each host
+    /// (files in the root share one bucket). Further URLs are skipped and
+    /// counted.
+    pub fn max_per_directory(mut self, max: usize) -> Self {
+        self.config.max_per_directory = Some(max);
+        self
+    }
+
+    /// Queue at most this many URLs per host.
+    pub fn max_per_subdomain(mut self, max: usize) -> Self {
+        self.config.max_per_subdomain = Some(max);
+        self
+    }
+
+    /// Skip URLs that look like crawler traps (default: true). See the
+    /// `max_segment_repeats`, `max_path_depth`, `max_query_params` and
+    /// `max_future_years` thresholds.
+    pub fn trap_detection(mut self, enabled: bool) -> Self {
+        self.config.trap_detection = enabled;
+        self
+    }
+
+    /// How often a run of path segments may repeat back to back before the
+    /// URL is treated as a trap (default: 3).
+    pub fn max_segment_repeats(mut self, repeats: usize) -> Self {
+        self.config.max_segment_repeats = repeats;
+        self
+    }
+
+    /// Maximum number of path segments (default: 15).
+    pub fn max_path_depth(mut self, depth: usize) -> Self {
+        self.config.max_path_depth = depth;
+        self
+    }
+
+    /// Maximum number of query parameters (default: 10).
+    pub fn max_query_params(mut self, params: usize) -> Self {
+        self.config.max_query_params = params;
+        self
+    }
+
+    /// Skip URLs with a date more than this many years ahead, or before
+    /// 1990, in their path or query (default: 2).
+    pub fn max_future_years(mut self, years: i32) -> Self {
+        self.config.max_future_years = years;
+        self
+    }
+
+    /// Ignore this query parameter when deciding whether a URL was already
+    /// visited. A trailing `*` matches a prefix, e.g. `utm_*`. URLs are still
+    /// fetched as found.
+    pub fn strip_param(mut self, name: impl Into<String>) -> Self {
+        self.config.strip_params.push(name.into());
+        self
+    }
+
+    /// Ignore the tracking and session parameters in
+    /// [`crate::extract::TRACKING_PARAMS`] when deduplicating.
+    pub fn strip_tracking_params(mut self) -> Self {
+        self.config.strip_params.extend(crate::extract::TRACKING_PARAMS.iter().map(|name| name.to_string()));
+        self
+    }
+
+    /// Treat URLs whose query parameters differ only in order as the same URL.
+    pub fn sort_params(mut self, enabled: bool) -> Self {
+        self.config.sort_params = enabled;
+        self
+    }
+
+    /// Treat `www.<base domain>` and the base domain as one host when
+    /// deduplicating URLs and counting budgets, and don't report `www` as a
+    /// subdomain (default: false).
+    pub fn fold_www(mut self, enabled: bool) -> Self {
+        self.config.fold_www = enabled;
+        self
+    }
+
+    /// Only crawl URLs matching this regex. May be given several times; a
+    /// URL must match at least one include pattern.
+    pub fn include_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.config.include_patterns.push(pattern.into());
+        self
+    }
+
+    /// Never crawl URLs matching this regex. May be given several times;
+    /// excludes win over includes.
+    pub fn exclude_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.config.exclude_patterns.push(pattern.into());
+        self
+    }
+
+    /// Also start the crawl from `url`, sharing the base URL's visited set
+    /// and scope. It must be on the base domain or an allowed domain. May be
+    /// given several times.
+    pub fn seed(mut self, url: impl Into<String>) -> Self {
+        self.config.seeds.push(url.into());
+        self
+    }
+
+    /// Treat `url` as already crawled: it's never fetched, though links to
+    /// it are still recorded, and the known URLs found are counted in
+    /// [`Results::known_urls_skipped`](crate::Results::known_urls_skipped).
+    /// It's deduplicated like discovered URLs, so another spelling of it is
+    /// skipped too. Seeds are crawled even when known. May be given several
+    /// times.
+    pub fn known_url(mut self, url: impl Into<String>) -> Self {
+        self.config.known_urls.push(url.into());
+        self
+    }
+
+    /// Treat `domain` and its subdomains as in scope alongside the base
+    /// domain. May be given several times.
+    pub fn allow_domain(mut self, domain: impl Into<String>) -> Self {
+        self.config.allowed_domains.push(domain.into());
+        self
+    }
+
+    /// Crawl in-scope hosts on any port (default: false, only the ports of
+    /// the seeds, with the standard port of each scheme counting as one).
+    pub fn any_port(mut self, any_port: bool) -> Self {
+        self.config.any_port = any_port;
+        self
+    }
+
+    /// Take the scope from a scope file: a Burp Suite scope export (JSON),
+    /// or one rule per line, such as `*.example.com`, `https://example.com:8443`
+    /// or `example.com/app/`, with `-` in front of exclude rules. The host
+    /// may be `*` or start with `*.` for subdomains; the port and path
+    /// prefix are optional. Exclude rules win; include rules, when there
+    /// are any, replace the base and allowed domains and the port check.
+    /// URLs out of scope are recorded as external links but never
+    /// requested, not even by [`check_external`](Self::check_external).
+    pub fn scope_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.scope_file = Some(path.into());
+        self
+    }
+
+    /// Whether the crawl would crawl `url`, going by the base and allowed
+    /// domains, the seeds' ports, the scope file and the include and exclude
+    /// patterns, without making any request. Fails when the configuration
+    /// would fail [`build`](Self::build) on those.
+    pub fn scope_verdict(&self, url: &str) -> Result<ScopeVerdict, CrawlerError> {
+        let scope = Scope::new(&self.config)?;
+        let filter = UrlFilter::new(&self.config.include_patterns, &self.config.exclude_patterns)?;
+        Ok(scope.verdict(&filter, url))
+    }
+
+    /// Validates the configuration, builds the HTTP client and, if enabled,
+    /// fetches robots.txt.
+    pub async fn build(self) -> Result<SilentCrawler, CrawlerError> {
+        SilentCrawler::new(self.config).await
+    }
+}
@@ -0,0 +1,174 @@
+//! An on-disk HTTP cache, so a re-crawl only downloads what changed.
+//!
+//! Successful bodies are stored along with their `ETag` and
+//! `Last-Modified` validators, each in a file named after a hash of its
+//! URL, with `index.json` listing the entries. The next crawl sends the
+//! validators as `If-None-Match` and `If-Modified-Since`, and a `304 Not
+//! Modified` answer is served from the stored body. Entries older than the
+//! max age are dropped when the cache is opened; when the crawl finishes,
+//! the least recently used are evicted until the bodies fit the size cap.
+
+use crate::content::fnv1a;
+use reqwest::header::{HeaderMap, HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, warn};
+
+/// Name of the index file in the cache directory.
+const INDEX: &str = "index.json";
+
+/// A stored response.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct CacheEntry {
+    /// Body file, relative to the cache directory
+    file: String,
+    status: u16,
+    #[serde(default)]
+    content_type: Option<String>,
+    #[serde(default)]
+    etag: Option<String>,
+    #[serde(default)]
+    last_modified: Option<String>,
+    size: u64,
+    /// When the body was stored and last served, in seconds since the
+    /// Unix epoch
+    stored_at: u64,
+    used_at: u64,
+}
+
+/// A response served from the cache.
+pub(crate) struct CachedResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub bytes: Vec<u8>,
+}
+
+/// The cache of one crawl.
+pub(crate) struct HttpCache {
+    dir: PathBuf,
+    max_size: u64,
+    /// Stored responses are replaced, but never used for requests
+    refresh: bool,
+    entries: Mutex<HashMap<String, CacheEntry>>,
+}
+
+impl HttpCache {
+    /// Creates `dir` if needed and reads its index, dropping entries older
+    /// than `max_age`.
+    pub fn open(dir: &Path, max_age: Duration, max_size: u64, refresh: bool) -> std::io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let mut entries: HashMap<String, CacheEntry> = std::fs::read(dir.join(INDEX))
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default();
+        let oldest = now().saturating_sub(max_age.as_secs());
+        entries.retain(|_, entry| {
+            let fresh = entry.stored_at >= oldest;
+            if !fresh {
+                let _ = std::fs::remove_file(dir.join(&entry.file));
+            }
+            fresh
+        });
+        Ok(HttpCache { dir: dir.to_path_buf(), max_size, refresh, entries: Mutex::new(entries) })
+    }
+
+    /// The conditional request headers for `url`, if its response is
+    /// cached.
+    pub fn validators(&self, url: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if self.refresh {
+            return headers;
+        }
+        if let Some(entry) = self.entries.lock().unwrap().get(url) {
+            let header = |value: &Option<String>| value.as_deref().and_then(|value| HeaderValue::from_str(value).ok());
+            if let Some(etag) = header(&entry.etag) {
+                headers.insert(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = header(&entry.last_modified) {
+                headers.insert(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+        headers
+    }
+
+    /// The stored response for `url`, after the server answered `304 Not
+    /// Modified`.
+    pub fn hit(&self, url: &str) -> Option<CachedResponse> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.get_mut(url)?;
+        match std::fs::read(self.dir.join(&entry.file)) {
+            Ok(bytes) => {
+                entry.used_at = now();
+                Some(CachedResponse { status: entry.status, content_type: entry.content_type.clone(), bytes })
+            }
+            Err(err) => {
+                warn!(url = %url, "cannot read the cached body: {}", err);
+                entries.remove(url);
+                None
+            }
+        }
+    }
+
+    /// Stores a successful response, if it has a validator to check it
+    /// against later. Failures are logged and otherwise ignored.
+    pub fn store(&self, url: &str, status: u16, content_type: Option<&str>, headers: &HeaderMap, bytes: &[u8]) {
+        let header = |name| headers.get(name).and_then(|value: &HeaderValue| value.to_str().ok()).map(str::to_string);
+        let (etag, last_modified) = (header(ETAG), header(LAST_MODIFIED));
+        if etag.is_none() && last_modified.is_none() {
+            return;
+        }
+        let file = format!("{:016x}", fnv1a(url.bytes()));
+        if let Err(err) = std::fs::write(self.dir.join(&file), bytes) {
+            warn!(url = %url, path = %self.dir.display(), "cannot cache the body: {}", err);
+            return;
+        }
+        let stored_at = now();
+        self.entries.lock().unwrap().insert(url.to_string(), CacheEntry {
+            file,
+            status,
+            content_type: content_type.map(str::to_string),
+            etag,
+            last_modified,
+            size: bytes.len() as u64,
+            stored_at,
+            used_at: stored_at,
+        });
+    }
+
+    /// Evicts the least recently used entries beyond the size cap and
+    /// writes the index.
+    pub fn finish(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        let mut total: u64 = entries.values().map(|entry| entry.size).sum();
+        if total > self.max_size {
+            let mut by_use: Vec<(String, u64, u64)> =
+                entries.iter().map(|(url, entry)| (url.clone(), entry.used_at, entry.size)).collect();
+            by_use.sort_by_key(|&(_, used_at, _)| used_at);
+            for (url, _, size) in by_use {
+                if total <= self.max_size {
+                    break;
+                }
+                if let Some(entry) = entries.remove(&url) {
+                    let _ = std::fs::remove_file(self.dir.join(&entry.file));
+                }
+                total -= size;
+            }
+            debug!(entries = entries.len(), bytes = total, "cache evicted to its size cap");
+        }
+        match serde_json::to_vec(&*entries) {
+            Ok(data) => {
+                if let Err(err) = std::fs::write(self.dir.join(INDEX), data) {
+                    warn!(path = %self.dir.display(), "cannot write the cache index: {}", err);
+                }
+            }
+            Err(err) => warn!("cannot serialize the cache index: {}", err),
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs())
+}
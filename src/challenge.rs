@@ -0,0 +1,52 @@
+//! Detection of bot challenge and WAF block pages, which answer in place
+//! of the real page and leave nothing to crawl.
+
+use reqwest::header::HeaderMap;
+
+/// Body markers of challenge and block pages, with the service serving
+/// them. Matched against the start of the body.
+const BODY_MARKERS: &[(&str, &str)] = &[
+    ("/cdn-cgi/challenge-platform/", "cloudflare"),
+    ("cf-chl-", "cloudflare"),
+    ("cf_chl_", "cloudflare"),
+    ("Attention Required! | Cloudflare", "cloudflare"),
+    ("Checking your browser before accessing", "cloudflare"),
+    ("Incapsula incident ID", "imperva"),
+    ("_Incapsula_Resource", "imperva"),
+    ("Sucuri WebSite Firewall", "sucuri"),
+    ("captcha-delivery.com", "datadome"),
+    ("px-captcha", "perimeterx"),
+];
+
+/// How much of the body is searched for markers.
+const BODY_PREFIX: usize = 64 * 1024;
+
+/// The service whose challenge or block page answered, if the response
+/// looks like one: a Cloudflare `cf-mitigated` header, a 403 or 503 from
+/// Cloudflare (`cf-ray`) or Akamai (`Reference #` block pages), or one of
+/// the known page markers.
+pub(crate) fn detect_challenge(status: u16, headers: &HeaderMap, body: Option<&str>) -> Option<&'static str> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok()).unwrap_or_default();
+    if header("cf-mitigated").eq_ignore_ascii_case("challenge") {
+        return Some("cloudflare");
+    }
+    let body = body.map(|body| &body[..floor_char_boundary(body, BODY_PREFIX)]).unwrap_or_default();
+    if matches!(status, 403 | 503) {
+        if headers.contains_key("cf-ray") {
+            return Some("cloudflare");
+        }
+        let akamai = header("server").to_ascii_lowercase().starts_with("akamaighost")
+            || (body.contains("Access Denied") && body.contains("Reference #"));
+        if akamai {
+            return Some("akamai");
+        }
+    }
+    BODY_MARKERS.iter().find(|(marker, _)| body.contains(marker)).map(|&(_, service)| service)
+}
+
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    if index >= text.len() {
+        return text.len();
+    }
+    (0..=index).rev().find(|&index| text.is_char_boundary(index)).unwrap_or(0)
+}
@@ -0,0 +1,124 @@
+//! Decoding of HTML bodies, which may be in any legacy encoding.
+//!
+//! The encoding is taken from, in order: a byte order mark, the
+//! `Content-Type` charset, a `<meta charset>` near the top of the page, and
+//! finally a guess from the bytes themselves.
+
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use regex::bytes::Regex;
+use url::Url;
+
+/// How far into the body a `<meta charset>` is looked for, as in the HTML
+/// prescan.
+const META_PRESCAN_BYTES: usize = 1024;
+
+/// Decodes the HTML `bytes` served from `url` with `content_type`.
+pub(crate) fn decode_html(bytes: &[u8], content_type: Option<&str>, url: &str) -> String {
+    let encoding = content_type
+        .and_then(header_charset)
+        .or_else(|| meta_charset(bytes))
+        .unwrap_or_else(|| guess(bytes, url));
+    // A byte order mark still overrides the chosen encoding
+    encoding.decode(bytes).0.into_owned()
+}
+
+fn header_charset(content_type: &str) -> Option<&'static Encoding> {
+    let label = content_type.split(';').find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim().eq_ignore_ascii_case("charset").then(|| value.trim().trim_matches(['"', '\'']))
+    })?;
+    Encoding::for_label(label.as_bytes())
+}
+
+/// The charset of a `<meta charset>` or `<meta http-equiv="Content-Type">`
+/// tag in the first [`META_PRESCAN_BYTES`] of the page.
+fn meta_charset(bytes: &[u8]) -> Option<&'static Encoding> {
+    let head = &bytes[..bytes.len().min(META_PRESCAN_BYTES)];
+    let meta = Regex::new(r#"(?i)<meta\s[^>]*?charset\s*=\s*["']?\s*([a-z0-9_:.\-]+)"#).unwrap();
+    let label = meta.captures(head)?.get(1)?.as_bytes();
+    let encoding = Encoding::for_label(label)?;
+    // The page was readable as ASCII to get this far, so it can't be UTF-16
+    if encoding == UTF_16BE || encoding == UTF_16LE {
+        return Some(UTF_8);
+    }
+    Some(encoding)
+}
+
+/// UTF-8 if the bytes are valid UTF-8, otherwise chardetng's guess, helped
+/// by the top-level domain of `url`.
+fn guess(bytes: &[u8], url: &str) -> &'static Encoding {
+    match std::str::from_utf8(bytes) {
+        // A body cut at the size limit may end inside a character
+        Ok(_) => return UTF_8,
+        Err(err) if err.error_len().is_none() => return UTF_8,
+        Err(_) => {}
+    }
+    let host = Url::parse(url).ok().and_then(|url| url.host_str().map(str::to_string));
+    let tld = host.as_deref().and_then(|host| host.rsplit('.').next()).map(str::as_bytes);
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    detector.guess(tld, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::extract_links;
+
+    /// "Новости" with a link to /новости/спорт, in windows-1251.
+    const WINDOWS_1251_PAGE: &[u8] = b"<html><head><title>\xcd\xee\xe2\xee\xf1\xf2\xe8</title></head><body><a href=\"/\xed\xee\xe2\xee\xf1\xf2\xe8/\xf1\xef\xee\xf0\xf2\">\xd1\xef\xee\xf0\xf2</a></body></html>";
+    const WINDOWS_1251_LINK: &str = "http://site.test/%D0%BD%D0%BE%D0%B2%D0%BE%D1%81%D1%82%D0%B8/%D1%81%D0%BF%D0%BE%D1%80%D1%82";
+
+    /// "ニュース" with a link to /ニュース/天気, in Shift_JIS.
+    const SHIFT_JIS_PAGE: &[u8] = b"<html><head><title>\x83j\x83\x85\x81[\x83X</title></head><body><a href=\"/\x83j\x83\x85\x81[\x83X/\x93V\x8bC\">\x93V\x8bC</a></body></html>";
+    const SHIFT_JIS_LINK: &str = "http://site.test/%E3%83%8B%E3%83%A5%E3%83%BC%E3%82%B9/%E5%A4%A9%E6%B0%97";
+
+    fn links(html: &str) -> Vec<String> {
+        extract_links(html, "http://site.test/").into_iter().collect()
+    }
+
+    fn with_meta(meta: &str, page: &[u8]) -> Vec<u8> {
+        let mut bytes = page.to_vec();
+        let at = bytes.windows(6).position(|window| window == b"<head>").unwrap() + 6;
+        bytes.splice(at..at, meta.bytes());
+        bytes
+    }
+
+    #[test]
+    fn windows_1251_from_the_content_type() {
+        let html = decode_html(WINDOWS_1251_PAGE, Some("text/html; charset=windows-1251"), "http://site.test/");
+        assert!(html.contains("<title>Новости</title>"));
+        assert_eq!(links(&html), [WINDOWS_1251_LINK]);
+    }
+
+    #[test]
+    fn shift_jis_from_the_content_type() {
+        let html = decode_html(SHIFT_JIS_PAGE, Some("text/html; charset=\"Shift_JIS\""), "http://site.test/");
+        assert!(html.contains("<title>ニュース</title>"));
+        assert_eq!(links(&html), [SHIFT_JIS_LINK]);
+    }
+
+    #[test]
+    fn meta_charsets_apply_without_a_header_charset() {
+        let page = with_meta(r#"<meta charset="windows-1251">"#, WINDOWS_1251_PAGE);
+        assert_eq!(links(&decode_html(&page, Some("text/html"), "http://site.test/")), [WINDOWS_1251_LINK]);
+
+        let page = with_meta(r#"<meta http-equiv="Content-Type" content="text/html; charset=shift_jis">"#, SHIFT_JIS_PAGE);
+        assert_eq!(links(&decode_html(&page, None, "http://site.test/")), [SHIFT_JIS_LINK]);
+    }
+
+    #[test]
+    fn the_header_charset_wins_over_the_meta_charset() {
+        let page = with_meta(r#"<meta charset="utf-8">"#, WINDOWS_1251_PAGE);
+        let html = decode_html(&page, Some("text/html; charset=cp1251"), "http://site.test/");
+        assert_eq!(links(&html), [WINDOWS_1251_LINK]);
+    }
+
+    #[test]
+    fn unlabelled_pages_are_guessed() {
+        assert_eq!(guess(WINDOWS_1251_PAGE, "http://site.ru/"), encoding_rs::WINDOWS_1251);
+        assert_eq!(guess(SHIFT_JIS_PAGE, "http://site.jp/"), encoding_rs::SHIFT_JIS);
+        assert_eq!(guess("<a href=\"/ü\">".as_bytes(), "http://site.test/"), UTF_8);
+    }
+}
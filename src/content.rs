@@ -0,0 +1,130 @@
+//! Fingerprints of page content, for finding URLs that serve the same page.
+//!
+//! The exact hash covers the HTML with all whitespace removed, after
+//! dropping volatile elements such as CSRF tokens. The simhash covers
+//! three-word shingles of the visible text, so pages that differ in a few
+//! words get hashes that differ in a few bits.
+
+use scraper::{Html, Selector};
+use std::collections::{BTreeMap, HashMap};
+
+/// Simhashes at most this many bits apart count as near-duplicates.
+pub(crate) const NEAR_DUPLICATE_DISTANCE: u32 = 3;
+
+/// Words per shingle in [`simhash`].
+const SHINGLE_WORDS: usize = 3;
+
+/// 64-bit FNV-1a, which unlike the std hasher is stable across releases, so
+/// hashes from a resumed crawl still compare equal.
+pub(crate) fn fnv1a(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in bytes {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+/// Hashes `html` without its whitespace and without the elements matching
+/// `volatile`.
+pub(crate) fn content_hash(html: &str, volatile: &[Selector]) -> u64 {
+    if volatile.is_empty() {
+        return fnv1a(html.bytes().filter(|byte| !byte.is_ascii_whitespace()));
+    }
+
+    let mut document = Html::parse_document(html);
+    let ids: Vec<_> = volatile
+        .iter()
+        .flat_map(|selector| document.select(selector).map(|element| element.id()).collect::<Vec<_>>())
+        .collect();
+    for id in ids {
+        if let Some(mut node) = document.tree.get_mut(id) {
+            node.detach();
+        }
+    }
+    fnv1a(document.html().bytes().filter(|byte| !byte.is_ascii_whitespace()))
+}
+
+/// The text of `html` outside scripts, styles and templates, with its
+/// whitespace collapsed to single spaces.
+pub(crate) fn visible_text(html: &str) -> String {
+    let document = Html::parse_document(html);
+    let skipped = Selector::parse("script, style, noscript, template").unwrap();
+    let hidden: Vec<_> = document.select(&skipped).map(|element| element.id()).collect();
+
+    let mut words = Vec::new();
+    for node in document.root_element().descendants() {
+        let Some(text) = node.value().as_text() else {
+            continue;
+        };
+        if node.ancestors().any(|ancestor| hidden.contains(&ancestor.id())) {
+            continue;
+        }
+        words.extend(text.split_whitespace());
+    }
+    words.join(" ")
+}
+
+/// A simhash of the visible text of `html`, or `None` if it has fewer words
+/// than a shingle.
+pub(crate) fn simhash(html: &str) -> Option<u64> {
+    let text = visible_text(html).to_lowercase();
+    let words: Vec<&str> = text.split_whitespace().collect();
+    if words.len() < SHINGLE_WORDS {
+        return None;
+    }
+
+    let mut votes = [0i64; 64];
+    for shingle in words.windows(SHINGLE_WORDS) {
+        let hash = fnv1a(shingle.join(" ").into_bytes());
+        for (bit, vote) in votes.iter_mut().enumerate() {
+            *vote += if hash & (1 << bit) != 0 { 1 } else { -1 };
+        }
+    }
+    Some(votes.iter().enumerate().filter(|(_, vote)| **vote > 0).fold(0, |hash, (bit, _)| hash | (1 << bit)))
+}
+
+/// Groups `urls` whose simhashes are within [`NEAR_DUPLICATE_DISTANCE`]
+/// bits of each other, transitively. Only groups of two or more are
+/// returned, each sorted, in order of their first URL.
+pub(crate) fn near_duplicate_groups(hashes: &[(&str, u64)]) -> Vec<Vec<String>> {
+    // Hashes within 3 bits agree on at least one of four 16-bit blocks, so
+    // only hashes sharing a block need comparing
+    let mut buckets: HashMap<(u32, u64), Vec<usize>> = HashMap::new();
+    for (index, (_, hash)) in hashes.iter().enumerate() {
+        for block in 0..4 {
+            buckets.entry((block, (hash >> (block * 16)) & 0xffff)).or_default().push(index);
+        }
+    }
+
+    let mut parents: Vec<usize> = (0..hashes.len()).collect();
+    fn root(parents: &mut [usize], mut index: usize) -> usize {
+        while parents[index] != index {
+            parents[index] = parents[parents[index]];
+            index = parents[index];
+        }
+        index
+    }
+    for members in buckets.values() {
+        for (position, &a) in members.iter().enumerate() {
+            for &b in &members[position + 1..] {
+                if (hashes[a].1 ^ hashes[b].1).count_ones() <= NEAR_DUPLICATE_DISTANCE {
+                    let (root_a, root_b) = (root(&mut parents, a), root(&mut parents, b));
+                    parents[root_a] = root_b;
+                }
+            }
+        }
+    }
+
+    let mut groups: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+    for (index, (url, _)) in hashes.iter().enumerate() {
+        let group = root(&mut parents, index);
+        groups.entry(group).or_default().push(url.to_string());
+    }
+    let mut groups: Vec<Vec<String>> = groups.into_values().filter(|urls| urls.len() > 1).collect();
+    for urls in &mut groups {
+        urls.sort();
+    }
+    groups.sort();
+    groups
+}
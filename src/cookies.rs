@@ -0,0 +1,61 @@
+//! Seeding the cookie jar from `--cookie` strings and Netscape cookies.txt files.
+
+use reqwest::cookie::Jar;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use url::Url;
+
+/// Adds the `name=value` pairs of a `Cookie` header string as host-only
+/// cookies for `url`'s host.
+pub(crate) fn add_cookie_header(jar: &Jar, header: &str, url: &Url) {
+    for pair in header.split(';') {
+        let pair = pair.trim();
+        if pair.contains('=') {
+            jar.add_cookie_str(&format!("{}; Path=/", pair), url);
+        }
+    }
+}
+
+/// Adds every cookie of a Netscape cookies.txt file (as exported by browsers
+/// and curl) to the jar, keeping its domain, path, secure flag and expiry.
+/// Returns the number of cookies read; malformed lines are skipped.
+pub(crate) fn add_netscape_cookies(jar: &Jar, text: &str) -> usize {
+    let mut count = 0;
+    for line in text.lines() {
+        // curl marks HttpOnly cookies with a prefix on an otherwise commented line
+        let line = line.strip_prefix("#HttpOnly_").unwrap_or(line);
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split('\t').collect();
+        let [domain, include_subdomains, path, secure, expires, name, value] = fields[..] else {
+            continue;
+        };
+        let host = domain.trim_start_matches('.');
+        let secure = secure.eq_ignore_ascii_case("TRUE");
+        let scheme = if secure { "https" } else { "http" };
+        let Ok(url) = Url::parse(&format!("{}://{}{}", scheme, host, path)) else {
+            continue;
+        };
+
+        let mut cookie = format!("{}={}; Path={}", name, value, path);
+        // Without a Domain attribute the cookie is only sent to this exact host
+        if include_subdomains.eq_ignore_ascii_case("TRUE") {
+            cookie.push_str(&format!("; Domain={}", host));
+        }
+        if secure {
+            cookie.push_str("; Secure");
+        }
+        // 0 means a session cookie
+        if let Ok(expires) = expires.parse::<u64>() {
+            if expires > 0 {
+                let expires_at: SystemTime = UNIX_EPOCH + Duration::from_secs(expires);
+                cookie.push_str(&format!("; Expires={}", httpdate::fmt_http_date(expires_at)));
+            }
+        }
+
+        jar.add_cookie_str(&cookie, &url);
+        count += 1;
+    }
+    count
+}
@@ -0,0 +1,3608 @@
+use crate::agents::UserAgentPool;
+use crate::api::{self, ApiState};
+use crate::builder::{Auth, Config, Login};
+use crate::cache::HttpCache;
+use crate::challenge::detect_challenge;
+use crate::charset::decode_html;
+use crate::content::{content_hash, near_duplicate_groups, simhash};
+use crate::cookies::{add_cookie_header, add_netscape_cookies};
+#[cfg(feature = "sqlite")]
+use crate::db::CrawlDb;
+#[cfg(feature = "render")]
+use crate::render::{RenderedPage, Renderer};
+use crate::dns::DnsResolver;
+use crate::error::CrawlerError;
+use crate::extract::{LinkSource, extract_hreflang, extract_pagination, PageMetadata, extract_assets, extract_canonical, extract_client_redirect, extract_directory, extract_emails, extract_feeds, extract_forms, extract_hidden_urls, extract_js_urls, extract_mixed_content, extract_links_from, extract_subdomain, is_directory_listing, canonicalize_url, unicode_host, clean_query, extract_metadata, normalize_url, slash_variant};
+use crate::feed::{is_feed_type, parse_feed};
+use crate::json::{is_json_type, json_links};
+use crate::language::detect_language;
+use crate::structured::{extract_structured_data, schema_types};
+use crate::pdf::{is_pdf_type, pdf_links};
+use crate::frontier::{CrawlStrategy, Frontier};
+use crate::grep::grep_page;
+use crate::har::{HarExchange, HarRecorder};
+use crate::login::{extract_token, merge_form};
+use crate::metrics::{Metrics, MetricsServer};
+use crate::mirror::Mirror;
+use crate::progress::{Counters, ProgressHandle};
+use crate::proxy::{redact_password, ClientPool};
+use crate::ratelimit::{BandwidthLimiter, HostLimiter, HostPermit};
+use crate::results::{BrokenLink, BruteForceHit, BudgetHit, BudgetKind, CanonicalIssue, CanonicalReason, ClientRedirect, ClientRedirectKind, CrawlError, DirectoryListing, DuplicateGroup, ErrorKind, Form, GraphEdge, GrepMatch, HreflangIssue, HreflangReason, HstsHost, MixedContent, PageRecord, PaginationChain, PaginationStep, ParameterStats, PdfDocument, Results, SecretFinding, RESULTS_SCHEMA_VERSION, SensitiveFile, SkippedUrl, SourceMap, SourceMapOrigin, TrapReason, TrappedUrl, UrlSource};
+use crate::robots::{RobotsDirectives, RobotsRules, RobotsTxt};
+use crate::scope::{Budgets, Scope, TrapRules, UrlFilter};
+use crate::secrets::{builtin_rules, scan_secrets, SecretRule};
+use crate::security::{captured_headers, parse_hsts, security_audit};
+use crate::sensitive::{is_genuine, COMMON_FILES};
+use crate::sourcemap::{inline_map, map_sources, mapping_url};
+use crate::sitemap::parse_sitemap;
+use crate::state::CrawlState;
+use crate::stream::StreamWriter;
+use crate::timing::{performance, slow_pages};
+use crate::visited::VisitedSet;
+use crate::warc::{WarcExchange, WarcWriter};
+use crate::webhook::{Webhook, WebhookEvent, WebhookItem};
+use dashmap::{DashMap, DashSet};
+use futures::stream::{self, FuturesUnordered, StreamExt};
+use rand::Rng;
+use regex::{Regex, RegexBuilder};
+use scraper::Selector;
+use serde::{Deserialize, Serialize};
+use reqwest::{Certificate, Client, Identity, Method, NoProxy, cookie::{CookieStore, Jar}, Proxy, RequestBuilder, Response, StatusCode, redirect::Policy, header::{HeaderMap, HeaderName, HeaderValue, USER_AGENT, ACCEPT, ACCEPT_LANGUAGE, ACCEPT_ENCODING, CONNECTION, UPGRADE_INSECURE_REQUESTS, CONTENT_LENGTH, CONTENT_TYPE, COOKIE, LOCATION, REFERER, RETRY_AFTER, STRICT_TRANSPORT_SECURITY}};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::net::TcpListener;
+use tokio::sync::{watch, OnceCell};
+use tokio::time::{sleep, sleep_until, timeout};
+use tracing::{debug, info, trace, warn};
+use url::{Host, Url};
+
+/// A configured crawler. Create one with [`crate::CrawlerBuilder`].
+pub struct SilentCrawler {
+    config: Config,
+    base_domain: String,
+    scope: Scope,
+    clients: ClientPool,
+    /// The resolver the clients share, with the addresses hosts resolved to
+    dns: DnsResolver,
+    user_agents: Option<UserAgentPool>,
+    /// The cookie jar the clients share
+    jar: Arc<Jar>,
+    /// Claimed URLs with their discovery depth. Concurrent maps keep workers
+    /// from serializing on one lock at high concurrency.
+    visited_urls: Arc<DashMap<String, usize>>,
+    /// Canonical forms of the visited URLs, which deduplication is based on;
+    /// in memory, in a bloom filter or on disk
+    canonical_urls: VisitedSet,
+    directories: DashSet<String>,
+    subdomains: DashSet<String>,
+    /// robots.txt rules per origin, fetched once on first use
+    robots: Arc<Mutex<HashMap<String, Arc<OnceCell<RobotsRules>>>>>,
+    pages: Arc<Mutex<Vec<PageRecord>>>,
+    errors: Arc<Mutex<Vec<CrawlError>>>,
+    sitemap_urls: Arc<Mutex<HashSet<String>>>,
+    /// Sitemaps cut off at the body size limit
+    truncated_sitemaps: Mutex<BTreeSet<String>>,
+    /// Feeds announced by crawled pages
+    feeds: Mutex<BTreeSet<String>>,
+    /// Out-of-scope links seen on crawled pages
+    external_links: Arc<Mutex<HashSet<String>>>,
+    /// Links between pages, when graph recording is enabled
+    graph: Arc<Mutex<BTreeSet<GraphEdge>>>,
+    /// Asset URLs referenced by each crawled page
+    assets: Arc<Mutex<HashMap<String, BTreeSet<String>>>>,
+    /// Files listed by each directory index page
+    directory_listings: Mutex<HashMap<String, Vec<String>>>,
+    /// Where each page reached through next or previous links is in its
+    /// chain, by URL
+    pagination: Mutex<HashMap<String, PaginationStep>>,
+    /// The hreflang alternates of each page, by URL
+    hreflang: Mutex<HashMap<String, BTreeMap<String, String>>>,
+    /// Dedup keys of the known URLs, which are never fetched
+    known_urls: Mutex<HashSet<String>>,
+    /// Dedup keys of the known URLs found during the crawl
+    known_urls_skipped: Mutex<HashSet<String>>,
+    /// Dedup keys of the alternates in languages that aren't crawled
+    other_languages: Mutex<HashSet<String>>,
+    /// Parsed PDF documents by URL
+    documents: Mutex<HashMap<String, PdfDocument>>,
+    /// Distinct forms by structural hash, with the pages they're on
+    forms: Mutex<HashMap<String, (Form, BTreeSet<String>)>>,
+    /// Query parameter names of claimed URLs and GET forms
+    parameters: Mutex<BTreeMap<String, ParameterStats>>,
+    parameters_dropped: AtomicUsize,
+    /// Scripts claimed for URL scanning, by dedup key
+    scanned_scripts: Mutex<BTreeMap<String, String>>,
+    emails: Mutex<BTreeSet<String>>,
+    matches: Mutex<Vec<GrepMatch>>,
+    secrets: Mutex<BTreeSet<SecretFinding>>,
+    /// Plain-http subresources of HTTPS pages
+    mixed_content: Mutex<BTreeSet<MixedContent>>,
+    /// Crawled pages linking to each URL, by dedup key, for the broken link
+    /// report
+    referrers: Mutex<HashMap<String, BTreeSet<String>>>,
+    /// Hosts seen permanently redirecting between `/path` and `/path/`, and
+    /// whether they add the slash (true) or remove it
+    slash_redirects: Mutex<HashMap<String, bool>>,
+    /// Whether each host serves HTTPS, in prefer-HTTPS mode, probed once
+    https_hosts: Mutex<HashMap<String, Arc<OnceCell<bool>>>>,
+    hsts_hosts: Mutex<BTreeMap<String, HstsHost>>,
+    /// With www folding, the host the other spelling redirects to
+    canonical_host: OnceLock<String>,
+    url_filter: UrlFilter,
+    /// Elements left out of content hashes
+    volatile_selectors: Vec<Selector>,
+    trap_rules: Option<TrapRules>,
+    /// Patterns whose URLs are fetched first in priority mode
+    priority_patterns: Vec<Regex>,
+    /// Patterns searched for in the text of crawled pages
+    grep_patterns: Vec<Regex>,
+    /// Built-in and custom secret rules, when secret scanning is enabled
+    secret_rules: Vec<SecretRule>,
+    budgets: Budgets,
+    /// URLs rejected by the trap heuristics
+    traps: Mutex<HashMap<String, TrapReason>>,
+    /// URLs rejected by the include/exclude patterns, with the matching pattern
+    skipped: Arc<Mutex<HashMap<String, Option<String>>>>,
+    stream: Option<StreamWriter>,
+    /// Directory fetched pages are saved into
+    mirror: Option<Mirror>,
+    cache: Option<HttpCache>,
+    warc: Option<WarcWriter>,
+    har: Option<HarRecorder>,
+    /// Headers every client sends, for the WARC and HAR request records
+    default_headers: HeaderMap,
+    /// Database that pages, links, errors and the frontier are written to
+    #[cfg(feature = "sqlite")]
+    db: Option<CrawlDb>,
+    /// Headless browser that HTML pages are rendered in
+    #[cfg(feature = "render")]
+    renderer: Option<Renderer>,
+    /// Only matching URLs are rendered
+    #[cfg(feature = "render")]
+    render_pattern: Option<Regex>,
+    stop: Arc<watch::Sender<bool>>,
+    host_limiter: HostLimiter,
+    /// Number of times a host throttled us with 429/503
+    throttle_events: AtomicUsize,
+    /// URLs fetched so far (or about to be), checked against `max_pages`
+    pages_fetched: AtomicUsize,
+    /// Set when the time limit stopped the crawl
+    timed_out: AtomicBool,
+    /// Body bytes read so far, checked against `max_total_bytes`
+    bytes_downloaded: AtomicU64,
+    /// Set when the download cap stopped the crawl
+    byte_limit_reached: AtomicBool,
+    bandwidth: Option<BandwidthLimiter>,
+    /// Challenge pages received in a row, for `max_consecutive_challenges`
+    consecutive_challenges: AtomicUsize,
+    /// Set when consecutive challenges stopped the crawl
+    stopped_by_challenges: AtomicBool,
+    progress: Arc<Counters>,
+    metrics: Arc<Metrics>,
+    /// Bound to the metrics address, taken when the crawl starts serving
+    metrics_listener: Mutex<Option<TcpListener>>,
+    webhook: Option<Webhook>,
+    /// What the crawl API serves, when it is enabled
+    api: Option<Arc<ApiState>>,
+    /// Bound to the API address, taken when the crawl starts serving
+    api_listener: Mutex<Option<TcpListener>>,
+    /// Checkpoint loaded by the builder, consumed when the crawl starts
+    resume_state: Mutex<Option<CrawlState>>,
+}
+
+/// Stops a running crawl: no new URLs are started, in-flight requests get a
+/// short grace period, and [`SilentCrawler::crawl`] returns partial results.
+#[derive(Debug, Clone)]
+pub struct StopHandle {
+    stop: Arc<watch::Sender<bool>>,
+}
+
+impl StopHandle {
+    pub fn stop(&self) {
+        self.stop.send_replace(true);
+    }
+}
+
+/// Maximum number of sitemap documents fetched, including nested indexes.
+const MAX_SITEMAPS: usize = 100;
+
+/// Bytes of a brute force response read to measure it.
+const MAX_PROBE_BODY: u64 = 1024 * 1024;
+
+/// Bytes of robots.txt read; Google ignores anything past 500 KiB as well.
+const MAX_ROBOTS_BODY: u64 = 500 * 1024;
+
+/// Example URLs kept per query parameter name.
+pub(crate) const PARAMETER_SAMPLES: usize = 5;
+
+/// Redirect hops followed for robots.txt and sitemap requests.
+const MAX_AUXILIARY_REDIRECTS: usize = 5;
+
+/// Longest Retry-After pause we honor, so a hostile header can't stall the crawl.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(300);
+
+/// External links checked at once. Each host's delay applies as well, so
+/// other sites only see a trickle of HEAD requests.
+const EXTERNAL_CHECK_CONCURRENCY: usize = 2;
+
+/// A URL waiting to be crawled.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct FrontierEntry {
+    pub url: String,
+    pub depth: usize,
+    pub source: UrlSource,
+    /// The page this URL was found on; `None` for seeds and sitemap URLs.
+    #[serde(default)]
+    pub parent: Option<String>,
+    /// The seed this URL was first reached from; `None` for sitemap URLs
+    /// and the pages found through them.
+    #[serde(default)]
+    pub seed: Option<String>,
+}
+
+/// What came back from a single request.
+pub(crate) struct PageResponse {
+    pub status: u16,
+    /// The server answered Not Modified and the body is the cached one.
+    pub from_cache: bool,
+    pub final_url: String,
+    pub content_type: Option<String>,
+    /// Body length for downloaded pages, otherwise the Content-Length header if present.
+    pub content_length: Option<u64>,
+    pub elapsed: Duration,
+    /// Time until the response headers arrived.
+    pub ttfb: Duration,
+    /// Parsed `Retry-After` header, if present.
+    pub retry_after: Option<Duration>,
+    /// `Location` header, if present.
+    pub location: Option<String>,
+    /// All response headers, as received.
+    pub headers: HeaderMap,
+    /// Decoded body, only populated for successful HTML responses.
+    pub body: Option<String>,
+    /// Raw body, when it was downloaded: for successful HTML responses, for
+    /// non-HTML ones being saved, and for every response when writing WARC.
+    pub bytes: Option<Vec<u8>>,
+    /// The body was cut at the size limit.
+    pub truncated: bool,
+}
+
+impl SilentCrawler {
+    pub(crate) async fn new(mut config: Config) -> Result<Self, CrawlerError> {
+        // Validate and normalize the seed URLs
+        for url in std::iter::once(&mut config.base_url).chain(&mut config.seeds) {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                *url = format!("http://{}", url);
+            }
+            // Seeds written as IRIs are spelled like the links found on pages,
+            // with a punycode host, so the two are compared alike
+            if !url.is_ascii() {
+                if let Ok(parsed) = Url::parse(url) {
+                    *url = parsed.to_string();
+                }
+            }
+        }
+        for seed in &config.seeds {
+            Url::parse(seed)?.host().ok_or(CrawlerError::MissingHost)?;
+        }
+
+        // Parse the base URL and scope the crawl to its registrable domain
+        let parsed_url = Url::parse(&config.base_url)?;
+        let scope = Scope::new(&config)?;
+        let base_domain = scope.base_domain().to_string();
+
+        let url_filter = UrlFilter::new(&config.include_patterns, &config.exclude_patterns)?;
+        let volatile_selectors = config
+            .volatile_selectors
+            .iter()
+            .map(|selector| {
+                Selector::parse(selector).map_err(|err| CrawlerError::InvalidSelector(format!("{:?}: {}", selector, err)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let priority_patterns = config
+            .priority_patterns
+            .iter()
+            .map(|pattern| Regex::new(pattern))
+            .collect::<Result<Vec<_>, _>>()?;
+        let grep_patterns = config
+            .grep_patterns
+            .iter()
+            .map(|pattern| RegexBuilder::new(pattern).case_insensitive(config.grep_ignore_case).build())
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut secret_rules = Vec::new();
+        if config.scan_secrets {
+            secret_rules = builtin_rules();
+            for (name, pattern) in &config.secret_rules {
+                secret_rules.push(SecretRule::new(name, pattern)?);
+            }
+        }
+        let canonical_urls = VisitedSet::new(
+            config.visited_backend,
+            config.bloom_capacity,
+            config.bloom_false_positive_rate,
+        )?;
+        let budgets = Budgets::new(config.max_per_directory, config.max_per_subdomain);
+        let trap_rules = config.trap_detection.then(|| TrapRules {
+            max_segment_repeats: config.max_segment_repeats,
+            max_path_depth: config.max_path_depth,
+            max_query_params: config.max_query_params,
+            max_future_years: config.max_future_years,
+            current_year: timestamp_now()[..4].parse().unwrap_or(2025),
+        });
+
+        // One cookie jar shared by every client; the jar scopes cookies per host
+        let jar = Arc::new(Jar::default());
+        for cookies in &config.cookies {
+            add_cookie_header(&jar, cookies, &parsed_url);
+        }
+        if let Some(path) = &config.cookies_file {
+            let count = add_netscape_cookies(&jar, &std::fs::read_to_string(path)?);
+            debug!(count, path = %path.display(), "loaded cookies");
+        }
+
+        // Overrides for hosts written in Unicode apply to their punycode form
+        for (host, _) in &mut config.resolve {
+            if let Ok(ascii) = Host::parse(host) {
+                *host = ascii.to_string();
+            }
+        }
+        let dns = DnsResolver::new(config.dns_server, config.ip_family, &config.resolve);
+        let clients = if config.proxies.is_empty() {
+            vec![(config.proxy.clone(), build_client(&config, config.proxy.as_deref(), &jar, &dns)?)]
+        } else {
+            config.proxies
+                .iter()
+                .map(|proxy| Ok((Some(proxy.clone()), build_client(&config, Some(proxy), &jar, &dns)?)))
+                .collect::<Result<Vec<_>, CrawlerError>>()?
+        };
+        let clients = ClientPool::new(clients, config.proxy_max_failures);
+        let user_agents = UserAgentPool::new(config.user_agents.clone(), config.user_agent_per_host);
+
+        #[cfg(feature = "sqlite")]
+        let db = match &config.db_path {
+            Some(path) => Some(CrawlDb::open(path)?),
+            None => None,
+        };
+        #[cfg(not(feature = "sqlite"))]
+        if config.db_path.is_some() {
+            return Err(CrawlerError::Storage("a crawl database needs the `sqlite` feature".to_string()));
+        }
+
+        #[cfg(feature = "render")]
+        let (renderer, render_pattern) = if config.render {
+            if let Some(selector) = &config.render_wait_for {
+                Selector::parse(selector).map_err(|err| CrawlerError::InvalidSelector(format!("{:?}: {}", selector, err)))?;
+            }
+            let render_pattern = config.render_pattern.as_deref().map(Regex::new).transpose()?;
+            let renderer = Renderer::launch(
+                config.browser_path.as_deref(),
+                &config.user_agent,
+                config.accept_invalid_certs,
+                config.render_concurrency,
+                config.render_wait_for.clone(),
+                config.render_timeout,
+            )
+            .await?;
+            (Some(renderer), render_pattern)
+        } else {
+            (None, None)
+        };
+        #[cfg(not(feature = "render"))]
+        if config.render {
+            return Err(CrawlerError::Browser("rendering needs the `render` feature".to_string()));
+        }
+
+        // Load the checkpoint before anything is fetched so a mismatch fails
+        // fast. The database is written continuously, so it is preferred.
+        #[cfg(feature = "sqlite")]
+        let db_state = match &db {
+            Some(db) if config.resume => Some(db.load(&config.base_url, config.max_depth)?),
+            Some(db) => {
+                db.start(&config.base_url, config.max_depth)?;
+                None
+            }
+            None => None,
+        };
+        #[cfg(not(feature = "sqlite"))]
+        let db_state = None;
+        let resume_state = match (db_state, &config.state_path, config.resume) {
+            (Some(state), _, _) => Some(state),
+            (None, Some(path), true) => Some(CrawlState::load(path, &config.base_url, config.max_depth)?),
+            (None, None, true) => {
+                return Err(CrawlerError::InvalidState("resuming requires a state file or database".to_string()));
+            }
+            _ => None,
+        };
+
+        let stream = match &config.stream_path {
+            Some(path) => Some(StreamWriter::open(path)?),
+            None => None,
+        };
+        let mirror = match &config.save_dir {
+            Some(dir) => Some(Mirror::open(dir, config.convert_links)?),
+            None => None,
+        };
+        let bandwidth = config.max_bandwidth.map(BandwidthLimiter::new);
+        let cache = match &config.cache_dir {
+            Some(dir) => Some(HttpCache::open(dir, config.cache_max_age, config.cache_max_size, config.refresh)?),
+            None => None,
+        };
+        let warc = match &config.warc_path {
+            Some(path) => Some(WarcWriter::open(path, &warc_info(&config))?),
+            None => None,
+        };
+        let har = config.har_path.as_deref().map(|path| HarRecorder::new(path, config.har_max_body));
+        let default_headers = default_headers(&config)?;
+
+        let host_limiter = HostLimiter::new(config.per_host_concurrency);
+        let progress = Arc::new(Counters::default());
+        // Bind now so an address in use fails the setup, not the crawl
+        let metrics_listener = match config.metrics_addr {
+            Some(addr) => Some(TcpListener::bind(addr).await?),
+            None => None,
+        };
+        let visited_urls = Arc::new(DashMap::new());
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let api_listener = match config.serve_addr {
+            Some(addr) => Some(TcpListener::bind(addr).await?),
+            None => None,
+        };
+        let api = api_listener.as_ref().map(|_| {
+            Arc::new(ApiState {
+                progress: progress.clone(),
+                visited: Arc::clone(&visited_urls),
+                errors: Arc::clone(&errors),
+                results: OnceLock::new(),
+                token: config.serve_token.clone(),
+            })
+        });
+        let webhook = match &config.webhook_url {
+            Some(url) => Some(Webhook::start(url, &config.base_url, config.webhook_events.clone())?),
+            None => None,
+        };
+
+        let crawler = SilentCrawler {
+            config,
+            base_domain,
+            scope,
+            clients,
+            dns,
+            user_agents,
+            jar,
+            visited_urls,
+            canonical_urls,
+            directories: DashSet::new(),
+            subdomains: DashSet::new(),
+            robots: Arc::new(Mutex::new(HashMap::new())),
+            pages: Arc::new(Mutex::new(Vec::new())),
+            errors,
+            sitemap_urls: Arc::new(Mutex::new(HashSet::new())),
+            truncated_sitemaps: Mutex::new(BTreeSet::new()),
+            feeds: Mutex::new(BTreeSet::new()),
+            external_links: Arc::new(Mutex::new(HashSet::new())),
+            graph: Arc::new(Mutex::new(BTreeSet::new())),
+            assets: Arc::new(Mutex::new(HashMap::new())),
+            directory_listings: Mutex::new(HashMap::new()),
+            pagination: Mutex::new(HashMap::new()),
+            hreflang: Mutex::new(HashMap::new()),
+            known_urls: Mutex::new(HashSet::new()),
+            known_urls_skipped: Mutex::new(HashSet::new()),
+            other_languages: Mutex::new(HashSet::new()),
+            documents: Mutex::new(HashMap::new()),
+            forms: Mutex::new(HashMap::new()),
+            parameters: Mutex::new(BTreeMap::new()),
+            parameters_dropped: AtomicUsize::new(0),
+            mixed_content: Mutex::new(BTreeSet::new()),
+            scanned_scripts: Mutex::new(BTreeMap::new()),
+            emails: Mutex::new(BTreeSet::new()),
+            matches: Mutex::new(Vec::new()),
+            secrets: Mutex::new(BTreeSet::new()),
+            referrers: Mutex::new(HashMap::new()),
+            slash_redirects: Mutex::new(HashMap::new()),
+            https_hosts: Mutex::new(HashMap::new()),
+            hsts_hosts: Mutex::new(BTreeMap::new()),
+            canonical_host: OnceLock::new(),
+            url_filter,
+            volatile_selectors,
+            trap_rules,
+            priority_patterns,
+            grep_patterns,
+            secret_rules,
+            budgets,
+            traps: Mutex::new(HashMap::new()),
+            skipped: Arc::new(Mutex::new(HashMap::new())),
+            stream,
+            mirror,
+            cache,
+            warc,
+            har,
+            default_headers,
+            #[cfg(feature = "sqlite")]
+            db,
+            #[cfg(feature = "render")]
+            renderer,
+            #[cfg(feature = "render")]
+            render_pattern,
+            stop: Arc::new(watch::channel(false).0),
+            host_limiter,
+            throttle_events: AtomicUsize::new(0),
+            pages_fetched: AtomicUsize::new(0),
+            timed_out: AtomicBool::new(false),
+            bytes_downloaded: AtomicU64::new(0),
+            byte_limit_reached: AtomicBool::new(false),
+            bandwidth,
+            consecutive_challenges: AtomicUsize::new(0),
+            stopped_by_challenges: AtomicBool::new(false),
+            metrics: Arc::new(Metrics::new(progress.clone())),
+            progress,
+            metrics_listener: Mutex::new(metrics_listener),
+            webhook,
+            api,
+            api_listener: Mutex::new(api_listener),
+            resume_state: Mutex::new(resume_state),
+        };
+
+        // All seeds share one scope, derived from the base URL
+        if let Some(seed) = crawler.seeds().find(|seed| !crawler.is_same_domain(seed)) {
+            return Err(CrawlerError::SeedOutOfScope(seed.to_string()));
+        }
+
+        // A pool benches dead proxies by itself; a single proxy must work
+        if let (Some(proxy), true) = (&crawler.config.proxy, crawler.config.proxies.is_empty()) {
+            crawler.check_proxy(proxy).await?;
+        }
+
+        if let Some(login) = &crawler.config.login {
+            crawler.login(login).await?;
+        }
+
+        // Fetch the base host's robots.txt up front so its Crawl-delay is known
+        if crawler.config.respect_robots {
+            crawler.robots_for(&parsed_url).await;
+        }
+
+        Ok(crawler)
+    }
+
+    /// Makes one request to the base URL so that a misconfigured proxy fails
+    /// the build instead of every request in the crawl.
+    async fn check_proxy(&self, proxy: &str) -> Result<(), CrawlerError> {
+        let proxy = redact_password(proxy);
+        let (_, client) = self.clients.pick();
+        match self.get(client, &self.config.base_url).send().await {
+            Ok(response) if response.status() == StatusCode::PROXY_AUTHENTICATION_REQUIRED => {
+                Err(CrawlerError::Proxy(format!("{} rejected the credentials (HTTP 407)", proxy)))
+            }
+            Err(err) if err.is_connect() => {
+                Err(CrawlerError::Proxy(format!("cannot connect through {}: {}", proxy, error_message(&err))))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Submits the login form and checks that it worked. Session cookies
+    /// end up in the shared cookie jar.
+    async fn login(&self, login: &Login) -> Result<(), CrawlerError> {
+        let check = login.check.as_deref().map(Regex::new).transpose()?;
+        let login_url = Url::parse(&self.config.base_url)?.join(&login.url)?;
+        let failed = |err: reqwest::Error| CrawlerError::Login(format!("{}: {}", login_url, error_message(&err)));
+
+        // Pick up a fresh CSRF token from the login page
+        let token = match &login.token_selector {
+            Some(selector) => {
+                let page = self.get_following_redirects(login_url.as_str()).await.map_err(failed)?;
+                let html = self.read_text(page, self.config.max_body_size).await.map_err(failed)?;
+                let token = extract_token(&html, selector).map_err(CrawlerError::Login)?;
+                if token.is_none() {
+                    return Err(CrawlerError::Login(format!(
+                        "no element with a name and value matches {:?} on {}", selector, login_url
+                    )));
+                }
+                token
+            }
+            None => None,
+        };
+
+        let (_, client) = self.clients.pick();
+        let response = self.with_auth(client.post(login_url.as_str()))
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(merge_form(&login.data, token))
+            .send()
+            .await
+            .map_err(failed)?;
+
+        // A successful login usually redirects; the target is fetched with GET
+        let redirect = response.headers()
+            .get(LOCATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|location| response.url().join(location).ok())
+            .filter(|_| response.status().is_redirection());
+        let response = match &redirect {
+            Some(target) => self.get_following_redirects(target.as_str()).await.map_err(failed)?,
+            None => response,
+        };
+        let status = response.status();
+        let final_url = response.url().clone();
+        if status.is_client_error() || status.is_server_error() {
+            return Err(CrawlerError::Login(format!("{} answered HTTP {}", final_url, status.as_u16())));
+        }
+
+        match check {
+            Some(check) => {
+                let body = self.read_text(response, self.config.max_body_size).await.map_err(failed)?;
+                if !check.is_match(&body) {
+                    return Err(CrawlerError::Login(format!(
+                        "{} does not match {:?}; check the credentials", final_url, check.as_str()
+                    )));
+                }
+            }
+            // Without a check, being sent back to the login form means it failed
+            None if redirect.is_some() && final_url.path() == login_url.path() => {
+                return Err(CrawlerError::Login(format!(
+                    "redirected back to {}; check the credentials", final_url
+                )));
+            }
+            None => {}
+        }
+
+        info!(url = %login_url, landed = %final_url, "logged in");
+        Ok(())
+    }
+
+    /// Returns a handle that can stop a running crawl from another task.
+    pub fn stop_handle(&self) -> StopHandle {
+        StopHandle { stop: self.stop.clone() }
+    }
+
+    /// Returns a handle for watching the crawl's progress from another task.
+    pub fn progress_handle(&self) -> ProgressHandle {
+        ProgressHandle { counters: self.progress.clone() }
+    }
+
+    /// The normalized base URL the crawl starts from.
+    pub fn base_url(&self) -> &str {
+        &self.config.base_url
+    }
+
+    /// Every URL the crawl starts from: the base URL, then any extra seeds.
+    pub fn seeds(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.config.base_url.as_str()).chain(self.config.seeds.iter().map(String::as_str))
+    }
+
+    /// The registrable domain (eTLD+1) of the base URL, used for scope
+    /// checks. IP addresses and single-label hosts are used as-is.
+    pub fn base_domain(&self) -> &str {
+        &self.base_domain
+    }
+
+    /// The delay actually used between requests to the base host: the
+    /// configured delay, or its robots.txt `Crawl-delay` if that is longer
+    /// and being honored.
+    pub fn effective_delay(&self) -> Duration {
+        let rules = Url::parse(&self.config.base_url)
+            .ok()
+            .and_then(|url| self.robots_cell(&url).get().cloned());
+        self.delay_for(rules.as_ref())
+    }
+
+    fn delay_for(&self, rules: Option<&RobotsRules>) -> Duration {
+        let mut delay = self.config.delay;
+        if self.config.respect_robots && self.config.respect_crawl_delay {
+            if let Some(crawl_delay) = rules.and_then(RobotsRules::crawl_delay) {
+                delay = delay.max(crawl_delay);
+            }
+        }
+        delay
+    }
+
+    /// Returns the robots.txt slot for the URL's origin, creating an empty
+    /// one the first time the origin is seen.
+    fn robots_cell(&self, url: &Url) -> Arc<OnceCell<RobotsRules>> {
+        let origin = url.origin().ascii_serialization();
+        let mut robots = self.robots.lock().unwrap();
+        robots.entry(origin).or_default().clone()
+    }
+
+    /// Returns the robots.txt rules for the URL's origin, fetching them on
+    /// first use. Concurrent callers for the same origin share one fetch.
+    async fn robots_for(&self, url: &Url) -> Arc<OnceCell<RobotsRules>> {
+        let cell = self.robots_cell(url);
+        cell.get_or_init(|| self.parse_robots_txt(url)).await;
+        cell
+    }
+
+    async fn parse_robots_txt(&self, url: &Url) -> RobotsRules {
+        // robots.txt always lives at the root of the host
+        let Ok(robots_url) = url.join("/robots.txt") else {
+            return RobotsRules::default();
+        };
+
+        match self.get_following_redirects(robots_url.as_str()).await {
+            Ok(response) => {
+                if response.status().is_success() {
+                    if let Ok(text) = self.read_text(response, MAX_ROBOTS_BODY.min(self.config.max_body_size)).await {
+                        let robots = RobotsTxt::parse(&text);
+                        return robots.rules_for(&self.config.user_agent);
+                    }
+                }
+            }
+            Err(_) => {
+                // If we can't access robots.txt, continue without any rules
+            }
+        }
+
+        RobotsRules::default()
+    }
+
+    /// Checks `url` against its host's robots.txt, fetching it if needed.
+    async fn is_allowed(&self, url: &str) -> bool {
+        if !self.config.respect_robots {
+            return true;
+        }
+
+        if let Ok(parsed_url) = Url::parse(url) {
+            let cell = self.robots_for(&parsed_url).await;
+            if let Some(rules) = cell.get() {
+                return rules.is_allowed(&robots_path(&parsed_url));
+            }
+        }
+
+        true
+    }
+
+    /// Like [`Self::is_allowed`], but only consults robots.txt rules that
+    /// were already fetched. Hosts without cached rules are allowed for now.
+    fn is_allowed_cached(&self, url: &str) -> bool {
+        if !self.config.respect_robots {
+            return true;
+        }
+
+        if let Ok(parsed_url) = Url::parse(url) {
+            if let Some(rules) = self.robots_cell(&parsed_url).get() {
+                return rules.is_allowed(&robots_path(&parsed_url));
+            }
+        }
+
+        true
+    }
+
+    /// Returns true if `url` is in scope: on the base domain, an allowed
+    /// domain, or a subdomain of either, or else let in by the scope file.
+    pub fn is_same_domain(&self, url: &str) -> bool {
+        self.scope.contains(url)
+    }
+
+    /// Whether `url` may be requested at all. With a scope file nothing
+    /// out of scope is, not even to check it.
+    fn may_request(&self, url: &str) -> bool {
+        !self.scope.has_rules() || self.scope.contains(url)
+    }
+
+    /// Plain GET that follows up to [`MAX_AUXILIARY_REDIRECTS`] redirects
+    /// without scope checks, for robots.txt and sitemaps.
+    async fn get_following_redirects(&self, url: &str) -> Result<Response, reqwest::Error> {
+        self.send_following_redirects(Method::GET, url).await
+    }
+
+    async fn send_following_redirects(&self, method: Method, url: &str) -> Result<Response, reqwest::Error> {
+        let mut current = url.to_string();
+        let mut hops = 0;
+        loop {
+            let response = self.send_method(method.clone(), &current, None).await?;
+            let location = response.headers().get(LOCATION).and_then(|value| value.to_str().ok());
+            match location.and_then(|location| response.url().join(location).ok()) {
+                Some(next) if response.status().is_redirection() && hops < MAX_AUXILIARY_REDIRECTS => {
+                    current = next.to_string();
+                    hops += 1;
+                }
+                _ => return Ok(response),
+            }
+        }
+    }
+
+    /// Starts a GET request with the configured credentials and, when
+    /// rotating, the User-Agent picked for it.
+    fn get(&self, client: &Client, url: &str) -> RequestBuilder {
+        self.request(client, Method::GET, url)
+    }
+
+    fn request(&self, client: &Client, method: Method, url: &str) -> RequestBuilder {
+        let mut request = self.with_auth(client.request(method, url));
+        if let Some(user_agents) = &self.user_agents {
+            request = request.header(USER_AGENT, user_agents.pick(url));
+        }
+        request
+    }
+
+    fn with_auth(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.config.auth {
+            Some(Auth::Basic { username, password }) => request.basic_auth(username, Some(password)),
+            Some(Auth::Bearer(token)) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    /// Sends a request through the next client of the pool and records
+    /// whether its proxy worked.
+    async fn send_method(&self, method: Method, url: &str, referer: Option<&str>) -> Result<Response, reqwest::Error> {
+        self.send_recorded(method, url, referer, HeaderMap::new()).await.map(|(_, response)| response)
+    }
+
+    /// Like [`Self::send_method`], but with `extra` headers, and also returns
+    /// the headers the request added to the client defaults.
+    async fn send_recorded(&self, method: Method, url: &str, referer: Option<&str>, extra: HeaderMap) -> Result<(HeaderMap, Response), reqwest::Error> {
+        let (index, client) = self.clients.pick();
+        let mut request = self.request(client, method, url).headers(extra);
+        if let Some(referer) = referer {
+            request = request.header(REFERER, referer);
+        }
+        let (client, request) = request.build_split();
+        let request = request?;
+        let headers = request.headers().clone();
+        let result = client.execute(request).await.map(|response| (headers, response));
+        let failed = match &result {
+            Ok((_, response)) => response.status() == StatusCode::PROXY_AUTHENTICATION_REQUIRED,
+            Err(err) => err.is_connect() || err.is_timeout(),
+        };
+        self.clients.record(index, failed);
+        result
+    }
+
+    /// Fetches `url`. In head-first mode a HEAD request comes first, and the
+    /// GET is skipped when its answer shows the body isn't wanted; in
+    /// head-only mode it is skipped whenever the server supports HEAD.
+    async fn fetch_url(&self, url: &str, referer: Option<&str>) -> Result<PageResponse, reqwest::Error> {
+        if self.config.head_first || self.config.head_only {
+            let head = self.fetch_with(Method::HEAD, url, referer).await?;
+            // Servers that don't support HEAD get a plain GET
+            if !matches!(head.status, 405 | 501) && (self.config.head_only || !self.wants_body(&head)) {
+                return Ok(head);
+            }
+        }
+        self.fetch_with(Method::GET, url, referer).await
+    }
+
+    /// Whether a HEAD response shows a body worth a GET: successful, not
+    /// declared larger than the size limit, and HTML, a feed, followed JSON
+    /// or a parsed PDF unless non-HTML bodies are saved or archived.
+    fn wants_body(&self, head: &PageResponse) -> bool {
+        let is_html = head.content_type.as_deref().is_some_and(|ct| ct.contains("text/html"));
+        let is_feed = self.is_feed(&head.final_url, head.content_type.as_deref());
+        let is_json = self.is_json(head.content_type.as_deref());
+        let is_pdf = self.is_pdf(head.content_type.as_deref());
+        let keeps_non_html = (self.mirror.is_some() && self.config.save_non_html)
+            || self.warc.is_some()
+            || self.har.as_ref().is_some_and(HarRecorder::keeps_bodies);
+        (200..300).contains(&head.status)
+            && (is_html || is_feed || is_json || is_pdf || keeps_non_html)
+            && head.content_length.is_none_or(|length| length <= self.body_limit(is_pdf))
+    }
+
+    /// Whether the response for `url` is a feed: served as RSS or Atom, or
+    /// announced as a feed by a crawled page.
+    fn is_feed(&self, url: &str, content_type: Option<&str>) -> bool {
+        content_type.is_some_and(is_feed_type) || self.feeds.lock().unwrap().contains(url)
+    }
+
+    /// Whether a response of `content_type` is JSON whose links are followed.
+    fn is_json(&self, content_type: Option<&str>) -> bool {
+        self.config.follow_json && content_type.is_some_and(is_json_type)
+    }
+
+    /// Whether a response of `content_type` is a PDF whose links are read.
+    fn is_pdf(&self, content_type: Option<&str>) -> bool {
+        self.config.parse_pdf && content_type.is_some_and(is_pdf_type)
+    }
+
+    /// Bytes of a body read at most: parsed PDFs have a limit of their own.
+    fn body_limit(&self, is_pdf: bool) -> u64 {
+        if is_pdf { self.config.max_pdf_size } else { self.config.max_body_size }
+    }
+
+    async fn fetch_with(&self, method: Method, url: &str, referer: Option<&str>) -> Result<PageResponse, reqwest::Error> {
+        let started = Instant::now();
+        let started_at = humantime::format_rfc3339_millis(SystemTime::now()).to_string();
+        let recording = self.warc.is_some() || self.har.is_some();
+        // Cookies are added as the request is sent, so read them beforehand
+        let cookie = Url::parse(url).ok().filter(|_| recording).and_then(|parsed| self.jar.cookies(&parsed));
+        let is_head = method == Method::HEAD;
+        let conditional = match &self.cache {
+            Some(cache) if !is_head => cache.validators(url),
+            _ => HeaderMap::new(),
+        };
+        let (request_headers, response) = self.send_recorded(method.clone(), url, referer, conditional).await?;
+        let wait = started.elapsed();
+
+        let status = response.status().as_u16();
+        let final_url = response.url().to_string();
+        let content_type = response.headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let location = response.headers()
+            .get(LOCATION)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let retry_after = response.headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_retry_after);
+
+        let headers = response.headers().clone();
+        self.record_hsts(response.url(), &headers);
+        let http_version = format!("{:?}", response.version());
+        let status_text = response.status().canonical_reason().unwrap_or_default();
+        let remote_addr = response.remote_addr();
+
+        // Not Modified: the page is the stored response
+        let cached = match &self.cache {
+            Some(cache) if status == 304 && !is_head => cache.hit(url),
+            _ => None,
+        };
+        let from_cache = cached.is_some();
+        let (page_status, content_type) = match &cached {
+            Some(cached) => (cached.status, cached.content_type.clone()),
+            None => (status, content_type),
+        };
+
+        // Only download the body of successful HTML, feed, followed JSON and
+        // parsed PDF responses, unless it is saved or archived
+        let is_html = content_type.as_deref().is_some_and(|ct| ct.contains("text/html"));
+        let success = (200..300).contains(&page_status);
+        let is_feed = self.is_feed(&final_url, content_type.as_deref());
+        let is_json = self.is_json(content_type.as_deref());
+        let is_pdf = self.is_pdf(content_type.as_deref());
+        let download = !is_head
+            && !from_cache
+            && ((success && (is_html || is_feed || is_json || is_pdf || (self.mirror.is_some() && self.config.save_non_html)))
+                || self.warc.is_some()
+                || self.har.as_ref().is_some_and(HarRecorder::keeps_bodies));
+        // reqwest reports a HEAD response's empty body here, not the header
+        let header_length = response.headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse().ok())
+            .or_else(|| response.content_length().filter(|_| !is_head));
+        let (bytes, truncated) = if let Some(cached) = cached {
+            (Some(cached.bytes), false)
+        } else if download {
+            let limit = self.body_limit(is_pdf);
+            let (bytes, truncated) = self.read_body(response, limit).await?;
+            if truncated {
+                debug!(url = %url, limit, "body cut at the size limit");
+            }
+            (Some(bytes), truncated)
+        } else {
+            (None, false)
+        };
+        let elapsed = started.elapsed();
+        let receive = elapsed - wait;
+        // Cached bodies weren't downloaded, and aren't archived as if they were
+        let received = bytes.as_deref().filter(|_| !from_cache);
+        self.metrics.record_response(status, elapsed);
+        if let (Some(cache), Some(bytes), 200, false) = (&self.cache, received, status, truncated) {
+            cache.store(url, status, content_type.as_deref(), &headers, bytes);
+        }
+        let body = bytes.as_deref().filter(|_| success && is_html).map(|bytes| decode_html(bytes, content_type.as_deref(), &final_url));
+        let content_length = match (&body, &bytes) {
+            // A cut body's real size is only known from the header
+            _ if truncated => header_length,
+            (Some(text), _) => Some(text.len() as u64),
+            (None, Some(bytes)) if success => Some(bytes.len() as u64),
+            _ => header_length,
+        };
+
+        if recording {
+            let mut sent = self.default_headers.clone();
+            sent.extend(request_headers);
+            if let Some(cookie) = cookie {
+                sent.insert(COOKIE, cookie);
+            }
+            if let Some(warc) = &self.warc {
+                warc.write(&WarcExchange {
+                    url,
+                    method: method.as_str(),
+                    request_headers: &sent,
+                    status_line: &format!("{} {} {}", http_version, status, status_text),
+                    response_headers: &headers,
+                    body: received.unwrap_or_default(),
+                    remote_addr,
+                });
+            }
+            if let Some(har) = &self.har {
+                har.record(&HarExchange {
+                    url,
+                    method: method.as_str(),
+                    started_at,
+                    http_version,
+                    request_headers: &sent,
+                    status,
+                    status_text,
+                    response_headers: &headers,
+                    content_type: content_type.as_deref(),
+                    body: received,
+                    content_length: header_length,
+                    location: location.as_deref(),
+                    remote_addr,
+                    wait,
+                    receive,
+                });
+            }
+        }
+
+        Ok(PageResponse {
+            status: page_status,
+            from_cache,
+            final_url,
+            content_type,
+            content_length,
+            elapsed,
+            ttfb: wait,
+            retry_after,
+            location,
+            headers,
+            body,
+            bytes,
+            truncated,
+        })
+    }
+
+    /// Reads at most `limit` bytes of a response body, through the
+    /// bandwidth limit. Returns the bytes and whether the body was cut.
+    async fn read_body(&self, mut response: Response, limit: u64) -> Result<(Vec<u8>, bool), reqwest::Error> {
+        let limit = usize::try_from(limit).unwrap_or(usize::MAX);
+        let mut bytes = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            self.count_download(chunk.len()).await;
+            if bytes.len() + chunk.len() > limit {
+                bytes.extend_from_slice(&chunk[..limit - bytes.len()]);
+                return Ok((bytes, true));
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        Ok((bytes, false))
+    }
+
+    /// Reads at most `limit` bytes of a response body as text, through the
+    /// bandwidth limit.
+    async fn read_text(&self, response: Response, limit: u64) -> Result<String, reqwest::Error> {
+        let (bytes, _) = self.read_body(response, limit).await?;
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    /// Counts downloaded body bytes, stopping the crawl once they exceed
+    /// the download cap, and waits for the bandwidth limit.
+    async fn count_download(&self, bytes: usize) {
+        let total = self.bytes_downloaded.fetch_add(bytes as u64, Ordering::Relaxed) + bytes as u64;
+        self.metrics.record_bytes(bytes);
+        if self.config.max_total_bytes.is_some_and(|max| total > max) && !self.byte_limit_reached.swap(true, Ordering::Relaxed) {
+            warn!(bytes = total, "stopping: download cap reached");
+            self.stop.send_replace(true);
+        }
+        if let Some(bandwidth) = &self.bandwidth {
+            bandwidth.consume(bytes).await;
+        }
+    }
+
+    /// The `X-Robots-Tag` and `<meta name="robots">` directives of a fetched
+    /// page, or none when they are ignored.
+    fn robots_directives(&self, response: &PageResponse, metadata: &PageMetadata) -> RobotsDirectives {
+        if !self.config.respect_meta_robots {
+            return RobotsDirectives::default();
+        }
+        let user_agent = &self.config.user_agent;
+        response
+            .headers
+            .get_all("x-robots-tag")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .chain(std::iter::once(metadata.robots.as_str()))
+            .fold(RobotsDirectives::default(), |directives, value| {
+                directives.merge(RobotsDirectives::parse(value, user_agent))
+            })
+    }
+
+    /// Crawls from the base URL and returns everything that was discovered.
+    pub async fn crawl(&self) -> Results {
+        let started = Instant::now();
+        *self.progress.started.lock().unwrap() = Some(started);
+        let deadline = self.config.max_time.map(|max_time| started + max_time);
+        let metrics_server = self
+            .metrics_listener
+            .lock()
+            .unwrap()
+            .take()
+            .map(|listener| MetricsServer::start(listener, self.metrics.clone()));
+        if let (Some(listener), Some(state)) = (self.api_listener.lock().unwrap().take(), &self.api) {
+            api::start(listener, state.clone());
+        }
+        self.claim_known_urls();
+        let resume_state = self.resume_state.lock().unwrap().take();
+        let pending = match resume_state {
+            // Continue from the checkpoint; its frontier URLs are already claimed
+            Some(state) => self.restore(state),
+            None => {
+                // Start crawling from the seeds, plus anything listed in sitemaps
+                let mut seeds: Vec<FrontierEntry> = self
+                    .seeds()
+                    .map(|url| FrontierEntry {
+                        url: url.to_string(),
+                        depth: 0,
+                        source: UrlSource::Seed,
+                        parent: None,
+                        seed: Some(url.to_string()),
+                    })
+                    .collect();
+                if self.config.use_sitemap {
+                    for url in self.discover_sitemap_urls().await {
+                        seeds.push(FrontierEntry { url, depth: 0, source: UrlSource::Sitemap, parent: None, seed: None });
+                    }
+                }
+
+                let mut pending = Frontier::new(self.config.strategy);
+                for seed in seeds {
+                    self.enqueue(&mut pending, seed);
+                }
+                pending
+            }
+        };
+        let frontier_remaining = self.crawl_concurrent(pending, deadline).await;
+        let broken_assets = if self.config.fetch_assets {
+            self.check_assets(deadline).await
+        } else {
+            Vec::new()
+        };
+        let sourcemaps = if self.config.find_sourcemaps || self.config.probe_sourcemaps {
+            self.find_sourcemaps(deadline).await
+        } else {
+            Vec::new()
+        };
+        let bruteforced = if self.config.wordlist.is_empty() {
+            Vec::new()
+        } else {
+            self.brute_force(deadline).await
+        };
+        let sensitive_files = if self.config.common_files {
+            self.find_common_files(deadline).await
+        } else {
+            Vec::new()
+        };
+        let broken_external = if self.config.check_external {
+            self.check_external_links(deadline).await
+        } else {
+            Vec::new()
+        };
+        if let Some(stream) = &self.stream {
+            stream.flush();
+        }
+        if let Some(warc) = &self.warc {
+            warc.flush();
+        }
+        if let Some(har) = &self.har {
+            har.finish();
+        }
+        let saved_files = self.mirror.as_ref().map_or(0, Mirror::finish);
+        if let Some(cache) = &self.cache {
+            cache.finish();
+        }
+        #[cfg(feature = "render")]
+        if let Some(renderer) = &self.renderer {
+            renderer.close().await;
+        }
+        if let Some(server) = metrics_server {
+            server.stop().await;
+        }
+
+        // Prepare and return results
+        let visited_urls: HashMap<String, usize> = self.visited_urls
+            .iter()
+            .map(|entry| (entry.key().clone(), *entry.value()))
+            .collect();
+        let mut urls: Vec<String> = visited_urls.keys().cloned().collect();
+        urls.sort();
+        let depths: BTreeMap<String, usize> = visited_urls
+            .iter()
+            .map(|(url, depth)| (url.clone(), *depth))
+            .collect();
+        let mut domains: BTreeMap<String, usize> = BTreeMap::new();
+        for url in visited_urls.keys() {
+            if let Some(domain) = self.scope.domain(url) {
+                *domains.entry(unicode_host(domain)).or_insert(0) += 1;
+            }
+        }
+        let urls_per_host = urls_per_host(&urls);
+
+        let mut dirs: Vec<String> = self.directories.iter().map(|directory| directory.clone()).collect();
+        dirs.sort();
+
+        let mut subs: Vec<String> = self.subdomains.iter().map(|subdomain| unicode_host(&subdomain)).collect();
+        subs.sort();
+
+        let mut pages = self.pages.lock().unwrap().clone();
+        pages.sort_by(|a, b| a.url.cmp(&b.url));
+
+        let mut errors = self.errors.lock().unwrap().clone();
+        errors.sort_by(|a, b| a.url.cmp(&b.url));
+        let broken_links = self.broken_links(&errors, broken_external);
+        let canonical_issues = self.canonical_issues(&pages, &errors);
+
+        let completed = !self.is_stopping();
+
+        let mut sitemap_urls: Vec<String> = self.sitemap_urls.lock().unwrap().iter().cloned().collect();
+        sitemap_urls.sort();
+
+        let noindex_pages: Vec<String> = pages.iter().filter(|page| page.noindex).map(|page| page.url.clone()).collect();
+        let (duplicate_groups, near_duplicate_groups) = duplicates(&pages);
+        let pagination = pagination_chains(&pages);
+        let schema_types = schema_types(&pages);
+        let hreflang: BTreeMap<String, BTreeMap<String, String>> =
+            self.hreflang.lock().unwrap().iter().map(|(page, alternates)| (page.clone(), alternates.clone())).collect();
+        let hreflang_issues = hreflang_issues(&hreflang, &pages, &errors);
+        let security_audit = self.config.security_audit.then(|| security_audit(&pages));
+        let performance = performance(&pages);
+        let slow_pages = self.config.slow_threshold.map(|threshold| slow_pages(&pages, threshold)).unwrap_or_default();
+
+        let mut external_links: Vec<String> = self.external_links.lock().unwrap().iter().cloned().collect();
+        external_links.sort();
+        let mut external_domains: BTreeMap<String, usize> = BTreeMap::new();
+        for link in &external_links {
+            if let Some(host) = Url::parse(link).ok().and_then(|url| url.host_str().map(unicode_host)) {
+                *external_domains.entry(host).or_insert(0) += 1;
+            }
+        }
+
+        let mut skipped = self.skipped_urls();
+        skipped.sort_by(|a, b| a.url.cmp(&b.url));
+        let mut traps = self.trapped_urls();
+        traps.sort_by(|a, b| a.url.cmp(&b.url));
+
+        let assets: BTreeMap<String, Vec<String>> = self.assets
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(page, assets)| (page.clone(), assets.iter().cloned().collect()))
+            .collect();
+        let asset_urls: Vec<String> = assets.values().flatten().cloned().collect::<BTreeSet<_>>().into_iter().collect();
+
+        let results = Results {
+            schema_version: RESULTS_SCHEMA_VERSION,
+            completed,
+            page_limit_reached: self.page_limit_reached(),
+            timed_out: self.timed_out.load(Ordering::Relaxed),
+            stopped_by_challenges: self.stopped_by_challenges.load(Ordering::Relaxed),
+            byte_limit_reached: self.byte_limit_reached.load(Ordering::Relaxed),
+            elapsed_ms: started.elapsed().as_millis() as u64,
+            bytes_downloaded: self.bytes_downloaded.load(Ordering::Relaxed),
+            frontier_remaining,
+            known_urls_skipped: self.known_urls_skipped.lock().unwrap().len(),
+            seeds: self.seeds().map(str::to_string).collect(),
+            throttle_events: self.throttle_events.load(Ordering::Relaxed),
+            urls,
+            depths,
+            domains,
+            urls_per_host,
+            pages,
+            errors,
+            sitemap_urls,
+            truncated_sitemaps: self.truncated_sitemaps.lock().unwrap().iter().cloned().collect(),
+            feeds: self.feeds.lock().unwrap().iter().cloned().collect(),
+            directory_listings: {
+                let mut listings: Vec<DirectoryListing> = self
+                    .directory_listings
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .map(|(url, files)| DirectoryListing { url: url.clone(), files: files.clone() })
+                    .collect();
+                listings.sort_by(|a, b| a.url.cmp(&b.url));
+                listings
+            },
+            documents: {
+                let mut documents: Vec<PdfDocument> = self.documents.lock().unwrap().values().cloned().collect();
+                documents.sort_by(|a, b| a.url.cmp(&b.url));
+                documents
+            },
+            noindex_pages,
+            external_links,
+            external_domains,
+            skipped,
+            traps,
+            budgets_exhausted: self
+                .budgets
+                .exhausted()
+                .into_iter()
+                .map(|hit| match hit.kind {
+                    BudgetKind::Subdomain => BudgetHit { bucket: unicode_host(&hit.bucket), ..hit },
+                    BudgetKind::Directory => hit,
+                })
+                .collect(),
+            pagination,
+            duplicate_groups,
+            near_duplicate_groups,
+            saved_files,
+            visited_set_bytes: self.canonical_urls.memory_bytes(),
+            graph: self.graph.lock().unwrap().iter().cloned().collect(),
+            assets,
+            forms: self.forms(),
+            parameters: self.parameters.lock().unwrap().clone(),
+            parameters_dropped: self.parameters_dropped.load(Ordering::Relaxed),
+            asset_urls,
+            broken_assets,
+            mixed_content: self.mixed_content.lock().unwrap().iter().cloned().collect(),
+            scanned_scripts: {
+                let mut scripts: Vec<String> = self.scanned_scripts.lock().unwrap().values().cloned().collect();
+                scripts.sort();
+                scripts
+            },
+            emails: self.emails.lock().unwrap().iter().cloned().collect(),
+            secrets: self.secrets.lock().unwrap().iter().cloned().collect(),
+            matches: {
+                let mut matches = self.matches.lock().unwrap().clone();
+                // Stable, so each page keeps its patterns in order
+                matches.sort_by(|a, b| a.url.cmp(&b.url));
+                matches
+            },
+            sourcemaps,
+            bruteforced,
+            sensitive_files,
+            broken_links,
+            canonical_issues,
+            schema_types,
+            hreflang,
+            hreflang_issues,
+            security_audit,
+            performance,
+            slow_pages,
+            proxies: self.clients.stats(),
+            resolved_hosts: self.dns.resolved(),
+            canonical_host: self.canonical_host.get().map(|host| unicode_host(host)),
+            hsts_hosts: self.hsts_hosts.lock().unwrap().values().cloned().collect(),
+            directories: dirs,
+            subdomains: subs,
+        };
+        if let Some(api) = &self.api {
+            api.finish(&results);
+        }
+        if let Some(webhook) = &self.webhook {
+            webhook.finish(&results).await;
+        }
+        results
+    }
+
+    /// Sends a HEAD request to every distinct asset and returns the ones that
+    /// failed or returned an error status. Servers that reject HEAD are asked
+    /// again with GET. Assets disallowed by robots.txt are not checked.
+    async fn check_assets(&self, deadline: Option<Instant>) -> Vec<CrawlError> {
+        let assets: BTreeSet<String> =
+            self.assets.lock().unwrap().values().flatten().filter(|asset| self.may_request(asset)).cloned().collect();
+        let checks = stream::iter(assets)
+            .map(|url| async move {
+                if self.is_stopping() || deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                    return None;
+                }
+                if !self.is_allowed(&url).await {
+                    debug!(url = %url, "skipped asset: disallowed by robots.txt");
+                    return None;
+                }
+                let error = self.check_url(url).await?;
+                info!(url = %error.url, "broken asset: {}", error.message);
+                Some(error)
+            })
+            .buffer_unordered(self.config.concurrency.max(1));
+
+        let mut broken: Vec<CrawlError> = checks.filter_map(|error| async move { error }).collect().await;
+        broken.sort_by(|a, b| a.url.cmp(&b.url));
+        broken
+    }
+
+    /// Sends a HEAD request to every collected external link, a few at a
+    /// time and within the per-host delay, and returns the broken ones.
+    async fn check_external_links(&self, deadline: Option<Instant>) -> Vec<CrawlError> {
+        let links: BTreeSet<String> =
+            self.external_links.lock().unwrap().iter().filter(|link| self.may_request(link)).cloned().collect();
+        let checks = stream::iter(links)
+            .map(|url| async move {
+                if self.is_stopping() || deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                    return None;
+                }
+                if !self.is_allowed(&url).await {
+                    debug!(url = %url, "skipped external link: disallowed by robots.txt");
+                    return None;
+                }
+                let error = self.check_url(url).await?;
+                info!(url = %error.url, "broken external link: {}", error.message);
+                Some(error)
+            })
+            .buffer_unordered(EXTERNAL_CHECK_CONCURRENCY);
+
+        checks.filter_map(|error| async move { error }).collect().await
+    }
+
+    /// Checks that `url` loads with a HEAD request, or a GET if the server
+    /// rejects HEAD, and returns the error if it doesn't.
+    async fn check_url(&self, url: String) -> Option<CrawlError> {
+        let permit = self.acquire_host(&url).await;
+        let mut result = self.send_following_redirects(Method::HEAD, &url).await;
+        if let Ok(response) = &result {
+            if matches!(response.status().as_u16(), 405 | 501) {
+                result = self.get_following_redirects(&url).await;
+            }
+        }
+        drop(permit);
+
+        match result {
+            Ok(response) if response.status().as_u16() < 400 => None,
+            Ok(response) => {
+                let status = response.status().as_u16();
+                Some(CrawlError {
+                    url,
+                    kind: ErrorKind::Status,
+                    message: status_message(status, self.config.auth.is_some()),
+                    status: Some(status),
+                    attempts: 1,
+                })
+            }
+            Err(err) => Some(CrawlError {
+                url,
+                kind: classify_error(&err),
+                message: error_message(&err),
+                status: None,
+                attempts: 1,
+            }),
+        }
+    }
+
+    /// Requests every wordlist path in every directory of the crawled URLs,
+    /// and their parent directories, and returns the ones that exist. A
+    /// random path is requested first in each directory, and paths
+    /// answering like it are taken as a catch-all response and left out.
+    async fn brute_force(&self, deadline: Option<Instant>) -> Vec<BruteForceHit> {
+        let directories = self.crawled_directories();
+        let expired = move || self.is_stopping() || deadline.is_some_and(|deadline| Instant::now() >= deadline);
+        let baselines: HashMap<String, Option<Probe>> = stream::iter(directories.iter().cloned())
+            .map(|directory| async move {
+                let random: String = rand::rng().sample_iter(rand::distr::Alphanumeric).take(16).map(char::from).collect();
+                let baseline = match Url::parse(&directory).and_then(|url| url.join(&random)) {
+                    Ok(url) if !expired() && self.is_allowed(url.as_str()).await => self.probe(url.as_str()).await,
+                    _ => None,
+                };
+                (directory, baseline)
+            })
+            .buffer_unordered(self.config.concurrency)
+            .collect()
+            .await;
+        let baselines = &baselines;
+
+        let candidates: BTreeSet<(String, String)> = directories
+            .iter()
+            .flat_map(|directory| {
+                let base = Url::parse(directory).ok();
+                self.config.wordlist.iter().filter_map(move |word| {
+                    let url = base.as_ref()?.join(word.trim_start_matches('/')).ok()?.to_string();
+                    Some((directory.clone(), url))
+                })
+            })
+            .filter(|(_, url)| {
+                !self.canonical_urls.contains(&self.dedup_key(url)) && self.url_filter.skip(url).is_none() && self.may_request(url)
+            })
+            .collect();
+        info!(directories = directories.len(), requests = candidates.len(), "brute forcing paths");
+
+        let hits = stream::iter(candidates)
+            .map(|(directory, url)| async move {
+                if expired() || !self.is_allowed(&url).await {
+                    return None;
+                }
+                let probe = self.probe(&url).await?;
+                let reported = match probe.status {
+                    200..=399 => true,
+                    401 | 403 => self.config.bruteforce_forbidden,
+                    _ => false,
+                };
+                if !reported || baselines[&directory].as_ref().is_some_and(|baseline| probe.answers_like(baseline)) {
+                    return None;
+                }
+                debug!(url = %url, status = probe.status, "brute force hit");
+                Some(BruteForceHit { url, status: probe.status, size: probe.size, location: probe.location })
+            })
+            .buffer_unordered(self.config.concurrency);
+        let mut hits: Vec<BruteForceHit> = hits.filter_map(|hit| async move { hit }).collect().await;
+        hits.sort_by(|a, b| a.url.cmp(&b.url));
+        hits
+    }
+
+    /// Requests each of [`COMMON_FILES`] on every crawled host and returns
+    /// the ones served with a success status and the expected content.
+    async fn find_common_files(&self, deadline: Option<Instant>) -> Vec<SensitiveFile> {
+        let origins: BTreeSet<String> = self
+            .visited_urls
+            .iter()
+            .filter_map(|entry| Url::parse(entry.key()).ok())
+            .map(|url| url.origin().ascii_serialization())
+            .collect();
+        let candidates: Vec<(String, &str)> = origins
+            .iter()
+            .flat_map(|origin| COMMON_FILES.iter().map(move |path| (format!("{}{}", origin, path), *path)))
+            .filter(|(url, _)| self.may_request(url))
+            .collect();
+        let checks = stream::iter(candidates)
+            .map(|(url, path)| async move {
+                if self.is_stopping() || deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                    return None;
+                }
+                if !self.is_allowed(&url).await {
+                    debug!(url = %url, "skipped common file: disallowed by robots.txt");
+                    return None;
+                }
+                let permit = self.acquire_host(&url).await;
+                let response = self.send_method(Method::GET, &url, None).await.ok()?;
+                let status = response.status().as_u16();
+                if !response.status().is_success() {
+                    return None;
+                }
+                let (body, _) = self.read_body(response, MAX_PROBE_BODY).await.ok()?;
+                drop(permit);
+                if !is_genuine(path, &body) {
+                    debug!(url = %url, "skipped common file: unexpected content");
+                    return None;
+                }
+                info!(url = %url, status, "found sensitive file");
+                if let Some(webhook) = &self.webhook {
+                    webhook.notify(WebhookEvent::SensitiveFile, WebhookItem {
+                        url: url.clone(),
+                        status: Some(status),
+                        ..WebhookItem::default()
+                    });
+                }
+                Some(SensitiveFile { url, status, size: body.len() as u64 })
+            })
+            .buffer_unordered(self.config.concurrency);
+        let mut found: Vec<SensitiveFile> = checks.filter_map(|file| async move { file }).collect().await;
+        found.sort_by(|a, b| a.url.cmp(&b.url));
+        found
+    }
+
+    /// The directories of the crawled URLs and their parents, as absolute
+    /// URLs, each host's root included.
+    fn crawled_directories(&self) -> BTreeSet<String> {
+        let mut directories = BTreeSet::new();
+        for entry in self.visited_urls.iter() {
+            let Ok(mut url) = Url::parse(entry.key()) else {
+                continue;
+            };
+            url.set_query(None);
+            url.set_fragment(None);
+            let mut path = url.path().to_string();
+            // Walk up from the URL's own directory to the root
+            while let Some(slash) = path.rfind('/') {
+                path.truncate(slash + 1);
+                url.set_path(&path);
+                if !directories.insert(url.to_string()) {
+                    break;
+                }
+                path.truncate(slash);
+            }
+        }
+        directories
+    }
+
+    /// Requests `url` once, without following redirects, for brute forcing.
+    async fn probe(&self, url: &str) -> Option<Probe> {
+        let permit = self.acquire_host(url).await;
+        let response = match self.send_method(Method::GET, url, None).await {
+            Ok(response) => response,
+            Err(err) => {
+                debug!(url = %url, "probe failed: {}", error_message(&err));
+                return None;
+            }
+        };
+        let status = response.status().as_u16();
+        let location = response
+            .headers()
+            .get(LOCATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|location| response.url().join(location).ok())
+            .map(|location| location.to_string());
+        let declared = response.content_length();
+        let size = match self.read_body(response, MAX_PROBE_BODY).await {
+            Ok((body, false)) => Some(body.len() as u64),
+            _ => declared,
+        };
+        drop(permit);
+        Some(Probe { url_len: url.len(), status, size, location })
+    }
+
+    /// Fetches every same-domain script referenced by crawled pages and
+    /// returns the source maps found for them.
+    async fn find_sourcemaps(&self, deadline: Option<Instant>) -> Vec<SourceMap> {
+        let scripts: BTreeSet<String> = self.assets
+            .lock()
+            .unwrap()
+            .values()
+            .flatten()
+            .filter(|asset| is_script_url(asset) && self.is_same_domain(asset))
+            .cloned()
+            .collect();
+        let lookups = stream::iter(scripts)
+            .map(|script| async move {
+                if self.is_stopping() || deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                    return None;
+                }
+                let map = self.sourcemap_for(script).await?;
+                info!(url = %map.url, sources = map.sources.len(), "found source map");
+                Some(map)
+            })
+            .buffer_unordered(self.config.concurrency.max(1));
+
+        let mut maps: Vec<SourceMap> = lookups.filter_map(|map| async move { map }).collect().await;
+        maps.sort_by(|a, b| a.script.cmp(&b.script));
+        maps
+    }
+
+    /// The source map of `script`, from its `SourceMap` header, its
+    /// `sourceMappingURL` comment or, when probing, `<script>.map`.
+    async fn sourcemap_for(&self, script: String) -> Option<SourceMap> {
+        if !self.is_allowed(&script).await {
+            debug!(url = %script, "skipped script: disallowed by robots.txt");
+            return None;
+        }
+        let permit = self.acquire_host(&script).await;
+        let response = self.get_following_redirects(&script).await.ok().filter(|response| response.status().is_success())?;
+        let script_url = response.url().clone();
+        let header = ["sourcemap", "x-sourcemap"]
+            .iter()
+            .find_map(|name| response.headers().get(*name)?.to_str().ok())
+            .map(str::to_string);
+        let (bytes, _) = self.read_body(response, self.config.max_js_size).await.ok()?;
+        drop(permit);
+
+        let body = String::from_utf8_lossy(&bytes);
+        let (origin, reference) = match (header, mapping_url(&body)) {
+            (Some(header), _) => (SourceMapOrigin::Header, header),
+            (None, Some(comment)) if comment.starts_with("data:") => {
+                let map = inline_map(comment)?;
+                let sources = map_sources(&map)?;
+                return Some(SourceMap {
+                    url: script_url.to_string(),
+                    script,
+                    origin: SourceMapOrigin::Inline,
+                    size: Some(map.len() as u64),
+                    sources: sources.sources,
+                    sources_content: sources.has_content,
+                    too_large: false,
+                });
+            }
+            (None, Some(comment)) => (SourceMapOrigin::Comment, comment.to_string()),
+            (None, None) if self.config.probe_sourcemaps => {
+                let mut probe = script_url.clone();
+                probe.set_query(None);
+                probe.set_fragment(None);
+                (SourceMapOrigin::Probe, format!("{}.map", probe))
+            }
+            (None, None) => return None,
+        };
+
+        let url = script_url.join(reference.trim()).ok()?.to_string();
+        if !self.is_same_domain(&url) || !self.is_allowed(&url).await {
+            debug!(url = %url, "skipped source map: out of scope or disallowed");
+            return None;
+        }
+        let _permit = self.acquire_host(&url).await;
+        let response = self.get_following_redirects(&url).await.ok().filter(|response| response.status().is_success())?;
+        let length = response.headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok());
+        let mut map = SourceMap {
+            url,
+            script,
+            origin,
+            size: length,
+            sources: Vec::new(),
+            sources_content: false,
+            too_large: true,
+        };
+        if length.is_some_and(|length| length > self.config.max_sourcemap_size) {
+            return Some(map);
+        }
+        let (bytes, truncated) = self.read_body(response, self.config.max_sourcemap_size).await.ok()?;
+        if truncated {
+            return Some(map);
+        }
+        // A probe answered with anything but a map found nothing
+        let sources = map_sources(&bytes)?;
+        map.size = Some(bytes.len() as u64);
+        map.sources = sources.sources;
+        map.sources_content = sources.has_content;
+        map.too_large = false;
+        Some(map)
+    }
+
+    /// Fetches `script`, unless it was scanned before or the script limit
+    /// is reached, and returns the in-scope URLs found in it.
+    async fn scan_script(&self, script: String) -> Vec<String> {
+        {
+            let key = self.dedup_key(&script);
+            let mut scanned = self.scanned_scripts.lock().unwrap();
+            if scanned.len() >= self.config.max_js_files || scanned.contains_key(&key) {
+                return Vec::new();
+            }
+            scanned.insert(key, script.clone());
+        }
+        if self.is_stopping() || !self.is_allowed(&script).await {
+            return Vec::new();
+        }
+
+        let permit = self.acquire_host(&script).await;
+        let response = match self.get_following_redirects(&script).await {
+            Ok(response) if response.status().is_success() => response,
+            Ok(response) => {
+                debug!(url = %script, status = response.status().as_u16(), "cannot scan script");
+                return Vec::new();
+            }
+            Err(err) => {
+                debug!(url = %script, "cannot scan script: {}", error_message(&err));
+                return Vec::new();
+            }
+        };
+        let final_url = response.url().to_string();
+        let Ok((bytes, truncated)) = self.read_body(response, self.config.max_js_size).await else {
+            return Vec::new();
+        };
+        drop(permit);
+
+        let body = String::from_utf8_lossy(&bytes);
+        self.record_secrets(&script, &body);
+        let urls: Vec<String> = extract_js_urls(&body, &final_url)
+            .into_iter()
+            .filter(|url| self.is_same_domain(url))
+            .collect();
+        debug!(url = %script, urls = urls.len(), truncated, "scanned script");
+        self.record_referrers(&script, &urls, &[]);
+        urls
+    }
+
+    /// Whether `response` is a bot challenge or block page. Stops the crawl
+    /// once there have been too many of them in a row.
+    fn record_challenge(&self, url: &str, response: &PageResponse) -> bool {
+        let Some(service) = detect_challenge(response.status, &response.headers, response.body.as_deref()) else {
+            self.consecutive_challenges.store(0, Ordering::Relaxed);
+            return false;
+        };
+        warn!(url = %url, status = response.status, service, "got a bot challenge page");
+        let consecutive = self.consecutive_challenges.fetch_add(1, Ordering::Relaxed) + 1;
+        if self.config.max_consecutive_challenges.is_some_and(|max| consecutive > max) && !self.is_stopping() {
+            warn!(consecutive, "stopping: too many bot challenges in a row");
+            self.stopped_by_challenges.store(true, Ordering::Relaxed);
+            self.stop.send_replace(true);
+        }
+        true
+    }
+
+    /// Records the files listed by the directory index at `url`, served
+    /// from `final_url`, and returns the `links` worth crawling: its
+    /// subdirectories and HTML pages. Column sorting links are dropped.
+    fn record_directory_listing(&self, url: &str, final_url: &str, links: Vec<String>) -> Vec<String> {
+        let page_path = Url::parse(final_url).map(|page| page.path().to_string()).unwrap_or_default();
+        let mut files = Vec::new();
+        let mut crawled = Vec::new();
+        for link in links {
+            let Ok(parsed) = Url::parse(&link) else {
+                continue;
+            };
+            let path = parsed.path().to_ascii_lowercase();
+            if parsed.query().is_some() && parsed.path() == page_path {
+                continue;
+            }
+            if path.ends_with('/') || path.ends_with(".html") || path.ends_with(".htm") {
+                crawled.push(link);
+            } else {
+                files.push(link);
+            }
+        }
+        files.sort();
+        debug!(url = %url, files = files.len(), directories = crawled.len(), "directory listing");
+        self.directory_listings.lock().unwrap().insert(url.to_string(), files);
+        crawled
+    }
+
+    /// Records that `form` is on `page`. The fields of a GET form are
+    /// query parameters of its action, so a new one adds to the parameter
+    /// inventory.
+    fn record_form(&self, page: String, form: Form) {
+        let mut forms = self.forms.lock().unwrap();
+        let (_, pages) = forms.entry(form.hash.clone()).or_insert_with(|| {
+            if form.method == "GET" {
+                let names: BTreeSet<&str> = form.fields.iter().map(|field| field.name.as_str()).collect();
+                self.record_parameters(names, &form.action);
+            }
+            (form, BTreeSet::new())
+        });
+        pages.insert(page);
+    }
+
+    /// Counts a use of each of `names` by `url`.
+    fn record_parameters<'a>(&self, names: impl IntoIterator<Item = &'a str>, url: &str) {
+        let mut parameters = self.parameters.lock().unwrap();
+        for name in names {
+            if name.is_empty() {
+                continue;
+            }
+            if !parameters.contains_key(name) && parameters.len() >= self.config.max_parameters {
+                self.parameters_dropped.fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
+            let stats = parameters.entry(name.to_string()).or_default();
+            stats.count += 1;
+            if stats.sample_urls.len() < PARAMETER_SAMPLES {
+                stats.sample_urls.push(url.to_string());
+            }
+        }
+    }
+
+    /// The distinct forms under the first page they're on, by URL.
+    fn forms(&self) -> BTreeMap<String, Vec<Form>> {
+        let mut by_page: BTreeMap<String, Vec<Form>> = BTreeMap::new();
+        for (form, pages) in self.forms.lock().unwrap().values() {
+            let mut pages = pages.iter().cloned();
+            let Some(first) = pages.next() else {
+                continue;
+            };
+            by_page.entry(first).or_default().push(Form { also_on: pages.collect(), ..form.clone() });
+        }
+        // Forms keep a stable order on each page
+        for forms in by_page.values_mut() {
+            forms.sort_by(|a, b| (&a.action, &a.hash).cmp(&(&b.action, &b.hash)));
+        }
+        by_page
+    }
+
+    /// Scans the page or script at `url` for secrets, if enabled.
+    fn record_secrets(&self, url: &str, body: &str) {
+        if self.secret_rules.is_empty() {
+            return;
+        }
+        let findings = scan_secrets(url, body, &self.secret_rules);
+        if !findings.is_empty() {
+            info!(url = %url, findings = findings.len(), "found exposed secrets");
+            self.secrets.lock().unwrap().extend(findings);
+        }
+    }
+
+    /// The broken crawled URLs in `errors` and the broken `external` links,
+    /// with their referrers.
+    fn broken_links(&self, errors: &[CrawlError], external: Vec<CrawlError>) -> Vec<BrokenLink> {
+        let referrers = self.referrers.lock().unwrap();
+        let mut broken: BTreeMap<String, BrokenLink> = BTreeMap::new();
+        let all = errors.iter().cloned().map(|error| (error, false)).chain(external.into_iter().map(|error| (error, true)));
+        for (error, external) in all {
+            let referrers = referrers
+                .get(&self.dedup_key(&error.url))
+                .map(|pages| pages.iter().cloned().collect())
+                .unwrap_or_default();
+            broken.insert(error.url.clone(), BrokenLink {
+                url: error.url,
+                status: error.status,
+                kind: error.kind,
+                message: error.message,
+                external,
+                referrers,
+            });
+        }
+        broken.into_values().collect()
+    }
+
+    /// Pages in `pages` whose canonical URL is on another host, redirects,
+    /// or returned something other than 200 according to `pages` and
+    /// `errors`.
+    fn canonical_issues(&self, pages: &[PageRecord], errors: &[CrawlError]) -> Vec<CanonicalIssue> {
+        let mut outcomes: HashMap<String, (CanonicalReason, Option<u16>)> = HashMap::new();
+        for error in errors {
+            outcomes.insert(self.dedup_key(&error.url), (CanonicalReason::Failed, error.status));
+        }
+        // Where the crawl was redirected, the page is also known by its final URL
+        for page in pages.iter().filter(|page| page.url != page.final_url) {
+            outcomes.entry(self.dedup_key(&page.final_url)).or_insert((CanonicalReason::Status, Some(page.status)));
+        }
+        for page in pages {
+            let outcome = if page.url != page.final_url || page.external_redirect.is_some() || page.client_redirect.is_some() {
+                (CanonicalReason::Redirect, None)
+            } else {
+                (CanonicalReason::Status, Some(page.status))
+            };
+            outcomes.insert(self.dedup_key(&page.url), outcome);
+        }
+
+        let host = |url: &str| Url::parse(url).ok().and_then(|url| url.host_str().map(str::to_string));
+        let mut issues = Vec::new();
+        for page in pages {
+            let Some(canonical) = &page.canonical else {
+                continue;
+            };
+            let (reason, status) = if host(canonical) != host(&page.final_url) {
+                (CanonicalReason::OtherHost, None)
+            } else {
+                match outcomes.get(&self.dedup_key(canonical)) {
+                    Some((CanonicalReason::Status, Some(200))) | None => continue,
+                    Some(&(reason, status)) => (reason, status),
+                }
+            };
+            issues.push(CanonicalIssue { page: page.url.clone(), canonical: canonical.clone(), reason, status });
+        }
+        issues
+    }
+
+    /// Collects in-scope page URLs from `/sitemap.xml` and the sitemaps
+    /// listed in robots.txt, following sitemap indexes.
+    async fn discover_sitemap_urls(&self) -> Vec<String> {
+        let Ok(base_url) = Url::parse(&self.config.base_url) else {
+            return Vec::new();
+        };
+
+        let mut queue: VecDeque<String> = VecDeque::new();
+        if let Ok(default_sitemap) = base_url.join("/sitemap.xml") {
+            queue.push_back(default_sitemap.to_string());
+        }
+        // robots.txt is read for its Sitemap lines even when its rules are ignored
+        if let Some(rules) = self.robots_for(&base_url).await.get() {
+            queue.extend(rules.sitemaps().iter().cloned());
+        }
+
+        let mut fetched = HashSet::new();
+        let mut urls = Vec::new();
+        while let Some(sitemap_url) = queue.pop_front() {
+            if fetched.len() >= MAX_SITEMAPS || !fetched.insert(sitemap_url.clone()) {
+                continue;
+            }
+            if !self.may_request(&sitemap_url) {
+                debug!(url = %sitemap_url, "skipped sitemap: out of scope");
+                continue;
+            }
+
+            let Ok(response) = self.get_following_redirects(&sitemap_url).await else {
+                continue;
+            };
+            if !response.status().is_success() {
+                continue;
+            }
+            let Ok((body, cut)) = self.read_body(response, self.config.max_body_size).await else {
+                continue;
+            };
+
+            let sitemap = parse_sitemap(&body, self.config.max_body_size);
+            if cut || sitemap.truncated {
+                warn!(url = %sitemap_url, "sitemap cut off at the body size limit");
+                self.truncated_sitemaps.lock().unwrap().insert(sitemap_url.clone());
+            }
+            queue.extend(sitemap.sitemaps);
+            for url in sitemap.urls {
+                if let Ok(normalized) = normalize_url(&url, &sitemap_url) {
+                    if self.is_same_domain(&normalized) {
+                        urls.push(normalized);
+                    }
+                }
+            }
+        }
+
+        self.sitemap_urls.lock().unwrap().extend(urls.iter().cloned());
+        urls
+    }
+
+    /// Runs the crawl loop until the frontier is empty or the crawl is
+    /// stopped. Returns the number of URLs left unfetched.
+    async fn crawl_concurrent(&self, mut pending: Frontier, deadline: Option<Instant>) -> usize {
+        // Requests currently in flight; their entries are kept alongside so
+        // checkpoints can put them back into the frontier
+        let mut in_flight = FuturesUnordered::new();
+        let mut in_flight_entries: HashMap<String, FrontierEntry> = HashMap::new();
+        let mut stop = self.stop.subscribe();
+        let mut last_checkpoint = Instant::now();
+
+        loop {
+            // Fill free slots from the pending queue, unless we're stopping
+            if !self.is_stopping() && !self.page_limit_reached() {
+                while in_flight.len() < self.config.concurrency {
+                    match pending.pop() {
+                        Some(entry) => {
+                            in_flight_entries.insert(self.dedup_key(&entry.url), entry.clone());
+                            in_flight.push(self.process_url(entry));
+                        }
+                        None => break,
+                    }
+                }
+            }
+
+            self.progress.frontier.store(pending.len() + in_flight.len(), Ordering::Relaxed);
+            if in_flight.is_empty() {
+                break;
+            }
+
+            // Wait for the next request to finish. Once a stop is requested,
+            // in-flight requests get a grace period and are then dropped.
+            let next = if self.is_stopping() {
+                match timeout(self.config.shutdown_grace, in_flight.next()).await {
+                    Ok(next) => next,
+                    Err(_) => break,
+                }
+            } else {
+                tokio::select! {
+                    next = in_flight.next() => next,
+                    _ = stop.wait_for(|stopped| *stopped) => continue,
+                    _ = wait_until(deadline) => {
+                        self.timed_out.store(true, Ordering::Relaxed);
+                        self.stop.send_replace(true);
+                        continue;
+                    }
+                }
+            };
+            let Some((entry, next_urls)) = next else {
+                break;
+            };
+            // By key, since the entry comes back with its URL upgraded to HTTPS
+            in_flight_entries.remove(&self.dedup_key(&entry.url));
+
+            // Only expand links that stay within the max depth. Links found
+            // while stopping are still queued so checkpoints keep them.
+            for (url, source) in next_urls {
+                // The next page of a listing continues the page before
+                let depth = if source == UrlSource::Pagination { entry.depth } else { entry.depth + 1 };
+                if depth > self.config.max_depth {
+                    continue;
+                }
+                if source == UrlSource::Pagination && self.pagination_step(&entry.url).step > self.config.max_pagination {
+                    debug!(url = %url, limit = self.config.max_pagination, "skipped: pagination limit reached");
+                    continue;
+                }
+                self.enqueue(&mut pending, FrontierEntry {
+                    url,
+                    depth,
+                    source,
+                    parent: Some(entry.url.clone()),
+                    seed: entry.seed.clone(),
+                });
+            }
+
+            if last_checkpoint.elapsed() >= self.config.checkpoint_interval {
+                self.checkpoint(&pending, in_flight_entries.values());
+                last_checkpoint = Instant::now();
+            }
+        }
+
+        // Final checkpoint; anything abandoned after a stop stays in the frontier
+        self.checkpoint(&pending, in_flight_entries.values());
+        pending.len() + in_flight_entries.len()
+    }
+
+    /// Writes the current progress to the database and the state file, if
+    /// either is configured.
+    fn checkpoint<'a>(&self, pending: &Frontier, in_flight: impl Iterator<Item = &'a FrontierEntry>) {
+        #[cfg(feature = "sqlite")]
+        let has_db = self.db.is_some();
+        #[cfg(not(feature = "sqlite"))]
+        let has_db = false;
+        if self.config.state_path.is_none() && !has_db {
+            return;
+        }
+
+        let visited: Vec<(String, usize)> =
+            self.visited_urls.iter().map(|entry| (entry.key().clone(), *entry.value())).collect();
+        let frontier: Vec<FrontierEntry> = in_flight.cloned().chain(pending.entries()).collect();
+        // Pages, links and errors are already in the database as they're found
+        #[cfg(feature = "sqlite")]
+        if let Some(db) = &self.db {
+            db.save_frontier(&visited, &frontier);
+        }
+        let Some(path) = &self.config.state_path else {
+            return;
+        };
+
+        let state = CrawlState {
+            base_url: self.config.base_url.clone(),
+            max_depth: self.config.max_depth,
+            visited: visited.into_iter().collect(),
+            frontier,
+            directories: self.directories.iter().map(|directory| directory.clone()).collect(),
+            subdomains: self.subdomains.iter().map(|subdomain| subdomain.clone()).collect(),
+            sitemap_urls: self.sitemap_urls.lock().unwrap().iter().cloned().collect(),
+            truncated_sitemaps: self.truncated_sitemaps.lock().unwrap().iter().cloned().collect(),
+            feeds: self.feeds.lock().unwrap().iter().cloned().collect(),
+            pages: self.pages.lock().unwrap().clone(),
+            errors: self.errors.lock().unwrap().clone(),
+            skipped: self.skipped_urls(),
+            traps: self.trapped_urls(),
+            budgets: self.budgets.exhausted(),
+            external_links: self.external_links.lock().unwrap().iter().cloned().collect(),
+            graph: self.graph.lock().unwrap().iter().cloned().collect(),
+            assets: self
+                .assets
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(page, assets)| (page.clone(), assets.iter().cloned().collect()))
+                .collect(),
+            forms: self.forms().into_iter().collect(),
+            directory_listings: self.directory_listings.lock().unwrap().clone(),
+            documents: self.documents.lock().unwrap().values().cloned().collect(),
+            hreflang: self.hreflang.lock().unwrap().clone(),
+            known_urls_skipped: self.known_urls_skipped.lock().unwrap().iter().cloned().collect(),
+            parameters: self.parameters.lock().unwrap().iter().map(|(name, stats)| (name.clone(), stats.clone())).collect(),
+            parameters_dropped: self.parameters_dropped.load(Ordering::Relaxed),
+            mixed_content: self.mixed_content.lock().unwrap().iter().cloned().collect(),
+            scanned_scripts: self.scanned_scripts.lock().unwrap().values().cloned().collect(),
+            emails: self.emails.lock().unwrap().iter().cloned().collect(),
+            matches: self.matches.lock().unwrap().clone(),
+            secrets: self.secrets.lock().unwrap().iter().cloned().collect(),
+            referrers: self
+                .referrers
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(key, pages)| (key.clone(), pages.iter().cloned().collect()))
+                .collect(),
+        };
+        // A failed checkpoint shouldn't abort the crawl; the next one may succeed
+        let _ = state.save(path);
+    }
+
+    /// Loads a checkpoint into the crawler and returns its frontier.
+    fn restore(&self, state: CrawlState) -> Frontier {
+        for url in state.visited.keys() {
+            self.canonical_urls.insert(self.dedup_key(url));
+            self.budgets.add(&self.fold_www(url));
+        }
+        self.budgets.restore(state.budgets);
+        for (url, depth) in state.visited {
+            self.visited_urls.insert(url, depth);
+        }
+        for directory in state.directories {
+            self.directories.insert(directory);
+        }
+        for subdomain in state.subdomains {
+            self.subdomains.insert(subdomain);
+        }
+        self.sitemap_urls.lock().unwrap().extend(state.sitemap_urls);
+        self.truncated_sitemaps.lock().unwrap().extend(state.truncated_sitemaps);
+        self.feeds.lock().unwrap().extend(state.feeds);
+        self.emails.lock().unwrap().extend(state.emails);
+        self.matches.lock().unwrap().extend(state.matches);
+        self.secrets.lock().unwrap().extend(state.secrets);
+        // Everything with a page or error record counts against the page limit
+        let fetched: HashSet<&str> = state.pages
+            .iter()
+            .map(|page| page.url.as_str())
+            .chain(state.errors.iter().map(|error| error.url.as_str()))
+            .collect();
+        self.pages_fetched.store(fetched.len(), Ordering::Relaxed);
+        self.progress.fetched.store(fetched.len(), Ordering::Relaxed);
+        self.progress.errors.store(state.errors.len(), Ordering::Relaxed);
+        self.pagination.lock().unwrap().extend(
+            state.pages.iter().filter_map(|page| Some((page.url.clone(), page.pagination.clone()?))),
+        );
+        *self.pages.lock().unwrap() = state.pages;
+        *self.errors.lock().unwrap() = state.errors;
+        self.external_links.lock().unwrap().extend(state.external_links);
+        {
+            let mut referrers = self.referrers.lock().unwrap();
+            for (key, pages) in state.referrers {
+                referrers.entry(key).or_default().extend(pages);
+            }
+            // The database has no referrer table, but keeps every link
+            for edge in &state.graph {
+                if !edge.external || self.config.check_external {
+                    referrers.entry(self.dedup_key(&edge.target)).or_default().insert(edge.source.clone());
+                }
+            }
+        }
+        // The database keeps every link, but they're only reported with graph recording on
+        if self.config.record_graph {
+            self.graph.lock().unwrap().extend(state.graph);
+        }
+        self.directory_listings.lock().unwrap().extend(state.directory_listings);
+        self.known_urls_skipped.lock().unwrap().extend(state.known_urls_skipped);
+        for (page, alternates) in &state.hreflang {
+            self.record_hreflang(page, alternates);
+        }
+        self.documents.lock().unwrap().extend(state.documents.into_iter().map(|document| (document.url.clone(), document)));
+        self.parameters.lock().unwrap().extend(state.parameters);
+        self.parameters_dropped.store(state.parameters_dropped, Ordering::Relaxed);
+        // Directly, since the parameters of the forms are already counted
+        let mut known_forms = self.forms.lock().unwrap();
+        for (page, forms) in state.forms {
+            for mut form in forms {
+                let pages = std::mem::take(&mut form.also_on);
+                known_forms.insert(form.hash.clone(), (form, std::iter::once(page.clone()).chain(pages).collect()));
+            }
+        }
+        drop(known_forms);
+        self.assets
+            .lock()
+            .unwrap()
+            .extend(state.assets.into_iter().map(|(page, assets)| (page, assets.into_iter().collect())));
+        self.mixed_content.lock().unwrap().extend(state.mixed_content);
+        self.scanned_scripts
+            .lock()
+            .unwrap()
+            .extend(state.scanned_scripts.into_iter().map(|script| (self.dedup_key(&script), script)));
+        self.skipped
+            .lock()
+            .unwrap()
+            .extend(state.skipped.into_iter().map(|skipped| (skipped.url, skipped.pattern)));
+        self.traps
+            .lock()
+            .unwrap()
+            .extend(state.traps.into_iter().map(|trap| (trap.url, trap.reason)));
+        let mut frontier = Frontier::new(self.config.strategy);
+        for entry in state.frontier {
+            let rank = self.priority_rank(&entry.url);
+            frontier.push(entry, rank);
+        }
+        frontier
+    }
+
+    fn is_stopping(&self) -> bool {
+        *self.stop.borrow()
+    }
+
+    fn page_limit_reached(&self) -> bool {
+        self.config.max_pages.is_some_and(|max_pages| self.pages_fetched.load(Ordering::Relaxed) >= max_pages)
+    }
+
+    /// Reserves one fetch against the page limit. Returns false, without
+    /// reserving, once the limit has been reached.
+    fn reserve_page(&self) -> bool {
+        let Some(max_pages) = self.config.max_pages else {
+            return true;
+        };
+        self.pages_fetched
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |fetched| (fetched < max_pages).then_some(fetched + 1))
+            .is_ok()
+    }
+
+    /// Marks the entry's URL as visited and adds it to the pending queue,
+    /// unless it was already seen, is disallowed or is filtered out.
+    fn enqueue(&self, pending: &mut Frontier, entry: FrontierEntry) {
+        if self.page_limit_reached() {
+            debug!(url = %entry.url, "skipped: page limit reached");
+            return;
+        }
+        if entry.depth > self.config.max_depth {
+            return;
+        }
+        if !self.is_allowed_cached(&entry.url) {
+            debug!(url = %entry.url, "skipped: disallowed by robots.txt");
+            return;
+        }
+        // The start URL is always crawled, whatever the patterns say
+        if entry.source != UrlSource::Seed && (!self.in_pattern_scope(&entry.url) || self.is_trap(&entry.url)) {
+            return;
+        }
+
+        if let Some(page) = self.slash_duplicate(&entry.url) {
+            debug!(url = %entry.url, page = %page, "skipped: same page as its trailing-slash variant");
+            return;
+        }
+
+        // Budgets are only spent on URLs that would actually be queued
+        if entry.source != UrlSource::Seed && !self.canonical_urls.contains(&self.dedup_key(&entry.url)) {
+            if let Err(bucket) = self.budgets.admit(&self.fold_www(&entry.url)) {
+                debug!(url = %entry.url, bucket = %bucket, "skipped: crawl budget used up");
+                return;
+            }
+        }
+
+        if self.claim(&entry.url, entry.depth) {
+            if entry.source == UrlSource::Seed {
+                self.budgets.add(&self.fold_www(&entry.url));
+            }
+            let rank = self.priority_rank(&entry.url);
+            pending.push(entry, rank);
+        } else if self.is_known(&entry.url) {
+            debug!(url = %entry.url, "skipped: already known");
+        } else {
+            debug!(url = %entry.url, "skipped: already visited");
+        }
+    }
+
+    /// Marks the known URLs as visited, so they're never fetched. They're
+    /// normalized and deduplicated like discovered links; the seeds are
+    /// left out, since the crawl has to start somewhere.
+    fn claim_known_urls(&self) {
+        let seeds: HashSet<String> = self.seeds().map(|url| self.dedup_key(url)).collect();
+        let mut known_urls = self.known_urls.lock().unwrap();
+        for url in &self.config.known_urls {
+            let Ok(url) = normalize_url(url.trim(), url.trim()) else {
+                debug!(url = %url, "ignoring known URL: not a valid URL");
+                continue;
+            };
+            let key = self.dedup_key(&url);
+            if !seeds.contains(&key) {
+                self.canonical_urls.insert(key.clone());
+                known_urls.insert(key);
+            }
+        }
+        debug!(count = known_urls.len(), "marked the known URLs as visited");
+    }
+
+    /// Whether `url` is a known URL, counting it as skipped if it is.
+    fn is_known(&self, url: &str) -> bool {
+        let key = self.dedup_key(url);
+        if !self.known_urls.lock().unwrap().contains(&key) {
+            return false;
+        }
+        self.known_urls_skipped.lock().unwrap().insert(key);
+        true
+    }
+
+    /// Checks `url` against the include/exclude patterns, recording it as
+    /// skipped if it is filtered out.
+    fn in_pattern_scope(&self, url: &str) -> bool {
+        match self.url_filter.skip(url).or_else(|| self.scope.skip(url)) {
+            Some(skipped) => {
+                match &skipped.pattern {
+                    Some(pattern) => debug!(url, pattern = %pattern, "skipped: matches exclude pattern"),
+                    None => debug!(url, "skipped: matches no include pattern"),
+                }
+                self.skipped.lock().unwrap().insert(skipped.url, skipped.pattern);
+                false
+            }
+            None => true,
+        }
+    }
+
+    /// Index of the first `--prioritize` pattern matching `url`.
+    fn priority_rank(&self, url: &str) -> Option<usize> {
+        if self.config.strategy != CrawlStrategy::Priority {
+            return None;
+        }
+        self.priority_patterns.iter().position(|pattern| pattern.is_match(url))
+    }
+
+    /// Checks `url` against the trap heuristics, remembering it as
+    /// trap-skipped if one of them fires.
+    fn is_trap(&self, url: &str) -> bool {
+        let Some(reason) = self.trap_rules.as_ref().and_then(|rules| rules.check(url)) else {
+            return false;
+        };
+        debug!(url, reason = %reason, "skipped: looks like a crawler trap");
+        self.traps.lock().unwrap().insert(url.to_string(), reason);
+        true
+    }
+
+    fn trapped_urls(&self) -> Vec<TrappedUrl> {
+        self.traps
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(url, reason)| TrappedUrl { url: url.clone(), reason: *reason })
+            .collect()
+    }
+
+    fn skipped_urls(&self) -> Vec<SkippedUrl> {
+        self.skipped
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(url, pattern)| SkippedUrl { url: url.clone(), pattern: pattern.clone() })
+            .collect()
+    }
+
+    /// Remembers that `from`'s host permanently redirects to `to` when the
+    /// two differ only by a trailing slash.
+    fn record_slash_redirect(&self, from: &str, to: &str, status: u16) {
+        if !matches!(status, 301 | 308) || slash_variant(from).as_deref() != Some(to) {
+            return;
+        }
+        if let Some(host) = Url::parse(from).ok().and_then(|url| url.host_str().map(str::to_string)) {
+            self.slash_redirects.lock().unwrap().insert(host, to.ends_with('/'));
+        }
+    }
+
+    /// With www folding, remembers the first host seen redirecting between
+    /// the base domain and its `www` variant.
+    fn record_www_redirect(&self, from: &str, to: &str) {
+        if !self.config.fold_www {
+            return;
+        }
+        let host = |url: &str| Url::parse(url).ok().and_then(|url| url.host_str().map(str::to_string));
+        let (Some(from), Some(to)) = (host(from), host(to)) else {
+            return;
+        };
+        let www = format!("www.{}", self.base_domain);
+        let folded = [self.base_domain.as_str(), www.as_str()];
+        if from != to && folded.contains(&from.as_str()) && folded.contains(&to.as_str()) && self.canonical_host.set(to).is_ok() {
+            debug!(host = %self.canonical_host.get().unwrap(), "the site redirects to its canonical host");
+        }
+    }
+
+    /// `url` with a `www.<base domain>` host replaced by the base domain,
+    /// when www folding is enabled.
+    fn fold_www(&self, url: &str) -> String {
+        if self.config.fold_www {
+            if let Ok(mut parsed) = Url::parse(url) {
+                if parsed.host_str().and_then(|host| host.strip_prefix("www.")) == Some(self.base_domain.as_str())
+                    && parsed.set_host(Some(&self.base_domain)).is_ok()
+                {
+                    return parsed.to_string();
+                }
+            }
+        }
+        url.to_string()
+    }
+
+    /// For a host known to redirect between `/path` and `/path/`, returns
+    /// the already-visited variant of `url` that it would redirect to.
+    fn slash_duplicate(&self, url: &str) -> Option<String> {
+        let parsed = Url::parse(url).ok()?;
+        let adds_slash = *self.slash_redirects.lock().unwrap().get(parsed.host_str()?)?;
+        if parsed.path().ends_with('/') == adds_slash {
+            return None;
+        }
+        let variant = slash_variant(url)?;
+        self.canonical_urls.contains(&self.dedup_key(&variant)).then_some(variant)
+    }
+
+    /// The canonical form of `url` with ignored query parameters removed.
+    fn dedup_key(&self, url: &str) -> String {
+        let mut canonical = self.fold_www(&canonicalize_url(url));
+        // Either scheme may be fetched as https://, so both are one page
+        if self.config.prefer_https {
+            if let Some(https) = https_variant(&canonical) {
+                canonical = https;
+            }
+        }
+        if self.config.strip_params.is_empty() && !self.config.sort_params {
+            return canonical;
+        }
+        clean_query(&canonical, &self.config.strip_params, self.config.sort_params)
+    }
+
+    /// In prefer-HTTPS mode, the https:// form of `url` if it is an
+    /// http:// URL on the default port and its host serves HTTPS.
+    async fn upgraded(&self, url: &str) -> Option<String> {
+        if !self.config.prefer_https {
+            return None;
+        }
+        let https = https_variant(url)?;
+        let host = Url::parse(url).ok()?.host_str()?.to_string();
+        self.serves_https(&host).await.then_some(https)
+    }
+
+    /// Whether `host` answers over HTTPS, with any status, probed with one
+    /// HEAD request of its root on first use.
+    async fn serves_https(&self, host: &str) -> bool {
+        let cell = self.https_hosts.lock().unwrap().entry(host.to_string()).or_default().clone();
+        *cell
+            .get_or_init(|| async {
+                let probe = format!("https://{}/", host);
+                match self.send_method(Method::HEAD, &probe, None).await {
+                    Ok(response) => {
+                        debug!(host, status = response.status().as_u16(), "host serves HTTPS");
+                        self.record_hsts(response.url(), response.headers());
+                        true
+                    }
+                    Err(err) => {
+                        debug!(host, "host doesn't serve HTTPS, staying on http: {}", error_message(&err));
+                        false
+                    }
+                }
+            })
+            .await
+    }
+
+    /// Remembers the `Strict-Transport-Security` policy of an HTTPS
+    /// response. A host sending one is known to serve HTTPS.
+    fn record_hsts(&self, url: &Url, headers: &HeaderMap) {
+        let (Some(host), "https") = (url.host_str(), url.scheme()) else {
+            return;
+        };
+        let Some(hsts) = headers.get(STRICT_TRANSPORT_SECURITY).and_then(|value| value.to_str().ok()).and_then(|value| parse_hsts(host, value)) else {
+            return;
+        };
+        if self.config.prefer_https {
+            let _ = self.https_hosts.lock().unwrap().entry(host.to_string()).or_default().set(true);
+        }
+        self.hsts_hosts.lock().unwrap().insert(host.to_string(), HstsHost { host: unicode_host(host), ..hsts });
+    }
+
+    /// Marks `url` as visited unless it, or another spelling of the same
+    /// URL, already is. The URL is reported as it was first seen.
+    fn claim(&self, url: &str, depth: usize) -> bool {
+        // The insert is atomic, so two workers can never both claim a URL
+        if !self.canonical_urls.insert(self.dedup_key(url)) {
+            return false;
+        }
+        self.visited_urls.insert(url.to_string(), depth);
+        if let Ok(parsed) = Url::parse(url) {
+            let names: BTreeSet<String> = parsed.query_pairs().map(|(name, _)| name.into_owned()).collect();
+            self.record_parameters(names.iter().map(String::as_str), url);
+        }
+        true
+    }
+
+    fn unclaim(&self, url: &str) {
+        self.canonical_urls.remove(&self.dedup_key(url));
+        self.visited_urls.remove(url);
+    }
+
+    /// Fetches `url`, retrying timeouts, connection failures, 429 and 5xx
+    /// responses with exponential backoff. Every attempt waits for the
+    /// host's rate limit. Returns the final outcome and the attempt count.
+    async fn fetch_with_retries(&self, url: &str, referer: Option<&str>) -> (Result<PageResponse, reqwest::Error>, u32) {
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let permit = self.acquire_host(url).await;
+            let result = self.fetch_url(url, referer).await;
+            drop(permit);
+
+            let retryable = match &result {
+                Ok(response) => response.status == 429 || (500..600).contains(&response.status),
+                Err(err) => matches!(classify_error(err), ErrorKind::Timeout | ErrorKind::ConnectTimeout | ErrorKind::Connect),
+            };
+
+            // The server is throttling us: hold back the whole host
+            let throttled = match &result {
+                Ok(response) if response.status == 429 || response.status == 503 => self.throttle_host(url, response),
+                _ => false,
+            };
+
+            if !retryable || attempts > self.config.retries || self.is_stopping() {
+                return (result, attempts);
+            }
+
+            // A Retry-After pause is enforced by the host limiter on the next attempt
+            if !throttled {
+                sleep(retry_backoff(self.config.retry_backoff, attempts)).await;
+            }
+        }
+    }
+
+    /// Reacts to a 429/503 response: pauses the host for its Retry-After
+    /// duration and, in adaptive mode, slows the host down for good.
+    /// Returns true if a Retry-After pause was applied.
+    fn throttle_host(&self, url: &str, response: &PageResponse) -> bool {
+        let Some(host) = Url::parse(url).ok().and_then(|url| url.host_str().map(str::to_string)) else {
+            return false;
+        };
+
+        let mut throttled = false;
+        if self.config.adaptive_throttle && response.status == 429 {
+            self.host_limiter.slow_down(&host, self.config.delay.max(Duration::from_secs(1)));
+            throttled = true;
+        }
+
+        let paused = match response.retry_after {
+            Some(retry_after) => {
+                self.host_limiter.pause(&host, retry_after.min(MAX_RETRY_AFTER));
+                throttled = true;
+                true
+            }
+            None => false,
+        };
+
+        if throttled {
+            self.throttle_events.fetch_add(1, Ordering::Relaxed);
+        }
+        paused
+    }
+
+    /// Waits for the URL's host to accept another request. Requests to one
+    /// host are spaced by the delay (plus jitter) and capped in concurrency.
+    async fn acquire_host(&self, url: &str) -> Option<HostPermit> {
+        let parsed_url = Url::parse(url).ok()?;
+        let host = parsed_url.host_str()?.to_string();
+
+        let rules = if self.config.respect_robots {
+            self.robots_cell(&parsed_url).get().cloned()
+        } else {
+            None
+        };
+        // A small random jitter avoids a perfectly regular request pattern
+        let jitter = Duration::from_secs_f64(rand::rng().random_range(0.0..0.5));
+        let interval = self.delay_for(rules.as_ref()) + jitter;
+
+        Some(self.host_limiter.acquire(&host, interval).await)
+    }
+
+    /// Records the hreflang `alternates` of the page at `url`, and which of
+    /// them are in languages that aren't crawled.
+    fn record_hreflang(&self, url: &str, alternates: &BTreeMap<String, String>) {
+        if !self.config.languages.is_empty() {
+            let mut other_languages = self.other_languages.lock().unwrap();
+            for (language, alternate) in alternates {
+                if !is_wanted_language(language, &self.config.languages) {
+                    other_languages.insert(self.dedup_key(alternate));
+                }
+            }
+        }
+        self.hreflang.lock().unwrap().insert(url.to_string(), alternates.clone());
+    }
+
+    /// Whether a page declared `url` an alternate in a language that isn't
+    /// crawled.
+    fn is_other_language(&self, url: &str) -> bool {
+        !self.config.languages.is_empty() && self.other_languages.lock().unwrap().contains(&self.dedup_key(url))
+    }
+
+    /// Where a page linked from `parent` as its next or previous page is in
+    /// the chain `parent` belongs to, or starts.
+    fn pagination_step(&self, parent: &str) -> PaginationStep {
+        match self.pagination.lock().unwrap().get(parent) {
+            Some(step) => PaginationStep { first: step.first.clone(), step: step.step + 1 },
+            None => PaginationStep { first: parent.to_string(), step: 1 },
+        }
+    }
+
+    fn record_page(&self, page: PageRecord) {
+        self.progress.fetched.fetch_add(1, Ordering::Relaxed);
+        if let Some(stream) = &self.stream {
+            stream.write_page(&page);
+        }
+        #[cfg(feature = "sqlite")]
+        if let Some(db) = &self.db {
+            db.write_page(&page);
+        }
+        self.pages.lock().unwrap().push(page);
+    }
+
+    fn record_edges(&self, source: &str, links: &[String], external: &[String]) {
+        let edge = |target: &String, external: bool| GraphEdge {
+            source: source.to_string(),
+            target: target.clone(),
+            external,
+        };
+        let mut graph = self.graph.lock().unwrap();
+        graph.extend(links.iter().map(|target| edge(target, false)));
+        graph.extend(external.iter().map(|target| edge(target, true)));
+    }
+
+    /// With rendering, the page `response` came from as the browser renders
+    /// it, if it is a successful HTML page matching the render pattern. The
+    /// browser's URL is returned without its fragment, which client-side
+    /// routers often set.
+    #[cfg(feature = "render")]
+    async fn render(&self, url: &str, response: &PageResponse) -> Option<RenderedPage> {
+        let renderer = self.renderer.as_ref()?;
+        if response.body.is_none() || self.render_pattern.as_ref().is_some_and(|pattern| !pattern.is_match(url)) {
+            return None;
+        }
+        match renderer.render(&response.final_url).await {
+            Ok(mut page) if page.url.starts_with("http://") || page.url.starts_with("https://") => {
+                page.url = normalize_url(&page.url, &page.url).unwrap_or(page.url);
+                debug!(url = %url, browser_url = %page.url, bytes = page.html.len(), "rendered");
+                Some(page)
+            }
+            Ok(page) => {
+                warn!(url = %url, browser_url = %page.url, "cannot render: the page did not load");
+                None
+            }
+            Err(err) => {
+                warn!(url = %url, "cannot render: {}", err);
+                None
+            }
+        }
+    }
+
+    /// With `deep_extract`, the in-scope URLs in the comments and inline
+    /// scripts of the page at `url` that aren't among its `links`.
+    fn hidden_links(&self, url: &str, html: &str, base_url: &str, links: &[String]) -> Vec<(String, UrlSource)> {
+        if !self.config.deep_extract {
+            return Vec::new();
+        }
+        let hidden = extract_hidden_urls(html, base_url);
+        let mut seen: HashSet<&str> = links.iter().map(String::as_str).collect();
+        let mut found = Vec::new();
+        for (urls, source) in [(&hidden.comments, UrlSource::Comment), (&hidden.inline_scripts, UrlSource::InlineScript)] {
+            for link in urls {
+                if self.is_same_domain(link) && seen.insert(link) {
+                    found.push((link.clone(), source));
+                }
+            }
+        }
+        debug!(url = %url, links = found.len(), "extracted hidden links");
+        let hidden_links: Vec<String> = found.iter().map(|(link, _)| link.clone()).collect();
+        self.record_referrers(url, &hidden_links, &[]);
+        found
+    }
+
+    fn record_referrers(&self, source: &str, links: &[String], external: &[String]) {
+        let checked_external = if self.config.check_external { external } else { &[] };
+        let mut referrers = self.referrers.lock().unwrap();
+        for link in links.iter().chain(checked_external) {
+            referrers.entry(self.dedup_key(link)).or_default().insert(source.to_string());
+        }
+    }
+
+    /// Remembers off-site web links; other schemes (ftp:, data:, ...) are dropped.
+    fn record_external(&self, links: Vec<String>) {
+        let mut external_links = self.external_links.lock().unwrap();
+        for link in links {
+            if link.starts_with("http://") || link.starts_with("https://") {
+                external_links.insert(link);
+            }
+        }
+    }
+
+    fn record_error(&self, error: CrawlError) {
+        self.progress.errors.fetch_add(1, Ordering::Relaxed);
+        self.metrics.record_error(error.kind);
+        if let Some(webhook) = &self.webhook {
+            webhook.notify(WebhookEvent::Error, WebhookItem {
+                url: error.url.clone(),
+                error: Some(error.kind.to_string()),
+                status: error.status,
+                ..WebhookItem::default()
+            });
+        }
+        #[cfg(feature = "sqlite")]
+        if let Some(db) = &self.db {
+            db.write_error(&error);
+        }
+        self.errors.lock().unwrap().push(error);
+    }
+
+    async fn process_url(&self, entry: FrontierEntry) -> (FrontierEntry, Vec<(String, UrlSource)>) {
+        // The visited key already covers both schemes, so only the listed URL changes
+        let entry = match self.upgraded(&entry.url).await {
+            Some(https) => {
+                debug!(url = %entry.url, "upgraded to HTTPS");
+                self.visited_urls.remove(&entry.url);
+                self.visited_urls.insert(https.clone(), entry.depth);
+                FrontierEntry { url: https, ..entry }
+            }
+            None => entry,
+        };
+        let url = entry.url.clone();
+
+        // The host's robots.txt may not have been known when this URL was queued
+        if !self.is_allowed(&url).await {
+            debug!(url = %url, "skipped: disallowed by robots.txt");
+            self.unclaim(&url);
+            return (entry, Vec::new());
+        }
+
+        // Other workers may have used up the page limit since this URL was started
+        if !self.reserve_page() {
+            return (entry, Vec::new());
+        }
+
+        // Extract and store subdomain if present
+        if let Some(subdomain) = extract_subdomain(&url, &self.base_domain) {
+            if !(self.config.fold_www && subdomain == "www") {
+                self.subdomains.insert(subdomain);
+            }
+        }
+
+        // Extract and store directory if present
+        if let Some(directory) = extract_directory(&url) {
+            self.directories.insert(directory);
+        }
+
+        // Like browsers, keep the original referer across redirect hops
+        let custom_referer = self.config.headers.iter().any(|(name, _)| name.trim().eq_ignore_ascii_case("referer"));
+        let referer = entry.parent.as_deref().filter(|_| self.config.send_referer && !custom_referer);
+
+        // Fetch page content, retrying transient failures and following
+        // in-scope redirects
+        let mut redirect_chain = Vec::new();
+        let mut external_redirect = None;
+        let mut too_many_redirects = false;
+        let mut current_url = url.clone();
+        let (response, attempts) = loop {
+            let (response, attempts) = self.fetch_with_retries(&current_url, referer).await;
+            let target = match &response {
+                Ok(response) if (300..400).contains(&response.status) => response
+                    .location
+                    .as_deref()
+                    .and_then(|location| normalize_url(location, &current_url).ok()),
+                _ => None,
+            };
+            let Some(target) = target else {
+                break (response, attempts);
+            };
+            if let Ok(response) = &response {
+                self.record_slash_redirect(&current_url, &target, response.status);
+                self.record_www_redirect(&current_url, &target);
+            }
+
+            if redirect_chain.is_empty() {
+                redirect_chain.push(url.clone());
+            }
+            redirect_chain.push(target.clone());
+
+            if redirect_chain.len() - 1 > self.config.max_redirects {
+                too_many_redirects = true;
+                break (response, attempts);
+            }
+            // Off-site redirects are recorded but never followed
+            if !self.is_same_domain(&target) {
+                external_redirect = Some(target);
+                break (response, attempts);
+            }
+            // Stop at disallowed or filtered targets and at pages that are crawled anyway
+            // A redirect to the other scheme of the page is the same page in prefer-HTTPS mode
+            let same_page = self.dedup_key(&target) == self.dedup_key(&current_url);
+            if !self.in_pattern_scope(&target)
+                || self.is_trap(&target)
+                || !self.is_allowed(&target).await
+                || !(same_page || self.claim(&target, entry.depth))
+            {
+                break (response, attempts);
+            }
+            current_url = target;
+        };
+
+        // Like a browser, a page that never stops redirecting is a failure,
+        // not a page
+        let failure = match response {
+            Ok(response) if !too_many_redirects => Ok(response),
+            Ok(_) => {
+                warn!(url = %url, attempts, "too many redirects");
+                Err(CrawlError {
+                    url: url.clone(),
+                    kind: ErrorKind::TooManyRedirects,
+                    message: format!("more than {} redirects", self.config.max_redirects),
+                    status: None,
+                    attempts,
+                })
+            }
+            Err(err) => {
+                warn!(url = %url, attempts, "request failed: {}", error_message(&err));
+                let kind = classify_error(&err);
+                let mut message = error_message(&err);
+                if kind == ErrorKind::Tls {
+                    message = format!("TLS certificate problem: {}", message);
+                }
+                Err(CrawlError {
+                    url: url.clone(),
+                    kind,
+                    message,
+                    status: err.status().map(|status| status.as_u16()),
+                    attempts,
+                })
+            }
+        };
+        let response = match failure {
+            Ok(response) => response,
+            Err(error) => {
+                if let Some(stream) = &self.stream {
+                    stream.write_failure(&error, entry.depth, entry.source, &timestamp_now());
+                }
+                self.progress.fetched.fetch_add(1, Ordering::Relaxed);
+                self.record_error(error);
+                return (entry, Vec::new());
+            }
+        };
+
+        info!(url = %url, status = response.status, elapsed_ms = response.elapsed.as_millis() as u64, "fetched");
+        if let Some(target) = &external_redirect {
+            debug!(url = %target, "skipped: off-domain redirect target");
+        }
+        if response.status >= 400 {
+            self.record_error(CrawlError {
+                url: url.clone(),
+                kind: ErrorKind::Status,
+                message: status_message(response.status, self.config.auth.is_some()),
+                status: Some(response.status),
+                attempts,
+            });
+        }
+
+        let challenged = self.record_challenge(&url, &response);
+        // The rendered DOM stands in for the HTML as fetched, unless the
+        // browser was sent to another page
+        #[cfg(feature = "render")]
+        let (response, rendered, browser_redirect) = match self.render(&url, &response).await {
+            Some(page) if self.dedup_key(&page.url) == self.dedup_key(&response.final_url) => {
+                (PageResponse { body: Some(page.html), ..response }, true, None)
+            }
+            Some(page) => (response, false, Some(page.url)),
+            None => (response, false, None),
+        };
+        #[cfg(not(feature = "render"))]
+        let (rendered, browser_redirect): (bool, Option<String>) = (false, None);
+        let metadata = response.body.as_deref().map(extract_metadata).unwrap_or_default();
+        let language = response.body.as_deref().filter(|_| !self.config.content_languages.is_empty()).and_then(detect_language);
+        let structured_data = response
+            .body
+            .as_deref()
+            .filter(|_| self.config.structured_data)
+            .and_then(|body| extract_structured_data(body, &response.final_url, self.config.max_json_ld_size));
+        let directives = self.robots_directives(&response, &metadata);
+        if directives.noindex {
+            debug!(url = %url, "page is marked noindex");
+        }
+        let canonical = response.body.as_deref().and_then(|body| extract_canonical(body, &response.final_url));
+        let directory_listing = response.body.as_deref().is_some_and(|body| is_directory_listing(&metadata, body));
+        let client_redirect = response
+            .body
+            .as_deref()
+            .and_then(|body| extract_client_redirect(body, &response.final_url))
+            .or_else(|| browser_redirect.map(|target| ClientRedirect { kind: ClientRedirectKind::JsRedirect, target, delay: None }));
+        if let Some(redirect) = &client_redirect {
+            debug!(url = %url, target = %redirect.target, kind = ?redirect.kind, "client-side redirect");
+        }
+        let pagination = entry.parent.as_deref().filter(|_| entry.source == UrlSource::Pagination).map(|parent| self.pagination_step(parent));
+        if let Some(step) = &pagination {
+            self.pagination.lock().unwrap().insert(url.clone(), step.clone());
+        }
+        let fetched_at = timestamp_now();
+        if let Some(mirror) = &self.mirror {
+            let is_html = response.body.is_some();
+            let saved = is_html || (self.config.save_non_html && (200..300).contains(&response.status));
+            if let Some(bytes) = response.bytes.as_deref().filter(|_| saved) {
+                mirror.save(&url, &response.final_url, response.status, &fetched_at, bytes, is_html);
+            }
+        }
+        self.record_page(PageRecord {
+            url: url.clone(),
+            depth: entry.depth,
+            source: entry.source,
+            parent_url: entry.parent.clone(),
+            seed: entry.seed.clone(),
+            pagination,
+            status: response.status,
+            final_url: response.final_url.clone(),
+            content_type: response.content_type.clone(),
+            size: response.content_length,
+            from_cache: response.from_cache,
+            elapsed_ms: response.elapsed.as_millis() as u64,
+            ttfb_ms: response.ttfb.as_millis() as u64,
+            fetched_at,
+            title: metadata.title,
+            description: metadata.description,
+            h1: metadata.h1,
+            language: language.map(str::to_string),
+            structured_data,
+            canonical: canonical.clone(),
+            noindex: directives.noindex,
+            challenged,
+            directory_listing,
+            truncated: response.truncated,
+            rendered,
+            content_hash: response
+                .body
+                .as_deref()
+                .map(|body| format!("{:016x}", content_hash(body, &self.volatile_selectors))),
+            simhash: response
+                .body
+                .as_deref()
+                .filter(|_| self.config.near_duplicates)
+                .and_then(simhash)
+                .map(|hash| format!("{:016x}", hash)),
+            redirect_chain,
+            external_redirect,
+            client_redirect: client_redirect.clone(),
+            headers: if self.config.capture_headers || self.config.security_audit {
+                captured_headers(&response.headers, self.config.capture_headers)
+            } else {
+                BTreeMap::new()
+            },
+        });
+
+        if let Some(html_content) = response.body {
+            // Pages in other languages are treated like nofollow ones
+            let other_language = language.is_some_and(|language| {
+                self.config.skip_other_languages && !self.config.content_languages.iter().any(|wanted| wanted == language)
+            });
+            let nofollow = directives.nofollow || other_language;
+            // Extract links from the page, keeping only those on the same domain
+            // Relative links resolve against where the page actually lives
+            let (mut links, mut external): (Vec<String>, Vec<String>) = if nofollow {
+                if other_language {
+                    debug!(url = %url, language, "skipped links: page is in another language");
+                } else {
+                    debug!(url = %url, "skipped links: page is marked nofollow");
+                }
+                (Vec::new(), Vec::new())
+            } else {
+                extract_links_from(&html_content, &response.final_url, &self.config.link_sources, self.config.respect_meta_robots)
+                    .into_iter()
+                    .partition(|link| self.is_same_domain(link))
+            };
+            if directory_listing {
+                links = self.record_directory_listing(&url, &response.final_url, links);
+            }
+            // Like HTTP redirects, client-side ones are followed on nofollow pages
+            let redirect = client_redirect
+                .filter(|redirect| {
+                    redirect.kind != ClientRedirectKind::MetaRefresh || self.config.link_sources.contains(&LinkSource::MetaRefresh)
+                })
+                .map(|redirect| (redirect.target, redirect.kind.source()));
+            if let Some((target, _)) = &redirect {
+                let found = if self.is_same_domain(target) { &mut links } else { &mut external };
+                if !found.contains(target) {
+                    found.push(target.clone());
+                }
+            }
+            let alternates = extract_hreflang(&html_content, &response.final_url);
+            if !alternates.is_empty() {
+                self.record_hreflang(&url, &alternates);
+                if !nofollow {
+                    for alternate in alternates.values() {
+                        if self.is_same_domain(alternate) && !links.contains(alternate) {
+                            links.push(alternate.clone());
+                        }
+                    }
+                }
+            }
+            // Like feeds, next and previous pages are followed whichever link
+            // sources are enabled
+            let pagination = if nofollow {
+                BTreeSet::new()
+            } else {
+                extract_pagination(&html_content, &response.final_url, self.config.respect_meta_robots)
+            };
+            for page in &pagination {
+                if self.is_same_domain(page) && !links.contains(page) {
+                    links.push(page.clone());
+                }
+            }
+            if !nofollow {
+                // Feeds are fetched whichever link sources are enabled
+                let feeds = extract_feeds(&html_content, &response.final_url);
+                let mut known = self.feeds.lock().unwrap();
+                for feed in feeds {
+                    if self.is_same_domain(&feed) && !links.contains(&feed) {
+                        links.push(feed.clone());
+                    }
+                    known.insert(feed);
+                }
+            }
+            trace!(url = %url, links = links.len(), external = external.len(), "extracted links");
+            let assets = extract_assets(&html_content, &response.final_url);
+            let scripts: Vec<String> = assets
+                .iter()
+                .filter(|_| self.config.scan_js && !nofollow)
+                .filter(|asset| is_script_url(asset) && self.is_same_domain(asset))
+                .cloned()
+                .collect();
+            for form in extract_forms(&html_content, &response.final_url) {
+                self.record_form(url.clone(), form);
+            }
+            if !assets.is_empty() {
+                self.assets.lock().unwrap().insert(url.clone(), assets);
+            }
+            if !self.grep_patterns.is_empty() {
+                let matches = grep_page(&url, &html_content, &self.grep_patterns, self.config.max_grep_snippets);
+                if !matches.is_empty() {
+                    debug!(url = %url, patterns = matches.len(), "grep matched");
+                    if let Some(webhook) = &self.webhook {
+                        for found in &matches {
+                            webhook.notify(WebhookEvent::Match, WebhookItem {
+                                url: found.url.clone(),
+                                pattern: Some(found.pattern.clone()),
+                                ..WebhookItem::default()
+                            });
+                        }
+                    }
+                    self.matches.lock().unwrap().extend(matches);
+                }
+            }
+            self.record_secrets(&url, &html_content);
+            if self.config.harvest_emails {
+                let emails = extract_emails(&html_content);
+                if !emails.is_empty() {
+                    debug!(url = %url, emails = emails.len(), "found email addresses");
+                    self.emails.lock().unwrap().extend(emails);
+                }
+            }
+            let mixed_content = extract_mixed_content(&html_content, &response.final_url);
+            if !mixed_content.is_empty() {
+                debug!(url = %url, resources = mixed_content.len(), "mixed content");
+                self.mixed_content.lock().unwrap().extend(mixed_content.into_iter().map(|(resource, element)| MixedContent {
+                    page: url.clone(),
+                    resource,
+                    element: element.to_string(),
+                }));
+            }
+            if self.config.record_graph {
+                self.record_edges(&url, &links, &external);
+            }
+            self.record_referrers(&url, &links, &external);
+            #[cfg(feature = "sqlite")]
+            if let Some(db) = &self.db {
+                db.write_edges(&url, &links, &external);
+            }
+            for link in &external {
+                debug!(url = %link, "skipped: off-domain");
+            }
+            if self.config.collect_external {
+                self.record_external(external);
+            }
+            if let Some(canonical) = canonical.filter(|_| self.config.follow_canonical && !nofollow) {
+                if self.dedup_key(&canonical) != self.dedup_key(&response.final_url) && self.is_same_domain(&canonical) {
+                    debug!(url = %url, canonical = %canonical, "following canonical instead of links");
+                    return (entry, vec![(canonical, UrlSource::Link)]);
+                }
+            }
+            let hidden = if nofollow {
+                Vec::new()
+            } else {
+                self.hidden_links(&url, &html_content, &response.final_url, &links)
+            };
+            let mut next: Vec<(String, UrlSource)> = links
+                .into_iter()
+                .filter(|link| !self.is_other_language(link))
+                .map(|link| match &redirect {
+                    Some((target, source)) if *target == link => (link, *source),
+                    _ if pagination.contains(&link) => (link, UrlSource::Pagination),
+                    _ => (link, UrlSource::Link),
+                })
+                .collect();
+            next.extend(hidden);
+            for script in scripts {
+                next.extend(self.scan_script(script).await.into_iter().map(|link| (link, UrlSource::Javascript)));
+            }
+            return (entry, next);
+        }
+
+        let is_feed = self.is_feed(&response.final_url, response.content_type.as_deref());
+        if let Some(bytes) = response.bytes.as_deref().filter(|_| is_feed && (200..300).contains(&response.status)) {
+            let links: BTreeSet<String> = parse_feed(bytes)
+                .iter()
+                .filter_map(|link| normalize_url(link, &response.final_url).ok())
+                .collect();
+            let (links, external): (Vec<String>, Vec<String>) = links.into_iter().partition(|link| self.is_same_domain(link));
+            debug!(url = %url, entries = links.len() + external.len(), "parsed feed");
+            self.record_found_links(&url, &links, external);
+            return (entry, links.into_iter().map(|link| (link, UrlSource::Link)).collect());
+        }
+
+        let is_json = self.is_json(response.content_type.as_deref());
+        if let Some(bytes) = response.bytes.as_deref().filter(|_| is_json && (200..300).contains(&response.status)) {
+            let mut next_pages = HashSet::new();
+            let mut links = BTreeSet::new();
+            for link in json_links(bytes) {
+                let Ok(found) = normalize_url(&link.url, &response.final_url) else {
+                    continue;
+                };
+                if !found.starts_with("http://") && !found.starts_with("https://") {
+                    continue;
+                }
+                if link.next {
+                    next_pages.insert(found.clone());
+                }
+                links.insert(found);
+            }
+            let (links, external): (Vec<String>, Vec<String>) = links.into_iter().partition(|link| self.is_same_domain(link));
+            debug!(url = %url, links = links.len() + external.len(), next = next_pages.len(), "parsed JSON");
+            self.record_found_links(&url, &links, external);
+            let next = links
+                .into_iter()
+                .map(|link| {
+                    let source = if next_pages.contains(&link) { UrlSource::Pagination } else { UrlSource::Json };
+                    (link, source)
+                })
+                .collect();
+            return (entry, next);
+        }
+
+        if self.is_pdf(response.content_type.as_deref()) && (200..300).contains(&response.status) {
+            let links = self.parse_pdf(&url, &response).await;
+            let (links, external): (Vec<String>, Vec<String>) = links.into_iter().partition(|link| self.is_same_domain(link));
+            self.record_found_links(&url, &links, external);
+            return (entry, links.into_iter().map(|link| (link, UrlSource::Pdf)).collect());
+        }
+
+        (entry, Vec::new())
+    }
+
+    /// Reads the PDF at `url` into [`Self::documents`] and returns its
+    /// links, resolved and deduplicated. PDFs that can't be read are listed
+    /// as skipped, with a warning.
+    async fn parse_pdf(&self, url: &str, response: &PageResponse) -> BTreeSet<String> {
+        let over_limit = response.truncated
+            || response.content_length.is_some_and(|length| length > self.config.max_pdf_size);
+        let parsed = match &response.bytes {
+            _ if over_limit => Err("over the PDF size limit".to_string()),
+            Some(bytes) => {
+                // Parsing is CPU-bound, and a panic in the PDF library only loses this document
+                let bytes = bytes.clone();
+                let max_stream_size = usize::try_from(self.config.max_pdf_size.saturating_mul(8)).unwrap_or(usize::MAX);
+                tokio::task::spawn_blocking(move || pdf_links(&bytes, max_stream_size))
+                    .await
+                    .unwrap_or_else(|_| Err("the PDF parser crashed".to_string()))
+            }
+            // Not downloaded, as with --head-only
+            None => return BTreeSet::new(),
+        };
+        let (document, links) = match parsed {
+            Ok(pdf) => {
+                let links: BTreeSet<String> = pdf
+                    .links
+                    .iter()
+                    .filter_map(|link| normalize_url(link, &response.final_url).ok())
+                    .filter(|link| link.starts_with("http://") || link.starts_with("https://"))
+                    .collect();
+                debug!(url = %url, pages = pdf.pages, links = links.len(), "parsed PDF");
+                let document = PdfDocument { url: url.to_string(), title: pdf.title, pages: pdf.pages, links: links.len(), skipped: None };
+                (document, links)
+            }
+            Err(reason) => {
+                warn!(url = %url, "skipped PDF: {}", reason);
+                let document = PdfDocument { url: url.to_string(), title: None, pages: 0, links: 0, skipped: Some(reason) };
+                (document, BTreeSet::new())
+            }
+        };
+        self.documents.lock().unwrap().insert(url.to_string(), document);
+        links
+    }
+
+    /// Records the in-scope and external links read from a feed, JSON or
+    /// PDF response at `url`, as extracting links from a page does.
+    fn record_found_links(&self, url: &str, links: &[String], external: Vec<String>) {
+        if self.config.record_graph {
+            self.record_edges(url, links, &external);
+        }
+        self.record_referrers(url, links, &external);
+        #[cfg(feature = "sqlite")]
+        if let Some(db) = &self.db {
+            db.write_edges(url, links, &external);
+        }
+        if self.config.collect_external {
+            self.record_external(external);
+        }
+    }
+}
+
+/// Counts `urls` by `host:port`, the host in its Unicode form.
+pub(crate) fn urls_per_host(urls: &[String]) -> BTreeMap<String, usize> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for url in urls.iter().filter_map(|url| Url::parse(url).ok()) {
+        if let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) {
+            *counts.entry(format!("{}:{}", unicode_host(host), port)).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+/// Whether the hreflang tag `language` is one of `languages` or a regional
+/// variant of one, `en-gb` of `en`. `x-default` always is.
+fn is_wanted_language(language: &str, languages: &[String]) -> bool {
+    language == "x-default"
+        || languages.iter().any(|wanted| {
+            language.strip_prefix(wanted.as_str()).is_some_and(|rest| rest.is_empty() || rest.starts_with('-'))
+        })
+}
+
+/// The problems with the hreflang alternates of every page in `hreflang`:
+/// alternates that `pages` and `errors` show are broken, crawled ones that
+/// don't list the page back, and pages without an `x-default`.
+pub(crate) fn hreflang_issues(
+    hreflang: &BTreeMap<String, BTreeMap<String, String>>,
+    pages: &[PageRecord],
+    errors: &[CrawlError],
+) -> Vec<HreflangIssue> {
+    let mut by_url: HashMap<&str, &PageRecord> = HashMap::new();
+    for page in pages {
+        by_url.entry(&page.final_url).or_insert(page);
+    }
+    for page in pages {
+        by_url.insert(&page.url, page);
+    }
+    let failed: HashMap<&str, Option<u16>> = errors.iter().map(|error| (error.url.as_str(), error.status)).collect();
+
+    let mut issues = Vec::new();
+    for (page, alternates) in hreflang {
+        let issue = |reason, language: Option<&String>, status| HreflangIssue {
+            page: page.clone(),
+            reason,
+            language: language.cloned(),
+            alternate: language.and_then(|language| alternates.get(language)).cloned(),
+            status,
+        };
+        if !alternates.contains_key("x-default") {
+            issues.push(issue(HreflangReason::MissingXDefault, None, None));
+        }
+        let final_url = by_url.get(page.as_str()).map(|record| record.final_url.as_str());
+        for (language, alternate) in alternates {
+            if alternate == page || Some(alternate.as_str()) == final_url {
+                continue;
+            }
+            if let Some(&status) = failed.get(alternate.as_str()) {
+                issues.push(issue(HreflangReason::Broken, Some(language), status));
+                continue;
+            }
+            let Some(target) = by_url.get(alternate.as_str()) else {
+                continue;
+            };
+            if target.status >= 400 {
+                issues.push(issue(HreflangReason::Broken, Some(language), Some(target.status)));
+            } else if target.is_html() {
+                let reciprocal = hreflang
+                    .get(&target.url)
+                    .is_some_and(|back| back.values().any(|url| url == page || Some(url.as_str()) == final_url));
+                if !reciprocal {
+                    issues.push(issue(HreflangReason::NotReciprocal, Some(language), None));
+                }
+            }
+        }
+    }
+    issues
+}
+
+/// The chains of next and previous pages among `pages`, with their pages
+/// in the order they were reached.
+pub(crate) fn pagination_chains(pages: &[PageRecord]) -> Vec<PaginationChain> {
+    let mut chains: BTreeMap<&str, Vec<(usize, &str)>> = BTreeMap::new();
+    for page in pages {
+        if let Some(step) = &page.pagination {
+            chains.entry(&step.first).or_default().push((step.step, &page.url));
+        }
+    }
+    chains
+        .into_iter()
+        .map(|(first, mut pages)| {
+            pages.sort();
+            PaginationChain { first: first.to_string(), pages: pages.into_iter().map(|(_, url)| url.to_string()).collect() }
+        })
+        .collect()
+}
+
+/// Groups `pages`, sorted by URL, by content hash and, where simhashes were
+/// computed, by near-identical text.
+pub(crate) fn duplicates(pages: &[PageRecord]) -> (Vec<DuplicateGroup>, Vec<Vec<String>>) {
+    let mut by_hash: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for page in pages {
+        if let Some(hash) = &page.content_hash {
+            by_hash.entry(hash).or_default().push(page.url.clone());
+        }
+    }
+    let mut groups: Vec<DuplicateGroup> = by_hash
+        .into_iter()
+        .filter(|(_, urls)| urls.len() > 1)
+        .map(|(hash, urls)| DuplicateGroup { hash: hash.to_string(), urls })
+        .collect();
+    groups.sort_by(|a, b| a.urls.cmp(&b.urls));
+
+    let simhashes: Vec<(&str, u64)> = pages
+        .iter()
+        .filter_map(|page| Some((page.url.as_str(), u64::from_str_radix(page.simhash.as_deref()?, 16).ok()?)))
+        .collect();
+    (groups, near_duplicate_groups(&simhashes))
+}
+
+/// Headers sent with every request: browser-like defaults, replaced by any
+/// configured header of the same name.
+fn default_headers(config: &Config) -> Result<HeaderMap, CrawlerError> {
+    let mut headers = HeaderMap::new();
+    headers.insert(USER_AGENT, HeaderValue::from_str(&config.user_agent)?);
+    // APIs that negotiate the content type answer JSON when it is followed
+    let accept = if config.follow_json {
+        "text/html,application/xhtml+xml,application/xml,application/json;q=0.9"
+    } else {
+        "text/html,application/xhtml+xml,application/xml"
+    };
+    headers.insert(ACCEPT, HeaderValue::from_static(accept));
+    headers.insert(ACCEPT_LANGUAGE, HeaderValue::from_static("en-US,en;q=0.9"));
+    let encodings = if config.compression { "gzip, deflate, br, zstd" } else { "identity" };
+    headers.insert(ACCEPT_ENCODING, HeaderValue::from_static(encodings));
+    headers.insert(CONNECTION, HeaderValue::from_static("keep-alive"));
+    headers.insert(UPGRADE_INSECURE_REQUESTS, HeaderValue::from_static("1"));
+
+    // Custom headers replace defaults of the same name
+    let mut custom = HeaderMap::new();
+    for (name, value) in &config.headers {
+        let header_name = HeaderName::from_bytes(name.trim().as_bytes())
+            .map_err(|_| CrawlerError::InvalidHeaderName(name.clone()))?;
+        custom.append(header_name, HeaderValue::from_str(value.trim())?);
+    }
+    headers.extend(custom);
+    Ok(headers)
+}
+
+/// Fields of the `warcinfo` record that starts each crawl's WARC output.
+fn warc_info(config: &Config) -> Vec<(&'static str, String)> {
+    let mut info = vec![
+        ("software", format!("silent-crawler/{}", env!("CARGO_PKG_VERSION"))),
+        ("format", "WARC File Format 1.1".to_string()),
+        ("conformsTo", "https://iipc.github.io/warc-specifications/specifications/warc-format/warc-1.1/".to_string()),
+        ("isPartOf", config.base_url.clone()),
+        ("robots", if config.respect_robots { "obey" } else { "ignore" }.to_string()),
+        ("http-header-user-agent", config.user_agent.clone()),
+        ("max-depth", config.max_depth.to_string()),
+        ("concurrency", format!("{} ({} per host)", config.concurrency, config.per_host_concurrency)),
+        ("delay", humantime::format_duration(config.delay).to_string()),
+    ];
+    info.extend(config.seeds.iter().map(|seed| ("seed", seed.clone())));
+    info
+}
+
+/// Whether `url` names a JavaScript file by its extension.
+fn is_script_url(url: &str) -> bool {
+    Url::parse(url).is_ok_and(|url| {
+        let path = url.path().to_ascii_lowercase();
+        path.ends_with(".js") || path.ends_with(".mjs")
+    })
+}
+
+/// The answer to a brute force request.
+struct Probe {
+    /// Length of the requested URL, which error pages often repeat
+    url_len: usize,
+    status: u16,
+    size: Option<u64>,
+    location: Option<String>,
+}
+
+impl Probe {
+    /// Whether this answer matches the `baseline` for a path that doesn't
+    /// exist: the same status and, for redirects, the same target, or
+    /// otherwise a size within a tenth of the baseline's, give or take the
+    /// URL quoted twice.
+    fn answers_like(&self, baseline: &Probe) -> bool {
+        if self.status != baseline.status {
+            return false;
+        }
+        if (300..400).contains(&self.status) {
+            return self.location == baseline.location;
+        }
+        match (self.size, baseline.size) {
+            (Some(size), Some(baseline_size)) => {
+                let quoted = 2 * self.url_len.abs_diff(baseline.url_len) as u64;
+                size.abs_diff(baseline_size) <= baseline_size / 10 + quoted
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Builds an HTTP client, going through `proxy` if one is given.
+fn build_client(config: &Config, proxy: Option<&str>, jar: &Arc<Jar>, dns: &DnsResolver) -> Result<Client, CrawlerError> {
+    // Redirects are followed by hand so every hop can be scope-checked
+    let mut builder = Client::builder()
+        .default_headers(default_headers(config)?)
+        .timeout(config.timeout)
+        .redirect(Policy::none())
+        .cookie_provider(jar.clone())
+        .dns_resolver(Arc::new(dns.clone()));
+    // Port 0 keeps the port of each URL
+    let mut overrides: BTreeMap<&str, Vec<SocketAddr>> = BTreeMap::new();
+    for (host, ip) in &config.resolve {
+        overrides.entry(host).or_default().push(SocketAddr::new(*ip, 0));
+    }
+    for (host, addrs) in overrides {
+        builder = builder.resolve_to_addrs(host, &addrs);
+    }
+    if let Some(timeout) = config.connect_timeout {
+        builder = builder.connect_timeout(timeout);
+    }
+    if let Some(timeout) = config.read_timeout {
+        builder = builder.read_timeout(timeout);
+    }
+    if let Some(timeout) = config.pool_idle_timeout {
+        builder = builder.pool_idle_timeout(timeout);
+    }
+    if let Some(max) = config.pool_max_idle_per_host {
+        builder = builder.pool_max_idle_per_host(max);
+    }
+
+    // Without an explicit proxy, reqwest picks up HTTP_PROXY/HTTPS_PROXY/NO_PROXY itself
+    if let Some(proxy) = proxy {
+        builder = builder.proxy(Proxy::all(proxy)?.no_proxy(NoProxy::from_env()));
+    }
+    if config.accept_invalid_certs || config.proxy_insecure {
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+    for path in &config.ca_certs {
+        for certificate in Certificate::from_pem_bundle(&std::fs::read(path)?)? {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    if let Some((cert_path, key_path)) = &config.client_identity {
+        let identity = Identity::from_pkcs8_pem(&std::fs::read(cert_path)?, &std::fs::read(key_path)?)?;
+        builder = builder.identity(identity);
+    }
+
+    Ok(builder.build()?)
+}
+
+/// The https:// form of an http:// URL without an explicit port.
+fn https_variant(url: &str) -> Option<String> {
+    let mut parsed = Url::parse(url).ok()?;
+    if parsed.scheme() != "http" || parsed.port().is_some() {
+        return None;
+    }
+    parsed.set_scheme("https").ok()?;
+    Some(parsed.to_string())
+}
+
+/// Error message for a failed status, with a hint for authentication failures.
+fn status_message(status: u16, has_credentials: bool) -> String {
+    match (status, has_credentials) {
+        (401, true) => "HTTP 401: the credentials may be wrong".to_string(),
+        (401, false) => "HTTP 401: authentication required".to_string(),
+        _ => format!("HTTP {}", status),
+    }
+}
+
+/// Sleeps until `deadline`, or forever if there is none.
+async fn wait_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => sleep_until(deadline.into()).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// The current time as an RFC 3339 timestamp.
+fn timestamp_now() -> String {
+    humantime::format_rfc3339_seconds(SystemTime::now()).to_string()
+}
+
+/// Parses a `Retry-After` value, either delay-seconds or an HTTP-date.
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    // A date in the past means "retry now"
+    Some(date.duration_since(SystemTime::now()).unwrap_or(Duration::ZERO))
+}
+
+/// Backoff before retry number `attempt` (1-based): `base * 2^(attempt - 1)`,
+/// capped at 30s, plus up to 50% random jitter.
+fn retry_backoff(base: Duration, attempt: u32) -> Duration {
+    let backoff = base.saturating_mul(2u32.saturating_pow(attempt - 1)).min(Duration::from_secs(30));
+    backoff + backoff.mul_f64(rand::rng().random_range(0.0..0.5))
+}
+
+/// Sorts a reqwest error into one of the reported error kinds.
+fn classify_error(err: &reqwest::Error) -> ErrorKind {
+    if err.is_timeout() {
+        return if err.is_connect() { ErrorKind::ConnectTimeout } else { ErrorKind::Timeout };
+    }
+
+    if err.is_connect() {
+        // reqwest doesn't expose resolver or TLS failures directly, so look
+        // for their messages in the error chain
+        let message = error_message(err).to_lowercase();
+        if message.contains("dns error") || message.contains("failed to lookup address") {
+            return ErrorKind::Dns;
+        }
+        if message.contains("certificate") || message.contains("ssl") || message.contains("tls") {
+            return ErrorKind::Tls;
+        }
+        return ErrorKind::Connect;
+    }
+
+    if err.is_status() {
+        return ErrorKind::Status;
+    }
+
+    if err.is_redirect() {
+        return ErrorKind::TooManyRedirects;
+    }
+
+    ErrorKind::Other
+}
+
+/// Flattens an error and all of its sources into a single message.
+fn error_message(err: &dyn std::error::Error) -> String {
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(cause) = source {
+        let cause_message = cause.to_string();
+        if !message.ends_with(&cause_message) {
+            message.push_str(": ");
+            message.push_str(&cause_message);
+        }
+        source = cause.source();
+    }
+    message
+}
+
+/// The part of a URL that robots.txt rules are matched against: the path
+/// together with the query string.
+fn robots_path(url: &Url) -> String {
+    let mut path = url.path().to_string();
+    if let Some(query) = url.query() {
+        path.push('?');
+        path.push_str(query);
+    }
+    path
+}
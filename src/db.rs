@@ -0,0 +1,265 @@
+//! A SQLite database that pages, links, errors and the frontier are written
+//! to while the crawl runs, and that an interrupted crawl resumes from.
+
+use crate::crawler::FrontierEntry;
+use crate::error::CrawlerError;
+use crate::results::{CrawlError, GraphEdge, PageRecord};
+use crate::state::CrawlState;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::Path;
+use std::sync::Mutex;
+
+const SCHEMA: &str = "
+    PRAGMA journal_mode = WAL;
+    PRAGMA synchronous = NORMAL;
+    CREATE TABLE IF NOT EXISTS meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+    CREATE TABLE IF NOT EXISTS pages (
+        url TEXT PRIMARY KEY,
+        depth INTEGER NOT NULL,
+        status INTEGER NOT NULL,
+        content_type TEXT,
+        size INTEGER,
+        fetched_at TEXT NOT NULL,
+        record TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS edges (
+        src TEXT NOT NULL,
+        dst TEXT NOT NULL,
+        external INTEGER NOT NULL,
+        PRIMARY KEY (src, dst)
+    ) WITHOUT ROWID;
+    CREATE TABLE IF NOT EXISTS errors (
+        url TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        message TEXT NOT NULL,
+        status INTEGER,
+        attempts INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS frontier (
+        url TEXT NOT NULL,
+        depth INTEGER NOT NULL,
+        source TEXT NOT NULL,
+        parent TEXT,
+        seed TEXT
+    );
+    CREATE TABLE IF NOT EXISTS visited (url TEXT PRIMARY KEY, depth INTEGER NOT NULL) WITHOUT ROWID;
+";
+
+/// The crawl database. Write failures are dropped so they never interrupt
+/// the crawl, like the other incremental outputs.
+pub(crate) struct CrawlDb {
+    connection: Mutex<Connection>,
+}
+
+fn storage_error(err: rusqlite::Error) -> CrawlerError {
+    CrawlerError::Storage(format!("database: {}", err))
+}
+
+/// The serde name of a unit enum variant, e.g. `"status"` for `ErrorKind::Status`.
+fn enum_text<T: Serialize>(value: T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn enum_parse<T: DeserializeOwned>(text: String) -> Result<T, CrawlerError> {
+    serde_json::from_value(serde_json::Value::String(text))
+        .map_err(|err| CrawlerError::InvalidState(format!("database: {}", err)))
+}
+
+impl CrawlDb {
+    /// Opens `path`, creating the database and its tables if needed.
+    pub fn open(path: &Path) -> Result<Self, CrawlerError> {
+        let connection = Connection::open(path).map_err(storage_error)?;
+        connection.execute_batch(SCHEMA).map_err(storage_error)?;
+        Ok(CrawlDb { connection: Mutex::new(connection) })
+    }
+
+    /// Empties the database for a new crawl of `base_url`.
+    pub fn start(&self, base_url: &str, max_depth: usize) -> Result<(), CrawlerError> {
+        let connection = self.connection.lock().unwrap();
+        connection
+            .execute_batch("DELETE FROM meta; DELETE FROM pages; DELETE FROM edges; DELETE FROM errors; DELETE FROM frontier; DELETE FROM visited;")
+            .map_err(storage_error)?;
+        connection
+            .execute(
+                "INSERT INTO meta (key, value) VALUES ('base_url', ?1), ('max_depth', ?2)",
+                params![base_url, max_depth.to_string()],
+            )
+            .map_err(storage_error)?;
+        Ok(())
+    }
+
+    /// Reads back a crawl recorded by [`Self::start`] and the writes after
+    /// it, checking that it belongs to the same crawl.
+    pub fn load(&self, base_url: &str, max_depth: usize) -> Result<CrawlState, CrawlerError> {
+        let connection = self.connection.lock().unwrap();
+        let meta = |key: &str| {
+            connection
+                .query_row("SELECT value FROM meta WHERE key = ?1", [key], |row| row.get::<_, String>(0))
+                .optional()
+                .map_err(storage_error)
+        };
+        let recorded_url = meta("base_url")?
+            .ok_or_else(|| CrawlerError::InvalidState("the database holds no crawl to resume".to_string()))?;
+        let recorded_depth: usize = meta("max_depth")?.and_then(|depth| depth.parse().ok()).unwrap_or_default();
+        if recorded_url != base_url {
+            return Err(CrawlerError::InvalidState(format!(
+                "database was recorded for {} but this crawl starts at {}",
+                recorded_url, base_url
+            )));
+        }
+        if recorded_depth != max_depth {
+            return Err(CrawlerError::InvalidState(format!(
+                "database was recorded with depth {} but this crawl uses depth {}",
+                recorded_depth, max_depth
+            )));
+        }
+
+        let mut state = CrawlState {
+            base_url: recorded_url,
+            max_depth,
+            ..CrawlState::default()
+        };
+
+        let mut statement = connection.prepare("SELECT url, depth FROM visited").map_err(storage_error)?;
+        let rows = statement
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as usize)))
+            .map_err(storage_error)?;
+        for row in rows {
+            let (url, depth) = row.map_err(storage_error)?;
+            state.visited.insert(url, depth);
+        }
+
+        let mut statement = connection.prepare("SELECT url, depth, source, parent, seed FROM frontier").map_err(storage_error)?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, i64>(1)? as usize,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                ))
+            })
+            .map_err(storage_error)?;
+        for row in rows {
+            let (url, depth, source, parent, seed) = row.map_err(storage_error)?;
+            state.frontier.push(FrontierEntry { url, depth, source: enum_parse(source)?, parent, seed });
+        }
+
+        let mut statement = connection.prepare("SELECT record FROM pages").map_err(storage_error)?;
+        let rows = statement.query_map([], |row| row.get::<_, String>(0)).map_err(storage_error)?;
+        for row in rows {
+            let record = row.map_err(storage_error)?;
+            let page: PageRecord = serde_json::from_str(&record)
+                .map_err(|err| CrawlerError::InvalidState(format!("database: {}", err)))?;
+            state.pages.push(page);
+        }
+
+        let mut statement = connection
+            .prepare("SELECT url, kind, message, status, attempts FROM errors")
+            .map_err(storage_error)?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, Option<u16>>(3)?,
+                    row.get::<_, u32>(4)?,
+                ))
+            })
+            .map_err(storage_error)?;
+        for row in rows {
+            let (url, kind, message, status, attempts) = row.map_err(storage_error)?;
+            state.errors.push(CrawlError { url, kind: enum_parse(kind)?, message, status, attempts });
+        }
+
+        let mut statement = connection.prepare("SELECT src, dst, external FROM edges").map_err(storage_error)?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok(GraphEdge { source: row.get(0)?, target: row.get(1)?, external: row.get(2)? })
+            })
+            .map_err(storage_error)?;
+        for row in rows {
+            state.graph.push(row.map_err(storage_error)?);
+        }
+
+        Ok(state)
+    }
+
+    pub fn write_page(&self, page: &PageRecord) {
+        let Ok(record) = serde_json::to_string(page) else {
+            return;
+        };
+        let connection = self.connection.lock().unwrap();
+        let _ = connection
+            .prepare_cached(
+                "INSERT OR REPLACE INTO pages (url, depth, status, content_type, size, fetched_at, record)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )
+            .and_then(|mut statement| {
+                statement.execute(params![
+                    page.url,
+                    page.depth as i64,
+                    page.status,
+                    page.content_type,
+                    page.size.map(|size| size as i64),
+                    page.fetched_at,
+                    record
+                ])
+            });
+    }
+
+    pub fn write_error(&self, error: &CrawlError) {
+        let connection = self.connection.lock().unwrap();
+        let _ = connection
+            .prepare_cached("INSERT INTO errors (url, kind, message, status, attempts) VALUES (?1, ?2, ?3, ?4, ?5)")
+            .and_then(|mut statement| {
+                statement.execute(params![error.url, enum_text(error.kind), error.message, error.status, error.attempts])
+            });
+    }
+
+    /// Records the in-scope and external links found on `source`.
+    pub fn write_edges(&self, source: &str, links: &[String], external: &[String]) {
+        let mut connection = self.connection.lock().unwrap();
+        let Ok(transaction) = connection.transaction() else {
+            return;
+        };
+        if let Ok(mut statement) = transaction.prepare_cached("INSERT OR IGNORE INTO edges (src, dst, external) VALUES (?1, ?2, ?3)") {
+            let targets = links.iter().map(|link| (link, false)).chain(external.iter().map(|link| (link, true)));
+            for (target, external) in targets {
+                let _ = statement.execute(params![source, target, external]);
+            }
+        }
+        let _ = transaction.commit();
+    }
+
+    /// Replaces the stored frontier and visited set, for resuming.
+    pub fn save_frontier(&self, visited: &[(String, usize)], frontier: &[FrontierEntry]) {
+        let mut connection = self.connection.lock().unwrap();
+        let Ok(transaction) = connection.transaction() else {
+            return;
+        };
+        let saved = (|| {
+            transaction.execute_batch("DELETE FROM frontier; DELETE FROM visited;")?;
+            let mut statement = transaction.prepare_cached("INSERT INTO visited (url, depth) VALUES (?1, ?2)")?;
+            for (url, depth) in visited {
+                statement.execute(params![url, *depth as i64])?;
+            }
+            let mut statement =
+                transaction.prepare_cached("INSERT INTO frontier (url, depth, source, parent, seed) VALUES (?1, ?2, ?3, ?4, ?5)")?;
+            for entry in frontier {
+                statement.execute(params![entry.url, entry.depth as i64, enum_text(entry.source), entry.parent, entry.seed])?;
+            }
+            Ok::<_, rusqlite::Error>(())
+        })();
+        if saved.is_ok() {
+            let _ = transaction.commit();
+        }
+    }
+}
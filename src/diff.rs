@@ -0,0 +1,84 @@
+//! Comparing the results of two crawls of the same site.
+
+use crate::output::read_json;
+use crate::results::Results;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io;
+use std::path::Path;
+
+/// What changed between two crawls.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResultsDiff {
+    /// URLs only the new crawl discovered, sorted.
+    pub urls_added: Vec<String>,
+    /// URLs only the old crawl discovered, sorted.
+    pub urls_removed: Vec<String>,
+    pub new_subdomains: Vec<String>,
+    pub new_directories: Vec<String>,
+    /// URLs fetched by both crawls whose status differs, sorted by URL.
+    pub status_changed: Vec<StatusChange>,
+}
+
+/// A URL whose status code changed. A missing status means the request
+/// failed without a response, such as a timeout or DNS failure.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct StatusChange {
+    pub url: String,
+    pub old: Option<u16>,
+    pub new: Option<u16>,
+}
+
+impl ResultsDiff {
+    /// Whether the crawls differ in anything compared.
+    pub fn is_empty(&self) -> bool {
+        self.urls_added.is_empty()
+            && self.urls_removed.is_empty()
+            && self.new_subdomains.is_empty()
+            && self.new_directories.is_empty()
+            && self.status_changed.is_empty()
+    }
+}
+
+/// Compares the results files `old` and `new`, as written by `-o` in JSON.
+/// Fails if either isn't a results file of this version.
+pub fn diff_files(old: &Path, new: &Path) -> io::Result<ResultsDiff> {
+    Ok(diff_results(&read_json(old)?, &read_json(new)?))
+}
+
+/// Compares the results of two crawls.
+pub fn diff_results(old: &Results, new: &Results) -> ResultsDiff {
+    let added = |old: &[String], new: &[String]| -> Vec<String> {
+        let old: BTreeSet<&String> = old.iter().collect();
+        let new: BTreeSet<&String> = new.iter().collect();
+        new.difference(&old).map(|item| item.to_string()).collect()
+    };
+
+    let old_statuses = statuses(old);
+    let status_changed = statuses(new)
+        .into_iter()
+        .filter_map(|(url, status)| match old_statuses.get(url) {
+            Some(&old) if old != status => Some(StatusChange { url: url.to_string(), old, new: status }),
+            _ => None,
+        })
+        .collect();
+
+    ResultsDiff {
+        urls_added: added(&old.urls, &new.urls),
+        urls_removed: added(&new.urls, &old.urls),
+        new_subdomains: added(&old.subdomains, &new.subdomains),
+        new_directories: added(&old.directories, &new.directories),
+        status_changed,
+    }
+}
+
+/// The outcome of every request: the status of each page, and `None` for
+/// URLs that failed without a response.
+pub(crate) fn statuses(results: &Results) -> BTreeMap<&str, Option<u16>> {
+    let mut statuses: BTreeMap<&str, Option<u16>> =
+        results.pages.iter().map(|page| (page.url.as_str(), Some(page.status))).collect();
+    for error in results.errors.iter().filter(|error| error.status.is_none()) {
+        statuses.entry(error.url.as_str()).or_insert(None);
+    }
+    statuses
+}
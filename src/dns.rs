@@ -0,0 +1,115 @@
+//! Resolving host names for the crawl's clients.
+//!
+//! Names are looked up through the system resolver, or through one DNS
+//! server when one is set, and the answers can be limited to IPv4 or IPv6.
+//! The addresses every host resolved to are kept for the results, so
+//! hosts served by different infrastructure stand out.
+
+use crate::extract::unicode_host;
+use hickory_resolver::config::{LookupIpStrategy, NameServerConfigGroup, ResolverConfig, ResolverOpts};
+use hickory_resolver::name_server::TokioConnectionProvider;
+use hickory_resolver::TokioResolver;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use tracing::trace;
+
+/// The addresses the crawler connects to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IpFamily {
+    /// IPv4 and IPv6, in the order the resolver returns them.
+    #[default]
+    Any,
+    /// IPv4 only.
+    V4,
+    /// IPv6 only.
+    V6,
+}
+
+impl IpFamily {
+    fn allows(self, ip: &IpAddr) -> bool {
+        match self {
+            IpFamily::Any => true,
+            IpFamily::V4 => ip.is_ipv4(),
+            IpFamily::V6 => ip.is_ipv6(),
+        }
+    }
+}
+
+/// The resolver shared by every client of one crawl.
+#[derive(Clone)]
+pub(crate) struct DnsResolver {
+    /// Asked instead of the system resolver, when a DNS server is set
+    server: Option<TokioResolver>,
+    family: IpFamily,
+    resolved: Arc<Mutex<BTreeMap<String, BTreeSet<IpAddr>>>>,
+}
+
+impl DnsResolver {
+    /// A resolver asking `server`, or the system resolver when there is
+    /// none. Hosts pinned to `overrides` are answered by the client before
+    /// it gets here, so they are recorded up front.
+    pub fn new(server: Option<SocketAddr>, family: IpFamily, overrides: &[(String, IpAddr)]) -> Self {
+        let server = server.map(|server| {
+            let group = NameServerConfigGroup::from_ips_clear(&[server.ip()], server.port(), true);
+            let mut options = ResolverOpts::default();
+            options.ip_strategy = match family {
+                IpFamily::Any => LookupIpStrategy::Ipv4AndIpv6,
+                IpFamily::V4 => LookupIpStrategy::Ipv4Only,
+                IpFamily::V6 => LookupIpStrategy::Ipv6Only,
+            };
+            TokioResolver::builder_with_config(ResolverConfig::from_parts(None, Vec::new(), group), TokioConnectionProvider::default())
+                .with_options(options)
+                .build()
+        });
+        let mut resolved: BTreeMap<String, BTreeSet<IpAddr>> = BTreeMap::new();
+        for (host, ip) in overrides {
+            resolved.entry(host.to_lowercase()).or_default().insert(*ip);
+        }
+        DnsResolver { server, family, resolved: Arc::new(Mutex::new(resolved)) }
+    }
+
+    /// The addresses each host resolved to, sorted, by the Unicode form of
+    /// the host.
+    pub fn resolved(&self) -> BTreeMap<String, Vec<IpAddr>> {
+        self.resolved
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(host, ips)| (unicode_host(host), ips.iter().copied().collect()))
+            .collect()
+    }
+
+    async fn lookup(&self, host: &str) -> Result<Vec<IpAddr>, String> {
+        let ips: Vec<IpAddr> = match &self.server {
+            Some(server) => server.lookup_ip(host).await.map_err(|err| err.to_string())?.iter().collect(),
+            None => tokio::net::lookup_host((host, 0))
+                .await
+                .map_err(|err| err.to_string())?
+                .map(|addr| addr.ip())
+                .collect(),
+        };
+        let ips: Vec<IpAddr> = ips.into_iter().filter(|ip| self.family.allows(ip)).collect();
+        match self.family {
+            _ if !ips.is_empty() => Ok(ips),
+            IpFamily::Any => Err("no addresses found".to_string()),
+            IpFamily::V4 => Err("no IPv4 address".to_string()),
+            IpFamily::V6 => Err("no IPv6 address".to_string()),
+        }
+    }
+}
+
+impl Resolve for DnsResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolver = self.clone();
+        Box::pin(async move {
+            let host = name.as_str().to_lowercase();
+            let ips = resolver.lookup(&host).await.map_err(|err| format!("cannot resolve {}: {}", host, err))?;
+            trace!(host = %host, addresses = ?ips, "resolved");
+            resolver.resolved.lock().unwrap().entry(host).or_default().extend(ips.iter().copied());
+            let addrs: Addrs = Box::new(ips.into_iter().map(|ip| SocketAddr::new(ip, 0)));
+            Ok(addrs)
+        })
+    }
+}
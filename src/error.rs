@@ -0,0 +1,108 @@
+use std::fmt;
+
+/// Errors that can occur while configuring or starting a crawl.
+#[derive(Debug)]
+pub enum CrawlerError {
+    /// The base URL could not be parsed.
+    InvalidUrl(url::ParseError),
+    /// The base URL parsed but has no host component.
+    MissingHost,
+    /// A seed URL is outside the scope of the base URL and allowed domains,
+    /// or of the scope file.
+    SeedOutOfScope(String),
+    /// A header value (such as the user agent) is not a valid HTTP header.
+    InvalidHeader(reqwest::header::InvalidHeaderValue),
+    /// A custom header name is not a valid HTTP header name.
+    InvalidHeaderName(String),
+    /// The underlying HTTP client could not be built.
+    Client(reqwest::Error),
+    /// An output file could not be opened.
+    Io(std::io::Error),
+    /// A state file could not be used to resume the crawl.
+    InvalidState(String),
+    /// An include, exclude, priority, grep or login check pattern is not a
+    /// valid regular expression.
+    InvalidPattern(regex::Error),
+    /// A volatile element selector is not a valid CSS selector.
+    InvalidSelector(String),
+    /// The scope file could not be parsed.
+    InvalidScope(String),
+    /// The configured proxy refused the connection or its credentials.
+    Proxy(String),
+    /// The login form could not be submitted or was rejected.
+    Login(String),
+    /// On-disk crawl storage could not be created or used.
+    Storage(String),
+    /// The headless browser for rendering could not be launched.
+    Browser(String),
+}
+
+impl fmt::Display for CrawlerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CrawlerError::InvalidUrl(err) => write!(f, "invalid URL: {}", err),
+            CrawlerError::MissingHost => write!(f, "invalid URL: missing domain"),
+            CrawlerError::SeedOutOfScope(url) => {
+                write!(f, "seed {} is out of scope: not on the base URL's domain or an allowed domain, or left out by the scope file", url)
+            }
+            CrawlerError::InvalidHeader(err) => write!(f, "invalid header value: {}", err),
+            CrawlerError::InvalidHeaderName(name) => write!(f, "invalid header name: {:?}", name),
+            CrawlerError::Client(err) => write!(f, "failed to build HTTP client: {}", err),
+            CrawlerError::Io(err) => write!(f, "I/O error: {}", err),
+            CrawlerError::InvalidState(message) => write!(f, "cannot resume: {}", message),
+            CrawlerError::InvalidPattern(err) => write!(f, "invalid pattern: {}", err),
+            CrawlerError::InvalidSelector(message) => write!(f, "invalid selector: {}", message),
+            CrawlerError::InvalidScope(message) => write!(f, "invalid scope file: {}", message),
+            CrawlerError::Proxy(message) => write!(f, "proxy error: {}", message),
+            CrawlerError::Login(message) => write!(f, "login failed: {}", message),
+            CrawlerError::Storage(message) => write!(f, "storage error: {}", message),
+            CrawlerError::Browser(message) => write!(f, "browser error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for CrawlerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CrawlerError::InvalidUrl(err) => Some(err),
+            CrawlerError::MissingHost | CrawlerError::SeedOutOfScope(_) => None,
+            CrawlerError::InvalidHeader(err) => Some(err),
+            CrawlerError::InvalidHeaderName(_) => None,
+            CrawlerError::Client(err) => Some(err),
+            CrawlerError::Io(err) => Some(err),
+            CrawlerError::InvalidState(_) | CrawlerError::InvalidSelector(_) | CrawlerError::InvalidScope(_) => None,
+            CrawlerError::InvalidPattern(err) => Some(err),
+            CrawlerError::Proxy(_) | CrawlerError::Login(_) | CrawlerError::Storage(_) | CrawlerError::Browser(_) => None,
+        }
+    }
+}
+
+impl From<url::ParseError> for CrawlerError {
+    fn from(err: url::ParseError) -> Self {
+        CrawlerError::InvalidUrl(err)
+    }
+}
+
+impl From<reqwest::header::InvalidHeaderValue> for CrawlerError {
+    fn from(err: reqwest::header::InvalidHeaderValue) -> Self {
+        CrawlerError::InvalidHeader(err)
+    }
+}
+
+impl From<reqwest::Error> for CrawlerError {
+    fn from(err: reqwest::Error) -> Self {
+        CrawlerError::Client(err)
+    }
+}
+
+impl From<std::io::Error> for CrawlerError {
+    fn from(err: std::io::Error) -> Self {
+        CrawlerError::Io(err)
+    }
+}
+
+impl From<regex::Error> for CrawlerError {
+    fn from(err: regex::Error) -> Self {
+        CrawlerError::InvalidPattern(err)
+    }
+}
@@ -0,0 +1,1024 @@
+use crate::content::fnv1a;
+use crate::feed::is_feed_type;
+use crate::results::{ClientRedirect, ClientRedirectKind, Form, FormField};
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt;
+use std::str::FromStr;
+use url::{form_urlencoded, Host, ParseError, Url};
+
+/// HTML elements that links are extracted from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LinkSource {
+    /// `<a href>`
+    Anchor,
+    /// `<iframe src>`
+    Iframe,
+    /// `<frame src>`
+    Frame,
+    /// `<area href>` in image maps
+    Area,
+    /// `<form action>`; the action is only ever fetched with GET
+    Form,
+    /// `<link href>` with `rel` alternate, canonical, next or prev
+    Link,
+    /// `<meta http-equiv="refresh" content="0; url=...">`
+    MetaRefresh,
+}
+
+impl LinkSource {
+    /// Every supported link source.
+    pub const ALL: [LinkSource; 7] = [
+        LinkSource::Anchor,
+        LinkSource::Iframe,
+        LinkSource::Frame,
+        LinkSource::Area,
+        LinkSource::Form,
+        LinkSource::Link,
+        LinkSource::MetaRefresh,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            LinkSource::Anchor => "a",
+            LinkSource::Iframe => "iframe",
+            LinkSource::Frame => "frame",
+            LinkSource::Area => "area",
+            LinkSource::Form => "form",
+            LinkSource::Link => "link",
+            LinkSource::MetaRefresh => "meta-refresh",
+        }
+    }
+
+    fn selector(self) -> &'static str {
+        match self {
+            LinkSource::Anchor => "a[href]",
+            LinkSource::Iframe => "iframe[src]",
+            LinkSource::Frame => "frame[src]",
+            LinkSource::Area => "area[href]",
+            LinkSource::Form => "form[action]",
+            LinkSource::Link => "link[href]",
+            LinkSource::MetaRefresh => "meta[http-equiv][content]",
+        }
+    }
+
+    /// The URL an element selected by [`Self::selector`] points to, if any.
+    fn url<'a>(self, element: ElementRef<'a>) -> Option<&'a str> {
+        let element = element.value();
+        match self {
+            LinkSource::Anchor | LinkSource::Area => element.attr("href"),
+            LinkSource::Iframe | LinkSource::Frame => element.attr("src"),
+            LinkSource::Form => element.attr("action"),
+            LinkSource::Link => {
+                let rel = element.attr("rel")?.to_ascii_lowercase();
+                let followed = rel
+                    .split_whitespace()
+                    .any(|rel| matches!(rel, "alternate" | "canonical" | "next" | "prev"));
+                if followed { element.attr("href") } else { None }
+            }
+            LinkSource::MetaRefresh => {
+                if !element.attr("http-equiv")?.eq_ignore_ascii_case("refresh") {
+                    return None;
+                }
+                meta_refresh(element.attr("content")?).map(|(_, target)| target)
+            }
+        }
+    }
+}
+
+impl fmt::Display for LinkSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for LinkSource {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        LinkSource::ALL
+            .into_iter()
+            .find(|source| source.name().eq_ignore_ascii_case(value.trim()))
+            .ok_or_else(|| {
+                let names: Vec<&str> = LinkSource::ALL.iter().map(|source| source.name()).collect();
+                format!("unknown link source {:?} (expected one of {})", value, names.join(", "))
+            })
+    }
+}
+
+/// Resolves `url` against `source_url` and strips the fragment. The path is
+/// left as it is: `/about` and `/about/` are told apart by the crawler when
+/// the site redirects between them. Links written as IRIs come out as plain
+/// URLs: hosts in their ASCII (punycode) form, and non-ASCII characters of
+/// the path and query percent-encoded as UTF-8.
+pub fn normalize_url(url: &str, source_url: &str) -> Result<String, ParseError> {
+    // Convert relative URL to absolute URL
+    let base_url = Url::parse(source_url)?;
+    let mut absolute_url = base_url.join(url)?;
+
+    absolute_url.set_fragment(None);
+    Ok(absolute_url.to_string())
+}
+
+/// The form of `url` used to tell whether two URLs are the same page. The
+/// scheme and host are lowercased and default ports dropped (by parsing),
+/// duplicate slashes in the path are collapsed, percent-encoded unreserved
+/// characters are decoded (other escapes get uppercase hex) and an empty
+/// query is removed. Unparseable URLs are returned unchanged.
+pub fn canonicalize_url(url: &str) -> String {
+    let Ok(mut parsed) = Url::parse(url) else {
+        return url.to_string();
+    };
+
+    if !parsed.cannot_be_a_base() {
+        let mut path = String::with_capacity(parsed.path().len());
+        for c in parsed.path().chars() {
+            if !(c == '/' && path.ends_with('/')) {
+                path.push(c);
+            }
+        }
+        parsed.set_path(&normalize_percent_encoding(&path));
+    }
+
+    match parsed.query() {
+        Some("") => parsed.set_query(None),
+        Some(query) => {
+            let query = normalize_percent_encoding(query);
+            parsed.set_query(Some(&query));
+        }
+        None => {}
+    }
+
+    parsed.to_string()
+}
+
+/// Query parameters that only track where a visitor came from, or carry a
+/// session, and never change the page. A trailing `*` matches a prefix.
+pub const TRACKING_PARAMS: &[&str] = &[
+    "utm_*",
+    "fbclid",
+    "gclid",
+    "dclid",
+    "gbraid",
+    "wbraid",
+    "msclkid",
+    "yclid",
+    "igshid",
+    "mc_cid",
+    "mc_eid",
+    "_ga",
+    "_gl",
+    "_hsenc",
+    "_hsmi",
+    "phpsessid",
+    "jsessionid",
+    "sessionid",
+];
+
+/// Removes the query parameters named in `strip` from `url` (compared
+/// case-insensitively; `name*` matches a prefix) and, with `sort`, orders the
+/// rest by name. Values and their encoding are kept as they are.
+pub fn clean_query(url: &str, strip: &[String], sort: bool) -> String {
+    let Ok(mut parsed) = Url::parse(url) else {
+        return url.to_string();
+    };
+    let Some(query) = parsed.query() else {
+        return url.to_string();
+    };
+
+    let stripped = |name: &str| {
+        strip.iter().any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => name.to_ascii_lowercase().starts_with(&prefix.to_ascii_lowercase()),
+            None => name.eq_ignore_ascii_case(pattern),
+        })
+    };
+    let mut pairs: Vec<(String, &str)> = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let name = pair.split('=').next().unwrap_or_default();
+            let name = form_urlencoded::parse(name.as_bytes())
+                .next()
+                .map(|(name, _)| name.into_owned())
+                .unwrap_or_default();
+            (name, pair)
+        })
+        .filter(|(name, _)| !stripped(name))
+        .collect();
+    if sort {
+        pairs.sort();
+    }
+
+    if pairs.is_empty() {
+        parsed.set_query(None);
+    } else {
+        let query: Vec<&str> = pairs.iter().map(|(_, pair)| *pair).collect();
+        parsed.set_query(Some(&query.join("&")));
+    }
+    parsed.to_string()
+}
+
+/// Decodes `%XX` escapes of unreserved characters (letters, digits and
+/// `-._~`) and uppercases the hex digits of the rest.
+fn normalize_percent_encoding(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut normalized = String::with_capacity(value.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        // from_str_radix alone would accept a sign, as in `%+1`
+        let escaped = bytes
+            .get(index + 1..index + 3)
+            .filter(|hex| bytes[index] == b'%' && hex.iter().all(u8::is_ascii_hexdigit));
+        match escaped.and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()) {
+            Some(byte) if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) => {
+                normalized.push(byte as char);
+                index += 3;
+            }
+            Some(byte) => {
+                normalized.push_str(&format!("%{:02X}", byte));
+                index += 3;
+            }
+            None => {
+                let c = value[index..].chars().next().unwrap();
+                normalized.push(c);
+                index += c.len_utf8();
+            }
+        }
+    }
+    normalized
+}
+
+/// `url` with a trailing slash added to or removed from its path, keeping
+/// the query. `None` for the root path.
+pub(crate) fn slash_variant(url: &str) -> Option<String> {
+    let mut parsed = Url::parse(url).ok()?;
+    let path = parsed.path().to_string();
+    if path.is_empty() || path == "/" {
+        return None;
+    }
+    match path.strip_suffix('/') {
+        Some(stripped) => parsed.set_path(stripped),
+        None => parsed.set_path(&format!("{}/", path)),
+    }
+    Some(parsed.to_string())
+}
+
+/// Returns every crawlable link found in anchor tags of `html_content`,
+/// normalized against `source_url`. No scope filtering is applied.
+pub fn extract_links(html_content: &str, source_url: &str) -> HashSet<String> {
+    extract_links_from(html_content, source_url, &[LinkSource::Anchor], false)
+}
+
+/// Like [`extract_links`], but collects links from each of `sources`. With
+/// `skip_nofollow`, links marked `rel="nofollow"` are left out.
+pub fn extract_links_from(html_content: &str, source_url: &str, sources: &[LinkSource], skip_nofollow: bool) -> HashSet<String> {
+    let mut links = HashSet::new();
+
+    // Parse HTML
+    let document = Html::parse_document(html_content);
+    let base_url = document_base(&document, source_url);
+
+    for &source in sources {
+        let selector = Selector::parse(source.selector()).unwrap();
+
+        for element in document.select(&selector) {
+            if skip_nofollow && has_rel(element, "nofollow") {
+                continue;
+            }
+            if let Some(href) = source.url(element) {
+                let href = href.trim();
+                // Skip unwanted link types
+                if href.is_empty() ||
+                   href.starts_with("javascript:") ||
+                   href.starts_with("mailto:") ||
+                   href.starts_with("tel:") ||
+                   href.starts_with('#') {
+                    continue;
+                }
+
+                // Normalize the URL
+                if let Ok(absolute_url) = normalize_url(href, &base_url) {
+                    links.insert(absolute_url);
+                }
+            }
+        }
+    }
+
+    links
+}
+
+fn has_rel(element: ElementRef, value: &str) -> bool {
+    element
+        .value()
+        .attr("rel")
+        .is_some_and(|rel| rel.split_whitespace().any(|rel| rel.eq_ignore_ascii_case(value)))
+}
+
+/// Descriptive fields of an HTML page, with whitespace collapsed. Missing
+/// or empty values are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageMetadata {
+    /// The `<title>`.
+    pub title: Option<String>,
+    /// The `content` of `<meta name="description">`.
+    pub description: Option<String>,
+    /// The text of the first `<h1>`.
+    pub h1: Option<String>,
+    /// The `content` of every `<meta name="robots">` tag, joined with
+    /// commas; empty when there is none.
+    pub robots: String,
+}
+
+/// Whether the page with `metadata` and `html_content` is a generated
+/// directory index: Apache, nginx and lighttpd's "Index of /...", Python's
+/// "Directory listing for /...", or IIS's "<host> - /..." listing.
+pub fn is_directory_listing(metadata: &PageMetadata, html_content: &str) -> bool {
+    let headings = [metadata.title.as_deref(), metadata.h1.as_deref()];
+    if headings.into_iter().flatten().any(|heading| heading.starts_with("Index of /") || heading.starts_with("Directory listing for /")) {
+        return true;
+    }
+    let iis_title = Regex::new(r"^\S+ - /").unwrap();
+    metadata.title.as_deref().is_some_and(|title| iis_title.is_match(title))
+        && (html_content.contains("[To Parent Directory]") || html_content.to_ascii_lowercase().contains("<pre>"))
+}
+
+/// Extracts the title, description, first heading and robots directives of
+/// `html_content`.
+pub fn extract_metadata(html_content: &str) -> PageMetadata {
+    let document = Html::parse_document(html_content);
+    let first_text = |selector: &str| {
+        let selector = Selector::parse(selector).unwrap();
+        document.select(&selector).next().and_then(|element| collapse_whitespace(&element.text().collect::<String>()))
+    };
+    let title = first_text("title");
+    let h1 = first_text("h1");
+
+    let selector = Selector::parse("meta[name][content]").unwrap();
+    let meta = |name: &'static str| {
+        document
+            .select(&selector)
+            .filter(move |meta| meta.value().attr("name").is_some_and(|value| value.trim().eq_ignore_ascii_case(name)))
+            .filter_map(|meta| meta.value().attr("content"))
+    };
+    let description = meta("description").find_map(collapse_whitespace);
+    let robots: Vec<&str> = meta("robots").collect();
+
+    PageMetadata { title, description, h1, robots: robots.join(",") }
+}
+
+/// Returns the RSS and Atom feeds `html_content` announces with
+/// `<link rel="alternate">`, normalized against `source_url`.
+pub fn extract_feeds(html_content: &str, source_url: &str) -> BTreeSet<String> {
+    let document = Html::parse_document(html_content);
+    let base_url = document_base(&document, source_url);
+    let selector = Selector::parse("link[rel][type][href]").unwrap();
+    document
+        .select(&selector)
+        .filter(|element| has_rel(*element, "alternate"))
+        .filter(|element| element.value().attr("type").is_some_and(is_feed_type))
+        .filter_map(|element| normalize_url(element.value().attr("href")?.trim(), &base_url).ok())
+        .filter(|url| url.starts_with("http://") || url.starts_with("https://"))
+        .collect()
+}
+
+/// Returns the language alternates `html_content` declares with
+/// `<link rel="alternate" hreflang>`, by lowercase language tag (like
+/// `en-gb` or `x-default`), normalized against `source_url`. The first
+/// alternate of a language wins.
+pub fn extract_hreflang(html_content: &str, source_url: &str) -> BTreeMap<String, String> {
+    let document = Html::parse_document(html_content);
+    let base_url = document_base(&document, source_url);
+    let selector = Selector::parse("link[rel][hreflang][href]").unwrap();
+    let mut alternates = BTreeMap::new();
+    for element in document.select(&selector).filter(|element| has_rel(*element, "alternate")) {
+        let language = element.value().attr("hreflang").unwrap_or_default().trim().to_ascii_lowercase();
+        let Some(url) = normalize_url(element.value().attr("href").unwrap_or_default().trim(), &base_url)
+            .ok()
+            .filter(|url| url.starts_with("http://") || url.starts_with("https://"))
+        else {
+            continue;
+        };
+        if !language.is_empty() {
+            alternates.entry(language).or_insert(url);
+        }
+    }
+    alternates
+}
+
+/// Returns the pages before and after `html_content` in a paginated
+/// listing: the `<link>`, `<a>` and `<area>` elements with `rel` next,
+/// prev or previous, normalized against `source_url`. Links that are also
+/// `rel="nofollow"` are left out when `skip_nofollow` is set.
+pub fn extract_pagination(html_content: &str, source_url: &str, skip_nofollow: bool) -> BTreeSet<String> {
+    let document = Html::parse_document(html_content);
+    let base_url = document_base(&document, source_url);
+    let selector = Selector::parse("link[rel][href], a[rel][href], area[rel][href]").unwrap();
+    document
+        .select(&selector)
+        .filter(|element| ["next", "prev", "previous"].iter().any(|rel| has_rel(*element, rel)))
+        .filter(|element| !(skip_nofollow && has_rel(*element, "nofollow")))
+        .filter_map(|element| normalize_url(element.value().attr("href")?.trim(), &base_url).ok())
+        .filter(|url| url.starts_with("http://") || url.starts_with("https://"))
+        .collect()
+}
+
+/// Returns the first `<link rel="canonical">` URL of `html_content`,
+/// resolved like a link against `source_url`. Non-http(s) values are
+/// ignored.
+pub fn extract_canonical(html_content: &str, source_url: &str) -> Option<String> {
+    let document = Html::parse_document(html_content);
+    let base_url = document_base(&document, source_url);
+    let selector = Selector::parse("link[rel][href]").unwrap();
+    let href = document.select(&selector).find(|element| has_rel(*element, "canonical"))?.value().attr("href")?;
+    normalize_url(href.trim(), &base_url)
+        .ok()
+        .filter(|url| url.starts_with("http://") || url.starts_with("https://"))
+}
+
+/// `text` with runs of whitespace replaced by single spaces and trimmed;
+/// `None` if nothing is left.
+fn collapse_whitespace(text: &str) -> Option<String> {
+    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!collapsed.is_empty()).then_some(collapsed)
+}
+
+/// The URL relative links in `document` resolve against: the first
+/// `<base href>` (itself resolved against the page URL), or the page URL
+/// when there is none or it is invalid.
+pub(crate) fn document_base(document: &Html, source_url: &str) -> String {
+    let selector = Selector::parse("base[href]").unwrap();
+    document
+        .select(&selector)
+        .next()
+        .and_then(|base| base.value().attr("href"))
+        .and_then(|href| Url::parse(source_url).ok()?.join(href.trim()).ok())
+        .filter(|base| !base.cannot_be_a_base())
+        .map(|base| base.to_string())
+        .unwrap_or_else(|| source_url.to_string())
+}
+
+/// Returns the forms of `html_content`, with actions normalized against
+/// `source_url`. A form without an action submits to `source_url` itself.
+/// Hidden fields named like `csrf_token`, `_token`, `authenticity_token`
+/// or `__RequestVerificationToken` count as CSRF tokens.
+pub fn extract_forms(html_content: &str, source_url: &str) -> Vec<Form> {
+    let document = Html::parse_document(html_content);
+    let base_url = document_base(&document, source_url);
+    let form_selector = Selector::parse("form").unwrap();
+    let field_selector = Selector::parse("input[name], select[name], textarea[name]").unwrap();
+    let csrf_name = Regex::new(r"(?i)csrf|xsrf|^_?token$|authenticity_token|requestverificationtoken|nonce").unwrap();
+
+    let mut forms = Vec::new();
+    for form in document.select(&form_selector) {
+        let action = match form.value().attr("action").map(str::trim).filter(|action| !action.is_empty()) {
+            Some(action) => match normalize_url(action, &base_url) {
+                Ok(action) => action,
+                Err(_) => continue,
+            },
+            None => source_url.to_string(),
+        };
+        let method = match form.value().attr("method").map(str::trim) {
+            Some(method) if method.eq_ignore_ascii_case("post") => "POST",
+            _ => "GET",
+        };
+
+        let mut fields = Vec::new();
+        let mut csrf_field = None;
+        for field in form.select(&field_selector) {
+            let element = field.value();
+            let name = element.attr("name").unwrap_or_default().to_string();
+            let kind = match element.name() {
+                "input" => element.attr("type").unwrap_or("text").trim().to_ascii_lowercase(),
+                other => other.to_string(),
+            };
+            if kind == "hidden" && csrf_field.is_none() && csrf_name.is_match(&name) {
+                csrf_field = Some(name.clone());
+            }
+            fields.push(FormField { name, kind });
+        }
+
+        let signature = std::iter::once(format!("{} {}", method, action))
+            .chain(fields.iter().map(|field| format!("{}={}", field.name, field.kind)))
+            .collect::<Vec<_>>()
+            .join("\n");
+        forms.push(Form {
+            has_password: fields.iter().any(|field| field.kind == "password"),
+            has_file_upload: fields.iter().any(|field| field.kind == "file"),
+            action,
+            method: method.to_string(),
+            fields,
+            csrf_field,
+            hash: format!("{:016x}", fnv1a(signature.into_bytes())),
+            also_on: Vec::new(),
+        });
+    }
+    forms
+}
+
+/// Returns the scripts, stylesheets and images referenced by `html_content`,
+/// normalized against `source_url`. Only http(s) URLs are kept, so inline
+/// `data:` images are left out.
+pub fn extract_assets(html_content: &str, source_url: &str) -> BTreeSet<String> {
+    let document = Html::parse_document(html_content);
+    let base_url = document_base(&document, source_url);
+    let mut references: Vec<&str> = Vec::new();
+
+    let select = |selector: &str| Selector::parse(selector).unwrap();
+    for element in document.select(&select("script[src]")) {
+        references.extend(element.value().attr("src"));
+    }
+    for element in document.select(&select("link[rel][href]")) {
+        let element = element.value();
+        if element.attr("rel").is_some_and(|rel| rel.split_whitespace().any(|rel| rel.eq_ignore_ascii_case("stylesheet"))) {
+            references.extend(element.attr("href"));
+        }
+    }
+    for element in document.select(&select("img[src]")) {
+        references.extend(element.value().attr("src"));
+    }
+    for element in document.select(&select("img[srcset], source[srcset]")) {
+        references.extend(element.value().attr("srcset").map(srcset_urls).unwrap_or_default());
+    }
+
+    references
+        .into_iter()
+        .map(str::trim)
+        .filter(|reference| !reference.is_empty())
+        .filter_map(|reference| normalize_url(reference, &base_url).ok())
+        .filter(|url| url.starts_with("http://") || url.starts_with("https://"))
+        .collect()
+}
+
+/// Returns the plain-http subresources of an HTTPS page as (URL, element
+/// name) pairs, sorted. Protocol-relative URLs resolve to HTTPS and are
+/// fine. Pages not served from an `https:` `source_url` have none.
+pub fn extract_mixed_content(html_content: &str, source_url: &str) -> BTreeSet<(String, &'static str)> {
+    if !source_url.starts_with("https://") {
+        return BTreeSet::new();
+    }
+    let document = Html::parse_document(html_content);
+    let base_url = document_base(&document, source_url);
+    let mut references: Vec<(&str, &'static str)> = Vec::new();
+
+    let select = |selector: &str| Selector::parse(selector).unwrap();
+    for (selector, name) in [("script[src]", "script"), ("img[src]", "img"), ("iframe[src]", "iframe"), ("source[src]", "source"), ("video[src]", "video"), ("audio[src]", "audio")] {
+        for element in document.select(&select(selector)) {
+            references.extend(element.value().attr("src").map(|src| (src, name)));
+        }
+    }
+    for element in document.select(&select("img[srcset], source[srcset]")) {
+        let name = if element.value().name() == "img" { "img" } else { "source" };
+        for url in element.value().attr("srcset").map(srcset_urls).unwrap_or_default() {
+            references.push((url, name));
+        }
+    }
+    // Links the browser loads with the page, not the ones pointing elsewhere
+    let loaded = ["stylesheet", "icon", "apple-touch-icon", "manifest", "preload", "modulepreload", "prefetch"];
+    for element in document.select(&select("link[rel][href]")) {
+        if loaded.iter().any(|rel| has_rel(element, rel)) {
+            references.extend(element.value().attr("href").map(|href| (href, "link")));
+        }
+    }
+
+    references
+        .into_iter()
+        .filter_map(|(reference, name)| Some((normalize_url(reference.trim(), &base_url).ok()?, name)))
+        .filter(|(url, _)| url.starts_with("http://"))
+        .collect()
+}
+
+/// Returns the lowercase email addresses in the text and `mailto:` links of
+/// `html_content`. Addresses written as `name [at] example [dot] com`, with
+/// the words in brackets, parentheses or braces, are read too.
+pub fn extract_emails(html_content: &str) -> BTreeSet<String> {
+    let document = Html::parse_document(html_content);
+    let mut text: String = document.root_element().text().collect::<Vec<_>>().join(" ");
+    let selector = Selector::parse("a[href]").unwrap();
+    for element in document.select(&selector) {
+        let href = element.value().attr("href").unwrap_or_default().trim();
+        if href.get(..7).is_some_and(|scheme| scheme.eq_ignore_ascii_case("mailto:")) {
+            // The query holds the subject and body, not addresses
+            let addresses = href[7..].split('?').next().unwrap_or_default();
+            text.push(' ');
+            text.push_str(&addresses.replace("%40", "@").replace(',', " "));
+        }
+    }
+
+    let at = Regex::new(r"(?i)\s*[\[({]\s*(?:at|@)\s*[\])}]\s*").unwrap();
+    let dot = Regex::new(r"(?i)\s*[\[({]\s*(?:dot|\.)\s*[\])}]\s*").unwrap();
+    let text = at.replace_all(&text, "@");
+    let text = dot.replace_all(&text, ".");
+    let email = Regex::new(r"(?i)\b[a-z0-9._%+-]+@[a-z0-9-]+(?:\.[a-z0-9-]+)*\.[a-z]{2,}\b").unwrap();
+    // Retina image names like logo@2x.png look like addresses
+    let static_file = Regex::new(r"(?i)\.(?:png|jpe?g|gif|svg|webp|ico|css|js)$").unwrap();
+    email
+        .find_iter(&text)
+        .map(|address| address.as_str().to_ascii_lowercase())
+        .filter(|address| !static_file.is_match(address))
+        .collect()
+}
+
+/// URLs found outside of links by [`extract_hidden_urls`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HiddenUrls {
+    /// From HTML comments.
+    pub comments: BTreeSet<String>,
+    /// From the text of `<script>` elements without `src`.
+    pub inline_scripts: BTreeSet<String>,
+}
+
+/// Returns the URLs in the comments and inline scripts of `html_content`,
+/// normalized against `source_url`: quoted strings that look like URLs or
+/// paths, as found by [`extract_js_urls`], and in comments also bare
+/// absolute URLs.
+pub fn extract_hidden_urls(html_content: &str, source_url: &str) -> HiddenUrls {
+    let document = Html::parse_document(html_content);
+    let base_url = document_base(&document, source_url);
+    let bare_url = Regex::new(r#"https?://[^\s"'`<>]+"#).unwrap();
+    let mut hidden = HiddenUrls::default();
+
+    for node in document.tree.nodes() {
+        if let Some(comment) = node.value().as_comment() {
+            hidden.comments.extend(extract_js_urls(comment, &base_url));
+            hidden.comments.extend(
+                bare_url
+                    .find_iter(comment)
+                    .filter_map(|url| normalize_url(url.as_str().trim_end_matches(['.', ',', ')', ';']), &base_url).ok()),
+            );
+        }
+    }
+    let selector = Selector::parse("script:not([src])").unwrap();
+    for script in document.select(&selector) {
+        hidden.inline_scripts.extend(extract_js_urls(&script.text().collect::<String>(), &base_url));
+    }
+    hidden
+}
+
+/// Returns the URL-like string literals of the JavaScript `script`, resolved
+/// against `script_url`: absolute and protocol-relative URLs, root-relative
+/// paths such as `'/api/v1/users'`, and relative paths that look like API
+/// endpoints, such as `"api/users"` or `` `v2/items` ``. Literals pointing
+/// at images, stylesheets or fonts are left out.
+pub fn extract_js_urls(script: &str, script_url: &str) -> BTreeSet<String> {
+    // Quoted strings without whitespace or template placeholders
+    let literal = Regex::new(r#"["'`]([^"'`\s\\<>{}]{2,1024})["'`]"#).unwrap();
+    let absolute = Regex::new(r"^(?:https?:)?//[\w.-]+(?::\d+)?(?:[/?#][^\s]*)?$").unwrap();
+    let root_relative = Regex::new(r"^/[\w.~%-]+(?:/[\w.~%-]*)*(?:\?[\w.~%=&+-]*)?$").unwrap();
+    let api_path = Regex::new(r"^(?:\.\.?/)?(?:[\w-]+/)*(?:api|graphql|rest|rpc|ajax|v\d+)(?:/[\w.~%-]+)+/?(?:\?[\w.~%=&+-]*)?$").unwrap();
+    let static_file = Regex::new(r"(?i)\.(?:png|jpe?g|gif|svg|webp|ico|css|woff2?|ttf|eot|map)(?:\?|$)").unwrap();
+
+    literal
+        .captures_iter(script)
+        .map(|captures| captures.get(1).unwrap().as_str())
+        .filter(|value| absolute.is_match(value) || root_relative.is_match(value) || api_path.is_match(value))
+        .filter(|value| !static_file.is_match(value))
+        .filter_map(|value| normalize_url(value, script_url).ok())
+        .filter(|url| url.starts_with("http://") || url.starts_with("https://"))
+        .collect()
+}
+
+/// Splits a `srcset` value such as `a.jpg 1x, b.jpg 2x` into its image URLs.
+/// Follows the HTML parsing rules: URLs may contain commas, a trailing comma
+/// ends a candidate without descriptors, and descriptors run to the next
+/// comma outside parentheses.
+fn srcset_urls(srcset: &str) -> Vec<&str> {
+    let mut urls = Vec::new();
+    let mut rest = srcset;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == ',');
+        if rest.is_empty() {
+            return urls;
+        }
+
+        let end = rest.find(|c: char| c.is_ascii_whitespace()).unwrap_or(rest.len());
+        let (url, after) = rest.split_at(end);
+        if url.ends_with(',') {
+            urls.push(url.trim_end_matches(','));
+            rest = after;
+            continue;
+        }
+        urls.push(url);
+
+        let mut depth = 0usize;
+        let mut consumed = after.len();
+        for (index, c) in after.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth = depth.saturating_sub(1),
+                ',' if depth == 0 => {
+                    consumed = index + 1;
+                    break;
+                }
+                _ => {}
+            }
+        }
+        rest = &after[consumed..];
+    }
+}
+
+/// Parses a meta refresh `content` value the way browsers do, returning
+/// the delay in whole seconds and the target. Accepts `5; url=/next`,
+/// `0;URL = '/next'`, `0, /next`, `1.5 url=/next` and the like; a value
+/// without a URL only reloads the page.
+fn meta_refresh(content: &str) -> Option<(u64, &str)> {
+    let content = content.trim_start();
+    let digits = content.find(|c: char| !c.is_ascii_digit()).unwrap_or(content.len());
+    let rest = &content[digits..];
+    if digits == 0 && !rest.starts_with('.') {
+        return None;
+    }
+    let delay = content[..digits].parse().unwrap_or(if digits == 0 { 0 } else { u64::MAX });
+    let rest = rest.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+    if !rest.is_empty() && !rest.starts_with([';', ',']) && !rest.starts_with(|c: char| c.is_ascii_whitespace()) {
+        return None;
+    }
+    let rest = rest.trim_start();
+    let rest = rest.strip_prefix([';', ',']).unwrap_or(rest).trim_start();
+    // `url=` is optional, and without the `=` the "url" is part of the target
+    let target = match rest.get(..3) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("url") => {
+            rest[3..].trim_start().strip_prefix('=').map_or(rest, str::trim_start)
+        }
+        _ => rest,
+    };
+    let target = match target.chars().next() {
+        Some(quote @ ('"' | '\'')) => target[1..].split(quote).next().unwrap_or_default(),
+        _ => target,
+    };
+    let target = target.trim();
+    (!target.is_empty()).then_some((delay, target))
+}
+
+/// Returns where `html_content` sends browsers, resolved against
+/// `source_url`: the target of a meta refresh, or else the first string
+/// literal that an inline script assigns to `location`, `location.href`
+/// (optionally through `window`, `document`, `self` or `top`) or passes to
+/// `location.replace()` or `location.assign()`. Only whole literals count,
+/// so `location = '/lang/' + code` is not a redirect, and targets that are
+/// the page itself or not web URLs are ignored.
+pub fn extract_client_redirect(html_content: &str, source_url: &str) -> Option<ClientRedirect> {
+    let document = Html::parse_document(html_content);
+    let base_url = document_base(&document, source_url);
+    let resolve = |target: &str| {
+        normalize_url(target.trim(), &base_url)
+            .ok()
+            .filter(|url| (url.starts_with("http://") || url.starts_with("https://")) && *url != source_url)
+    };
+
+    let meta = Selector::parse(LinkSource::MetaRefresh.selector()).unwrap();
+    let refresh = document
+        .select(&meta)
+        .filter(|element| element.value().attr("http-equiv").is_some_and(|value| value.eq_ignore_ascii_case("refresh")))
+        .find_map(|element| meta_refresh(element.value().attr("content")?));
+    if let Some((delay, target)) = refresh {
+        return resolve(target).map(|target| ClientRedirect { kind: ClientRedirectKind::MetaRefresh, target, delay: Some(delay) });
+    }
+
+    let assignment = Regex::new(
+        r#"(?:^|[^\w$.])(?:(?:window|document|self|top)\.)?location(?:(?:\.href)?\s*=\s*|\.(?:replace|assign)\(\s*)(?:"([^"\\]*)"|'([^'\\]*)')[ \t]*(?:[;)}\r\n]|$)"#,
+    )
+    .unwrap();
+    let scripts = Selector::parse("script:not([src])").unwrap();
+    document
+        .select(&scripts)
+        .flat_map(|script| {
+            let text = script.text().collect::<String>();
+            assignment
+                .captures_iter(&text)
+                .filter_map(|captures| captures.get(1).or_else(|| captures.get(2)))
+                .map(|target| target.as_str().to_string())
+                .collect::<Vec<_>>()
+        })
+        .find_map(|target| resolve(&target))
+        .map(|target| ClientRedirect { kind: ClientRedirectKind::JsRedirect, target, delay: None })
+}
+
+/// Returns the registrable domain (eTLD+1) of a host according to the
+/// public suffix list, e.g. `example.co.uk` for `www.example.co.uk`.
+/// IP addresses, and names without a registrable part, are returned as-is.
+pub fn registrable_domain(host: &Host<&str>) -> String {
+    match host {
+        Host::Domain(domain) => {
+            let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+            match psl::domain_str(&domain) {
+                Some(registrable) => registrable.to_string(),
+                None => domain,
+            }
+        }
+        Host::Ipv4(_) | Host::Ipv6(_) => host.to_string(),
+    }
+}
+
+/// The Unicode form of a host name, for reports: `xn--mnchen-3ya.example`
+/// becomes `münchen.example`. Hosts that aren't valid punycode, and IP
+/// addresses, are returned unchanged.
+pub fn unicode_host(host: &str) -> String {
+    match idna::domain_to_unicode(host) {
+        (unicode, Ok(())) => unicode,
+        (_, Err(_)) => host.to_string(),
+    }
+}
+
+/// Returns the subdomain label(s) of `url` relative to `base_domain`, if any.
+pub fn extract_subdomain(url: &str, base_domain: &str) -> Option<String> {
+    if let Ok(parsed_url) = Url::parse(url) {
+        if let Some(domain) = parsed_url.host_str() {
+            // If the domain is the same as the base domain, no subdomain
+            if domain == base_domain {
+                return None;
+            }
+
+            // Extract the subdomain part
+            if domain.ends_with(&format!(".{}", base_domain)) {
+                return Some(domain[..domain.len() - base_domain.len() - 1].to_string());
+            }
+        }
+    }
+
+    None
+}
+
+/// Returns the directory portion of the URL's path, e.g. `/blog/` for
+/// `/blog/post.html`. The root path yields `None`.
+pub fn extract_directory(url: &str) -> Option<String> {
+    if let Ok(parsed_url) = Url::parse(url) {
+        let path = parsed_url.path();
+
+        // No path or just root
+        if path.is_empty() || path == "/" {
+            return None;
+        }
+
+        // Directory-like path (ends with /)
+        if path.ends_with('/') {
+            return Some(path.to_string());
+        }
+
+        // File-like path, get the directory part
+        if let Some(last_slash_pos) = path.rfind('/') {
+            if last_slash_pos > 0 {  // Not just starting with /
+                return Some(path[..=last_slash_pos].to_string());
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(links: HashSet<String>) -> Vec<String> {
+        let mut links: Vec<String> = links.into_iter().collect();
+        links.sort();
+        links
+    }
+
+    const PAGE: &str = "http://site.test/articles/2024/post.html";
+
+    #[test]
+    fn canonical_urls_have_a_lowercase_scheme_and_host() {
+        assert_eq!(canonicalize_url("HTTP://Example.COM/"), "http://example.com/");
+        assert_eq!(canonicalize_url("https://WWW.Example.com/Path"), "https://www.example.com/Path");
+    }
+
+    #[test]
+    fn canonical_urls_drop_default_ports() {
+        assert_eq!(canonicalize_url("http://example.com:80/a"), "http://example.com/a");
+        assert_eq!(canonicalize_url("https://example.com:443/a"), "https://example.com/a");
+        assert_eq!(canonicalize_url("http://example.com:443/a"), "http://example.com:443/a");
+        assert_eq!(canonicalize_url("https://example.com:8443/a"), "https://example.com:8443/a");
+    }
+
+    #[test]
+    fn canonical_urls_collapse_duplicate_slashes() {
+        assert_eq!(canonicalize_url("http://example.com//a///b/"), "http://example.com/a/b/");
+        assert_eq!(canonicalize_url("http://example.com/a?next=//b"), "http://example.com/a?next=//b");
+    }
+
+    #[test]
+    fn canonical_urls_decode_unreserved_escapes_only() {
+        assert_eq!(canonicalize_url("http://example.com/%7euser/%41%2d%5F%2E"), "http://example.com/~user/A-_.");
+        assert_eq!(canonicalize_url("http://example.com/a%2fb?q=%3d%e2%82%ac"), "http://example.com/a%2Fb?q=%3D%E2%82%AC");
+        assert_eq!(canonicalize_url("http://example.com/?q=%7E"), "http://example.com/?q=~");
+    }
+
+    #[test]
+    fn canonical_urls_drop_an_empty_query() {
+        assert_eq!(canonicalize_url("http://example.com/a?"), "http://example.com/a");
+        assert_eq!(canonicalize_url("http://example.com/a?b"), "http://example.com/a?b");
+    }
+
+    #[test]
+    fn canonical_urls_combine_every_rule() {
+        assert_eq!(canonicalize_url("HTTP://Example.com:80/a//b"), canonicalize_url("http://example.com/a/b"));
+        assert_eq!(canonicalize_url("HTTP://Example.com:80/a//b"), "http://example.com/a/b");
+    }
+
+    #[test]
+    fn stray_percent_signs_are_left_alone() {
+        assert_eq!(normalize_percent_encoding("100%"), "100%");
+        assert_eq!(normalize_percent_encoding("%zz%4"), "%zz%4");
+        assert_eq!(normalize_percent_encoding("%+1"), "%+1");
+        assert_eq!(normalize_percent_encoding("%-1%e9"), "%-1%E9");
+        assert_eq!(canonicalize_url("http://example.com/100%25/%+1"), "http://example.com/100%25/%+1");
+    }
+
+    #[test]
+    fn unparseable_urls_are_returned_unchanged() {
+        assert_eq!(canonicalize_url("not a url"), "not a url");
+    }
+
+    #[test]
+    fn normalizing_keeps_the_trailing_slash_and_drops_the_fragment() {
+        let page = "http://site.test/docs/";
+        assert_eq!(normalize_url("/about", page).unwrap(), "http://site.test/about");
+        assert_eq!(normalize_url("/about/", page).unwrap(), "http://site.test/about/");
+        assert_eq!(normalize_url("/about#team", page).unwrap(), "http://site.test/about");
+        assert_eq!(normalize_url("/about/#team", page).unwrap(), "http://site.test/about/");
+        assert_eq!(normalize_url("/search/?q=rust#results", page).unwrap(), "http://site.test/search/?q=rust");
+        assert_eq!(normalize_url("/api/v1/users?page=2", page).unwrap(), "http://site.test/api/v1/users?page=2");
+        assert_eq!(normalize_url("#top", page).unwrap(), "http://site.test/docs/");
+    }
+
+    #[test]
+    fn slash_variants_toggle_the_trailing_slash() {
+        assert_eq!(slash_variant("http://site.test/about").as_deref(), Some("http://site.test/about/"));
+        assert_eq!(slash_variant("http://site.test/about/").as_deref(), Some("http://site.test/about"));
+        assert_eq!(slash_variant("http://site.test/").as_deref(), None);
+        assert_eq!(slash_variant("http://site.test").as_deref(), None);
+    }
+
+    #[test]
+    fn slash_variants_keep_the_query() {
+        assert_eq!(slash_variant("http://site.test/search?q=rust&page=2").as_deref(), Some("http://site.test/search/?q=rust&page=2"));
+        assert_eq!(slash_variant("http://site.test/search/?q=rust").as_deref(), Some("http://site.test/search?q=rust"));
+        assert_eq!(slash_variant("http://site.test/?q=rust").as_deref(), None);
+    }
+
+    #[test]
+    fn slash_variants_of_extensionless_rest_paths() {
+        assert_eq!(slash_variant("http://site.test/api/v1/users").as_deref(), Some("http://site.test/api/v1/users/"));
+        assert_eq!(slash_variant("http://site.test/api/v1/users/42/").as_deref(), Some("http://site.test/api/v1/users/42"));
+        assert_eq!(slash_variant("http://site.test/api/v1.2/users").as_deref(), Some("http://site.test/api/v1.2/users/"));
+    }
+
+    #[test]
+    fn slash_variants_of_normalized_links_never_keep_the_fragment() {
+        let link = normalize_url("/about#team", "http://site.test/").unwrap();
+        assert_eq!(slash_variant(&link).as_deref(), Some("http://site.test/about/"));
+    }
+
+    #[test]
+    fn relative_links_resolve_against_the_base_href() {
+        let html = r#"<html><head><base href="http://site.test/static/docs/"></head><body>
+            <a href="intro.html">intro</a>
+            <a href="../images/logo.png">logo</a>
+            <a href="?page=2">next</a>
+            <a href="/root.html">root</a>
+            <a href="https://other.test/x">other</a>
+        </body></html>"#;
+        assert_eq!(
+            sorted(extract_links(html, PAGE)),
+            [
+                "http://site.test/root.html",
+                "http://site.test/static/docs/?page=2",
+                "http://site.test/static/docs/intro.html",
+                "http://site.test/static/images/logo.png",
+                "https://other.test/x",
+            ]
+        );
+    }
+
+    #[test]
+    fn a_relative_base_href_resolves_against_the_page() {
+        let html = r#"<head><base href="../../docs/"></head><a href="intro.html">intro</a>"#;
+        assert_eq!(sorted(extract_links(html, PAGE)), ["http://site.test/docs/intro.html"]);
+    }
+
+    #[test]
+    fn only_the_first_base_href_counts() {
+        let html = r#"<head><base href="/first/"><base href="/second/"></head><a href="page">page</a>"#;
+        assert_eq!(sorted(extract_links(html, PAGE)), ["http://site.test/first/page"]);
+    }
+
+    #[test]
+    fn without_a_base_href_links_resolve_against_the_page() {
+        let html = r#"<a href="intro.html">intro</a><a href="../index.html">index</a>"#;
+        assert_eq!(
+            sorted(extract_links(html, PAGE)),
+            ["http://site.test/articles/2024/intro.html", "http://site.test/articles/index.html"]
+        );
+    }
+
+    #[test]
+    fn the_base_href_applies_to_other_extractors_too() {
+        let html = r#"<head><base href="http://site.test/static/docs/"><link rel="canonical" href="post.html"></head>
+            <img src="logo.png"><form action="search"><input name="q"></form>"#;
+        assert_eq!(extract_canonical(html, PAGE).as_deref(), Some("http://site.test/static/docs/post.html"));
+        assert!(extract_assets(html, PAGE).contains("http://site.test/static/docs/logo.png"));
+        assert_eq!(extract_forms(html, PAGE)[0].action, "http://site.test/static/docs/search");
+    }
+}
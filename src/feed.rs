@@ -0,0 +1,93 @@
+//! RSS and Atom feed parsing, for the entry links feeds list.
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+/// Content types feeds are served with, besides generic XML.
+const FEED_TYPES: [&str; 2] = ["application/rss+xml", "application/atom+xml"];
+
+/// Whether `content_type` is one of the RSS or Atom media types.
+pub fn is_feed_type(content_type: &str) -> bool {
+    let content_type = content_type.split(';').next().unwrap_or_default().trim();
+    FEED_TYPES.iter().any(|feed_type| content_type.eq_ignore_ascii_case(feed_type))
+}
+
+/// Returns the links of the entries of an RSS (0.9x, 1.0 or 2.0) or Atom
+/// feed, in document order and unresolved. Elements are matched by local
+/// name, so namespace prefixes don't matter. Malformed XML yields whatever
+/// links were read before the error.
+pub fn parse_feed(body: &[u8]) -> Vec<String> {
+    let mut links = Vec::new();
+    let mut reader = Reader::from_reader(body);
+    reader.config_mut().trim_text(true);
+
+    // Whether we're inside an <item> or <entry>, and inside its text <link>
+    let mut in_entry = false;
+    let mut in_link = false;
+    let mut link = String::new();
+    let mut buf = Vec::new();
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(element)) => match element.local_name().as_ref() {
+                b"item" | b"entry" => in_entry = true,
+                b"link" if in_entry => match atom_link(&element) {
+                    Some(href) => links.extend(href),
+                    None => {
+                        in_link = true;
+                        link.clear();
+                    }
+                },
+                _ => {}
+            },
+            Ok(Event::Empty(element)) if in_entry && element.local_name().as_ref() == b"link" => {
+                links.extend(atom_link(&element).flatten());
+            }
+            Ok(Event::Text(text)) if in_link => {
+                if let Ok(text) = text.unescape() {
+                    link.push_str(&text);
+                }
+            }
+            Ok(Event::CData(data)) if in_link => {
+                link.push_str(&String::from_utf8_lossy(&data));
+            }
+            Ok(Event::End(element)) => match element.local_name().as_ref() {
+                b"item" | b"entry" => in_entry = false,
+                b"link" if in_link => {
+                    in_link = false;
+                    let value = link.trim();
+                    if !value.is_empty() {
+                        links.push(value.to_string());
+                    }
+                }
+                _ => {}
+            },
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    links
+}
+
+/// For an Atom-style `<link href>`, the `href` if the link points to the
+/// entry itself (no `rel`, or `rel="alternate"`), or `Some(None)` if it
+/// points elsewhere, like `self` or `enclosure`. `None` for an RSS `<link>`
+/// whose URL is its text.
+fn atom_link(element: &BytesStart) -> Option<Option<String>> {
+    let mut href = None;
+    let mut alternate = true;
+    for attribute in element.attributes().flatten() {
+        let Ok(value) = attribute.unescape_value() else {
+            continue;
+        };
+        match attribute.key.local_name().as_ref() {
+            b"href" => href = Some(value.trim().to_string()),
+            b"rel" => alternate = value.trim().eq_ignore_ascii_case("alternate"),
+            _ => {}
+        }
+    }
+    let href = href?;
+    Some((alternate && !href.is_empty()).then_some(href))
+}
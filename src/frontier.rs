@@ -0,0 +1,188 @@
+//! The queue of URLs waiting to be crawled, ordered by the crawl strategy.
+
+use crate::crawler::FrontierEntry;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::str::FromStr;
+
+/// The order in which queued URLs are fetched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CrawlStrategy {
+    /// Breadth-first: every URL at one depth before any deeper URL.
+    #[default]
+    Bfs,
+    /// Depth-first: the deepest, most recently found URL first.
+    Dfs,
+    /// URLs matching a priority pattern first (earlier patterns win), then
+    /// breadth-first.
+    Priority,
+}
+
+impl fmt::Display for CrawlStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CrawlStrategy::Bfs => "bfs",
+            CrawlStrategy::Dfs => "dfs",
+            CrawlStrategy::Priority => "priority",
+        })
+    }
+}
+
+impl FromStr for CrawlStrategy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "bfs" => Ok(CrawlStrategy::Bfs),
+            "dfs" => Ok(CrawlStrategy::Dfs),
+            "priority" => Ok(CrawlStrategy::Priority),
+            _ => Err(format!("unknown crawl strategy {:?} (expected bfs, dfs or priority)", value)),
+        }
+    }
+}
+
+struct Queued {
+    /// Larger keys are fetched first
+    key: (Reverse<usize>, i64, i64),
+    entry: FrontierEntry,
+}
+
+impl PartialEq for Queued {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl Eq for Queued {}
+
+impl PartialOrd for Queued {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Queued {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+/// Pending URLs, popped in strategy order.
+pub(crate) struct Frontier {
+    strategy: CrawlStrategy,
+    queue: BinaryHeap<Queued>,
+    /// Insertion counter, to keep FIFO (or LIFO) order within a depth
+    pushed: i64,
+}
+
+impl Frontier {
+    pub fn new(strategy: CrawlStrategy) -> Self {
+        Frontier { strategy, queue: BinaryHeap::new(), pushed: 0 }
+    }
+
+    /// Queues `entry`. `rank` is the index of the first priority pattern the
+    /// URL matches, if any; it only matters for [`CrawlStrategy::Priority`].
+    pub fn push(&mut self, entry: FrontierEntry, rank: Option<usize>) {
+        self.pushed += 1;
+        let depth = entry.depth as i64;
+        let key = match self.strategy {
+            CrawlStrategy::Bfs => (Reverse(0), -depth, -self.pushed),
+            CrawlStrategy::Dfs => (Reverse(0), depth, self.pushed),
+            CrawlStrategy::Priority => (Reverse(rank.unwrap_or(usize::MAX)), -depth, -self.pushed),
+        };
+        self.queue.push(Queued { key, entry });
+    }
+
+    pub fn pop(&mut self) -> Option<FrontierEntry> {
+        self.queue.pop().map(|queued| queued.entry)
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// The queued entries in the order they would be fetched.
+    pub fn entries(&self) -> Vec<FrontierEntry> {
+        let mut queued: Vec<&Queued> = self.queue.iter().collect();
+        queued.sort_by(|a, b| b.cmp(a));
+        queued.into_iter().map(|queued| queued.entry.clone()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::results::UrlSource;
+
+    fn entry(url: &str, depth: usize) -> FrontierEntry {
+        FrontierEntry { url: url.to_string(), depth, source: UrlSource::Link, parent: None, seed: None }
+    }
+
+    /// The links on each page of a two-level site.
+    fn links(url: &str) -> &'static [&'static str] {
+        match url {
+            "/" => &["/a", "/b"],
+            "/a" => &["/a/1", "/a/2"],
+            "/b" => &["/b/1", "/b/2"],
+            _ => &[],
+        }
+    }
+
+    /// Crawls the site in `strategy` order and returns the fetch order.
+    fn crawl(strategy: CrawlStrategy) -> Vec<String> {
+        let mut frontier = Frontier::new(strategy);
+        frontier.push(entry("/", 0), None);
+        let mut order = Vec::new();
+        while let Some(next) = frontier.pop() {
+            for link in links(&next.url) {
+                frontier.push(entry(link, next.depth + 1), None);
+            }
+            order.push(next.url);
+        }
+        order
+    }
+
+    #[test]
+    fn bfs_fetches_each_level_before_the_next() {
+        assert_eq!(crawl(CrawlStrategy::Bfs), ["/", "/a", "/b", "/a/1", "/a/2", "/b/1", "/b/2"]);
+    }
+
+    #[test]
+    fn dfs_follows_the_newest_link_down_first() {
+        assert_eq!(crawl(CrawlStrategy::Dfs), ["/", "/b", "/b/2", "/b/1", "/a", "/a/2", "/a/1"]);
+    }
+
+    #[test]
+    fn bfs_puts_shallow_urls_first_whatever_the_push_order() {
+        let mut frontier = Frontier::new(CrawlStrategy::Bfs);
+        frontier.push(entry("/deep", 2), None);
+        frontier.push(entry("/shallow", 1), None);
+        frontier.push(entry("/deeper", 3), None);
+        frontier.push(entry("/also-shallow", 1), None);
+        let order: Vec<String> = std::iter::from_fn(|| frontier.pop()).map(|entry| entry.url).collect();
+        assert_eq!(order, ["/shallow", "/also-shallow", "/deep", "/deeper"]);
+    }
+
+    #[test]
+    fn priority_ranks_come_before_depth() {
+        let mut frontier = Frontier::new(CrawlStrategy::Priority);
+        frontier.push(entry("/plain", 1), None);
+        frontier.push(entry("/second-pattern", 1), Some(1));
+        frontier.push(entry("/deep-first-pattern", 4), Some(0));
+        frontier.push(entry("/shallow-plain", 0), None);
+        frontier.push(entry("/first-pattern", 2), Some(0));
+        let order: Vec<String> = std::iter::from_fn(|| frontier.pop()).map(|entry| entry.url).collect();
+        assert_eq!(order, ["/first-pattern", "/deep-first-pattern", "/second-pattern", "/shallow-plain", "/plain"]);
+    }
+
+    #[test]
+    fn entries_are_listed_in_fetch_order() {
+        let mut frontier = Frontier::new(CrawlStrategy::Priority);
+        frontier.push(entry("/plain", 1), None);
+        frontier.push(entry("/ranked", 1), Some(0));
+        let listed: Vec<String> = frontier.entries().into_iter().map(|entry| entry.url).collect();
+        assert_eq!(listed, ["/ranked", "/plain"]);
+        assert_eq!(frontier.len(), 2);
+    }
+}
@@ -0,0 +1,47 @@
+//! Content grep: matching user-supplied patterns against the visible text of
+//! crawled pages.
+
+use crate::content::visible_text;
+use crate::results::GrepMatch;
+use regex::Regex;
+
+/// Characters of text kept on each side of a match in its snippet.
+const CONTEXT_CHARS: usize = 40;
+
+/// Runs `patterns` over the visible text of the page at `url`, which has
+/// its whitespace collapsed so that a pattern's spaces match line breaks
+/// too. Each pattern that matches gives one record with its match count and
+/// the snippets of its first `max_snippets` matches.
+pub(crate) fn grep_page(url: &str, html: &str, patterns: &[Regex], max_snippets: usize) -> Vec<GrepMatch> {
+    let text = visible_text(html);
+    let mut matches = Vec::new();
+    for pattern in patterns {
+        let mut found = GrepMatch { url: url.to_string(), pattern: pattern.as_str().to_string(), count: 0, snippets: Vec::new() };
+        for hit in pattern.find_iter(&text) {
+            found.count += 1;
+            if found.snippets.len() < max_snippets {
+                found.snippets.push(snippet(&text, hit.start(), hit.end()));
+            }
+        }
+        if found.count > 0 {
+            matches.push(found);
+        }
+    }
+    matches
+}
+
+/// The match at `start..end` of `text` with up to [`CONTEXT_CHARS`] of
+/// context on each side, marked with an ellipsis where the text goes on.
+fn snippet(text: &str, start: usize, end: usize) -> String {
+    let from = text[..start].char_indices().rev().nth(CONTEXT_CHARS - 1).map_or(0, |(index, _)| index);
+    let to = text[end..].char_indices().nth(CONTEXT_CHARS).map_or(text.len(), |(index, _)| end + index);
+    let mut snippet = String::new();
+    if from > 0 {
+        snippet.push('…');
+    }
+    snippet.push_str(text[from..to].trim());
+    if to < text.len() {
+        snippet.push('…');
+    }
+    snippet
+}
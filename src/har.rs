@@ -0,0 +1,272 @@
+//! HAR 1.2 export: every fetch with its headers, sizes and timings, written
+//! as one JSON file when the crawl ends.
+
+use base64::Engine;
+use reqwest::header::HeaderMap;
+use serde::Serialize;
+use std::io;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::warn;
+use url::Url;
+
+/// One fetch, as recorded by the crawler.
+pub(crate) struct HarExchange<'a> {
+    pub url: &'a str,
+    pub method: &'a str,
+    /// RFC 3339 timestamp with milliseconds of when the request started
+    pub started_at: String,
+    pub http_version: String,
+    pub request_headers: &'a HeaderMap,
+    pub status: u16,
+    pub status_text: &'a str,
+    pub response_headers: &'a HeaderMap,
+    pub content_type: Option<&'a str>,
+    /// The body, when it was downloaded
+    pub body: Option<&'a [u8]>,
+    /// Content-Length, for bodies that were not downloaded
+    pub content_length: Option<u64>,
+    pub location: Option<&'a str>,
+    pub remote_addr: Option<SocketAddr>,
+    /// Time until the response headers arrived
+    pub wait: Duration,
+    /// Time spent downloading the body
+    pub receive: Duration,
+}
+
+#[derive(Serialize)]
+struct Har<'a> {
+    log: Log<'a>,
+}
+
+#[derive(Serialize)]
+struct Log<'a> {
+    version: &'static str,
+    creator: Creator,
+    /// Entries aren't grouped into pages, which HAR allows
+    pages: Vec<()>,
+    entries: &'a [Entry],
+}
+
+#[derive(Serialize)]
+struct Creator {
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    started_date_time: String,
+    time: f64,
+    request: Request,
+    response: Response,
+    cache: Cache,
+    timings: Timings,
+    #[serde(rename = "serverIPAddress", skip_serializing_if = "Option::is_none")]
+    server_ip_address: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Request {
+    method: String,
+    url: String,
+    http_version: String,
+    cookies: Vec<NameValue>,
+    headers: Vec<NameValue>,
+    query_string: Vec<NameValue>,
+    headers_size: i64,
+    body_size: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Response {
+    status: u16,
+    status_text: String,
+    http_version: String,
+    cookies: Vec<NameValue>,
+    headers: Vec<NameValue>,
+    content: Content,
+    #[serde(rename = "redirectURL")]
+    redirect_url: String,
+    headers_size: i64,
+    body_size: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Content {
+    size: i64,
+    mime_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    encoding: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Cache {}
+
+/// Phases reqwest doesn't expose (blocked, DNS, connect, SSL) are -1, as
+/// the spec allows.
+#[derive(Serialize)]
+struct Timings {
+    blocked: f64,
+    dns: f64,
+    connect: f64,
+    ssl: f64,
+    send: f64,
+    wait: f64,
+    receive: f64,
+}
+
+#[derive(Serialize)]
+struct NameValue {
+    name: String,
+    value: String,
+}
+
+/// Collects fetches and writes them as a HAR file.
+pub(crate) struct HarRecorder {
+    path: PathBuf,
+    /// Bytes of each body to include; `None` for whole bodies
+    max_body: Option<usize>,
+    entries: Mutex<Vec<Entry>>,
+}
+
+impl HarRecorder {
+    pub fn new(path: &Path, max_body: Option<usize>) -> Self {
+        HarRecorder { path: path.to_path_buf(), max_body, entries: Mutex::new(Vec::new()) }
+    }
+
+    /// Whether any of the bodies go into the file, so they need downloading.
+    pub fn keeps_bodies(&self) -> bool {
+        self.max_body != Some(0)
+    }
+
+    pub fn record(&self, exchange: &HarExchange<'_>) {
+        let query_string = Url::parse(exchange.url)
+            .map(|url| {
+                url.query_pairs()
+                    .map(|(name, value)| NameValue { name: name.into_owned(), value: value.into_owned() })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let body_size = match exchange.body {
+            Some(body) => body.len() as i64,
+            None if exchange.method == "HEAD" => 0,
+            None => exchange.content_length.map_or(-1, |length| length as i64),
+        };
+        let wait = milliseconds(exchange.wait);
+        let receive = milliseconds(exchange.receive);
+
+        let entry = Entry {
+            started_date_time: exchange.started_at.clone(),
+            time: wait + receive,
+            request: Request {
+                method: exchange.method.to_string(),
+                url: exchange.url.to_string(),
+                http_version: exchange.http_version.clone(),
+                cookies: Vec::new(),
+                headers: name_values(exchange.request_headers),
+                query_string,
+                headers_size: -1,
+                body_size: 0,
+            },
+            response: Response {
+                status: exchange.status,
+                status_text: exchange.status_text.to_string(),
+                http_version: exchange.http_version.clone(),
+                cookies: Vec::new(),
+                headers: name_values(exchange.response_headers),
+                content: self.content(exchange, body_size),
+                redirect_url: exchange.location.unwrap_or_default().to_string(),
+                headers_size: -1,
+                body_size,
+            },
+            cache: Cache {},
+            timings: Timings { blocked: -1.0, dns: -1.0, connect: -1.0, ssl: -1.0, send: 0.0, wait, receive },
+            server_ip_address: exchange.remote_addr.map(|addr| addr.ip().to_string()),
+        };
+        self.entries.lock().unwrap().push(entry);
+    }
+
+    /// The body as HAR content: text when it is UTF-8, base64 otherwise, cut
+    /// to the configured maximum.
+    fn content(&self, exchange: &HarExchange<'_>, size: i64) -> Content {
+        let mut content = Content {
+            size,
+            mime_type: exchange.content_type.unwrap_or("x-unknown").to_string(),
+            text: None,
+            encoding: None,
+            comment: None,
+        };
+        let Some(body) = exchange.body else {
+            return content;
+        };
+        let limit = self.max_body.unwrap_or(usize::MAX);
+        if limit == 0 || body.is_empty() {
+            return content;
+        }
+
+        let kept = &body[..body.len().min(limit)];
+        match std::str::from_utf8(kept) {
+            Ok(text) => content.text = Some(text.to_string()),
+            // A cut may split a character; keep the text up to it
+            Err(err) if kept.len() < body.len() && err.error_len().is_none() => {
+                content.text = Some(String::from_utf8_lossy(&kept[..err.valid_up_to()]).into_owned());
+            }
+            Err(_) => {
+                content.text = Some(base64::engine::general_purpose::STANDARD.encode(kept));
+                content.encoding = Some("base64");
+            }
+        }
+        if kept.len() < body.len() {
+            content.comment = Some(format!("truncated to {} of {} bytes", kept.len(), body.len()));
+        }
+        content
+    }
+
+    /// Writes the collected entries, in order of their start time. Failures
+    /// are logged, like the other outputs written by the crawler.
+    pub fn finish(&self) {
+        if let Err(err) = self.write() {
+            warn!(path = %self.path.display(), "cannot write the HAR file: {}", err);
+        }
+    }
+
+    fn write(&self) -> io::Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.sort_by(|a, b| a.started_date_time.cmp(&b.started_date_time));
+        let har = Har {
+            log: Log {
+                version: "1.2",
+                creator: Creator { name: "silent-crawler", version: env!("CARGO_PKG_VERSION") },
+                pages: Vec::new(),
+                entries: &entries,
+            },
+        };
+        let json = serde_json::to_string_pretty(&har)?;
+        std::fs::write(&self.path, json)
+    }
+}
+
+fn name_values(headers: &HeaderMap) -> Vec<NameValue> {
+    headers
+        .iter()
+        .map(|(name, value)| NameValue {
+            name: name.as_str().to_string(),
+            value: String::from_utf8_lossy(value.as_bytes()).into_owned(),
+        })
+        .collect()
+}
+
+fn milliseconds(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
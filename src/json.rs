@@ -0,0 +1,100 @@
+//! Link extraction from JSON API responses: URL-valued fields, HAL and
+//! JSON:API link objects, and the `next` links of paginated listings.
+
+use serde_json::Value;
+
+/// Whether `content_type` is JSON: `application/json`, or a `+json` type
+/// such as HAL's `application/hal+json` or JSON:API's
+/// `application/vnd.api+json`.
+pub fn is_json_type(content_type: &str) -> bool {
+    let media_type = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    media_type == "application/json" || (media_type.starts_with("application/") && media_type.ends_with("+json"))
+}
+
+/// A link found in a JSON document, unresolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonLink {
+    pub url: String,
+    /// The link points at the next page of a paginated listing.
+    pub next: bool,
+}
+
+/// What the field a value sits in says about it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    /// Strings count only when they are absolute URLs
+    Plain,
+    /// Strings count when they look like URLs or paths, relative ones included
+    Link,
+    /// A `links` or `_links` object, whose fields are link relations
+    Links,
+}
+
+/// Returns the links of a JSON document, in document order and unresolved.
+/// Strings anywhere that are absolute `http(s)` URLs count, and so do the
+/// paths and relative URLs in `href`, `url`, `uri`, `link` and `next`
+/// fields and in the relations of `links` and `_links` objects (HAL's
+/// `{"next": {"href": ...}}` and JSON:API's `{"next": "..."}` alike).
+/// Links in a `next` field (or `next_page`, `nextPageUrl`,
+/// `@odata.nextLink` and the like) or relation are next links. HAL
+/// templates such as `/orders{?page}` are skipped, and invalid or
+/// truncated JSON has no links.
+pub fn json_links(body: &[u8]) -> Vec<JsonLink> {
+    let mut links = Vec::new();
+    if let Ok(document) = serde_json::from_slice::<Value>(body) {
+        collect(&document, Field::Plain, false, &mut links);
+    }
+    links
+}
+
+fn collect(value: &Value, field: Field, next: bool, links: &mut Vec<JsonLink>) {
+    match value {
+        Value::String(text) => {
+            let text = text.trim();
+            let is_link = match field {
+                Field::Plain => is_absolute_url(text),
+                Field::Link | Field::Links => is_absolute_url(text) || is_relative_url(text),
+            };
+            if is_link && !text.contains(['{', '}']) && !text.contains(char::is_whitespace) {
+                links.push(JsonLink { url: text.to_string(), next: next && field != Field::Plain });
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect(item, field, next, links);
+            }
+        }
+        Value::Object(fields) => {
+            for (name, child) in fields {
+                let name = field_name(name);
+                let child_field = match field {
+                    Field::Links => Field::Link,
+                    _ if matches!(name.as_str(), "links" | "link") && child.is_object() => Field::Links,
+                    _ if matches!(name.as_str(), "href" | "url" | "uri" | "link" | "links") || is_next_field(&name) => Field::Link,
+                    _ => Field::Plain,
+                };
+                collect(child, child_field, next || is_next_field(&name), links);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// `name` lowercased without punctuation, so `_links` is `links` and
+/// `@odata.nextLink` is `odatanextlink`.
+fn field_name(name: &str) -> String {
+    name.chars().filter(char::is_ascii_alphanumeric).map(|c| c.to_ascii_lowercase()).collect()
+}
+
+fn is_next_field(name: &str) -> bool {
+    matches!(name, "next" | "nextpage" | "nexturl" | "nextpageurl" | "nextlink" | "nexthref" | "odatanextlink")
+}
+
+fn is_absolute_url(text: &str) -> bool {
+    let lower = text.get(..8).unwrap_or(text).to_ascii_lowercase();
+    (lower.starts_with("http://") && text.len() > 7) || (lower.starts_with("https://") && text.len() > 8)
+}
+
+fn is_relative_url(text: &str) -> bool {
+    ["/", "?", "./", "../"].iter().any(|prefix| text.starts_with(prefix))
+}
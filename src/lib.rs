@@ -0,0 +1,65 @@
+//! Silent Crawler: a fast, concurrent web crawler.
+//!
+//! Build a crawler with [`CrawlerBuilder`], run it with
+//! [`SilentCrawler::crawl`] and inspect the returned [`Results`].
+
+mod agents;
+mod api;
+mod builder;
+mod cache;
+mod challenge;
+mod charset;
+mod content;
+mod cookies;
+mod crawler;
+pub mod diff;
+#[cfg(feature = "sqlite")]
+mod db;
+mod dns;
+mod error;
+pub mod extract;
+pub mod feed;
+mod frontier;
+mod grep;
+mod har;
+pub mod json;
+mod language;
+mod login;
+pub mod merge;
+mod metrics;
+mod mirror;
+pub mod output;
+pub mod pdf;
+mod progress;
+mod proxy;
+mod ratelimit;
+#[cfg(feature = "render")]
+mod render;
+pub mod replay;
+mod results;
+pub mod robots;
+mod scope;
+mod secrets;
+mod security;
+mod sensitive;
+mod sourcemap;
+pub mod sitemap;
+mod state;
+mod stream;
+mod structured;
+mod timing;
+mod visited;
+mod warc;
+mod webhook;
+
+pub use agents::BUILTIN_USER_AGENTS;
+pub use builder::{CrawlerBuilder, DEFAULT_USER_AGENT};
+pub use crawler::{SilentCrawler, StopHandle};
+pub use dns::IpFamily;
+pub use error::CrawlerError;
+pub use frontier::CrawlStrategy;
+pub use progress::{Progress, ProgressHandle};
+pub use scope::ScopeVerdict;
+pub use visited::VisitedBackend;
+pub use webhook::WebhookEvent;
+pub use results::{BrokenLink, BruteForceHit, BudgetHit, BudgetKind, CanonicalIssue, ClientRedirect, ClientRedirectKind, CanonicalReason, CrawlError, DirectoryListing, DuplicateGroup, ErrorKind, Form, FormField, GraphEdge, GrepMatch, HreflangIssue, HreflangReason, HstsHost, LatencyStats, MixedContent, PageRecord, PaginationChain, PaginationStep, ParameterStats, PdfDocument, Performance, ProxyStats, Results, SecretFinding, SecurityAudit, SecurityOffender, SensitiveFile, SkippedUrl, SlowPage, SourceMap, SourceMapOrigin, StructuredData, TrapReason, TrappedUrl, UrlSource, RESULTS_SCHEMA_VERSION};
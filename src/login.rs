@@ -0,0 +1,37 @@
+//! Helpers for the form login performed before a crawl starts.
+
+use scraper::{Html, Selector};
+use url::form_urlencoded;
+
+/// Finds the element matching `selector` in a login page and returns its
+/// form field name and value, e.g. a hidden CSRF `<input>` or a
+/// `<meta name="csrf-token" content="...">` tag.
+pub(crate) fn extract_token(html: &str, selector: &str) -> Result<Option<(String, String)>, String> {
+    let selector = Selector::parse(selector).map_err(|err| format!("invalid token selector {:?}: {}", selector, err))?;
+    let document = Html::parse_document(html);
+
+    let Some(element) = document.select(&selector).next() else {
+        return Ok(None);
+    };
+    let element = element.value();
+    let (Some(name), Some(value)) = (element.attr("name"), element.attr("value").or(element.attr("content"))) else {
+        return Ok(None);
+    };
+    Ok(Some((name.to_string(), value.to_string())))
+}
+
+/// Adds `field` to URL-encoded form `data`, replacing any value the data
+/// already has for that name.
+pub(crate) fn merge_form(data: &str, field: Option<(String, String)>) -> String {
+    let pairs: Vec<(String, String)> = form_urlencoded::parse(data.as_bytes()).into_owned().collect();
+    let mut serializer = form_urlencoded::Serializer::new(String::new());
+    for (name, value) in &pairs {
+        if field.as_ref().is_none_or(|(field_name, _)| field_name != name) {
+            serializer.append_pair(name, value);
+        }
+    }
+    if let Some((name, value)) = &field {
+        serializer.append_pair(name, value);
+    }
+    serializer.finish()
+}
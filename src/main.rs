@@ -1,449 +1,1745 @@
-use clap::Parser;
-use futures::stream::{FuturesUnordered, StreamExt};
-use rand::Rng;
-use reqwest::{Client, header::{HeaderMap, HeaderValue, USER_AGENT, ACCEPT, ACCEPT_LANGUAGE, ACCEPT_ENCODING, CONNECTION, UPGRADE_INSECURE_REQUESTS}};
-use scraper::{Html, Selector};
-use serde::Serialize;
-use std::collections::HashSet;
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, ArgMatches, CommandFactory, FromArgMatches, Parser};
+use silent_crawler::extract::{normalize_url, LinkSource};
+use silent_crawler::{diff, merge, output, replay, CanonicalReason, CrawlStrategy, CrawlerBuilder, CrawlerError, ErrorKind, HreflangReason, IpFamily, LatencyStats, ProgressHandle, UrlSource, VisitedBackend, WebhookEvent};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::ffi::OsString;
 use std::fs::File;
-use std::io::Write;
-use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::io::{IsTerminal, Write};
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use std::time::Duration;
-use tokio::time::sleep;
-use url::{Url, ParseError};
+use tracing::Level;
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::prelude::*;
+use url::Url;
 
 #[derive(Parser, Debug)]
-#[clap(author = "Silent Crawler", version, about = "A fast web crawler written in Rust")]
+#[clap(author = "Silent Crawler", version, about = "A fast web crawler written in Rust",
+       after_help = "Crawling is the default; `silent-crawler crawl URL` is the same as `silent-crawler URL`. Other subcommands:\n  \
+                    diff OLD.json NEW.json   compare the results of two crawls\n  \
+                    merge FILE... -o OUT     combine results files into one\n  \
+                    replay RESULTS.json      fetch the URLs of a results file again and list status changes")]
 struct Args {
-    /// Base URL to crawl
-    #[clap(index = 1)]
-    url: String,
+    /// URLs to start crawling from; the first one is the base URL
+    #[clap(index = 1, value_name = "URL", required_unless_present = "seeds")]
+    urls: Vec<String>,
+
+    /// Also start from the URLs in this file, one per line ("-" reads stdin)
+    #[clap(long, value_name = "FILE")]
+    seeds: Option<PathBuf>,
+
+    /// Never fetch the URLs in this file, one per line, or the pages of a previous results JSON
+    #[clap(long, value_name = "FILE")]
+    known_urls: Option<PathBuf>,
 
     /// Maximum recursion depth
     #[clap(short, long, default_value = "3")]
     depth: usize,
 
-    /// Delay between requests in seconds
+    /// Stop after fetching this many pages
+    #[clap(long, value_name = "N")]
+    max_pages: Option<usize>,
+
+    /// Stop the crawl when more than N responses in a row are bot challenge pages
+    #[clap(long, value_name = "N")]
+    stop_on_challenge: Option<usize>,
+
+    /// Stop the crawl after this long, in seconds or as a duration like "10m"
+    #[clap(long, value_name = "DURATION", value_parser = parse_duration)]
+    max_time: Option<Duration>,
+
+    /// Download at most this many bytes per second, across all requests
+    #[clap(long, value_name = "BYTES_PER_SEC")]
+    max_bandwidth: Option<u64>,
+
+    /// Stop the crawl once more than this many bytes have been downloaded
+    #[clap(long, value_name = "BYTES")]
+    max_total_bytes: Option<u64>,
+
+    /// Delay between requests to the same host in seconds
     #[clap(short, long, default_value = "0.5")]
     wait: f64,
 
+    /// Increase a host's delay for the rest of the crawl each time it returns 429
+    #[clap(long)]
+    adaptive_throttle: bool,
+
+    /// Maximum redirect hops to follow per URL
+    #[clap(long, default_value = "10")]
+    max_redirects: usize,
+
+    /// Rotate requests over the proxies listed in this file, one URL per line
+    #[clap(long, value_name = "PATH", conflicts_with = "proxy")]
+    proxy_list: Option<PathBuf>,
+
+    /// Take a proxy from the --proxy-list out of rotation after this many consecutive failures
+    #[clap(long, value_name = "N", default_value = "3")]
+    proxy_max_failures: usize,
+
+    /// Cookies to send to the base URL's host, e.g. "session=abc; theme=dark" (repeatable)
+    #[clap(long, value_name = "COOKIES")]
+    cookie: Vec<String>,
+
+    /// Load cookies from a Netscape cookies.txt file (as exported from a browser)
+    #[clap(long, value_name = "PATH")]
+    cookies_file: Option<PathBuf>,
+
+    /// Log in by submitting --login-data to this form URL before crawling
+    #[clap(long, value_name = "URL", requires = "login_data")]
+    login_url: Option<String>,
+
+    /// URL-encoded login form fields, e.g. "user=x&pass=y"
+    #[clap(long, value_name = "DATA", requires = "login_url")]
+    login_data: Option<String>,
+
+    /// Regex the page after logging in must match
+    #[clap(long, value_name = "REGEX", requires = "login_url")]
+    login_check: Option<String>,
+
+    /// CSS selector of a CSRF token field on the login page to submit with the form
+    #[clap(long, value_name = "SELECTOR", requires = "login_url")]
+    login_token_selector: Option<String>,
+
+    /// Rotate through a built-in list of current browser User-Agents
+    #[clap(long, conflicts_with = "user_agent_file")]
+    random_agent: bool,
+
+    /// Rotate through the User-Agents in this file, one per line
+    #[clap(long, value_name = "PATH")]
+    user_agent_file: Option<PathBuf>,
+
+    /// When rotating User-Agents, pick a new one per request or keep one per host
+    #[clap(long, value_enum, default_value = "request")]
+    agent_rotation: AgentRotation,
+
+    /// Don't send the linking page as the Referer header
+    #[clap(long)]
+    no_referer: bool,
+
+    /// Ask servers for uncompressed responses (Accept-Encoding: identity)
+    #[clap(long)]
+    no_compression: bool,
+
+    /// Output file path for results
+    #[clap(short, long)]
+    output: Option<PathBuf>,
+
+    /// Output format; inferred from the --output extension when omitted
+    #[clap(long, value_enum)]
+    format: Option<OutputFormat>,
+
+    /// Write the link graph to this file
+    #[clap(long, value_name = "PATH")]
+    graph: Option<PathBuf>,
+
+    /// Link graph format; inferred from the --graph extension when omitted
+    #[clap(long, value_enum)]
+    graph_format: Option<GraphFormat>,
+
+    /// Append one JSON line per finished URL to this file while crawling
+    #[clap(long, value_name = "PATH")]
+    stream: Option<PathBuf>,
+
+    /// Periodically save crawl progress to this file
+    #[clap(long, value_name = "PATH")]
+    state: Option<PathBuf>,
+
+    /// Write pages, links, errors and the frontier to this SQLite database while crawling
+    #[clap(long, value_name = "PATH")]
+    db: Option<PathBuf>,
+
+    /// Append request and response records for every fetch to this gzipped WARC file
+    #[clap(long, value_name = "PATH")]
+    warc: Option<PathBuf>,
+
+    /// Write every request and response, with headers and timings, to this HAR 1.2 file
+    #[clap(long, value_name = "PATH")]
+    har: Option<PathBuf>,
+
+    /// Keep at most this many bytes of each body in the HAR file (0 leaves bodies out)
+    #[clap(long, value_name = "BYTES", requires = "har")]
+    har_max_body: Option<usize>,
+
+    /// Save fetched pages under this directory, one file per URL, with a manifest.json
+    #[clap(long, value_name = "DIR")]
+    save_dir: Option<PathBuf>,
+
+    /// Rewrite links between saved pages to relative paths for offline browsing
+    #[clap(long, requires = "save_dir")]
+    convert_links: bool,
+
+    /// Also save non-HTML responses such as PDFs and images
+    #[clap(long, requires = "save_dir")]
+    save_non_html: bool,
+
+    /// Cache responses in this directory and re-fetch them with conditional requests (If-None-Match, If-Modified-Since)
+    #[clap(long, value_name = "DIR")]
+    cache_dir: Option<PathBuf>,
+
+    /// Drop cached responses older than this, in seconds or as a duration like "3d" [default: 7d]
+    #[clap(long, value_name = "DURATION", value_parser = parse_duration, requires = "cache_dir")]
+    cache_max_age: Option<Duration>,
+
+    /// Evict the least recently used cached responses beyond this many bytes [default: 1073741824]
+    #[clap(long, value_name = "BYTES", requires = "cache_dir")]
+    cache_max_size: Option<u64>,
+
+    /// Fetch everything in full, ignoring the --cache-dir contents, and store the fresh responses
+    #[clap(long, requires = "cache_dir")]
+    refresh: bool,
+
+    /// Resume the crawl saved in the --db database or --state file
+    #[clap(long)]
+    resume: bool,
+
+    /// Ignore robots.txt restrictions
+    #[clap(long)]
+    ignore_robots: bool,
+
+    /// Maximum number of concurrent requests per host
+    #[clap(long, default_value = "2")]
+    per_host_concurrency: usize,
+
+    /// Ignore Crawl-delay from robots.txt and always use --wait
+    #[clap(long)]
+    ignore_crawl_delay: bool,
+
+    /// Follow rel="nofollow" links and ignore meta robots and X-Robots-Tag directives
+    #[clap(long)]
+    ignore_meta_robots: bool,
+
+    /// Leave elements matching this CSS selector (e.g. a CSRF token input) out of duplicate detection (repeatable)
+    #[clap(long, value_name = "CSS")]
+    volatile_selector: Vec<String>,
+
+    /// Also group pages with nearly identical text
+    #[clap(long)]
+    near_duplicate: bool,
+
+    /// Seed the crawl with URLs from sitemap.xml and robots.txt Sitemap entries
+    #[clap(long)]
+    use_sitemap: bool,
+
+    /// Also crawl this domain and its subdomains (repeatable)
+    #[clap(long, value_name = "DOMAIN")]
+    allow_domain: Vec<String>,
+
+    /// Crawl every port of in-scope hosts, not only the ports of the seeds
+    #[clap(long)]
+    any_port: bool,
+
+    /// Take the scope from this file: a Burp Suite scope export (JSON), or one rule per line, like *.example.com or -example.com/logout
+    #[clap(long, value_name = "FILE")]
+    scope: Option<PathBuf>,
+
+    /// Print whether this URL is in scope, and why, instead of crawling; exits with 1 if it isn't
+    #[clap(long, value_name = "URL")]
+    dry_run_scope: Option<String>,
+
+    /// Elements to follow links from: a, iframe, frame, area, form, link, meta-refresh
+    #[clap(long, value_name = "LIST", value_delimiter = ',', default_value = "a,iframe,frame,area,form,link,meta-refresh")]
+    link_sources: Vec<LinkSource>,
+
+    /// Check every referenced script, stylesheet and image with a HEAD request
+    #[clap(long)]
+    fetch_assets: bool,
+
+    /// Render HTML pages in headless Chromium and extract links from the rendered DOM
+    #[clap(long)]
+    render: bool,
+
+    /// Pages rendered at once, one browser tab each
+    #[clap(long, value_name = "N", default_value = "2", requires = "render")]
+    render_concurrency: usize,
+
+    /// Only render URLs matching this regex; fetch the rest without the browser
+    #[clap(long, value_name = "REGEX", requires = "render")]
+    render_pattern: Option<String>,
+
+    /// Wait for this CSS selector instead of network idle before reading the DOM
+    #[clap(long, value_name = "SELECTOR", requires = "render")]
+    render_wait_for: Option<String>,
+
+    /// Time allowed to render a page, in seconds or as a duration like "45s" [default: 30s]
+    #[clap(long, value_name = "DURATION", value_parser = parse_duration, requires = "render")]
+    render_timeout: Option<Duration>,
+
+    /// Chromium or Chrome executable to render with [default: $CHROME, or found on the system]
+    #[clap(long, value_name = "PATH", requires = "render")]
+    browser_path: Option<PathBuf>,
+
+    /// Don't collect links to external sites in the results
+    #[clap(long)]
+    no_external: bool,
+
+    /// Send HEAD before GET and skip bodies that aren't HTML or are over --max-body-size
+    #[clap(long)]
+    head_first: bool,
+
+    /// Fetch http:// URLs over HTTPS on hosts that serve it, checked once per host
+    #[clap(long)]
+    prefer_https: bool,
+
+    /// Stop reading a response body after this many bytes [default: 10485760]
+    #[clap(long, value_name = "BYTES")]
+    max_body_size: Option<u64>,
+
+    /// After crawling, check external links with slow HEAD requests and report the broken ones
+    #[clap(long, conflicts_with = "no_external")]
+    check_external: bool,
+
+    /// Treat a page's canonical URL as what it is a copy of: queue the canonical instead of the page's links
+    #[clap(long)]
+    follow_canonical: bool,
+
+    /// Only crawl the hreflang alternates in these languages, regional variants included (e.g. en,de)
+    #[clap(long, value_name = "LIST", value_delimiter = ',')]
+    languages: Vec<String>,
+
+    /// Detect the language of each page's text, expecting these languages (e.g. en,de)
+    #[clap(long, value_name = "CODES", value_delimiter = ',')]
+    content_language: Vec<String>,
+
+    /// Don't follow links from pages detected to be in another language than --content-language
+    #[clap(long, requires = "content_language")]
+    skip_other_languages: bool,
+
+    /// Record each page's JSON-LD, OpenGraph, Twitter card and microdata, and count the schema.org types used
+    #[clap(long)]
+    structured_data: bool,
+
+    /// Leave JSON-LD blocks larger than this many bytes unparsed, flagged as invalid [default: 524288]
+    #[clap(long, value_name = "BYTES", requires = "structured_data")]
+    max_json_ld_size: Option<usize>,
+
+    /// Record the pages whose text matches this regex, with snippets (repeatable)
+    #[clap(long, value_name = "REGEX")]
+    grep: Vec<String>,
+
+    /// Read more --grep patterns from this file, one per line
+    #[clap(long, value_name = "PATH")]
+    grep_file: Option<PathBuf>,
+
+    /// Match the --grep patterns case-insensitively
+    #[clap(long)]
+    grep_ignore_case: bool,
+
+    /// Keep at most this many snippets per page and pattern [default: 3]
+    #[clap(long, value_name = "N")]
+    max_grep_snippets: Option<usize>,
+
+    /// Look for API keys, tokens and private keys in crawled pages (and scripts, with --scan-js)
+    #[clap(long)]
+    scan_secrets: bool,
+
+    /// Add secret rules from this file, one "name regex" per line (implies --scan-secrets)
+    #[clap(long, value_name = "PATH")]
+    secrets_rules: Option<PathBuf>,
+
+    /// Collect email addresses from crawled pages, including mailto: links
+    #[clap(long)]
+    harvest_emails: bool,
+
+    /// Also crawl URLs found in HTML comments and inline scripts
+    #[clap(long)]
+    deep_extract: bool,
+
+    /// Parse JSON responses and crawl their URL-valued fields and next links
+    #[clap(long)]
+    follow_json: bool,
+
+    /// Follow at most this many next/prev links in a row; they don't count against --depth [default: 200]
+    #[clap(long, value_name = "N")]
+    max_pagination: Option<usize>,
+
+    /// Parse PDF responses and crawl the URLs in their link annotations and text
+    #[clap(long)]
+    parse_pdf: bool,
+
+    /// Read at most this many bytes of each PDF with --parse-pdf [default: 20971520]
+    #[clap(long, value_name = "BYTES", requires = "parse_pdf")]
+    max_pdf_size: Option<u64>,
+
+    /// Fetch same-domain .js files referenced by crawled pages and crawl the URLs found in them
+    #[clap(long)]
+    scan_js: bool,
+
+    /// After the crawl, try every path in this file in each crawled directory (noisy)
+    #[clap(long, value_name = "PATH")]
+    wordlist: Option<PathBuf>,
+
+    /// Also report --wordlist paths answering 401 or 403
+    #[clap(long, requires = "wordlist")]
+    report_forbidden: bool,
+
+    /// After the crawl, check each host for files like /.git/HEAD, /.env and /backup.zip
+    #[clap(long)]
+    common_files: bool,
+
+    /// Track at most this many distinct query parameter names [default: 1000]
+    #[clap(long, value_name = "N")]
+    max_parameters: Option<usize>,
+
+    /// Scan at most this many scripts with --scan-js [default: 200]
+    #[clap(long, value_name = "N", requires = "scan_js")]
+    max_js_files: Option<usize>,
+
+    /// Read at most this many bytes of each script with --scan-js [default: 2097152]
+    #[clap(long, value_name = "BYTES", requires = "scan_js")]
+    max_js_size: Option<u64>,
+
+    /// After crawling, look for the source maps of same-domain scripts and list the original files they name
+    #[clap(long)]
+    sourcemaps: bool,
+
+    /// Like --sourcemaps, also trying <script>.map for scripts that don't name a map
+    #[clap(long)]
+    probe_sourcemaps: bool,
+
+    /// Leave source maps larger than this many bytes unread [default: 20971520]
+    #[clap(long, value_name = "BYTES")]
+    max_sourcemap_size: Option<u64>,
+
+    /// Include every response header of each page in the results
+    #[clap(long)]
+    capture_headers: bool,
+
+    /// Report HTML pages missing the standard security headers, and the Server and X-Powered-By values seen
+    #[clap(long)]
+    security_audit: bool,
+
+    /// Serve Prometheus metrics of the crawl at /metrics on this address, like 0.0.0.0:9090, until it finishes
+    #[clap(long, value_name = "ADDR")]
+    metrics_addr: Option<SocketAddr>,
+
+    /// Serve a read-only JSON API over the crawl on this address, like 127.0.0.1:8080: /status, /urls, /errors and /results
+    #[clap(long, value_name = "ADDR")]
+    serve: Option<SocketAddr>,
+
+    /// Require this bearer token on every request to the --serve API
+    #[clap(long, value_name = "TOKEN", env = "SILENT_CRAWLER_SERVE_TOKEN", hide_env_values = true, requires = "serve")]
+    serve_token: Option<String>,
+
+    /// POST a JSON notification to this URL (a Slack incoming webhook works) on the --webhook-on events
+    #[clap(long, value_name = "URL")]
+    webhook: Option<String>,
+
+    /// Events the webhook is notified of: finish, error, match, sensitive-file. Findings are batched
+    #[clap(long, value_name = "LIST", value_delimiter = ',', default_value = "finish", requires = "webhook")]
+    webhook_on: Vec<WebhookEvent>,
+
+    /// List the pages that took at least this many milliseconds to load as "slow_pages" in the results
+    #[clap(long, value_name = "MS")]
+    slow_threshold: Option<u64>,
+
+    /// Exit with status 1 when broken links are found
+    #[clap(long)]
+    fail_on_broken: bool,
+
+    /// Only crawl URLs matching this regex (repeatable)
+    #[clap(long, value_name = "REGEX")]
+    include_pattern: Vec<String>,
+
+    /// Never crawl URLs matching this regex (repeatable; wins over --include-pattern)
+    #[clap(long, value_name = "REGEX")]
+    exclude_pattern: Vec<String>,
+
+    /// Crawl order: bfs, dfs or priority (default: bfs, or priority with --prioritize)
+    #[clap(long, value_name = "STRATEGY")]
+    strategy: Option<CrawlStrategy>,
+
+    /// Fetch URLs matching this regex before all others (repeatable; earlier patterns win)
+    #[clap(long, value_name = "REGEX")]
+    prioritize: Vec<String>,
+
+    /// Where seen URLs are kept for deduplication: memory, bloom (less memory,
+    /// occasionally skips a new URL) or disk (a temporary SQLite database)
+    #[clap(long, value_name = "BACKEND", default_value = "memory")]
+    visited_backend: VisitedBackend,
+
+    /// Number of URLs the bloom filter is sized for
+    #[clap(long, value_name = "N", default_value = "10000000")]
+    bloom_capacity: usize,
+
+    /// Acceptable bloom filter false-positive rate
+    #[clap(long, value_name = "RATE", default_value = "0.001")]
+    bloom_fp_rate: f64,
+
+    /// Fetch at most N URLs per first-level directory of each host
+    #[clap(long, value_name = "N")]
+    max_per_directory: Option<usize>,
+
+    /// Fetch at most N URLs per host
+    #[clap(long, value_name = "N")]
+    max_per_subdomain: Option<usize>,
+
+    /// Don't skip URLs that look like crawler traps
+    #[clap(long)]
+    no_trap_detection: bool,
+
+    /// Skip URLs whose path repeats a run of segments more than this many times in a row
+    #[clap(long, value_name = "N", default_value = "3")]
+    max_segment_repeats: usize,
+
+    /// Skip URLs with more path segments than this
+    #[clap(long, value_name = "N", default_value = "15")]
+    max_path_depth: usize,
+
+    /// Skip URLs with more query parameters than this
+    #[clap(long, value_name = "N", default_value = "10")]
+    max_query_params: usize,
+
+    /// Skip calendar URLs dated more than this many years ahead (or before 1990)
+    #[clap(long, value_name = "YEARS", default_value = "2")]
+    max_future_years: i32,
+
+    /// Ignore these query parameters when deduplicating URLs (comma-separated;
+    /// `utm_*` matches a prefix). URLs are still fetched, and matched against
+    /// --include-pattern/--exclude-pattern, with all their parameters
+    #[clap(long, value_name = "LIST", value_delimiter = ',')]
+    strip_params: Vec<String>,
+
+    /// Ignore common tracking and session parameters (utm_*, fbclid, gclid,
+    /// PHPSESSID, ...) when deduplicating URLs
+    #[clap(long)]
+    strip_tracking_params: bool,
+
+    /// Treat URLs whose query parameters only differ in order as the same URL
+    #[clap(long)]
+    sort_params: bool,
+
+    /// Treat www.<domain> and <domain> as the same host, and don't report "www" as a subdomain
+    #[clap(long)]
+    fold_www: bool,
+
+    /// Maximum number of concurrent requests
+    #[clap(short = 'c', long, default_value = "10")]
+    concurrency: usize,
+
+    /// Don't print progress to stderr while crawling
+    #[clap(short, long)]
+    quiet: bool,
+
+    /// Log verbosity: -v fetched URLs, -vv skipped URLs and reasons, -vvv everything
+    #[clap(short, long, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Write logs to this file instead of stderr
+    #[clap(long, value_name = "PATH")]
+    log_file: Option<PathBuf>,
+
+    /// Read options from this TOML file (default: ./silent-crawler.toml if it exists); command-line flags override it
+    #[clap(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Print the resolved configuration as TOML and exit
+    #[clap(long)]
+    print_config: bool,
+
+    #[clap(flatten)]
+    client: ClientOptions,
+}
+
+/// Options for the HTTP client, shared by the subcommands that send
+/// requests.
+#[derive(clap::Args, Debug)]
+#[clap(next_help_heading = "Client options")]
+struct ClientOptions {
     /// Request timeout in seconds
     #[clap(short, long, default_value = "10")]
     timeout: u64,
 
+    /// Time allowed to connect, in seconds or as a duration like "500ms" [default: --timeout]
+    #[clap(long, value_name = "DURATION", value_parser = parse_duration)]
+    connect_timeout: Option<Duration>,
+
+    /// Fail a response whose body stalls for this long between reads [default: --timeout]
+    #[clap(long, value_name = "DURATION", value_parser = parse_duration)]
+    read_timeout: Option<Duration>,
+
+    /// Close kept-alive connections idle for this long [default: 90s]
+    #[clap(long, value_name = "DURATION", value_parser = parse_duration)]
+    pool_idle_timeout: Option<Duration>,
+
+    /// Keep at most this many idle connections per host (0 disables keep-alive) [default: unlimited]
+    #[clap(long, value_name = "N")]
+    pool_max_idle_per_host: Option<usize>,
+
+    /// Connect to these addresses for HOST instead of resolving it, as in curl, e.g. "app.example.com:443:10.0.0.5" (repeatable; every port of the host is pinned)
+    #[clap(long, value_name = "HOST:PORT:IP[,IP...]", value_parser = parse_resolve)]
+    resolve: Vec<(String, Vec<IpAddr>)>,
+
+    /// Resolve host names through this DNS server instead of the system resolver
+    #[clap(long, value_name = "IP[:PORT]", value_parser = parse_dns_server)]
+    dns_server: Option<SocketAddr>,
+
+    /// Only connect over IPv4
+    #[clap(short = '4', long, conflicts_with = "ipv6")]
+    ipv4: bool,
+
+    /// Only connect over IPv6
+    #[clap(short = '6', long)]
+    ipv6: bool,
+
+    /// Retries for timeouts, connection failures, 429 and 5xx responses
+    #[clap(long, default_value = "2")]
+    retries: u32,
+
     /// Custom User-Agent string
     #[clap(short, long)]
     user_agent: Option<String>,
 
-    /// Output file path for results (JSON format)
+    /// Route requests through this proxy (http://, https:// or socks5://, with optional user:pass@)
+    #[clap(long, value_name = "URL")]
+    proxy: Option<String>,
+
+    /// Skip TLS certificate verification (for proxies that intercept TLS)
+    #[clap(long)]
+    proxy_insecure: bool,
+
+    /// Accept invalid and self-signed TLS certificates
+    #[clap(long)]
+    insecure: bool,
+
+    /// Also trust the CA certificates in this PEM file
+    #[clap(long, value_name = "PATH")]
+    ca_cert: Option<PathBuf>,
+
+    /// Client certificate (PEM) for mutual TLS
+    #[clap(long, value_name = "PATH", requires = "client_key")]
+    client_cert: Option<PathBuf>,
+
+    /// Private key (PKCS#8 PEM) for --client-cert
+    #[clap(long, value_name = "PATH", requires = "client_cert")]
+    client_key: Option<PathBuf>,
+
+    /// Extra request header, e.g. "X-Api-Key: secret" (repeatable)
+    #[clap(short = 'H', long = "header", value_name = "NAME: VALUE", value_parser = parse_header)]
+    headers: Vec<(String, String)>,
+
+    /// Read extra request headers from this file, one "Name: value" per line
+    #[clap(long, value_name = "PATH")]
+    headers_file: Option<PathBuf>,
+
+    /// HTTP Basic credentials for every request (takes precedence over --auth-bearer)
+    #[clap(long, value_name = "USER:PASS")]
+    auth_basic: Option<String>,
+
+    /// Bearer token for every request
+    #[clap(long, value_name = "TOKEN", env = "SILENT_CRAWLER_TOKEN", hide_env_values = true)]
+    auth_bearer: Option<String>,
+}
+
+impl ClientOptions {
+    /// Sets the client options on `builder`.
+    fn apply(&self, mut builder: CrawlerBuilder) -> Result<CrawlerBuilder, Box<dyn std::error::Error>> {
+        builder = builder
+            .timeout(Duration::from_secs(self.timeout))
+            .retries(self.retries)
+            .proxy_insecure(self.proxy_insecure)
+            .accept_invalid_certs(self.insecure);
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(timeout) = self.read_timeout {
+            builder = builder.read_timeout(timeout);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        for (host, ips) in &self.resolve {
+            for &ip in ips {
+                builder = builder.resolve(host, ip);
+            }
+        }
+        if let Some(server) = self.dns_server {
+            builder = builder.dns_server(server);
+        }
+        if self.ipv4 {
+            builder = builder.ip_family(IpFamily::V4);
+        } else if self.ipv6 {
+            builder = builder.ip_family(IpFamily::V6);
+        }
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(proxy);
+        }
+        if let Some(path) = &self.ca_cert {
+            builder = builder.ca_cert(path);
+        }
+        if let (Some(cert), Some(key)) = (&self.client_cert, &self.client_key) {
+            builder = builder.client_cert(cert, key);
+        }
+        if let Some(path) = &self.headers_file {
+            for (line, entry) in read_list(path)?.iter().enumerate() {
+                let (name, value) = parse_header(entry)
+                    .map_err(|err| format!("{}: entry {}: {}", path.display(), line + 1, err))?;
+                builder = builder.header(name, value);
+            }
+        }
+        if let Some(credentials) = &self.auth_basic {
+            let (username, password) = credentials.split_once(':').unwrap_or((credentials, ""));
+            builder = builder.basic_auth(username, password);
+        } else if let Some(token) = &self.auth_bearer {
+            builder = builder.bearer_auth(token);
+        }
+        for (name, value) in &self.headers {
+            builder = builder.header(name, value);
+        }
+        if let Some(user_agent) = &self.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+        Ok(builder)
+    }
+}
+
+/// The `diff` subcommand, parsed apart from the crawl options.
+#[derive(Parser, Debug)]
+#[clap(name = "silent-crawler diff", bin_name = "silent-crawler diff", version,
+       about = "Compare two results files written with -o in JSON. Exits with status 1 when they differ, 2 on errors")]
+struct DiffArgs {
+    /// Results of the earlier crawl
+    #[clap(value_name = "OLD")]
+    old: PathBuf,
+
+    /// Results of the later crawl
+    #[clap(value_name = "NEW")]
+    new: PathBuf,
+
+    /// Print the differences as text or as JSON
+    #[clap(long, value_name = "FORMAT", default_value = "text")]
+    format: DiffFormat,
+}
+
+/// The `merge` subcommand.
+#[derive(Parser, Debug)]
+#[clap(name = "silent-crawler merge", bin_name = "silent-crawler merge", version,
+       about = "Combine results files written with -o in JSON into one deduplicated set")]
+struct MergeArgs {
+    /// Results files, oldest first; for each URL, the last file with a page or error for it wins
+    #[clap(value_name = "FILE", required = true)]
+    files: Vec<PathBuf>,
+
+    /// Write the merged results to this file instead of standard output
     #[clap(short, long)]
     output: Option<PathBuf>,
+}
+
+/// The `replay` subcommand.
+#[derive(Parser, Debug)]
+#[clap(name = "silent-crawler replay", bin_name = "silent-crawler replay", version,
+       about = "Request every URL in a results file again, without following links, and report the URLs that broke, \
+                changed status or changed size. Exits with status 1 when any did, 2 on errors")]
+struct ReplayArgs {
+    /// Results file written with -o in JSON; a JSON list or text file of URLs also works
+    #[clap(value_name = "RESULTS")]
+    results: PathBuf,
+
+    /// Send GET requests instead of HEAD
+    #[clap(long)]
+    get: bool,
+
+    /// Report URLs whose size changed by more than this many percent
+    #[clap(long, value_name = "PERCENT", default_value = "10")]
+    size_threshold: f64,
+
+    /// Maximum number of concurrent requests
+    #[clap(short = 'c', long, default_value = "10")]
+    concurrency: usize,
+
+    /// Maximum number of concurrent requests per host
+    #[clap(long, default_value = "2")]
+    per_host_concurrency: usize,
+
+    /// Delay between requests to the same host in seconds
+    #[clap(short, long, default_value = "0.5")]
+    wait: f64,
+
+    /// Increase a host's delay for the rest of the run each time it returns 429
+    #[clap(long)]
+    adaptive_throttle: bool,
 
     /// Ignore robots.txt restrictions
     #[clap(long)]
     ignore_robots: bool,
 
-    /// Maximum number of concurrent requests
-    #[clap(short = 'c', long, default_value = "10")]
-    concurrency: usize,
+    /// Ignore Crawl-delay from robots.txt and always use --wait
+    #[clap(long)]
+    ignore_crawl_delay: bool,
+
+    /// Save the new results to this file, in JSON
+    #[clap(short, long)]
+    output: Option<PathBuf>,
+
+    /// Print the report as text or as JSON
+    #[clap(long, value_name = "FORMAT", default_value = "text")]
+    format: DiffFormat,
+
+    #[clap(flatten)]
+    client: ClientOptions,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum DiffFormat {
+    Text,
+    Json,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum GraphFormat {
+    Dot,
+    Graphml,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum AgentRotation {
+    Request,
+    Host,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Json,
+    Csv,
+}
+
+/// Parses plain seconds ("90", "1.5") or a humantime duration ("10m", "1h 30m").
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    if let Ok(seconds) = value.parse::<f64>() {
+        return Duration::try_from_secs_f64(seconds).map_err(|err| err.to_string());
+    }
+    humantime::parse_duration(value).map_err(|err| err.to_string())
+}
+
+/// Parses a "Name: value" header.
+fn parse_header(value: &str) -> Result<(String, String), String> {
+    match value.split_once(':') {
+        Some((name, header_value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), header_value.trim().to_string()))
+        }
+        _ => Err(format!("expected \"Name: value\", got {:?}", value)),
+    }
+}
+
+/// Parses a curl-style "host:port:ip" override, with several addresses
+/// separated by commas and IPv6 addresses optionally in brackets.
+fn parse_resolve(value: &str) -> Result<(String, Vec<IpAddr>), String> {
+    let invalid = || format!("expected \"host:port:ip\", got {:?}", value);
+    let mut parts = value.splitn(3, ':');
+    let (Some(host), Some(port), Some(ips)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(invalid());
+    };
+    if host.is_empty() || port.parse::<u16>().is_err() {
+        return Err(invalid());
+    }
+    let ips = ips
+        .split(',')
+        .map(|ip| ip.trim().trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| invalid())?;
+    Ok((host.to_string(), ips))
+}
+
+/// Parses a DNS server address, on port 53 unless another is given.
+fn parse_dns_server(value: &str) -> Result<SocketAddr, String> {
+    value
+        .parse::<SocketAddr>()
+        .or_else(|_| value.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>().map(|ip| SocketAddr::new(ip, 53)))
+        .map_err(|_| format!("expected an IP address with an optional port, got {:?}", value))
+}
+
+/// Reads one entry per line, skipping blank lines and `#` comments. A
+/// path of `-` reads standard input.
+fn read_list(path: &Path) -> std::io::Result<Vec<String>> {
+    let text = if path == Path::new("-") {
+        std::io::read_to_string(std::io::stdin())?
+    } else {
+        std::fs::read_to_string(path)?
+    };
+    Ok(text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Reads a --known-urls file: the pages of a results file written with
+/// -o, or else one URL per line as with [`read_list`].
+fn read_known_urls(path: &Path) -> std::io::Result<Vec<String>> {
+    let is_results =
+        path != Path::new("-") && std::fs::read(path)?.iter().find(|byte| !byte.is_ascii_whitespace()) == Some(&b'{');
+    if !is_results {
+        return read_list(path);
+    }
+    Ok(output::read_json(path)?.pages.into_iter().map(|page| page.url).collect())
+}
+
+/// Config file read from the current directory when --config isn't given.
+const DEFAULT_CONFIG_FILE: &str = "silent-crawler.toml";
+
+/// Sections listed in the response time summary, slowest first.
+const SLOWEST_SECTIONS: usize = 10;
+
+/// Options that only make sense on the command line.
+const CLI_ONLY: &[&str] = &["config", "print_config", "help", "version"];
+
+/// The config file key for an option: its long flag name, or the argument
+/// name for the positional URLs.
+fn config_key(arg: &Arg) -> String {
+    arg.get_long().map(str::to_string).unwrap_or_else(|| arg.get_id().as_str().replace('_', "-"))
+}
+
+/// Parses the command line, taking options it doesn't set from the config
+/// file. The file's keys are the long flag names, in kebab or snake case.
+fn parse_args(cli: Vec<OsString>) -> Result<(Args, ArgMatches), Box<dyn std::error::Error>> {
+    // A lenient first pass finds the config file and the explicitly set options
+    let explicit = Args::command().ignore_errors(true).get_matches_from(&cli);
+    let path = match explicit.get_one::<PathBuf>("config") {
+        Some(path) => Some(path.clone()),
+        None => Some(PathBuf::from(DEFAULT_CONFIG_FILE)).filter(|path| path.is_file()),
+    };
+
+    let mut argv = cli.clone();
+    if let Some(path) = path {
+        let text = std::fs::read_to_string(&path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        let table: toml::Table = text.parse().map_err(|err| format!("{}: {}", path.display(), err))?;
+        let (options, urls) = config_args(&table, &explicit, &path);
+        argv.splice(1..1, options);
+        argv.extend(urls);
+    }
+
+    let matches = Args::command().get_matches_from(argv);
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    Ok((args, matches))
+}
+
+/// Turns config file entries into command-line arguments, skipping options
+/// already set on the command line or through the environment. Returns the
+/// flags and, separately, the positional URLs.
+fn config_args(table: &toml::Table, explicit: &ArgMatches, path: &Path) -> (Vec<OsString>, Vec<OsString>) {
+    let command = Args::command();
+    let arguments: Vec<&Arg> = command.get_arguments().filter(|arg| !CLI_ONLY.contains(&arg.get_id().as_str())).collect();
+    let mut options = Vec::new();
+    let mut urls = Vec::new();
+
+    for (key, value) in table {
+        let Some(arg) = arguments.iter().find(|arg| config_key(arg) == key.replace('_', "-")) else {
+            let valid: Vec<String> = arguments.iter().map(|arg| config_key(arg)).collect();
+            eprintln!("Warning: {}: unknown key {:?} (valid keys: {})", path.display(), key, valid.join(", "));
+            continue;
+        };
+        let id = arg.get_id().as_str();
+        if matches!(explicit.value_source(id), Some(ValueSource::CommandLine | ValueSource::EnvVariable)) {
+            continue;
+        }
+
+        let values: Vec<String> = match value {
+            toml::Value::Array(items) => items.iter().map(config_value).collect(),
+            value => vec![config_value(value)],
+        };
+        let flag = format!("--{}", config_key(arg));
+        match arg.get_action() {
+            ArgAction::SetTrue => match value {
+                toml::Value::Boolean(true) => options.push(flag.into()),
+                toml::Value::Boolean(false) => {}
+                _ => eprintln!("Warning: {}: {:?} must be true or false", path.display(), key),
+            },
+            ArgAction::Count => match value {
+                toml::Value::Integer(count) => options.extend((0..*count).map(|_| OsString::from(&flag))),
+                _ => eprintln!("Warning: {}: {:?} must be a number", path.display(), key),
+            },
+            ArgAction::Append if arg.get_long().is_none() => urls.extend(values.into_iter().map(OsString::from)),
+            ArgAction::Append => options.extend(values.iter().map(|value| format!("{}={}", flag, value).into())),
+            _ => match &values[..] {
+                [value] => options.push(format!("{}={}", flag, value).into()),
+                _ => eprintln!("Warning: {}: {:?} takes a single value", path.display(), key),
+            },
+        }
+    }
+    // URLs on the command line replace the configured ones
+    if explicit.value_source("urls") == Some(ValueSource::CommandLine) {
+        urls.clear();
+    }
+    if !urls.is_empty() {
+        urls.insert(0, "--".into());
+    }
+    (options, urls)
+}
+
+fn config_value(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(text) => text.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// The resolved options as a config file, defaults included. Values taken
+/// from environment variables are left out, since they may be secrets.
+fn resolved_config(matches: &ArgMatches) -> String {
+    let mut table = toml::Table::new();
+    for arg in Args::command().get_arguments() {
+        let id = arg.get_id().as_str();
+        if CLI_ONLY.contains(&id) || matches!(matches.value_source(id), None | Some(ValueSource::EnvVariable)) {
+            continue;
+        }
+        let value = match arg.get_action() {
+            ArgAction::SetTrue => toml::Value::Boolean(matches.get_flag(id)),
+            ArgAction::Count => toml::Value::Integer(matches.get_count(id).into()),
+            action => {
+                let mut values: Vec<toml::Value> = matches
+                    .get_raw(id)
+                    .into_iter()
+                    .flatten()
+                    .map(|raw| typed_value(&raw.to_string_lossy()))
+                    .collect();
+                match (action, values.len()) {
+                    (ArgAction::Append, _) => toml::Value::Array(values),
+                    (_, 1) => values.remove(0),
+                    _ => continue,
+                }
+            }
+        };
+        table.insert(config_key(arg), value);
+    }
+    toml::to_string(&table).unwrap_or_default()
+}
+
+/// Writes numbers as TOML numbers and everything else as strings.
+fn typed_value(raw: &str) -> toml::Value {
+    if let Ok(number) = raw.parse::<i64>() {
+        toml::Value::Integer(number)
+    } else if let Some(number) = raw.parse::<f64>().ok().filter(|number| number.is_finite()) {
+        toml::Value::Float(number)
+    } else {
+        toml::Value::String(raw.to_string())
+    }
 }
 
-#[derive(Serialize, Debug)]
-struct Results {
-    urls: Vec<String>,
-    directories: Vec<String>,
-    subdomains: Vec<String>,
+/// Sends the crawler's log events to stderr, or to `log_file` if given.
+/// Without `-v` only errors are logged.
+fn init_logging(verbose: u8, log_file: Option<&Path>) -> std::io::Result<()> {
+    let level = match verbose {
+        0 => Level::ERROR,
+        1 => Level::INFO,
+        2 => Level::DEBUG,
+        _ => Level::TRACE,
+    };
+    let filter = Targets::new().with_target("silent_crawler", level);
+
+    match log_file {
+        Some(path) => {
+            let file = File::create(path)?;
+            tracing_subscriber::registry()
+                .with(tracing_subscriber::fmt::layer().with_writer(Mutex::new(file)).with_ansi(false))
+                .with(filter)
+                .init();
+        }
+        None => {
+            tracing_subscriber::registry()
+                .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr).with_ansi(std::io::stderr().is_terminal()))
+                .with(filter)
+                .init();
+        }
+    }
+    Ok(())
 }
 
-struct SilentCrawler {
-    base_url: String,
-    base_domain: String,
-    max_depth: usize,
-    delay: f64,
-    client: Client,
-    visited_urls: Arc<Mutex<HashSet<String>>>,
-    directories: Arc<Mutex<HashSet<String>>>,
-    subdomains: Arc<Mutex<HashSet<String>>>,
-    disallowed_paths: Arc<Mutex<HashSet<String>>>,
-    respect_robots: bool,
-    concurrency: usize,
+/// Prints a progress line every second, overwriting it in place on a
+/// terminal. Non-interactive stderr gets a new line every ten seconds.
+async fn report_progress(progress: ProgressHandle, interactive: bool) {
+    let period = if interactive { Duration::from_secs(1) } else { Duration::from_secs(10) };
+    let mut ticker = tokio::time::interval(period);
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        let snapshot = progress.snapshot();
+        let line = format!("[{}] fetched {}, frontier {}, errors {}, {:.1} req/s",
+                           humantime::format_duration(Duration::from_secs(snapshot.elapsed.as_secs())),
+                           snapshot.fetched, snapshot.frontier, snapshot.errors, snapshot.requests_per_sec());
+        if interactive {
+            eprint!("\r\x1b[K{}", line);
+            let _ = std::io::stderr().flush();
+        } else {
+            eprintln!("{}", line);
+        }
+    }
 }
 
-impl SilentCrawler {
-    async fn new(
-        base_url: &str,
-        max_depth: usize,
-        delay: f64,
-        timeout: u64,
-        user_agent: Option<&str>,
-        respect_robots: bool,
-        concurrency: usize,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
-        // Validate and normalize the base URL
-        let mut url = base_url.to_string();
-        if !url.starts_with("http://") && !url.starts_with("https://") {
-            url = format!("http://{}", url);
-        }
-        
-        // Parse the base URL to extract domain
-        let parsed_url = Url::parse(&url)?;
-        let base_domain = parsed_url.host_str()
-            .ok_or("Invalid URL: missing domain")?
-            .to_string();
-            
-        // Create HTTP client with headers and timeout
-        let mut headers = HeaderMap::new();
-        
-        // Define default user agent if none provided
-        let ua = user_agent.unwrap_or(
-            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36"
-        );
-        
-        headers.insert(USER_AGENT, HeaderValue::from_str(ua)?);
-        headers.insert(ACCEPT, HeaderValue::from_static("text/html,application/xhtml+xml,application/xml"));
-        headers.insert(ACCEPT_LANGUAGE, HeaderValue::from_static("en-US,en;q=0.9"));
-        headers.insert(ACCEPT_ENCODING, HeaderValue::from_static("gzip, deflate"));
-        headers.insert(CONNECTION, HeaderValue::from_static("keep-alive"));
-        headers.insert(UPGRADE_INSECURE_REQUESTS, HeaderValue::from_static("1"));
-        
-        let client = Client::builder()
-            .default_headers(headers)
-            .timeout(Duration::from_secs(timeout))
-            .build()?;
-        
-        let crawler = SilentCrawler {
-            base_url: url,
-            base_domain,
-            max_depth,
-            delay,
-            client,
-            visited_urls: Arc::new(Mutex::new(HashSet::new())),
-            directories: Arc::new(Mutex::new(HashSet::new())),
-            subdomains: Arc::new(Mutex::new(HashSet::new())),
-            disallowed_paths: Arc::new(Mutex::new(HashSet::new())),
-            respect_robots,
-            concurrency,
-        };
-        
-        // Parse robots.txt if required
-        if respect_robots {
-            crawler.parse_robots_txt().await?;
-        }
-        
-        Ok(crawler)
-    }
-
-    async fn parse_robots_txt(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let robots_url = format!("{}/robots.txt", self.base_url.trim_end_matches('/'));
-        
-        match self.client.get(&robots_url).send().await {
-            Ok(response) => {
-                if response.status().is_success() {
-                    if let Ok(text) = response.text().await {
-                        let mut disallowed_paths = self.disallowed_paths.lock().unwrap();
-                        
-                        for line in text.lines() {
-                            let line = line.trim().to_lowercase();
-                            if line.starts_with("disallow:") {
-                                if let Some(path) = line.split(':').nth(1) {
-                                    let path = path.trim();
-                                    if !path.is_empty() {
-                                        disallowed_paths.insert(path.to_string());
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            Err(_) => {
-                // If we can't access robots.txt, continue with empty disallowed paths
-            }
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Without a subcommand the arguments are those of `crawl`
+    let mut cli: Vec<OsString> = std::env::args_os().collect();
+    match cli.get(1).and_then(|arg| arg.to_str()) {
+        Some("crawl") => {
+            cli.remove(1);
         }
-        
-        Ok(())
+        Some("diff") => std::process::exit(run_diff(&DiffArgs::parse_from(&cli[1..]))),
+        Some("merge") => std::process::exit(run_merge(&MergeArgs::parse_from(&cli[1..]))),
+        Some("replay") => std::process::exit(run_replay(&ReplayArgs::parse_from(&cli[1..])).await),
+        _ => {}
+    }
+
+    // Parse command line arguments, on top of the config file
+    let (args, matches) = parse_args(cli)?;
+    if args.print_config {
+        print!("{}", resolved_config(&matches));
+        return Ok(());
+    }
+    init_logging(args.verbose, args.log_file.as_deref())?;
+
+    let mut seeds = args.urls.clone();
+    if let Some(path) = &args.seeds {
+        seeds.extend(read_list(path)?);
+    }
+    let mut grep_patterns = args.grep.clone();
+    if let Some(path) = &args.grep_file {
+        grep_patterns.extend(read_list(path)?);
+    }
+    let Some((base_url, extra_seeds)) = seeds.split_first() else {
+        return Err("no seed URLs given".into());
+    };
+    match extra_seeds.len() {
+        _ if args.dry_run_scope.is_some() => {}
+        0 => println!("Starting silent crawler on {}", base_url),
+        count => println!("Starting silent crawler on {} and {} more seed(s)", base_url, count),
     }
 
-    fn is_allowed(&self, url: &str) -> bool {
-        if !self.respect_robots {
-            return true;
+    // Initialize crawler
+    let mut builder = CrawlerBuilder::new(base_url)
+        .depth(args.depth)
+        .delay(Duration::from_secs_f64(args.wait))
+        .adaptive_throttle(args.adaptive_throttle)
+        .max_redirects(args.max_redirects)
+        .respect_robots(!args.ignore_robots)
+        .respect_crawl_delay(!args.ignore_crawl_delay)
+        .respect_meta_robots(!args.ignore_meta_robots)
+        .near_duplicates(args.near_duplicate)
+        .concurrency(args.concurrency)
+        .per_host_concurrency(args.per_host_concurrency)
+        .use_sitemap(args.use_sitemap)
+        .collect_external(!args.no_external)
+        .check_external(args.check_external)
+        .follow_canonical(args.follow_canonical)
+        .skip_other_languages(args.skip_other_languages)
+        .structured_data(args.structured_data)
+        .scan_js(args.scan_js)
+        .deep_extract(args.deep_extract)
+        .follow_json(args.follow_json)
+        .parse_pdf(args.parse_pdf)
+        .harvest_emails(args.harvest_emails)
+        .grep_ignore_case(args.grep_ignore_case)
+        .scan_secrets(args.scan_secrets)
+        .common_files(args.common_files)
+        .find_sourcemaps(args.sourcemaps)
+        .probe_sourcemaps(args.probe_sourcemaps)
+        .capture_headers(args.capture_headers)
+        .security_audit(args.security_audit)
+        .head_first(args.head_first)
+        .prefer_https(args.prefer_https)
+        .proxy_max_failures(args.proxy_max_failures)
+        .send_referer(!args.no_referer)
+        .compression(!args.no_compression)
+        .record_graph(args.graph.is_some())
+        .link_sources(args.link_sources.iter().copied())
+        .fetch_assets(args.fetch_assets)
+        .render(args.render)
+        .render_concurrency(args.render_concurrency);
+    if let Some(pattern) = &args.render_pattern {
+        builder = builder.render_pattern(pattern);
+    }
+    if let Some(selector) = &args.render_wait_for {
+        builder = builder.render_wait_for(selector);
+    }
+    if let Some(timeout) = args.render_timeout {
+        builder = builder.render_timeout(timeout);
+    }
+    if let Some(path) = &args.browser_path {
+        builder = builder.browser_path(path);
+    }
+    for language in args.languages.iter().filter(|language| !language.trim().is_empty()) {
+        builder = builder.language(language);
+    }
+    for language in args.content_language.iter().filter(|language| !language.trim().is_empty()) {
+        builder = builder.content_language(language);
+    }
+    for selector in &args.volatile_selector {
+        builder = builder.volatile_selector(selector);
+    }
+    for seed in extra_seeds {
+        builder = builder.seed(seed);
+    }
+    if let Some(path) = &args.known_urls {
+        for url in read_known_urls(path)? {
+            builder = builder.known_url(url);
         }
-        
-        if let Ok(parsed_url) = Url::parse(url) {
-            let path = parsed_url.path();
-            
-            let disallowed_paths = self.disallowed_paths.lock().unwrap();
-            for disallowed in &*disallowed_paths {
-                if path.starts_with(disallowed) {
-                    return false;
-                }
-            }
+    }
+    for domain in &args.allow_domain {
+        builder = builder.allow_domain(domain);
+    }
+    if let Some(path) = &args.scope {
+        builder = builder.scope_file(path);
+    }
+    for pattern in &args.include_pattern {
+        builder = builder.include_pattern(pattern);
+    }
+    for pattern in &args.exclude_pattern {
+        builder = builder.exclude_pattern(pattern);
+    }
+    for name in &args.strip_params {
+        builder = builder.strip_param(name.trim());
+    }
+    if args.strip_tracking_params {
+        builder = builder.strip_tracking_params();
+    }
+    builder = builder
+        .sort_params(args.sort_params)
+        .fold_www(args.fold_www)
+        .any_port(args.any_port)
+        .trap_detection(!args.no_trap_detection)
+        .max_segment_repeats(args.max_segment_repeats)
+        .max_path_depth(args.max_path_depth)
+        .max_query_params(args.max_query_params)
+        .max_future_years(args.max_future_years);
+    let strategy = args.strategy.unwrap_or(if args.prioritize.is_empty() {
+        CrawlStrategy::Bfs
+    } else {
+        CrawlStrategy::Priority
+    });
+    builder = builder
+        .strategy(strategy)
+        .visited_backend(args.visited_backend)
+        .bloom_filter(args.bloom_capacity, args.bloom_fp_rate);
+    for pattern in &args.prioritize {
+        builder = builder.prioritize(pattern);
+    }
+    if let Some(max) = args.max_per_directory {
+        builder = builder.max_per_directory(max);
+    }
+    if let Some(max) = args.max_per_subdomain {
+        builder = builder.max_per_subdomain(max);
+    }
+    if let Some(max_pages) = args.max_pages {
+        builder = builder.max_pages(max_pages);
+    }
+    if let Some(max_consecutive) = args.stop_on_challenge {
+        builder = builder.stop_on_challenge(max_consecutive);
+    }
+    if let Some(max_time) = args.max_time {
+        builder = builder.max_time(max_time);
+    }
+    if let Some(bytes_per_sec) = args.max_bandwidth {
+        builder = builder.max_bandwidth(bytes_per_sec);
+    }
+    if let Some(max_bytes) = args.max_total_bytes {
+        builder = builder.max_total_bytes(max_bytes);
+    }
+    if let Some(addr) = args.serve {
+        builder = builder.serve(addr);
+    }
+    if let Some(token) = &args.serve_token {
+        builder = builder.serve_token(token);
+    }
+    if let Some(url) = &args.webhook {
+        builder = builder.webhook(url).webhook_on(args.webhook_on.iter().copied());
+    }
+    if let Some(addr) = args.metrics_addr {
+        builder = builder.metrics_addr(addr);
+    }
+    if let Some(threshold) = args.slow_threshold {
+        builder = builder.slow_threshold(Duration::from_millis(threshold));
+    }
+    if let Some(path) = &args.proxy_list {
+        builder = builder.proxies(read_list(path)?);
+    }
+    if let (Some(login_url), Some(login_data)) = (&args.login_url, &args.login_data) {
+        builder = builder.login(login_url, login_data);
+        if let Some(check) = &args.login_check {
+            builder = builder.login_check(check);
+        }
+        if let Some(selector) = &args.login_token_selector {
+            builder = builder.login_token_selector(selector);
         }
-        
-        true
     }
-
-    fn is_same_domain(&self, url: &str) -> bool {
-        if let Ok(parsed_url) = Url::parse(url) {
-            if let Some(url_domain) = parsed_url.host_str() {
-                // Check for subdomain of base domain
-                return url_domain == self.base_domain || url_domain.ends_with(&format!(".{}", self.base_domain));
-            }
+    for cookies in &args.cookie {
+        builder = builder.cookie(cookies);
+    }
+    if let Some(path) = &args.cookies_file {
+        builder = builder.cookies_file(path);
+    }
+    builder = args.client.apply(builder)?;
+    if args.random_agent {
+        builder = builder.random_user_agent();
+    } else if let Some(path) = &args.user_agent_file {
+        builder = builder.user_agents(read_list(path)?);
+    }
+    builder = builder.user_agent_per_host(args.agent_rotation == AgentRotation::Host);
+    if let Some(stream_path) = &args.stream {
+        builder = builder.stream_to(stream_path);
+    }
+    if let Some(state_path) = &args.state {
+        builder = builder.state_file(state_path);
+    }
+    if let Some(db_path) = &args.db {
+        builder = builder.database(db_path);
+    }
+    if let Some(max_body_size) = args.max_body_size {
+        builder = builder.max_body_size(max_body_size);
+    }
+    for pattern in &grep_patterns {
+        builder = builder.grep(pattern);
+    }
+    if let Some(path) = &args.secrets_rules {
+        for (line, entry) in read_list(path)?.iter().enumerate() {
+            let Some((name, pattern)) = entry.split_once(char::is_whitespace) else {
+                return Err(format!("{}: entry {}: expected \"name regex\"", path.display(), line + 1).into());
+            };
+            builder = builder.secret_rule(name, pattern.trim_start());
         }
-        false
-    }
-
-    fn normalize_url(&self, url: &str, source_url: &str) -> Result<String, ParseError> {
-        // Convert relative URL to absolute URL
-        let base_url = Url::parse(source_url)?;
-        let absolute_url = base_url.join(url)?;
-        
-        // Remove fragments
-        let mut normalized_url = absolute_url.to_string();
-        if let Some(pos) = normalized_url.find('#') {
-            normalized_url.truncate(pos);
-        }
-        
-        // Ensure URL ends with / if it's a directory-like URL (no file extension)
-        let path = absolute_url.path();
-        if !path.is_empty() && 
-           !path.split('/').last().unwrap_or("").contains('.') && 
-           !normalized_url.ends_with('/') {
-            normalized_url.push('/');
-        }
-        
-        Ok(normalized_url)
-    }
-
-    fn extract_links(&self, html_content: &str, source_url: &str) -> HashSet<String> {
-        let mut links = HashSet::new();
-        
-        // Parse HTML
-        let document = Html::parse_document(html_content);
-        
-        // Select all anchor tags with href attributes
-        let selector = Selector::parse("a[href]").unwrap();
-        
-        for element in document.select(&selector) {
-            if let Some(href) = element.value().attr("href") {
-                // Skip unwanted link types
-                if href.starts_with("javascript:") || 
-                   href.starts_with("mailto:") || 
-                   href.starts_with("tel:") || 
-                   href.starts_with('#') {
-                    continue;
-                }
-                
-                // Normalize the URL
-                if let Ok(absolute_url) = self.normalize_url(href, source_url) {
-                    // Only include URLs from the same domain
-                    if self.is_same_domain(&absolute_url) {
-                        links.insert(absolute_url);
-                    }
+    }
+    if let Some(max_snippets) = args.max_grep_snippets {
+        builder = builder.max_grep_snippets(max_snippets);
+    }
+    if let Some(path) = &args.wordlist {
+        builder = builder.wordlist(read_list(path)?).bruteforce_forbidden(args.report_forbidden);
+    }
+    if let Some(max_parameters) = args.max_parameters {
+        builder = builder.max_parameters(max_parameters);
+    }
+    if let Some(max_js_files) = args.max_js_files {
+        builder = builder.max_js_files(max_js_files);
+    }
+    if let Some(max_pagination) = args.max_pagination {
+        builder = builder.max_pagination(max_pagination);
+    }
+    if let Some(max_json_ld_size) = args.max_json_ld_size {
+        builder = builder.max_json_ld_size(max_json_ld_size);
+    }
+    if let Some(max_pdf_size) = args.max_pdf_size {
+        builder = builder.max_pdf_size(max_pdf_size);
+    }
+    if let Some(max_js_size) = args.max_js_size {
+        builder = builder.max_js_size(max_js_size);
+    }
+    if let Some(max_sourcemap_size) = args.max_sourcemap_size {
+        builder = builder.max_sourcemap_size(max_sourcemap_size);
+    }
+    if let Some(warc_path) = &args.warc {
+        builder = builder.warc(warc_path);
+    }
+    if let Some(har_path) = &args.har {
+        builder = builder.har(har_path);
+        if let Some(max_body) = args.har_max_body {
+            builder = builder.har_max_body(max_body);
+        }
+    }
+    if let Some(save_dir) = &args.save_dir {
+        builder = builder.save_to(save_dir).convert_links(args.convert_links).save_non_html(args.save_non_html);
+    }
+    if let Some(cache_dir) = &args.cache_dir {
+        builder = builder.cache_dir(cache_dir).refresh(args.refresh);
+        if let Some(max_age) = args.cache_max_age {
+            builder = builder.cache_max_age(max_age);
+        }
+        if let Some(max_size) = args.cache_max_size {
+            builder = builder.cache_max_size(max_size);
+        }
+    }
+    builder = builder.resume(args.resume);
+    if let Some(url) = &args.dry_run_scope {
+        let url = normalize_url(url.trim(), url.trim()).unwrap_or_else(|_| url.trim().to_string());
+        let verdict = builder.scope_verdict(&url)?;
+        let rule = verdict.rule.map(|rule| format!(" ({})", rule)).unwrap_or_default();
+        println!("{}: {}: {}{}", url, if verdict.in_scope { "in scope" } else { "out of scope" }, verdict.reason, rule);
+        std::process::exit(if verdict.in_scope { 0 } else { 1 });
+    }
+    let crawler = match builder.build().await {
+        Ok(crawler) => crawler,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            if let CrawlerError::SeedOutOfScope(_) = err {
+                if args.scope.is_some() {
+                    eprintln!("(the scope file leaves it out; see why with --dry-run-scope)");
+                } else {
+                    eprintln!("(add the seed's domain with --allow-domain to crawl it too)");
                 }
             }
+            std::process::exit(1);
         }
-        
-        links
-    }
+    };
 
-    fn extract_subdomain(&self, url: &str) -> Option<String> {
-        if let Ok(parsed_url) = Url::parse(url) {
-            if let Some(domain) = parsed_url.host_str() {
-                // If the domain is the same as the base domain, no subdomain
-                if domain == self.base_domain {
-                    return None;
-                }
-                
-                // Extract the subdomain part
-                if domain.ends_with(&format!(".{}", self.base_domain)) {
-                    return Some(domain[..domain.len() - self.base_domain.len() - 1].to_string());
-                }
+    // Report the delay after robots.txt has been read, since Crawl-delay may raise it
+    let delay = crawler.effective_delay().as_secs_f64();
+    println!("Max depth: {}, Delay: {}s{}, Timeout: {}s, Concurrent requests: {} ({} per host)",
+             args.depth, delay,
+             if delay > args.wait { " (from robots.txt Crawl-delay)" } else { "" },
+             args.client.timeout, args.concurrency, args.per_host_concurrency);
+    println!("Respecting robots.txt: {}", !args.ignore_robots);
+
+    // First Ctrl-C stops the crawl gracefully, a second one exits immediately
+    let stop = crawler.stop_handle();
+    let interrupts = tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            eprintln!("\nInterrupted: finishing in-flight requests (press Ctrl-C again to exit immediately)");
+            stop.stop();
+            if tokio::signal::ctrl_c().await.is_ok() {
+                std::process::exit(130);
             }
         }
-        
-        None
+    });
+
+    // Report progress on stderr so piped stdout stays clean
+    let interactive = std::io::stderr().is_terminal();
+    // Logs on stderr would garble an in-place progress line
+    let show_progress = !args.quiet && (args.verbose == 0 || args.log_file.is_some());
+    let reporter = show_progress.then(|| tokio::spawn(report_progress(crawler.progress_handle(), interactive)));
+
+    // Run the crawler
+    let results = crawler.crawl().await;
+    if let Some(reporter) = reporter {
+        reporter.abort();
+        if interactive {
+            eprint!("\r\x1b[K");
+        }
     }
 
-    fn extract_directory(&self, url: &str) -> Option<String> {
-        if let Ok(parsed_url) = Url::parse(url) {
-            let path = parsed_url.path();
-            
-            // No path or just root
-            if path.is_empty() || path == "/" {
-                return None;
-            }
-            
-            // Directory-like path (ends with /)
-            if path.ends_with('/') {
-                return Some(path.to_string());
-            }
-            
-            // File-like path, get the directory part
-            if let Some(last_slash_pos) = path.rfind('/') {
-                if last_slash_pos > 0 {  // Not just starting with /
-                    return Some(path[..=last_slash_pos].to_string());
-                }
-            }
+    // Display summary
+    println!("\nCrawl Summary:");
+    if results.timed_out {
+        println!("Time limit reached; results are partial.");
+    } else if results.byte_limit_reached {
+        println!("Download cap of {} reached; results are partial.", format_bytes(args.max_total_bytes.unwrap_or_default()));
+    } else if results.stopped_by_challenges {
+        println!("Stopped after {} bot challenges in a row; results are partial.", args.stop_on_challenge.unwrap_or_default() + 1);
+    } else if !results.completed {
+        println!("Crawl was interrupted; results are partial.");
+    }
+    if results.page_limit_reached {
+        println!("Page limit of {} reached; remaining URLs were not fetched.", args.max_pages.unwrap_or_default());
+    }
+    println!("Elapsed: {}", humantime::format_duration(Duration::from_secs(results.elapsed_ms / 1000)));
+    println!("Downloaded: {}", format_bytes(results.bytes_downloaded));
+    if results.frontier_remaining > 0 {
+        println!("Unvisited frontier URLs: {}", results.frontier_remaining);
+    }
+    if results.known_urls_skipped > 0 {
+        println!("Known URLs skipped: {}", results.known_urls_skipped);
+    }
+    println!("Total URLs discovered: {}", results.urls.len());
+    if results.seeds.len() > 1 {
+        let counts = results.seed_counts();
+        println!("Seeds: {}", results.seeds.len());
+        for seed in &results.seeds {
+            println!("  {}: {} pages", seed, counts.get(seed.as_str()).copied().unwrap_or(0));
         }
-        
-        None
-    }
-
-    async fn fetch_url(&self, url: &str) -> Option<String> {
-        match self.client.get(url).send().await {
-            Ok(response) => {
-                // Check for successful response
-                if response.status().is_success() {
-                    if let Some(content_type) = response.headers().get("content-type") {
-                        let content_type = content_type.to_str().unwrap_or("");
-                        
-                        // Only process HTML content
-                        if content_type.contains("text/html") {
-                            if let Ok(text) = response.text().await {
-                                return Some(text);
-                            }
-                        }
-                    }
-                }
-            }
-            Err(_) => {
-                // Silently handle any request errors
+    }
+    println!("Visited set ({}): ~{}", args.visited_backend, format_bytes(results.visited_set_bytes));
+    println!("Directories found: {}", results.directories.len());
+    println!("Subdomains discovered: {}", results.subdomains.len());
+    if !args.allow_domain.is_empty() {
+        for (domain, count) in &results.domains {
+            println!("  {}: {} URLs", domain, count);
+        }
+    }
+    if results.urls_per_host.len() > 1 {
+        println!("URLs per host:port:");
+        for (host, count) in &results.urls_per_host {
+            println!("  {}: {}", host, count);
+        }
+    }
+    if results.resolved_hosts.len() > 1 || !args.client.resolve.is_empty() {
+        println!("Resolved hosts: {}", results.resolved_hosts.len());
+        for (host, ips) in &results.resolved_hosts {
+            let ips: Vec<String> = ips.iter().map(|ip| ip.to_string()).collect();
+            println!("  {}: {}", host, ips.join(", "));
+        }
+    }
+    if args.use_sitemap {
+        println!("Sitemap URLs: {} ({} unreachable, error status or cut off)",
+                 results.sitemap_urls.len(), results.sitemap_failures());
+    }
+    if args.follow_json {
+        let json = results.pages.iter().filter(|page| page.is_json()).count();
+        let next = results.pages.iter().filter(|page| page.is_json() && page.source == UrlSource::Pagination).count();
+        println!("JSON responses: {} ({} next pages followed)", json, next);
+    }
+    if !results.pagination.is_empty() {
+        let pages: usize = results.pagination.iter().map(|chain| chain.pages.len()).sum();
+        println!("Pagination chains: {} ({} pages followed)", results.pagination.len(), pages);
+    }
+    if args.parse_pdf {
+        let skipped = results.documents.iter().filter(|document| document.skipped.is_some()).count();
+        println!("PDF documents: {} ({} skipped)", results.documents.len(), skipped);
+    }
+    if args.render {
+        println!("Rendered pages: {}", results.pages.iter().filter(|page| page.rendered).count());
+    }
+    let client_redirects = results.pages.iter().filter(|page| page.client_redirect.is_some()).count();
+    if client_redirects > 0 {
+        println!("Client-side redirects: {} (meta refresh or script)", client_redirects);
+    }
+    if args.scan_js {
+        let found = results.pages.iter().filter(|page| page.source == UrlSource::Javascript).count();
+        println!("Scripts scanned: {} ({} pages found through them)", results.scanned_scripts.len(), found);
+    }
+    if args.sourcemaps || args.probe_sourcemaps {
+        let sources: usize = results.sourcemaps.iter().map(|map| map.sources.len()).sum();
+        println!("Source maps found: {} ({} original files)", results.sourcemaps.len(), sources);
+    }
+    if !results.feeds.is_empty() {
+        println!("Feeds found: {}", results.feeds.len());
+    }
+    if !results.directory_listings.is_empty() {
+        let files: usize = results.directory_listings.iter().map(|listing| listing.files.len()).sum();
+        println!("Directory listings: {} ({} files listed)", results.directory_listings.len(), files);
+    }
+    if args.wordlist.is_some() {
+        println!("Brute force hits: {}", results.bruteforced.len());
+    }
+    if args.common_files {
+        println!("Sensitive files found: {}", results.sensitive_files.len());
+    }
+    if !results.parameters.is_empty() {
+        match results.parameters_dropped {
+            0 => println!("Query parameters: {}", results.parameters.len()),
+            dropped => println!("Query parameters: {} ({} uses of further names not tracked)", results.parameters.len(), dropped),
+        }
+    }
+    let forms: Vec<_> = results.forms.values().flatten().collect();
+    if !forms.is_empty() {
+        let unprotected = forms.iter().filter(|form| form.method == "POST" && form.csrf_field.is_none()).count();
+        println!("Forms found: {} ({} POST forms without a CSRF token)", forms.len(), unprotected);
+    }
+    if args.harvest_emails {
+        println!("Emails found: {}", results.emails.len());
+    }
+    if !grep_patterns.is_empty() {
+        println!("Grep matches:");
+        for pattern in &grep_patterns {
+            let pages: Vec<_> = results.matches.iter().filter(|found| &found.pattern == pattern).collect();
+            let count: usize = pages.iter().map(|found| found.count).sum();
+            println!("  {}: {} matches on {} pages", pattern, count, pages.len());
+        }
+    }
+    let hashed = results.pages.iter().filter(|page| page.content_hash.is_some()).count();
+    if hashed > 0 {
+        println!("Unique pages: {} of {} HTML pages ({} duplicate groups)",
+                 results.unique_pages(), hashed, results.duplicate_groups.len());
+    }
+    if args.near_duplicate {
+        let pages: usize = results.near_duplicate_groups.iter().map(Vec::len).sum();
+        println!("Near-duplicate groups: {} ({} pages)", results.near_duplicate_groups.len(), pages);
+    }
+    let truncated = results.pages.iter().filter(|page| page.truncated).count();
+    if truncated > 0 {
+        println!("Bodies cut at --max-body-size: {}", truncated);
+    }
+    let missing_titles = results.missing_titles();
+    if !missing_titles.is_empty() {
+        println!("Pages without a title: {}", missing_titles.len());
+    }
+    let duplicate_titles = results.duplicate_titles();
+    if !duplicate_titles.is_empty() {
+        let pages: usize = duplicate_titles.values().map(Vec::len).sum();
+        println!("Duplicate titles: {} (shared by {} pages)", duplicate_titles.len(), pages);
+    }
+    if !results.noindex_pages.is_empty() {
+        let sitemap: HashSet<&str> = results.sitemap_urls.iter().map(String::as_str).collect();
+        let listed = results.noindex_pages.iter().filter(|url| sitemap.contains(url.as_str())).count();
+        if args.use_sitemap {
+            println!("Noindex pages: {} ({} listed in the sitemap)", results.noindex_pages.len(), listed);
+        } else {
+            println!("Noindex pages: {}", results.noindex_pages.len());
+        }
+    }
+    if !args.no_external {
+        println!("External links: {} across {} domains",
+                 results.external_links.len(), results.external_domains.len());
+    }
+    if args.fetch_assets {
+        println!("Assets referenced: {} ({} broken)", results.asset_urls.len(), results.broken_assets.len());
+    } else if !results.asset_urls.is_empty() {
+        println!("Assets referenced: {}", results.asset_urls.len());
+    }
+    if !results.mixed_content.is_empty() {
+        let pages: HashSet<&str> = results.mixed_content.iter().map(|finding| finding.page.as_str()).collect();
+        println!("Mixed content: {} http resources on {} HTTPS pages", results.mixed_content.len(), pages.len());
+    }
+    if !results.broken_links.is_empty() || args.check_external {
+        let external = results.broken_links.iter().filter(|link| link.external).count();
+        println!("Broken links: {} ({} external)", results.broken_links.len(), external);
+    }
+    if !results.canonical_issues.is_empty() {
+        let mut reasons: BTreeMap<CanonicalReason, usize> = BTreeMap::new();
+        for issue in &results.canonical_issues {
+            *reasons.entry(issue.reason).or_insert(0) += 1;
+        }
+        let reasons: Vec<String> = reasons.iter().map(|(reason, count)| format!("{}: {}", reason, count)).collect();
+        println!("Canonical problems: {} ({})", results.canonical_issues.len(), reasons.join(", "));
+    }
+    if !args.content_language.is_empty() {
+        let mut languages: BTreeMap<&str, usize> = BTreeMap::new();
+        for page in results.pages.iter().filter(|page| page.is_html()) {
+            *languages.entry(page.language.as_deref().unwrap_or("undetected")).or_insert(0) += 1;
+        }
+        let mut counts: Vec<(&str, usize)> = languages.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        let counts: Vec<String> = counts.iter().map(|(language, count)| format!("{}: {}", language, count)).collect();
+        println!("Content languages: {}", counts.join(", "));
+    }
+    if !results.schema_types.is_empty() {
+        let mut counts: Vec<(&String, &usize)> = results.schema_types.iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        let counts: Vec<String> = counts.iter().map(|(kind, count)| format!("{}: {}", kind, count)).collect();
+        println!("Schema.org types: {}", counts.join(", "));
+    }
+    let invalid_json_ld: usize =
+        results.pages.iter().filter_map(|page| page.structured_data.as_ref()).map(|data| data.invalid_json_ld.len()).sum();
+    if invalid_json_ld > 0 {
+        println!("Invalid JSON-LD blocks: {}", invalid_json_ld);
+    }
+    if !results.hreflang.is_empty() {
+        println!("Pages with hreflang alternates: {}", results.hreflang.len());
+    }
+    if !results.hreflang_issues.is_empty() {
+        let mut reasons: BTreeMap<HreflangReason, usize> = BTreeMap::new();
+        for issue in &results.hreflang_issues {
+            *reasons.entry(issue.reason).or_insert(0) += 1;
+        }
+        let reasons: Vec<String> = reasons.iter().map(|(reason, count)| format!("{}: {}", reason, count)).collect();
+        println!("Hreflang problems: {} ({})", results.hreflang_issues.len(), reasons.join(", "));
+    }
+    if args.scan_secrets || args.secrets_rules.is_some() {
+        let mut rules: BTreeMap<&str, usize> = BTreeMap::new();
+        for finding in &results.secrets {
+            *rules.entry(&finding.rule).or_insert(0) += 1;
+        }
+        let rules: Vec<String> = rules.iter().map(|(rule, count)| format!("{}: {}", rule, count)).collect();
+        if rules.is_empty() {
+            println!("Secrets found: 0");
+        } else {
+            println!("Secrets found: {} ({})", results.secrets.len(), rules.join(", "));
+        }
+    }
+    let performance = &results.performance;
+    if performance.overall.pages > 0 {
+        let stats = |stats: &LatencyStats| {
+            format!("p50 {} ms, p90 {} ms, p99 {} ms", stats.p50_ms, stats.p90_ms, stats.p99_ms)
+        };
+        println!("Response times: {} (time to first byte p50 {} ms, p90 {} ms, p99 {} ms)",
+                 stats(&performance.overall), performance.overall.ttfb_p50_ms,
+                 performance.overall.ttfb_p90_ms, performance.overall.ttfb_p99_ms);
+        if performance.sections.len() > 1 {
+            let mut sections: Vec<_> = performance.sections.iter().collect();
+            sections.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.p90_ms));
+            for (section, section_stats) in sections.into_iter().take(SLOWEST_SECTIONS) {
+                println!("  {}: {} ({} pages)", section, stats(section_stats), section_stats.pages);
             }
         }
-        
-        None
-    }
-
-    async fn crawl(&self) -> Results {
-        // Start crawling from base URL
-        self.crawl_concurrent(&self.base_url, 0).await;
-        
-        // Prepare and return results
-        let visited_urls = self.visited_urls.lock().unwrap();
-        let mut urls: Vec<String> = visited_urls.iter().cloned().collect();
-        urls.sort();
-        
-        let directories = self.directories.lock().unwrap();
-        let mut dirs: Vec<String> = directories.iter().cloned().collect();
-        dirs.sort();
-        
-        let subdomains = self.subdomains.lock().unwrap();
-        let mut subs: Vec<String> = subdomains.iter().cloned().collect();
-        subs.sort();
-        
-        Results {
-            urls,
-            directories: dirs,
-            subdomains: subs,
-        }
-    }
-
-    async fn crawl_concurrent(&self, start_url: &str, depth: usize) {
-        // Don't crawl beyond max depth
-        if depth > self.max_depth {
-            return;
-        }
-        
-        // Initialize queue with start URL if valid
-        let mut queue = FuturesUnordered::new();
-        
-        // Check if the URL is allowed and not already visited
-        {
-            let visited = self.visited_urls.lock().unwrap();
-            if !visited.contains(start_url) && self.is_allowed(start_url) {
-                queue.push(self.process_url(start_url.to_string(), depth));
+    }
+    if let Some(threshold) = args.slow_threshold {
+        println!("Slow pages (at least {} ms): {}", threshold, results.slow_pages.len());
+    }
+    if let Some(audit) = &results.security_audit {
+        println!("Security headers missing ({} HTML pages):", audit.pages);
+        for (header, count) in &audit.missing {
+            println!("  {}: {}", header, count);
+        }
+        if !audit.worst_offenders.is_empty() {
+            println!("  Worst pages:");
+            for offender in &audit.worst_offenders {
+                println!("    {} (missing {})", offender.url, offender.missing.join(", "));
             }
         }
-        
-        // Process the queue with bounded concurrency
-        while let Some(next_urls) = queue.next().await {
-            // Add new discovered URLs to the queue if not at max depth
-            if depth < self.max_depth {
-                for url in next_urls {
-                    let visited = self.visited_urls.lock().unwrap();
-                    if !visited.contains(&url) && self.is_allowed(&url) {
-                        queue.push(self.process_url(url, depth + 1));
-                        
-                        // Limit concurrent tasks
-                        if queue.len() >= self.concurrency {
-                            break;
-                        }
-                    }
-                }
+        for (header, values) in [("Server", &audit.servers), ("X-Powered-By", &audit.powered_by)] {
+            if !values.is_empty() {
+                let values: Vec<String> = values.iter().map(|(value, count)| format!("{} ({})", value, count)).collect();
+                println!("{}: {}", header, values.join(", "));
             }
         }
     }
-
-    async fn process_url(&self, url: String, _depth: usize) -> Vec<String> {
-        // Add URL to visited set
-        {
-            let mut visited = self.visited_urls.lock().unwrap();
-            visited.insert(url.clone());
+    if !results.skipped.is_empty() {
+        println!("Skipped by URL patterns: {}", results.skipped.len());
+    }
+    if !results.traps.is_empty() {
+        let reasons: Vec<String> = results
+            .trap_counts()
+            .iter()
+            .map(|(reason, count)| format!("{}: {}", reason, count))
+            .collect();
+        println!("Trap-skipped URLs: {} ({})", results.traps.len(), reasons.join(", "));
+    }
+    if !results.budgets_exhausted.is_empty() {
+        println!("Crawl budget used up (coverage incomplete):");
+        for hit in &results.budgets_exhausted {
+            println!("  {} {}: {} URLs skipped", hit.kind, hit.bucket, hit.skipped);
         }
-        
-        // Extract and store subdomain if present
-        if let Some(subdomain) = self.extract_subdomain(&url) {
-            let mut subdomains = self.subdomains.lock().unwrap();
-            subdomains.insert(subdomain);
+    }
+    if let Some(host) = &results.canonical_host {
+        println!("Canonical host (from redirects): {}", host);
+    }
+    if !results.hsts_hosts.is_empty() {
+        let preload = results.hsts_hosts.iter().filter(|hsts| hsts.preload).count();
+        println!("HSTS hosts: {} ({} asking for preload)", results.hsts_hosts.len(), preload);
+    }
+    if results.throttle_events > 0 {
+        println!("Throttled by server: {} times", results.throttle_events);
+    }
+    for proxy in &results.proxies {
+        println!("Proxy {}: {} requests, {:.0}% failed{}", proxy.proxy, proxy.requests,
+                 proxy.failure_rate() * 100.0, if proxy.benched { " (benched)" } else { "" });
+    }
+    println!("Errors: {}", results.errors.len());
+    for (kind, count) in results.error_counts() {
+        println!("  {}: {}", kind, count);
+        if kind == ErrorKind::Dns {
+            println!("    (hosts: {})", results.unresolved_hosts().join(", "));
         }
-        
-        // Extract and store directory if present
-        if let Some(directory) = self.extract_directory(&url) {
-            let mut directories = self.directories.lock().unwrap();
-            directories.insert(directory);
+        if kind == ErrorKind::Tls {
+            println!("    (certificate problems: use --ca-cert to trust the issuer, or --insecure to skip verification)");
         }
-        
-        // Add a small delay between requests
-        let jitter = rand::rng().random_range(0.0..0.5);
-        sleep(Duration::from_secs_f64(self.delay + jitter)).await;
-        
-        // Fetch page content
-        if let Some(html_content) = self.fetch_url(&url).await {
-            // Extract links from the page
-            return self.extract_links(&html_content, &url).into_iter().collect();
+    }
+
+    if let Some(graph_path) = &args.graph {
+        let is_graphml_path = graph_path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("graphml"));
+        let format = args.graph_format.unwrap_or(if is_graphml_path { GraphFormat::Graphml } else { GraphFormat::Dot });
+        match format {
+            GraphFormat::Dot => output::write_dot(&results, graph_path)?,
+            GraphFormat::Graphml => output::write_graphml(&results, graph_path)?,
         }
-        
-        Vec::new()
+        println!("Link graph ({} edges) saved to {}", results.graph.len(), graph_path.display());
+    }
+    if let Some(warc_path) = &args.warc {
+        println!("WARC records appended to {}", warc_path.display());
+    }
+    if let Some(har_path) = &args.har {
+        println!("HAR saved to {}", har_path.display());
+    }
+    if args.cache_dir.is_some() {
+        let cached = results.pages.iter().filter(|page| page.from_cache).count();
+        println!("Not modified, from the cache: {} of {} pages", cached, results.pages.len());
+    }
+    if let Some(save_dir) = &args.save_dir {
+        println!("Mirror: {} files in {}{}", results.saved_files, save_dir.display(),
+                 if args.convert_links { " (links converted)" } else { "" });
     }
-}
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Parse command line arguments
-    let args = Args::parse();
-    
-    println!("Starting silent crawler on {}", args.url);
-    println!("Max depth: {}, Delay: {}s, Timeout: {}s, Concurrent requests: {}", 
-             args.depth, args.wait, args.timeout, args.concurrency);
-    println!("Respecting robots.txt: {}", !args.ignore_robots);
-    
-    // Initialize crawler
-    let crawler = SilentCrawler::new(
-        &args.url,
-        args.depth,
-        args.wait,
-        args.timeout,
-        args.user_agent.as_deref(),
-        !args.ignore_robots,
-        args.concurrency,
-    ).await?;
-    
-    // Run the crawler
-    let results = crawler.crawl().await;
-    
-    // Display summary
-    println!("\nCrawl Summary:");
-    println!("Total URLs discovered: {}", results.urls.len());
-    println!("Directories found: {}", results.directories.len());
-    println!("Subdomains discovered: {}", results.subdomains.len());
-    
     // Output detailed results if requested
     if let Some(output_path) = args.output {
-        let json = serde_json::to_string_pretty(&results)?;
-        let mut file = File::create(output_path.clone())?;
-        file.write_all(json.as_bytes())?;
+        let is_csv_path = output_path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+        let format = args.format.unwrap_or(if is_csv_path { OutputFormat::Csv } else { OutputFormat::Json });
+        match format {
+            OutputFormat::Json => output::write_json(&results, &output_path)?,
+            OutputFormat::Csv => {
+                for extra_path in output::write_csv(&results, &output_path)? {
+                    println!("Saved {}", extra_path.display());
+                }
+            }
+        }
         println!("\nDetailed results saved to {}", output_path.display());
     } else {
         // Print results to stdout
@@ -451,12 +1747,212 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         for directory in &results.directories {
             println!("  {}", directory);
         }
-        
+
         println!("\nDiscovered Subdomains:");
         for subdomain in &results.subdomains {
             println!("  {}", subdomain);
         }
+
+        if !results.broken_links.is_empty() {
+            println!("\nBroken Links:");
+            for link in &results.broken_links {
+                println!("  {} ({})", link.url, link.message);
+                for referrer in &link.referrers {
+                    println!("    linked from {}", referrer);
+                }
+            }
+        }
+    }
+
+    // Last, so it isn't lost above the rest of the summary
+    let challenged = results.pages.iter().filter(|page| page.challenged).count();
+    if challenged > 0 {
+        eprintln!(
+            "\nWARNING: {} of {} responses were bot challenge or WAF block pages, not the real pages. \
+             The site is probably blocking the crawler; see \"challenged\" in the page records.",
+            challenged,
+            results.pages.len()
+        );
+    }
+
+    // The API serves the results until the user is done with them
+    if let Some(addr) = args.serve {
+        interrupts.abort();
+        eprintln!("\nCrawl finished; still serving the results at http://{}/results. Press Ctrl-C to exit.", addr);
+        tokio::signal::ctrl_c().await?;
     }
-    
+
+    if args.fail_on_broken && !results.broken_links.is_empty() {
+        std::process::exit(1);
+    }
+
     Ok(())
 }
+
+/// Prints the differences between two results files and returns the exit
+/// status: 0 when they match, 1 when they differ and 2 when they can't be
+/// compared.
+fn run_diff(args: &DiffArgs) -> i32 {
+    let diff = match diff::diff_files(&args.old, &args.new) {
+        Ok(diff) => diff,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            return 2;
+        }
+    };
+    match args.format {
+        DiffFormat::Json => println!("{}", serde_json::to_string_pretty(&diff).unwrap_or_default()),
+        DiffFormat::Text if diff.is_empty() => println!("No differences."),
+        DiffFormat::Text => {
+            for (title, marker, urls) in [
+                ("URLs added", "+", &diff.urls_added),
+                ("URLs removed", "-", &diff.urls_removed),
+                ("New subdomains", "+", &diff.new_subdomains),
+                ("New directories", "+", &diff.new_directories),
+            ] {
+                if !urls.is_empty() {
+                    println!("{} ({}):", title, urls.len());
+                    for url in urls {
+                        println!("  {} {}", marker, url);
+                    }
+                }
+            }
+            if !diff.status_changed.is_empty() {
+                println!("Status changed ({}):", diff.status_changed.len());
+                for change in &diff.status_changed {
+                    println!("  {}: {} -> {}", change.url, format_status(change.old), format_status(change.new));
+                }
+            }
+        }
+    }
+    i32::from(!diff.is_empty())
+}
+
+/// Writes the merged results files to `--output` or standard output and
+/// returns the exit status.
+fn run_merge(args: &MergeArgs) -> i32 {
+    let results = match merge::merge_files(&args.files) {
+        Ok(results) => results,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            return 1;
+        }
+    };
+    let written = match &args.output {
+        Some(path) => output::write_json(&results, path).map_err(|err| format!("{}: {}", path.display(), err)),
+        None => serde_json::to_string_pretty(&results).map(|json| println!("{}", json)).map_err(|err| err.to_string()),
+    };
+    if let Err(err) = written {
+        eprintln!("error: {}", err);
+        return 1;
+    }
+    if let Some(path) = &args.output {
+        eprintln!("Merged {} files ({} URLs, {} pages) into {}",
+                  args.files.len(), results.urls.len(), results.pages.len(), path.display());
+    }
+    0
+}
+
+/// Requests the URLs of a results file again, prints what broke or
+/// changed and returns the exit status: 0 when nothing did, 1 when
+/// something did and 2 on errors.
+async fn run_replay(args: &ReplayArgs) -> i32 {
+    match replay(args).await {
+        Ok(changed) => i32::from(changed),
+        Err(err) => {
+            eprintln!("error: {}", err);
+            2
+        }
+    }
+}
+
+/// Whether anything broke or changed.
+async fn replay(args: &ReplayArgs) -> Result<bool, Box<dyn std::error::Error>> {
+    init_logging(0, None)?;
+    let old = replay::read_input(&args.results)?;
+    let Some(base_url) = old.seeds.first().or(old.urls.first()) else {
+        return Err(format!("{}: no URLs to replay", args.results.display()).into());
+    };
+
+    // Only the listed URLs are fetched, whichever hosts they are on
+    let mut builder = CrawlerBuilder::new(base_url)
+        .depth(0)
+        .delay(Duration::from_secs_f64(args.wait))
+        .concurrency(args.concurrency)
+        .per_host_concurrency(args.per_host_concurrency)
+        .adaptive_throttle(args.adaptive_throttle)
+        .respect_robots(!args.ignore_robots)
+        .respect_crawl_delay(!args.ignore_crawl_delay)
+        .collect_external(false)
+        .head_only(!args.get)
+        // Sizes from HEAD are the declared Content-Length, which compression would change
+        .compression(args.get);
+    builder = args.client.apply(builder)?;
+    let hosts: BTreeSet<String> = old
+        .urls
+        .iter()
+        .filter_map(|url| Url::parse(url).ok()?.host_str().map(str::to_string))
+        .collect();
+    for host in &hosts {
+        builder = builder.allow_domain(host);
+    }
+    for url in &old.urls {
+        builder = builder.seed(url);
+    }
+    let crawler = builder.build().await?;
+    eprintln!("Replaying {} URLs from {}", old.urls.len(), args.results.display());
+    let new = crawler.crawl().await;
+    if let Some(path) = &args.output {
+        output::write_json(&new, path)?;
+    }
+
+    let report = replay::compare(&old, &new, args.size_threshold);
+    match args.format {
+        DiffFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+        DiffFormat::Text if report.is_empty() => println!("No changes ({} URLs checked).", report.checked),
+        DiffFormat::Text => {
+            println!("Checked {} URLs.", report.checked);
+            for (title, changes) in [("Broken", &report.broken), ("Status changed", &report.status_changed)] {
+                if !changes.is_empty() {
+                    println!("{} ({}):", title, changes.len());
+                    for change in changes {
+                        println!("  {}: {} -> {}", change.url, format_status(change.old), format_status(change.new));
+                    }
+                }
+            }
+            if !report.size_changed.is_empty() {
+                println!("Size changed by more than {}% ({}):", args.size_threshold, report.size_changed.len());
+                for change in &report.size_changed {
+                    println!("  {}: {} -> {} ({:+.0}%)", change.url, format_bytes(change.old), format_bytes(change.new), change.percent);
+                }
+            }
+            if !report.failing.is_empty() {
+                println!("Failing, with no earlier status ({}):", report.failing.len());
+                for failing in &report.failing {
+                    println!("  {}: {}", failing.url, format_status(failing.status));
+                }
+            }
+        }
+    }
+    Ok(!report.is_empty())
+}
+
+/// A status code, or "failed" for requests that got no response.
+fn format_status(status: Option<u16>) -> String {
+    status.map_or_else(|| "failed".to_string(), |status| status.to_string())
+}
+
+/// Formats a byte count with a binary unit, e.g. `12.3 MiB`.
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
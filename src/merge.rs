@@ -0,0 +1,229 @@
+//! Combining the results of several crawls into one deduplicated set.
+//!
+//! Files are taken oldest first. Lists of URLs are unioned; for records
+//! about one URL, such as its page and errors, the last file that has any
+//! wins. Sections computed from the pages, like the duplicate groups, the
+//! response times and the security audit, are computed again.
+
+use crate::crawler::{duplicates, hreflang_issues, pagination_chains, urls_per_host, PARAMETER_SAMPLES};
+use crate::extract::unicode_host;
+use crate::output::read_json;
+use crate::results::{
+    BrokenLink, Form, ParameterStats, ProxyStats, Results, RESULTS_SCHEMA_VERSION,
+};
+use crate::security::security_audit;
+use crate::structured::schema_types;
+use crate::timing::{performance, slow_pages};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+use url::Url;
+
+/// Reads the results files at `paths`, oldest first, and merges them.
+pub fn merge_files<P: AsRef<Path>>(paths: &[P]) -> io::Result<Results> {
+    let all = paths.iter().map(|path| read_json(path.as_ref())).collect::<io::Result<Vec<_>>>()?;
+    Ok(merge_results(&all))
+}
+
+/// Merges the results of several crawls, oldest first.
+pub fn merge_results(all: &[Results]) -> Results {
+    // The last file with a page or error for a URL has its current state
+    let mut owner: HashMap<&str, usize> = HashMap::new();
+    for (index, results) in all.iter().enumerate() {
+        for url in results.pages.iter().map(|page| &page.url).chain(results.errors.iter().map(|error| &error.url)) {
+            owner.insert(url, index);
+        }
+    }
+    let owned = |index: usize, url: &str| owner.get(url) == Some(&index);
+    let mut pages: Vec<_> = all
+        .iter()
+        .enumerate()
+        .flat_map(|(index, results)| results.pages.iter().filter(move |page| owned(index, &page.url)))
+        .cloned()
+        .collect();
+    pages.sort_by(|a, b| a.url.cmp(&b.url));
+    let mut errors: Vec<_> = all
+        .iter()
+        .enumerate()
+        .flat_map(|(index, results)| results.errors.iter().filter(move |error| owned(index, &error.url)))
+        .cloned()
+        .collect();
+    errors.sort_by(|a, b| a.url.cmp(&b.url));
+
+    let urls = union(all, |results| &results.urls);
+    let mut depths: BTreeMap<String, usize> = BTreeMap::new();
+    for (url, &depth) in all.iter().flat_map(|results| &results.depths) {
+        let merged = depths.entry(url.clone()).or_insert(depth);
+        *merged = (*merged).min(depth);
+    }
+    let domains = all
+        .iter()
+        .flat_map(|results| results.domains.keys())
+        // Files from before hosts were reported in Unicode have punycode domains
+        .map(|domain| unicode_host(domain))
+        .map(|domain| {
+            let suffix = format!(".{}", domain);
+            let count = urls
+                .iter()
+                .filter_map(|url| Url::parse(url).ok())
+                .filter_map(|url| url.host_str().map(unicode_host))
+                .filter(|host| *host == domain || host.ends_with(&suffix))
+                .count();
+            (domain, count)
+        })
+        .collect();
+
+    let external_links = union(all, |results| &results.external_links);
+    let mut external_domains: BTreeMap<String, usize> = BTreeMap::new();
+    for link in &external_links {
+        if let Some(host) = Url::parse(link).ok().and_then(|url| url.host_str().map(unicode_host)) {
+            *external_domains.entry(host).or_insert(0) += 1;
+        }
+    }
+
+    let assets: BTreeMap<String, Vec<String>> = latest_entries(all, |results| &results.assets);
+    let asset_urls = assets.values().flatten().cloned().collect::<BTreeSet<_>>().into_iter().collect();
+
+    // Internal broken links are only still broken if the merged errors say so
+    let broken: HashSet<&str> = errors.iter().map(|error| error.url.as_str()).collect();
+    let broken_links: Vec<BrokenLink> = latest_by(all, |results| &results.broken_links, |link| link.url.clone())
+        .into_iter()
+        .filter(|link| link.external || broken.contains(link.url.as_str()))
+        .collect();
+
+    // The smallest listed time is the closest to the threshold that was used
+    let slow_threshold = all.iter().flat_map(|results| &results.slow_pages).map(|page| page.elapsed_ms).min();
+    let (duplicate_groups, near_duplicate_groups) = duplicates(&pages);
+    let hreflang = latest_entries(all, |results| &results.hreflang);
+
+    Results {
+        schema_version: RESULTS_SCHEMA_VERSION,
+        completed: all.iter().all(|results| results.completed),
+        page_limit_reached: all.iter().any(|results| results.page_limit_reached),
+        timed_out: all.iter().any(|results| results.timed_out),
+        stopped_by_challenges: all.iter().any(|results| results.stopped_by_challenges),
+        byte_limit_reached: all.iter().any(|results| results.byte_limit_reached),
+        elapsed_ms: all.iter().map(|results| results.elapsed_ms).sum(),
+        bytes_downloaded: all.iter().map(|results| results.bytes_downloaded).sum(),
+        frontier_remaining: all.iter().map(|results| results.frontier_remaining).sum(),
+        known_urls_skipped: all.iter().map(|results| results.known_urls_skipped).sum(),
+        seeds: {
+            let mut seen = HashSet::new();
+            all.iter().flat_map(|results| &results.seeds).filter(|seed| seen.insert(*seed)).cloned().collect()
+        },
+        depths,
+        directories: union(all, |results| &results.directories),
+        subdomains: union(all, |results| &results.subdomains),
+        domains,
+        urls_per_host: urls_per_host(&urls),
+        noindex_pages: pages.iter().filter(|page| page.noindex).map(|page| page.url.clone()).collect(),
+        sitemap_urls: union(all, |results| &results.sitemap_urls),
+        truncated_sitemaps: union(all, |results| &results.truncated_sitemaps),
+        feeds: union(all, |results| &results.feeds),
+        directory_listings: latest_by(all, |results| &results.directory_listings, |listing| listing.url.clone()),
+        documents: latest_by(all, |results| &results.documents, |document| document.url.clone()),
+        external_links,
+        external_domains,
+        skipped: latest_by(all, |results| &results.skipped, |skipped| skipped.url.clone()),
+        traps: latest_by(all, |results| &results.traps, |trap| trap.url.clone()),
+        budgets_exhausted: latest_by(all, |results| &results.budgets_exhausted, |hit| (hit.kind, hit.bucket.clone())),
+        graph: union(all, |results| &results.graph),
+        assets,
+        forms: merge_forms(all),
+        parameters: merge_parameters(all),
+        parameters_dropped: all.iter().map(|results| results.parameters_dropped).max().unwrap_or_default(),
+        asset_urls,
+        emails: union(all, |results| &results.emails),
+        scanned_scripts: union(all, |results| &results.scanned_scripts),
+        bruteforced: latest_by(all, |results| &results.bruteforced, |hit| hit.url.clone()),
+        sensitive_files: latest_by(all, |results| &results.sensitive_files, |file| file.url.clone()),
+        sourcemaps: latest_by(all, |results| &results.sourcemaps, |map| (map.script.clone(), map.url.clone())),
+        broken_assets: latest_by(all, |results| &results.broken_assets, |asset| asset.url.clone()),
+        secrets: union(all, |results| &results.secrets),
+        matches: latest_by(all, |results| &results.matches, |found| (found.url.clone(), found.pattern.clone())),
+        mixed_content: union(all, |results| &results.mixed_content),
+        broken_links,
+        canonical_issues: latest_by(all, |results| &results.canonical_issues, |issue| issue.page.clone()),
+        schema_types: schema_types(&pages),
+        hreflang_issues: hreflang_issues(&hreflang, &pages, &errors),
+        hreflang,
+        security_audit: all.iter().any(|results| results.security_audit.is_some()).then(|| security_audit(&pages)),
+        performance: performance(&pages),
+        slow_pages: slow_threshold
+            .map(|threshold| slow_pages(&pages, Duration::from_millis(threshold)))
+            .unwrap_or_default(),
+        pagination: pagination_chains(&pages),
+        duplicate_groups,
+        near_duplicate_groups,
+        saved_files: all.iter().map(|results| results.saved_files).max().unwrap_or_default(),
+        visited_set_bytes: all.iter().map(|results| results.visited_set_bytes).max().unwrap_or_default(),
+        proxies: merge_proxies(all),
+        resolved_hosts: latest_entries(all, |results| &results.resolved_hosts),
+        canonical_host: all.iter().rev().find_map(|results| results.canonical_host.clone()),
+        hsts_hosts: latest_by(all, |results| &results.hsts_hosts, |hsts| hsts.host.clone()),
+        throttle_events: all.iter().map(|results| results.throttle_events).sum(),
+        urls,
+        pages,
+        errors,
+    }
+}
+
+/// The sorted, deduplicated items of a list in every file.
+fn union<T: Ord + Clone>(all: &[Results], list: impl Fn(&Results) -> &Vec<T>) -> Vec<T> {
+    all.iter().flat_map(list).cloned().collect::<BTreeSet<_>>().into_iter().collect()
+}
+
+/// The records of a list in every file, one per key, the last file's
+/// winning, sorted by key.
+fn latest_by<T: Clone, K: Ord>(all: &[Results], list: impl Fn(&Results) -> &Vec<T>, key: impl Fn(&T) -> K) -> Vec<T> {
+    let mut merged = BTreeMap::new();
+    for record in all.iter().flat_map(list) {
+        merged.insert(key(record), record.clone());
+    }
+    merged.into_values().collect()
+}
+
+/// The entries of a map in every file, the last file's winning.
+fn latest_entries<V: Clone>(all: &[Results], map: impl Fn(&Results) -> &BTreeMap<String, V>) -> BTreeMap<String, V> {
+    all.iter().flat_map(map).map(|(key, value)| (key.clone(), value.clone())).collect()
+}
+
+/// Forms by page, the last file's winning, each form listed once even if
+/// another crawl filed it under a different page.
+fn merge_forms(all: &[Results]) -> BTreeMap<String, Vec<Form>> {
+    let mut seen = HashSet::new();
+    latest_entries(all, |results| &results.forms)
+        .into_iter()
+        .map(|(page, forms)| (page, forms.into_iter().filter(|form| seen.insert(form.hash.clone())).collect::<Vec<_>>()))
+        .filter(|(_, forms)| !forms.is_empty())
+        .collect()
+}
+
+/// Parameter counts don't add up across crawls of one site, so the highest
+/// is kept, with sample URLs from every file.
+fn merge_parameters(all: &[Results]) -> BTreeMap<String, ParameterStats> {
+    let mut merged: BTreeMap<String, ParameterStats> = BTreeMap::new();
+    for (name, stats) in all.iter().flat_map(|results| &results.parameters) {
+        let entry = merged.entry(name.clone()).or_insert_with(|| ParameterStats { count: 0, sample_urls: Vec::new() });
+        entry.count = entry.count.max(stats.count);
+        for url in &stats.sample_urls {
+            if entry.sample_urls.len() < PARAMETER_SAMPLES && !entry.sample_urls.contains(url) {
+                entry.sample_urls.push(url.clone());
+            }
+        }
+    }
+    merged
+}
+
+/// Proxy request counts summed, with the last file's benched state.
+fn merge_proxies(all: &[Results]) -> Vec<ProxyStats> {
+    let mut merged: BTreeMap<String, ProxyStats> = BTreeMap::new();
+    for stats in all.iter().flat_map(|results| &results.proxies) {
+        let entry = merged.entry(stats.proxy.clone()).or_insert_with(|| ProxyStats { requests: 0, failures: 0, ..stats.clone() });
+        entry.requests += stats.requests;
+        entry.failures += stats.failures;
+        entry.benched = stats.benched;
+    }
+    merged.into_values().collect()
+}
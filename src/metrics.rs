@@ -0,0 +1,182 @@
+//! Prometheus metrics for a running crawl, and the HTTP server exposing
+//! them at `/metrics`.
+//!
+//! The crawl updates the counters as requests complete; each scrape renders
+//! them in the Prometheus text format.
+
+use crate::progress::Counters;
+use crate::results::ErrorKind;
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::header::CONTENT_TYPE;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use hyper_util::server::graceful::GracefulShutdown;
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio::time::timeout;
+use tracing::{debug, info};
+
+/// Upper bounds of the response time histogram buckets, in milliseconds.
+const RESPONSE_TIME_BUCKETS: [u64; 8] = [50, 100, 250, 500, 1000, 2500, 5000, 10000];
+
+/// How long scrapes in progress may take to finish once the crawl is done.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+/// Counters for the crawl metrics that [`Counters`] doesn't track.
+#[derive(Debug, Default)]
+pub(crate) struct Metrics {
+    progress: Arc<Counters>,
+    bytes: AtomicU64,
+    statuses: Mutex<BTreeMap<u16, u64>>,
+    errors: Mutex<BTreeMap<ErrorKind, u64>>,
+    /// Responses per histogram bucket, the last one for those slower than
+    /// every bound
+    response_times: [AtomicU64; RESPONSE_TIME_BUCKETS.len() + 1],
+    response_time_sum_ms: AtomicU64,
+}
+
+impl Metrics {
+    pub fn new(progress: Arc<Counters>) -> Self {
+        Metrics { progress, ..Metrics::default() }
+    }
+
+    /// Counts a response: its status and its time.
+    pub fn record_response(&self, status: u16, elapsed: Duration) {
+        *self.statuses.lock().unwrap().entry(status).or_insert(0) += 1;
+        let elapsed_ms = elapsed.as_millis() as u64;
+        let bucket = RESPONSE_TIME_BUCKETS.iter().position(|&bound| elapsed_ms <= bound).unwrap_or(RESPONSE_TIME_BUCKETS.len());
+        self.response_times[bucket].fetch_add(1, Ordering::Relaxed);
+        self.response_time_sum_ms.fetch_add(elapsed_ms, Ordering::Relaxed);
+    }
+
+    /// Counts body bytes read, from any response.
+    pub fn record_bytes(&self, bytes: usize) {
+        self.bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_error(&self, kind: ErrorKind) {
+        *self.errors.lock().unwrap().entry(kind).or_insert(0) += 1;
+    }
+
+    /// The metrics in the Prometheus text exposition format.
+    fn render(&self) -> String {
+        let mut text = String::new();
+        describe(&mut text, "pages_fetched_total", "counter", "URLs fetched, successfully or not.");
+        let _ = writeln!(text, "silent_crawler_pages_fetched_total {}", self.progress.fetched.load(Ordering::Relaxed));
+        describe(&mut text, "frontier_size", "gauge", "URLs queued or in flight.");
+        let _ = writeln!(text, "silent_crawler_frontier_size {}", self.progress.frontier.load(Ordering::Relaxed));
+        describe(&mut text, "bytes_downloaded_total", "counter", "Response body bytes downloaded.");
+        let _ = writeln!(text, "silent_crawler_bytes_downloaded_total {}", self.bytes.load(Ordering::Relaxed));
+
+        describe(&mut text, "errors_total", "counter", "Failed requests and error statuses, by kind.");
+        for (kind, count) in self.errors.lock().unwrap().iter() {
+            let _ = writeln!(text, "silent_crawler_errors_total{{kind=\"{}\"}} {}", kind, count);
+        }
+        describe(&mut text, "responses_total", "counter", "Responses received, by status code.");
+        for (status, count) in self.statuses.lock().unwrap().iter() {
+            let _ = writeln!(text, "silent_crawler_responses_total{{status=\"{}\"}} {}", status, count);
+        }
+
+        describe(&mut text, "response_time_seconds", "histogram", "Time from sending a request to the end of its body.");
+        let mut cumulative = 0;
+        for (index, bucket) in self.response_times.iter().enumerate() {
+            cumulative += bucket.load(Ordering::Relaxed);
+            let bound = match RESPONSE_TIME_BUCKETS.get(index) {
+                Some(&bound) => (bound as f64 / 1000.0).to_string(),
+                None => "+Inf".to_string(),
+            };
+            let _ = writeln!(text, "silent_crawler_response_time_seconds_bucket{{le=\"{}\"}} {}", bound, cumulative);
+        }
+        let sum = self.response_time_sum_ms.load(Ordering::Relaxed) as f64 / 1000.0;
+        let _ = writeln!(text, "silent_crawler_response_time_seconds_sum {}", sum);
+        let _ = writeln!(text, "silent_crawler_response_time_seconds_count {}", cumulative);
+        text
+    }
+}
+
+/// Writes the `# HELP` and `# TYPE` lines of the metric `name`.
+fn describe(text: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(text, "# HELP silent_crawler_{} {}", name, help);
+    let _ = writeln!(text, "# TYPE silent_crawler_{} {}", name, kind);
+}
+
+/// The metrics server, running until [`MetricsServer::stop`].
+pub(crate) struct MetricsServer {
+    shutdown: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+}
+
+impl MetricsServer {
+    /// Starts answering scrapes on `listener`.
+    pub fn start(listener: TcpListener, metrics: Arc<Metrics>) -> Self {
+        let (shutdown, stopped) = oneshot::channel();
+        if let Ok(addr) = listener.local_addr() {
+            info!(address = %addr, "serving metrics");
+        }
+        MetricsServer { shutdown, task: tokio::spawn(serve(listener, metrics, stopped)) }
+    }
+
+    /// Stops accepting connections and waits briefly for the open ones to
+    /// finish.
+    pub async fn stop(self) {
+        let _ = self.shutdown.send(());
+        let _ = self.task.await;
+    }
+}
+
+async fn serve(listener: TcpListener, metrics: Arc<Metrics>, mut stopped: oneshot::Receiver<()>) {
+    let graceful = GracefulShutdown::new();
+    loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(err) => {
+                    debug!(error = %err, "metrics connection failed");
+                    continue;
+                }
+            },
+            _ = &mut stopped => break,
+        };
+        let metrics = metrics.clone();
+        let service = service_fn(move |request| {
+            let response = respond(&request, &metrics);
+            async move { Ok::<_, Infallible>(response) }
+        });
+        let connection = graceful.watch(http1::Builder::new().serve_connection(TokioIo::new(stream), service));
+        tokio::spawn(async move {
+            if let Err(err) = connection.await {
+                debug!(error = %err, "metrics connection failed");
+            }
+        });
+    }
+    drop(listener);
+    if timeout(SHUTDOWN_GRACE, graceful.shutdown()).await.is_err() {
+        debug!("metrics connections still open at shutdown");
+    }
+}
+
+fn respond(request: &Request<Incoming>, metrics: &Metrics) -> Response<Full<Bytes>> {
+    let (status, content_type, body) = match (request.method(), request.uri().path()) {
+        (&Method::GET | &Method::HEAD, "/metrics") => {
+            (StatusCode::OK, "text/plain; version=0.0.4; charset=utf-8", metrics.render())
+        }
+        (_, "/metrics") => (StatusCode::METHOD_NOT_ALLOWED, "text/plain", "method not allowed\n".to_string()),
+        _ => (StatusCode::NOT_FOUND, "text/plain", "not found\n".to_string()),
+    };
+    let mut response = Response::new(Full::new(Bytes::from(body)));
+    *response.status_mut() = status;
+    if let Ok(value) = content_type.parse() {
+        response.headers_mut().insert(CONTENT_TYPE, value);
+    }
+    response
+}
@@ -0,0 +1,339 @@
+//! Mirror mode: fetched pages saved to disk in a wget-like layout.
+//!
+//! `https://example.com/docs/` is saved as `example.com/docs/index.html` and
+//! `https://example.com/docs/intro` as `example.com/docs/intro.html`, so the
+//! two never collide. A query string becomes part of the file name after an
+//! `@`. A `manifest.json` maps each saved file to the URL it came from.
+
+use crate::content::fnv1a;
+use crate::extract::document_base;
+use regex::{Captures, Regex};
+use scraper::Html;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use tracing::warn;
+use url::Url;
+
+/// Name of the manifest file in the save directory.
+const MANIFEST: &str = "manifest.json";
+
+/// Longest file name kept as is; longer ones are shortened and hashed.
+const MAX_NAME_LEN: usize = 200;
+
+/// Where a saved file came from.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ManifestEntry {
+    url: String,
+    /// The URL the content was served from, when a redirect was followed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    final_url: Option<String>,
+    status: u16,
+    fetched_at: String,
+    /// Whether the file is an HTML page, whose links can be converted.
+    #[serde(default)]
+    html: bool,
+}
+
+/// Writes fetched pages under a directory.
+pub(crate) struct Mirror {
+    dir: PathBuf,
+    convert_links: bool,
+    /// Saved files by path relative to `dir`, including those from earlier
+    /// crawls into the same directory
+    manifest: Mutex<BTreeMap<String, ManifestEntry>>,
+}
+
+impl Mirror {
+    /// Creates `dir` if needed and reads the manifest it already has.
+    pub fn open(dir: &Path, convert_links: bool) -> std::io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let manifest = std::fs::read(dir.join(MANIFEST))
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default();
+        Ok(Mirror { dir: dir.to_path_buf(), convert_links, manifest: Mutex::new(manifest) })
+    }
+
+    /// Saves the body of `url`, which was served from `final_url`. Failures
+    /// are logged and otherwise ignored, so they never stop the crawl.
+    pub fn save(&self, url: &str, final_url: &str, status: u16, fetched_at: &str, body: &[u8], html: bool) {
+        let Some(relative) = local_path(final_url, html) else {
+            return;
+        };
+        let mut path = self.dir.join(&relative);
+        // A directory from a longer URL may already have this name
+        if path.is_dir() {
+            path.as_mut_os_string().push(".file");
+        }
+        if let Some(parent) = path.parent() {
+            self.move_aside(parent);
+        }
+        let written = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(&path, body));
+        if let Err(err) = written {
+            warn!(url = %url, path = %path.display(), "cannot save page: {}", err);
+            return;
+        }
+
+        let file = path.strip_prefix(&self.dir).unwrap_or(&path).to_string_lossy().replace('\\', "/");
+        self.manifest.lock().unwrap().insert(file, ManifestEntry {
+            url: url.to_string(),
+            final_url: (final_url != url).then(|| final_url.to_string()),
+            status,
+            fetched_at: fetched_at.to_string(),
+            html,
+        });
+    }
+
+    /// Renames a file saved from a shorter URL that has the name of one of
+    /// the directories of `dir`, the way [`save`](Self::save) names a file
+    /// whose directory came first: `a` becomes `a.file`.
+    fn move_aside(&self, dir: &Path) {
+        let mut ancestors: Vec<&Path> = dir.ancestors().take_while(|ancestor| *ancestor != self.dir).collect();
+        ancestors.reverse();
+        let Some(file) = ancestors.into_iter().find(|ancestor| ancestor.is_file()) else {
+            return;
+        };
+        let mut moved = file.to_path_buf();
+        moved.as_mut_os_string().push(".file");
+        // Another save may have moved it already
+        if std::fs::rename(file, &moved).is_err() {
+            return;
+        }
+
+        let key = |path: &Path| path.strip_prefix(&self.dir).unwrap_or(path).to_string_lossy().replace('\\', "/");
+        let mut manifest = self.manifest.lock().unwrap();
+        if let Some(entry) = manifest.remove(&key(file)) {
+            manifest.insert(key(&moved), entry);
+        }
+    }
+
+    /// Converts links if enabled and writes the manifest. Returns the number
+    /// of files in the manifest.
+    pub fn finish(&self) -> usize {
+        let manifest = self.manifest.lock().unwrap();
+        if self.convert_links {
+            self.convert_all(&manifest);
+        }
+        match serde_json::to_vec_pretty(&*manifest) {
+            Ok(data) => {
+                if let Err(err) = std::fs::write(self.dir.join(MANIFEST), data) {
+                    warn!(path = %self.dir.display(), "cannot write the mirror manifest: {}", err);
+                }
+            }
+            Err(err) => warn!("cannot serialize the mirror manifest: {}", err),
+        }
+        manifest.len()
+    }
+
+    /// Rewrites links between saved pages to relative paths, so the mirror
+    /// can be browsed offline. Links to anything not saved stay absolute.
+    fn convert_all(&self, manifest: &BTreeMap<String, ManifestEntry>) {
+        let mut files: HashMap<String, &str> = HashMap::new();
+        for (file, entry) in manifest {
+            files.insert(entry.url.clone(), file);
+            if let Some(final_url) = &entry.final_url {
+                files.insert(final_url.clone(), file);
+            }
+        }
+
+        for (file, entry) in manifest.iter().filter(|(_, entry)| entry.html) {
+            let path = self.dir.join(file);
+            let Ok(html) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            let page_url = entry.final_url.as_deref().unwrap_or(&entry.url);
+            let converted = convert_links(&html, page_url, file, &files);
+            if let Err(err) = std::fs::write(&path, converted) {
+                warn!(path = %path.display(), "cannot convert links: {}", err);
+            }
+        }
+    }
+}
+
+/// `html`, saved as `file`, with its links to saved pages made relative and
+/// its `<base>` tag removed, since a remote base would break them.
+fn convert_links(html: &str, page_url: &str, file: &str, files: &HashMap<String, &str>) -> String {
+    let base = document_base(&Html::parse_document(html), page_url);
+    let Ok(base) = Url::parse(&base) else {
+        return html.to_string();
+    };
+
+    // Quoted `href`, `src` and `action` attributes
+    let link_attribute = Regex::new(r#"(?i)\b(href|src|action)(\s*=\s*)("[^"]*"|'[^']*')"#).unwrap();
+    let base_tag = Regex::new(r"(?i)<base\b[^>]*>").unwrap();
+    let converted = link_attribute.replace_all(html, |captures: &Captures| {
+        let quoted = &captures[3];
+        let value = quoted[1..quoted.len() - 1].replace("&amp;", "&");
+        let Ok(mut target) = base.join(value.trim()) else {
+            return captures[0].to_string();
+        };
+        let fragment = target.fragment().map(|fragment| format!("#{}", fragment));
+        target.set_fragment(None);
+        match files.get(target.as_str()) {
+            Some(target_file) => format!(
+                "{}{}\"{}{}\"",
+                &captures[1],
+                &captures[2],
+                relative_link(file, target_file).replace('&', "&amp;"),
+                fragment.unwrap_or_default()
+            ),
+            None => captures[0].to_string(),
+        }
+    });
+    base_tag.replace_all(&converted, "").into_owned()
+}
+
+/// The link from the saved file `from` to the saved file `to`, both relative
+/// to the save directory.
+fn relative_link(from: &str, to: &str) -> String {
+    let from: Vec<&str> = from.split('/').collect();
+    let to: Vec<&str> = to.split('/').collect();
+    let from_dir = &from[..from.len() - 1];
+    let common = from_dir.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    let mut parts: Vec<String> = vec!["..".to_string(); from_dir.len() - common];
+    parts.extend(to[common..].iter().map(|part| encode_link_part(part)));
+    parts.join("/")
+}
+
+/// Escapes the characters of a file name that mean something in a URL.
+fn encode_link_part(part: &str) -> String {
+    let mut encoded = String::with_capacity(part.len());
+    for c in part.chars() {
+        match c {
+            '%' => encoded.push_str("%25"),
+            '#' => encoded.push_str("%23"),
+            '?' => encoded.push_str("%3F"),
+            ' ' => encoded.push_str("%20"),
+            '"' => encoded.push_str("%22"),
+            c => encoded.push(c),
+        }
+    }
+    encoded
+}
+
+/// The path `url` is saved under, relative to the save directory. HTML
+/// pages whose name has no `.html` or `.htm` extension get one, and
+/// directory URLs are saved as `index.html`.
+fn local_path(url: &str, html: bool) -> Option<PathBuf> {
+    let url = Url::parse(url).ok()?;
+    let host = match url.port() {
+        Some(port) => format!("{}_{}", url.host_str()?, port),
+        None => url.host_str()?.to_string(),
+    };
+    let mut path = PathBuf::from(sanitize(&host));
+
+    let segments: Vec<&str> = url.path_segments().map(|segments| segments.collect()).unwrap_or_default();
+    let (name, directories) = segments.split_last().unwrap_or((&"", &[]));
+    for directory in directories {
+        path.push(sanitize(directory));
+    }
+
+    let mut name = if name.is_empty() { "index.html".to_string() } else { sanitize(name) };
+    if let Some(query) = url.query() {
+        name = format!("{}@{}", name, sanitize(query));
+    }
+    let lowercase = name.to_ascii_lowercase();
+    if html && (url.query().is_some() || !(lowercase.ends_with(".html") || lowercase.ends_with(".htm"))) {
+        name.push_str(".html");
+    }
+    path.push(shorten(name));
+
+    // Sanitizing leaves no way out of the save directory, but make sure
+    path.components().all(|component| matches!(component, Component::Normal(_))).then_some(path)
+}
+
+/// Replaces characters that aren't safe in file names on common systems.
+fn sanitize(segment: &str) -> String {
+    let sanitized: String = segment
+        .chars()
+        .map(|c| if c.is_control() || matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') { '_' } else { c })
+        .collect();
+    match sanitized.as_str() {
+        "" => "_".to_string(),
+        "." | ".." => sanitized.replace('.', "_"),
+        _ => sanitized,
+    }
+}
+
+/// Keeps file names under [`MAX_NAME_LEN`] bytes, adding a hash of the full
+/// name so shortened names stay distinct.
+fn shorten(name: String) -> String {
+    if name.len() <= MAX_NAME_LEN {
+        return name;
+    }
+    let hash = fnv1a(name.bytes());
+    let mut end = MAX_NAME_LEN - 17;
+    while !name.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}-{:016x}", &name[..end], hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty save directory for one test.
+    fn save_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("silent-crawler-mirror-{}-{}", std::process::id(), test));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn manifest(dir: &Path) -> BTreeMap<String, ManifestEntry> {
+        serde_json::from_slice(&std::fs::read(dir.join(MANIFEST)).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn a_file_saved_after_its_directory_gets_a_suffix() {
+        let dir = save_dir("directory-first");
+        let mirror = Mirror::open(&dir, false).unwrap();
+        mirror.save("http://site.test/a/b", "http://site.test/a/b", 200, "now", b"page", true);
+        mirror.save("http://site.test/a", "http://site.test/a", 200, "now", b"data", false);
+        assert_eq!(mirror.finish(), 2);
+
+        assert_eq!(std::fs::read(dir.join("site.test/a/b.html")).unwrap(), b"page");
+        assert_eq!(std::fs::read(dir.join("site.test/a.file")).unwrap(), b"data");
+        let manifest = manifest(&dir);
+        assert_eq!(manifest["site.test/a.file"].url, "http://site.test/a");
+        assert_eq!(manifest["site.test/a/b.html"].url, "http://site.test/a/b");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_file_saved_before_its_directory_is_moved_aside() {
+        let dir = save_dir("file-first");
+        let mirror = Mirror::open(&dir, false).unwrap();
+        mirror.save("http://site.test/a", "http://site.test/a", 200, "now", b"data", false);
+        mirror.save("http://site.test/a/b/c", "http://site.test/a/b/c", 200, "now", b"page", true);
+        mirror.save("http://site.test/a/d", "http://site.test/a/d", 200, "now", b"other", true);
+        assert_eq!(mirror.finish(), 3);
+
+        assert_eq!(std::fs::read(dir.join("site.test/a/b/c.html")).unwrap(), b"page");
+        assert_eq!(std::fs::read(dir.join("site.test/a/d.html")).unwrap(), b"other");
+        assert_eq!(std::fs::read(dir.join("site.test/a.file")).unwrap(), b"data");
+        let manifest = manifest(&dir);
+        assert_eq!(manifest["site.test/a.file"].url, "http://site.test/a");
+        assert!(!manifest.contains_key("site.test/a"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn directory_urls_and_pages_of_the_same_name_never_collide() {
+        let dir = save_dir("slash");
+        let mirror = Mirror::open(&dir, false).unwrap();
+        mirror.save("http://site.test/a", "http://site.test/a", 200, "now", b"page", true);
+        mirror.save("http://site.test/a/", "http://site.test/a/", 200, "now", b"index", true);
+        assert_eq!(mirror.finish(), 2);
+
+        assert_eq!(std::fs::read(dir.join("site.test/a.html")).unwrap(), b"page");
+        assert_eq!(std::fs::read(dir.join("site.test/a/index.html")).unwrap(), b"index");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,230 @@
+//! Writing [`Results`] to files, and reading them back.
+
+use crate::results::{Results, RESULTS_SCHEMA_VERSION};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Writes the results as pretty-printed JSON.
+pub fn write_json(results: &Results, path: &Path) -> io::Result<()> {
+    let json = serde_json::to_string_pretty(results)?;
+    let mut file = File::create(path)?;
+    file.write_all(json.as_bytes())
+}
+
+/// Reads results written by [`write_json`]. Fails with
+/// [`io::ErrorKind::InvalidData`] if the file isn't a results file of this
+/// [`RESULTS_SCHEMA_VERSION`], since another layout would be misread.
+pub fn read_json(path: &Path) -> io::Result<Results> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), message));
+    let text = std::fs::read_to_string(path).map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path.display(), err)))?;
+    let value: serde_json::Value = serde_json::from_str(&text).map_err(|err| invalid(format!("not JSON ({})", err)))?;
+    let Some(object) = value.as_object() else {
+        return Err(invalid("not a results file".to_string()));
+    };
+    match object.get("schema_version").and_then(serde_json::Value::as_u64) {
+        Some(version) if version == u64::from(RESULTS_SCHEMA_VERSION) => {}
+        Some(version) => {
+            return Err(invalid(format!(
+                "results schema version {} is not supported (expected {}); re-crawl with this version",
+                version, RESULTS_SCHEMA_VERSION
+            )));
+        }
+        None => {
+            return Err(invalid(
+                "no schema_version: not a results file, or written by a version from before results were versioned".to_string(),
+            ));
+        }
+    }
+    serde_json::from_value(value).map_err(|err| invalid(format!("not a results file ({})", err)))
+}
+
+/// Writes one CSV row per discovered URL to `path`, and the directories,
+/// subdomains and broken links to sibling files (`<name>_directories.csv`,
+/// `<name>_subdomains.csv` and `<name>_broken_links.csv`). Returns the paths
+/// of the sibling files.
+pub fn write_csv(results: &Results, path: &Path) -> io::Result<Vec<PathBuf>> {
+    let pages: HashMap<&str, _> = results.pages.iter().map(|page| (page.url.as_str(), page)).collect();
+
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record(["url", "depth", "status", "content_type", "size", "parent_url", "seed", "title", "description", "h1"])?;
+    for url in &results.urls {
+        let page = pages.get(url.as_str());
+        let depth = results.depths.get(url).map(|depth| depth.to_string()).unwrap_or_default();
+        writer.write_record([
+            url.as_str(),
+            &depth,
+            &page.map(|page| page.status.to_string()).unwrap_or_default(),
+            page.and_then(|page| page.content_type.as_deref()).unwrap_or(""),
+            &page.and_then(|page| page.size).map(|size| size.to_string()).unwrap_or_default(),
+            page.and_then(|page| page.parent_url.as_deref()).unwrap_or(""),
+            page.and_then(|page| page.seed.as_deref()).unwrap_or(""),
+            page.and_then(|page| page.title.as_deref()).unwrap_or(""),
+            page.and_then(|page| page.description.as_deref()).unwrap_or(""),
+            page.and_then(|page| page.h1.as_deref()).unwrap_or(""),
+        ])?;
+    }
+    writer.flush()?;
+
+    let directories_path = sibling_path(path, "directories");
+    write_column(&directories_path, "directory", &results.directories)?;
+    let subdomains_path = sibling_path(path, "subdomains");
+    write_column(&subdomains_path, "subdomain", &results.subdomains)?;
+    let broken_links_path = sibling_path(path, "broken_links");
+    write_broken_links(&broken_links_path, results)?;
+
+    Ok(vec![directories_path, subdomains_path, broken_links_path])
+}
+
+/// One row per broken link and referrer, or a single row with no referrer.
+fn write_broken_links(path: &Path, results: &Results) -> io::Result<()> {
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record(["url", "status", "error", "external", "referrer"])?;
+    for link in &results.broken_links {
+        let status = link.status.map(|status| status.to_string()).unwrap_or_default();
+        let external = link.external.to_string();
+        let referrers = if link.referrers.is_empty() { vec![String::new()] } else { link.referrers.clone() };
+        for referrer in &referrers {
+            writer.write_record([link.url.as_str(), &status, &link.message, &external, referrer])?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_column(path: &Path, header: &str, values: &[String]) -> io::Result<()> {
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record([header])?;
+    for value in values {
+        writer.write_record([value])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// `results.csv` + `directories` -> `results_directories.csv`
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("results");
+    path.with_file_name(format!("{}_{}.csv", stem, suffix))
+}
+
+/// How a node of the link graph was reached.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum NodeKind {
+    Crawled,
+    /// In scope but never fetched (beyond the depth limit, filtered, ...)
+    Uncrawled,
+    External,
+}
+
+struct Node {
+    kind: NodeKind,
+    status: Option<u16>,
+    depth: Option<usize>,
+}
+
+/// Every URL that appears in the graph, with what is known about it.
+fn graph_nodes(results: &Results) -> BTreeMap<&str, Node> {
+    let pages: HashMap<&str, u16> = results.pages.iter().map(|page| (page.url.as_str(), page.status)).collect();
+    let node = |url: &str, external: bool| Node {
+        kind: match (pages.contains_key(url), external) {
+            (true, _) => NodeKind::Crawled,
+            (false, false) => NodeKind::Uncrawled,
+            (false, true) => NodeKind::External,
+        },
+        status: pages.get(url).copied(),
+        depth: results.depths.get(url).copied(),
+    };
+
+    let mut nodes = BTreeMap::new();
+    for edge in &results.graph {
+        nodes.entry(edge.source.as_str()).or_insert_with(|| node(&edge.source, false));
+        nodes.entry(edge.target.as_str()).or_insert_with(|| node(&edge.target, edge.external));
+    }
+    nodes
+}
+
+/// Writes the link graph as Graphviz DOT. Uncrawled targets are dashed and
+/// external ones are grey.
+pub fn write_dot(results: &Results, path: &Path) -> io::Result<()> {
+    let mut out = String::from("digraph crawl {\n    node [shape=box, fontsize=10];\n");
+    for (url, node) in graph_nodes(results) {
+        // `\n` is a DOT line break, so it is added after escaping
+        let mut label = dot_escape(url);
+        if let Some(status) = node.status {
+            label.push_str(&format!("\\n{}", status));
+        }
+        if let Some(depth) = node.depth {
+            label.push_str(&format!("\\ndepth {}", depth));
+        }
+        let style = match node.kind {
+            NodeKind::Crawled => "",
+            NodeKind::Uncrawled => ", style=dashed",
+            NodeKind::External => ", style=dashed, color=gray, fontcolor=gray",
+        };
+        out.push_str(&format!("    \"{}\" [label=\"{}\"{}];\n", dot_escape(url), label, style));
+    }
+    for edge in &results.graph {
+        let style = if edge.external { " [style=dotted, color=gray]" } else { "" };
+        out.push_str(&format!("    \"{}\" -> \"{}\"{};\n", dot_escape(&edge.source), dot_escape(&edge.target), style));
+    }
+    out.push_str("}\n");
+
+    File::create(path)?.write_all(out.as_bytes())
+}
+
+/// Writes the link graph as GraphML, with `url`, `status`, `depth` and
+/// `kind` node attributes and an `external` edge attribute.
+pub fn write_graphml(results: &Results, path: &Path) -> io::Result<()> {
+    let mut out = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+        "  <key id=\"url\" for=\"node\" attr.name=\"url\" attr.type=\"string\"/>\n",
+        "  <key id=\"status\" for=\"node\" attr.name=\"status\" attr.type=\"int\"/>\n",
+        "  <key id=\"depth\" for=\"node\" attr.name=\"depth\" attr.type=\"int\"/>\n",
+        "  <key id=\"kind\" for=\"node\" attr.name=\"kind\" attr.type=\"string\"/>\n",
+        "  <key id=\"external\" for=\"edge\" attr.name=\"external\" attr.type=\"boolean\"/>\n",
+        "  <graph id=\"crawl\" edgedefault=\"directed\">\n",
+    ));
+
+    let nodes = graph_nodes(results);
+    let ids: HashMap<&str, usize> = nodes.keys().enumerate().map(|(index, url)| (*url, index)).collect();
+    for (index, (url, node)) in nodes.iter().enumerate() {
+        out.push_str(&format!("    <node id=\"n{}\">\n      <data key=\"url\">{}</data>\n", index, xml_escape(url)));
+        if let Some(status) = node.status {
+            out.push_str(&format!("      <data key=\"status\">{}</data>\n", status));
+        }
+        if let Some(depth) = node.depth {
+            out.push_str(&format!("      <data key=\"depth\">{}</data>\n", depth));
+        }
+        let kind = match node.kind {
+            NodeKind::Crawled => "crawled",
+            NodeKind::Uncrawled => "uncrawled",
+            NodeKind::External => "external",
+        };
+        out.push_str(&format!("      <data key=\"kind\">{}</data>\n    </node>\n", kind));
+    }
+    for edge in &results.graph {
+        out.push_str(&format!(
+            "    <edge source=\"n{}\" target=\"n{}\"><data key=\"external\">{}</data></edge>\n",
+            ids[edge.source.as_str()], ids[edge.target.as_str()], edge.external
+        ));
+    }
+    out.push_str("  </graph>\n</graphml>\n");
+
+    File::create(path)?.write_all(out.as_bytes())
+}
+
+fn dot_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
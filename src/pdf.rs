@@ -0,0 +1,73 @@
+//! Link extraction from PDF documents: the targets of link annotations and
+//! the URLs written out in their text.
+
+use lopdf::{decode_text_string, Dictionary, Document, LoadOptions, Object};
+use regex::Regex;
+use std::collections::BTreeSet;
+
+/// Whether `content_type` is `application/pdf`.
+pub fn is_pdf_type(content_type: &str) -> bool {
+    content_type.split(';').next().unwrap_or_default().trim().eq_ignore_ascii_case("application/pdf")
+}
+
+/// What a PDF says about itself and where it links to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PdfLinks {
+    /// The `Title` of the document information dictionary.
+    pub title: Option<String>,
+    pub pages: usize,
+    /// Link annotation targets and URLs in the text, unresolved and
+    /// deduplicated.
+    pub links: Vec<String>,
+}
+
+/// Reads the links of a PDF. Streams are decompressed to at most
+/// `max_stream_size` bytes each. Documents that can't be parsed, or that
+/// are encrypted with a password other than the empty one, are errors.
+pub fn pdf_links(bytes: &[u8], max_stream_size: usize) -> Result<PdfLinks, String> {
+    let options = LoadOptions::with_max_decompressed_size(max_stream_size);
+    let document = Document::load_mem_with_options(bytes, options).map_err(|err| err.to_string())?;
+    if document.is_encrypted() && !document.was_encrypted() {
+        return Err("encrypted".to_string());
+    }
+    let title = document
+        .trailer
+        .get_deref(b"Info", &document)
+        .and_then(Object::as_dict)
+        .and_then(|info| info.get_deref(b"Title", &document))
+        .ok()
+        .and_then(|title| decode_text_string(title).ok())
+        .map(|title| title.trim().to_string())
+        .filter(|title| !title.is_empty());
+
+    // URLs written out in the text, up to the first space or bracket
+    let text_url = Regex::new(r#"(?i)\bhttps?://[^\s<>"'()\[\]{}]+"#).unwrap();
+    let pages = document.get_pages();
+    let mut links = BTreeSet::new();
+    for (&number, &page) in &pages {
+        for annotation in document.get_page_annotations(page).unwrap_or_default() {
+            if let Some(uri) = annotation_uri(&document, annotation) {
+                links.insert(uri);
+            }
+        }
+        // Pages whose fonts can't be decoded still have their annotations
+        if let Ok(text) = document.extract_text_with_limit(&[number], max_stream_size) {
+            for found in text_url.find_iter(&text) {
+                links.insert(found.as_str().trim_end_matches(['.', ',', ';', ':', '!', '?']).to_string());
+            }
+        }
+    }
+    Ok(PdfLinks { title, pages: pages.len(), links: links.into_iter().collect() })
+}
+
+/// The target of a link annotation with a URI action.
+fn annotation_uri(document: &Document, annotation: &Dictionary) -> Option<String> {
+    let action = annotation.get_deref(b"A", document).and_then(Object::as_dict).ok()?;
+    if action.get(b"S").and_then(Object::as_name).ok()? != b"URI" {
+        return None;
+    }
+    // URIs are 7-bit ASCII strings, but some writers use text strings
+    let uri = action.get_deref(b"URI", document).ok()?;
+    let uri = decode_text_string(uri).ok().or_else(|| uri.as_str().ok().map(|uri| String::from_utf8_lossy(uri).into_owned()))?;
+    Some(uri.trim().to_string()).filter(|uri| !uri.is_empty())
+}
@@ -0,0 +1,55 @@
+//! Live counters for reporting the progress of a running crawl.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    pub fetched: AtomicUsize,
+    pub frontier: AtomicUsize,
+    pub errors: AtomicUsize,
+    pub started: Mutex<Option<Instant>>,
+}
+
+/// A snapshot of a running crawl.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    /// URLs that have been fetched, successfully or not.
+    pub fetched: usize,
+    /// URLs queued or in flight.
+    pub frontier: usize,
+    pub errors: usize,
+    /// Time since the crawl started; zero before it has.
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    /// Average fetch rate since the crawl started.
+    pub fn requests_per_sec(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            self.fetched as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Reads the progress of a crawl from another task while it runs.
+#[derive(Debug, Clone)]
+pub struct ProgressHandle {
+    pub(crate) counters: Arc<Counters>,
+}
+
+impl ProgressHandle {
+    pub fn snapshot(&self) -> Progress {
+        let counters = &self.counters;
+        Progress {
+            fetched: counters.fetched.load(Ordering::Relaxed),
+            frontier: counters.frontier.load(Ordering::Relaxed),
+            errors: counters.errors.load(Ordering::Relaxed),
+            elapsed: counters.started.lock().unwrap().map(|started| started.elapsed()).unwrap_or_default(),
+        }
+    }
+}
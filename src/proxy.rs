@@ -0,0 +1,103 @@
+//! Spreading requests over a pool of HTTP clients, one per proxy.
+
+use crate::results::ProxyStats;
+use reqwest::Client;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use tracing::warn;
+use url::Url;
+
+struct PoolEntry {
+    /// Proxy URL with any password hidden; `None` for a direct client
+    label: Option<String>,
+    client: Client,
+    requests: AtomicUsize,
+    failures: AtomicUsize,
+    consecutive_failures: AtomicUsize,
+    /// Set after too many consecutive failures; benched clients are skipped
+    benched: AtomicBool,
+}
+
+/// Round-robin over the configured clients, benching proxies that keep failing.
+pub(crate) struct ClientPool {
+    entries: Vec<PoolEntry>,
+    next: AtomicUsize,
+    max_failures: usize,
+}
+
+impl ClientPool {
+    /// `clients` pairs each client with the proxy it uses, if any. Must not be empty.
+    pub fn new(clients: Vec<(Option<String>, Client)>, max_failures: usize) -> Self {
+        let entries = clients
+            .into_iter()
+            .map(|(proxy, client)| PoolEntry {
+                label: proxy.as_deref().map(redact_password),
+                client,
+                requests: AtomicUsize::new(0),
+                failures: AtomicUsize::new(0),
+                consecutive_failures: AtomicUsize::new(0),
+                benched: AtomicBool::new(false),
+            })
+            .collect();
+        ClientPool {
+            entries,
+            next: AtomicUsize::new(0),
+            max_failures: max_failures.max(1),
+        }
+    }
+
+    /// Picks the next client that isn't benched. If every proxy is benched,
+    /// rotation continues over all of them rather than stopping the crawl.
+    pub fn pick(&self) -> (usize, &Client) {
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let len = self.entries.len();
+        let index = (0..len)
+            .map(|offset| (start + offset) % len)
+            .find(|&index| !self.entries[index].benched.load(Ordering::Relaxed))
+            .unwrap_or(start % len);
+        (index, &self.entries[index].client)
+    }
+
+    /// Records the outcome of a request made with client `index`.
+    pub fn record(&self, index: usize, failed: bool) {
+        let entry = &self.entries[index];
+        entry.requests.fetch_add(1, Ordering::Relaxed);
+        if !failed {
+            entry.consecutive_failures.store(0, Ordering::Relaxed);
+            return;
+        }
+
+        entry.failures.fetch_add(1, Ordering::Relaxed);
+        let consecutive = entry.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        // A single client has nothing to fall back to, so it is never benched
+        if self.entries.len() > 1 && consecutive >= self.max_failures && !entry.benched.swap(true, Ordering::Relaxed) {
+            warn!(proxy = entry.label.as_deref().unwrap_or("direct"), consecutive, "benching proxy after repeated failures");
+        }
+    }
+
+    /// Request counts per proxy; empty when no proxy pool is in use.
+    pub fn stats(&self) -> Vec<ProxyStats> {
+        if self.entries.len() < 2 {
+            return Vec::new();
+        }
+        self.entries
+            .iter()
+            .map(|entry| ProxyStats {
+                proxy: entry.label.clone().unwrap_or_default(),
+                requests: entry.requests.load(Ordering::Relaxed),
+                failures: entry.failures.load(Ordering::Relaxed),
+                benched: entry.benched.load(Ordering::Relaxed),
+            })
+            .collect()
+    }
+}
+
+/// Hides the password of a URL with credentials, for messages and results.
+pub(crate) fn redact_password(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(mut parsed) if parsed.password().is_some() => {
+            let _ = parsed.set_password(Some("***"));
+            parsed.to_string()
+        }
+        _ => url.to_string(),
+    }
+}
@@ -0,0 +1,126 @@
+//! Per-host politeness: request spacing and a cap on concurrent requests;
+//! and a global cap on download bandwidth.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::{sleep, sleep_until, Instant};
+
+/// Upper bound for the adaptive per-host penalty.
+const MAX_PENALTY: Duration = Duration::from_secs(60);
+
+struct HostSlot {
+    /// Limits concurrent requests to the host
+    semaphore: Arc<Semaphore>,
+    /// Earliest time the next request to the host may start
+    next_start: Mutex<Instant>,
+    /// Extra spacing added after the host throttled us (adaptive mode)
+    penalty: Mutex<Duration>,
+}
+
+/// Hands out per-host permits, keyed by host name.
+pub(crate) struct HostLimiter {
+    per_host_concurrency: usize,
+    hosts: Mutex<HashMap<String, Arc<HostSlot>>>,
+}
+
+/// Held for the duration of a request; dropping it frees the host slot.
+pub(crate) struct HostPermit {
+    _permit: OwnedSemaphorePermit,
+}
+
+impl HostLimiter {
+    pub fn new(per_host_concurrency: usize) -> Self {
+        HostLimiter {
+            per_host_concurrency: per_host_concurrency.max(1),
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Waits until a request to `host` may start: a concurrency slot is free
+    /// and at least `interval` has passed since the previous request started.
+    pub async fn acquire(&self, host: &str, interval: Duration) -> HostPermit {
+        let slot = self.slot(host);
+        let interval = interval + *slot.penalty.lock().unwrap();
+
+        let permit = slot.semaphore.clone().acquire_owned().await.expect("semaphore is never closed");
+
+        // Reserve the next start time, then wait for it outside the lock
+        let start = {
+            let mut next_start = slot.next_start.lock().unwrap();
+            let start = (*next_start).max(Instant::now());
+            *next_start = start + interval;
+            start
+        };
+        sleep_until(start).await;
+
+        HostPermit { _permit: permit }
+    }
+
+    /// Holds back all requests to `host` for at least `duration`.
+    pub fn pause(&self, host: &str, duration: Duration) {
+        let slot = self.slot(host);
+        let mut next_start = slot.next_start.lock().unwrap();
+        *next_start = (*next_start).max(Instant::now() + duration);
+    }
+
+    /// Permanently increases the spacing between requests to `host`: the
+    /// first call adds `initial`, later calls double the added amount.
+    pub fn slow_down(&self, host: &str, initial: Duration) {
+        let slot = self.slot(host);
+        let mut penalty = slot.penalty.lock().unwrap();
+        *penalty = if penalty.is_zero() {
+            initial
+        } else {
+            (*penalty * 2).min(MAX_PENALTY)
+        };
+    }
+
+    fn slot(&self, host: &str) -> Arc<HostSlot> {
+        let mut hosts = self.hosts.lock().unwrap();
+        hosts
+            .entry(host.to_string())
+            .or_insert_with(|| {
+                Arc::new(HostSlot {
+                    semaphore: Arc::new(Semaphore::new(self.per_host_concurrency)),
+                    next_start: Mutex::new(Instant::now()),
+                    penalty: Mutex::new(Duration::ZERO),
+                })
+            })
+            .clone()
+    }
+}
+
+/// A token bucket shared by every request, refilled at `rate` bytes per
+/// second and holding at most a second's worth. Bodies are read through it
+/// chunk by chunk; a chunk that overdraws the bucket waits until the debt
+/// is paid off, so concurrent readers share the rate.
+pub(crate) struct BandwidthLimiter {
+    rate: f64,
+    /// Bytes available, negative when in debt, and when they were counted
+    bucket: Mutex<(f64, Instant)>,
+}
+
+impl BandwidthLimiter {
+    pub fn new(bytes_per_sec: u64) -> Self {
+        let rate = bytes_per_sec.max(1) as f64;
+        BandwidthLimiter { rate, bucket: Mutex::new((rate, Instant::now())) }
+    }
+
+    /// Takes `bytes` from the bucket, waiting while it is in debt.
+    pub async fn consume(&self, bytes: usize) {
+        let debt = {
+            let mut bucket = self.bucket.lock().unwrap();
+            let (available, counted_at) = &mut *bucket;
+            let now = Instant::now();
+            *available = (*available + now.duration_since(*counted_at).as_secs_f64() * self.rate).min(self.rate);
+            *counted_at = now;
+            *available -= bytes as f64;
+            -*available
+        };
+        if debt > 0.0 {
+            sleep(Duration::from_secs_f64(debt / self.rate)).await;
+        }
+    }
+}
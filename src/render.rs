@@ -0,0 +1,125 @@
+//! Rendering pages in headless Chromium, for sites whose links only exist
+//! once their JavaScript has run.
+//!
+//! One browser is launched per crawl and every page is loaded in a tab of
+//! its own, a few at a time since tabs are expensive. A page counts as
+//! rendered once the network has been idle for half a second, or once an
+//! element matches the configured selector; its DOM is then serialized
+//! back to HTML for the usual extraction.
+
+use crate::error::CrawlerError;
+use chromiumoxide::cdp::browser_protocol::page::EventLifecycleEvent;
+use chromiumoxide::error::CdpError;
+use chromiumoxide::{Browser, BrowserConfig, Page};
+use futures::StreamExt;
+use std::path::Path;
+use std::time::Duration;
+use tokio::sync::{Mutex, Semaphore};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+/// How often the wait selector is looked for.
+const SELECTOR_POLL: Duration = Duration::from_millis(100);
+
+/// A page as the browser rendered it.
+pub(crate) struct RenderedPage {
+    pub html: String,
+    /// Where the browser ended up, after any redirects done by scripts.
+    pub url: String,
+}
+
+/// The browser of one crawl.
+pub(crate) struct Renderer {
+    browser: Mutex<Browser>,
+    /// Drives the browser connection; the browser stops responding without it
+    handler: JoinHandle<()>,
+    tabs: Semaphore,
+    wait_for: Option<String>,
+    timeout: Duration,
+}
+
+impl Renderer {
+    /// Launches Chromium, the one at `executable` or else the one found on
+    /// the system, with the crawl's user agent.
+    pub async fn launch(
+        executable: Option<&Path>,
+        user_agent: &str,
+        accept_invalid_certs: bool,
+        tabs: usize,
+        wait_for: Option<String>,
+        timeout: Duration,
+    ) -> Result<Self, CrawlerError> {
+        let mut config = BrowserConfig::builder().arg(format!("--user-agent={}", user_agent)).request_timeout(timeout);
+        if let Some(executable) = executable {
+            config = config.chrome_executable(executable);
+        }
+        if !accept_invalid_certs {
+            config = config.respect_https_errors();
+        }
+        let failed = |err: String| CrawlerError::Browser(format!("cannot launch Chromium: {}", err));
+        let config = config.build().map_err(failed)?;
+        let (browser, mut handler) = Browser::launch(config).await.map_err(|err| failed(err.to_string()))?;
+        let handler = tokio::spawn(async move { while handler.next().await.is_some() {} });
+        debug!(tabs, "launched the browser");
+        Ok(Renderer { browser: Mutex::new(browser), handler, tabs: Semaphore::new(tabs), wait_for, timeout })
+    }
+
+    /// Loads `url` in a new tab and returns its DOM. A page still loading
+    /// when the timeout runs out is taken as it is.
+    pub async fn render(&self, url: &str) -> Result<RenderedPage, String> {
+        let _tab = self.tabs.acquire().await.map_err(|err| err.to_string())?;
+        let page = self.browser.lock().await.new_page("about:blank").await.map_err(|err| err.to_string())?;
+        let rendered = match tokio::time::timeout(self.timeout, self.load(&page, url)).await {
+            Ok(Err(err)) => Err(err.to_string()),
+            loaded => {
+                if loaded.is_err() {
+                    debug!(url = %url, "render timed out, taking the DOM as it is");
+                }
+                match (page.content().await, page.url().await) {
+                    (Ok(html), Ok(current)) => Ok(RenderedPage { html, url: current.unwrap_or_else(|| url.to_string()) }),
+                    (Err(err), _) | (_, Err(err)) => Err(err.to_string()),
+                }
+            }
+        };
+        if let Err(err) = page.close().await {
+            debug!(url = %url, "cannot close the tab: {}", err);
+        }
+        rendered
+    }
+
+    async fn load(&self, page: &Page, url: &str) -> Result<(), CdpError> {
+        let mut events = page.event_listener::<EventLifecycleEvent>().await?;
+        page.goto(url).await?;
+        if let Some(selector) = &self.wait_for {
+            while page.find_element(selector.as_str()).await.is_err() {
+                tokio::time::sleep(SELECTOR_POLL).await;
+            }
+            return Ok(());
+        }
+        let frame = page.mainframe().await?;
+        // The tab's lifecycle starts over with "init" for the navigation, after
+        // the events of about:blank
+        let mut navigated = false;
+        while let Some(event) = events.next().await {
+            if frame.as_ref() != Some(&event.frame_id) {
+                continue;
+            }
+            match event.name.as_str() {
+                "init" => navigated = true,
+                "networkIdle" if navigated => break,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Closes the browser.
+    pub async fn close(&self) {
+        let mut browser = self.browser.lock().await;
+        if let Err(err) = browser.close().await {
+            warn!("cannot close the browser: {}", err);
+        }
+        let _ = browser.wait().await;
+        self.handler.abort();
+    }
+}
@@ -0,0 +1,129 @@
+//! Checking the URLs of an earlier crawl again, and reporting what broke or
+//! changed since.
+
+use crate::diff::{statuses, StatusChange};
+use crate::output::read_json;
+use crate::results::Results;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+
+/// How the URLs of a results file fared when fetched again.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct ReplayReport {
+    /// URLs fetched again.
+    pub checked: usize,
+    /// URLs that succeeded or redirected before and now return an error
+    /// status or fail, sorted by URL.
+    pub broken: Vec<StatusChange>,
+    /// Other status changes, such as URLs that were fixed or now redirect.
+    pub status_changed: Vec<StatusChange>,
+    /// URLs with the same status whose size changed by more than the
+    /// threshold.
+    pub size_changed: Vec<SizeChange>,
+    /// URLs failing now that the file had no status for, as in files that
+    /// only list URLs.
+    pub failing: Vec<FailingUrl>,
+}
+
+/// A URL that returns an error status, or fails without a response when
+/// `status` is missing.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FailingUrl {
+    pub url: String,
+    pub status: Option<u16>,
+}
+
+/// A URL whose body size changed.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SizeChange {
+    pub url: String,
+    pub old: u64,
+    pub new: u64,
+    /// The change relative to the old size, in percent, negative when the
+    /// body shrank.
+    pub percent: f64,
+}
+
+impl ReplayReport {
+    /// Whether anything broke or changed.
+    pub fn is_empty(&self) -> bool {
+        self.broken.is_empty() && self.status_changed.is_empty() && self.size_changed.is_empty() && self.failing.is_empty()
+    }
+}
+
+/// Reads the URLs to replay from `path`: a results file, or, from older
+/// versions and other tools, a JSON object or array with only the URLs,
+/// or a text file with one URL per line.
+pub fn read_input(path: &Path) -> io::Result<Results> {
+    let versioned = read_json(path);
+    let text = match &versioned {
+        Err(err) if err.kind() == io::ErrorKind::InvalidData => std::fs::read_to_string(path)?,
+        _ => return versioned,
+    };
+    let urls: Vec<String> = match serde_json::from_str::<serde_json::Value>(&text) {
+        Ok(serde_json::Value::Object(object)) if !object.contains_key("schema_version") => {
+            // Sections that changed shape since are dropped along with their page records
+            if let Ok(results) = serde_json::from_value::<Results>(serde_json::Value::Object(object.clone())) {
+                return Ok(results);
+            }
+            let urls = object.get("urls").cloned().unwrap_or_default();
+            serde_json::from_value(urls).map_err(|_| invalid_input(path))?
+        }
+        Ok(value @ serde_json::Value::Array(_)) => serde_json::from_value(value).map_err(|_| invalid_input(path))?,
+        Ok(_) => return versioned,
+        Err(_) => text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_string)
+            .collect(),
+    };
+    Ok(Results { urls, ..Results::default() })
+}
+
+fn invalid_input(path: &Path) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{}: expected a results file or a list of URLs", path.display()))
+}
+
+/// Compares the new results of a replay with the stored ones. A size
+/// counts as changed when it differs by more than `size_threshold`
+/// percent; sizes are only compared when both runs know them.
+pub fn compare(old: &Results, new: &Results, size_threshold: f64) -> ReplayReport {
+    let old_statuses = statuses(old);
+    let new_statuses = statuses(new);
+    let failed = |status: Option<u16>| status.is_none_or(|status| status >= 400);
+
+    let mut report = ReplayReport { checked: new_statuses.len(), ..ReplayReport::default() };
+    for (&url, &status) in &new_statuses {
+        let change = |old| StatusChange { url: url.to_string(), old, new: status };
+        match old_statuses.get(url) {
+            Some(&old) if old == status => {}
+            Some(&old) if !failed(old) && failed(status) => report.broken.push(change(old)),
+            Some(&old) => report.status_changed.push(change(old)),
+            None if failed(status) => report.failing.push(FailingUrl { url: url.to_string(), status }),
+            None => {}
+        }
+    }
+
+    let old_sizes: BTreeMap<&str, (u16, u64)> = old
+        .pages
+        .iter()
+        .filter_map(|page| Some((page.url.as_str(), (page.status, page.size?))))
+        .collect();
+    for page in &new.pages {
+        let (Some(&(status, old)), Some(new)) = (old_sizes.get(page.url.as_str()), page.size) else {
+            continue;
+        };
+        if status != page.status || old == new {
+            continue;
+        }
+        let percent = if old == 0 { 100.0 } else { (new as f64 - old as f64) * 100.0 / old as f64 };
+        if percent.abs() > size_threshold {
+            report.size_changed.push(SizeChange { url: page.url.clone(), old, new, percent });
+        }
+    }
+    report.size_changed.sort_by(|a, b| a.url.cmp(&b.url));
+    report
+}
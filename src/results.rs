@@ -0,0 +1,973 @@
+use crate::extract::unicode_host;
+use crate::json::is_json_type;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt;
+use std::net::IpAddr;
+
+/// Version of the results JSON layout, in [`Results::schema_version`].
+/// Bumped whenever a field is renamed, removed or changes meaning.
+pub const RESULTS_SCHEMA_VERSION: u32 = 1;
+
+/// Everything collected during a crawl. Sections missing from a results
+/// file read back are left empty.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Results {
+    /// Layout version of the serialized results, [`RESULTS_SCHEMA_VERSION`].
+    pub schema_version: u32,
+    /// False when the crawl was stopped before the frontier was exhausted.
+    pub completed: bool,
+    /// True when the crawl ended because the page limit was reached.
+    pub page_limit_reached: bool,
+    /// True when the crawl was stopped by its time limit.
+    pub timed_out: bool,
+    /// True when the crawl was stopped because too many responses in a
+    /// row were bot challenges.
+    pub stopped_by_challenges: bool,
+    /// True when the crawl was stopped by its download cap.
+    pub byte_limit_reached: bool,
+    /// Wall-clock duration of the crawl in milliseconds.
+    pub elapsed_ms: u64,
+    /// Response body bytes read, after decompression, robots.txt and
+    /// sitemaps included.
+    pub bytes_downloaded: u64,
+    /// Queued or in-flight URLs that were never fetched.
+    pub frontier_remaining: usize,
+    /// Known URLs found during the crawl and not fetched, each counted once.
+    #[serde(skip_serializing_if = "is_zero")]
+    pub known_urls_skipped: usize,
+    /// The URLs the crawl started from, base URL first.
+    pub seeds: Vec<String>,
+    pub urls: Vec<String>,
+    /// Depth at which each URL was first discovered (seeds are 0).
+    pub depths: BTreeMap<String, usize>,
+    pub directories: Vec<String>,
+    pub subdomains: Vec<String>,
+    /// Number of discovered URLs per in-scope domain: the base domain and
+    /// any allowed domains that were reached.
+    pub domains: BTreeMap<String, usize>,
+    /// Number of discovered URLs per `host:port`, with the scheme's
+    /// standard port spelled out, e.g. `example.com:443`.
+    pub urls_per_host: BTreeMap<String, usize>,
+    /// One record per URL that produced an HTTP response, sorted by URL.
+    pub pages: Vec<PageRecord>,
+    /// Failed requests and non-success responses, sorted by URL.
+    pub errors: Vec<CrawlError>,
+    /// In-scope URLs listed in sitemap.xml files, when sitemap seeding is enabled.
+    pub sitemap_urls: Vec<String>,
+    /// Sitemaps cut off at the body size limit, sorted. The locations read
+    /// before the cut are still crawled.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub truncated_sitemaps: Vec<String>,
+    /// RSS and Atom feeds announced by crawled pages, sorted. In-scope
+    /// feeds are fetched and their entry links crawled.
+    pub feeds: Vec<String>,
+    /// Directory index pages, sorted by URL.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub directory_listings: Vec<DirectoryListing>,
+    /// PDF documents, sorted by URL. Only listed when PDFs are parsed.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub documents: Vec<PdfDocument>,
+    /// Pages that asked not to be indexed through `<meta name="robots">` or
+    /// `X-Robots-Tag`, sorted. Empty when meta robots are ignored.
+    pub noindex_pages: Vec<String>,
+    /// Out-of-scope links found on crawled pages, sorted. They are never fetched.
+    pub external_links: Vec<String>,
+    /// Number of distinct external links per external host.
+    pub external_domains: BTreeMap<String, usize>,
+    /// Discovered URLs that were not crawled because of an include or
+    /// exclude pattern, sorted by URL.
+    pub skipped: Vec<SkippedUrl>,
+    /// Discovered URLs that were not crawled because they looked like a
+    /// crawler trap, sorted by URL.
+    pub traps: Vec<TrappedUrl>,
+    /// Directories and hosts whose budget ran out, so their coverage is
+    /// incomplete.
+    pub budgets_exhausted: Vec<BudgetHit>,
+    /// Deduplicated links from crawled pages, when graph recording is
+    /// enabled, sorted by source and target.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub graph: Vec<GraphEdge>,
+    /// Scripts, stylesheets and images referenced by each crawled page.
+    /// Assets are never crawled for links.
+    pub assets: BTreeMap<String, Vec<String>>,
+    /// Forms found on crawled pages, by page. A form found on several
+    /// pages, like a search box, is listed once under the first of them
+    /// by URL.
+    pub forms: BTreeMap<String, Vec<Form>>,
+    /// Every query parameter name in the crawled URLs and GET form fields.
+    pub parameters: BTreeMap<String, ParameterStats>,
+    /// Parameter uses not counted because the name limit was reached.
+    #[serde(skip_serializing_if = "is_zero")]
+    pub parameters_dropped: usize,
+    /// Every distinct asset URL, sorted.
+    pub asset_urls: Vec<String>,
+    /// Email addresses found on crawled pages, when email harvesting is
+    /// enabled, lowercase and sorted.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub emails: Vec<String>,
+    /// Scripts fetched to look for URLs, when JavaScript scanning is
+    /// enabled, sorted.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub scanned_scripts: Vec<String>,
+    /// Wordlist paths that exist in crawled directories, when brute forcing
+    /// is enabled, sorted by URL.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub bruteforced: Vec<BruteForceHit>,
+    /// Common sensitive files found on crawled hosts, when the check is
+    /// enabled, sorted by URL.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sensitive_files: Vec<SensitiveFile>,
+    /// Source maps of same-domain scripts, when source map discovery is
+    /// enabled, sorted by script.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sourcemaps: Vec<SourceMap>,
+    /// Assets that failed to load or returned an error status, when asset
+    /// checking is enabled, sorted by URL.
+    pub broken_assets: Vec<CrawlError>,
+    /// Secrets found in crawled pages and scanned scripts, when secret
+    /// scanning is enabled, sorted by URL and rule.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub secrets: Vec<SecretFinding>,
+    /// Pages matching the grep patterns, one record per page and pattern,
+    /// sorted by URL and then in pattern order.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub matches: Vec<GrepMatch>,
+    /// Plain-http scripts, stylesheets, images and frames referenced by
+    /// HTTPS pages, sorted by page and resource.
+    pub mixed_content: Vec<MixedContent>,
+    /// Crawled URLs that failed or returned an error status, and external
+    /// links that did when external checking is enabled, with the pages
+    /// linking to them, sorted by URL.
+    pub broken_links: Vec<BrokenLink>,
+    /// Pages whose canonical URL is on another host or didn't load with
+    /// 200, sorted by page. Canonical URLs that weren't crawled aren't
+    /// checked.
+    pub canonical_issues: Vec<CanonicalIssue>,
+    /// How many pages use each schema.org type in their structured data, by
+    /// type name without the `https://schema.org/` prefix.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub schema_types: BTreeMap<String, usize>,
+    /// The language alternates each page declares with `hreflang`, by
+    /// page and then by lowercase language tag.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub hreflang: BTreeMap<String, BTreeMap<String, String>>,
+    /// Problems with the declared alternates, sorted by page. Alternates
+    /// that weren't crawled are only checked for `x-default`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub hreflang_issues: Vec<HreflangIssue>,
+    /// Security headers missing from HTML pages and the server software
+    /// announced, when the security audit is enabled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub security_audit: Option<SecurityAudit>,
+    /// Response time percentiles, overall and per first-level directory.
+    pub performance: Performance,
+    /// Pages that took at least the slow threshold, when one is set,
+    /// slowest first.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub slow_pages: Vec<SlowPage>,
+    /// Paginated listings followed through next and previous links, sorted
+    /// by their first page.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pagination: Vec<PaginationChain>,
+    /// Pages with identical content (ignoring whitespace and volatile
+    /// elements), in groups of two or more, sorted by their first URL.
+    pub duplicate_groups: Vec<DuplicateGroup>,
+    /// Pages whose visible text is nearly the same, in groups of two or
+    /// more, when near-duplicate detection is enabled.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub near_duplicate_groups: Vec<Vec<String>>,
+    /// Files in the mirror directory, when pages are saved to disk,
+    /// including those saved by earlier crawls into it.
+    pub saved_files: usize,
+    /// Approximate memory used by the visited set that deduplicates URLs.
+    pub visited_set_bytes: u64,
+    /// Requests and failures per proxy, when a proxy list is used.
+    pub proxies: Vec<ProxyStats>,
+    /// The addresses each host connected to resolved to, sorted, such as
+    /// the crawled hosts or the proxy. Hosts pinned with a resolve
+    /// override list the pinned addresses.
+    pub resolved_hosts: BTreeMap<String, Vec<IpAddr>>,
+    /// With www folding, the host that the base domain or its `www`
+    /// variant redirected to, once one was seen redirecting to the other.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub canonical_host: Option<String>,
+    /// Hosts that sent a valid `Strict-Transport-Security` header over
+    /// HTTPS, sorted, with the policy they last sent.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub hsts_hosts: Vec<HstsHost>,
+    /// How many times a host throttled the crawl (429/503 with Retry-After,
+    /// or any 429 in adaptive mode).
+    pub throttle_events: usize,
+}
+
+impl Results {
+    /// Number of recorded errors per kind.
+    pub fn error_counts(&self) -> BTreeMap<ErrorKind, usize> {
+        let mut counts = BTreeMap::new();
+        for error in &self.errors {
+            *counts.entry(error.kind).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Parent → child discovery edges: for every fetched page that was found
+    /// by following a link, the page it was first found on.
+    pub fn edges(&self) -> Vec<(&str, &str)> {
+        self.pages
+            .iter()
+            .filter_map(|page| page.parent_url.as_deref().map(|parent| (parent, page.url.as_str())))
+            .collect()
+    }
+
+    /// Number of fetched pages first reached from each seed.
+    pub fn seed_counts(&self) -> BTreeMap<&str, usize> {
+        let mut counts = BTreeMap::new();
+        for page in &self.pages {
+            if let Some(seed) = &page.seed {
+                *counts.entry(seed.as_str()).or_insert(0) += 1;
+            }
+        }
+        counts
+    }
+
+    /// HTML pages without a title.
+    pub fn missing_titles(&self) -> Vec<&str> {
+        self.pages
+            .iter()
+            .filter(|page| page.is_html() && page.title.is_none())
+            .map(|page| page.url.as_str())
+            .collect()
+    }
+
+    /// Titles shared by more than one page, with the URLs of those pages.
+    pub fn duplicate_titles(&self) -> BTreeMap<&str, Vec<&str>> {
+        let mut titles: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for page in &self.pages {
+            if let Some(title) = &page.title {
+                titles.entry(title.as_str()).or_default().push(page.url.as_str());
+            }
+        }
+        titles.retain(|_, urls| urls.len() > 1);
+        titles
+    }
+
+    /// Number of distinct contents among the pages that were hashed.
+    pub fn unique_pages(&self) -> usize {
+        let hashes: HashSet<&str> = self.pages.iter().filter_map(|page| page.content_hash.as_deref()).collect();
+        hashes.len()
+    }
+
+    /// Number of trap-skipped URLs per heuristic.
+    pub fn trap_counts(&self) -> BTreeMap<TrapReason, usize> {
+        let mut counts = BTreeMap::new();
+        for trap in &self.traps {
+            *counts.entry(trap.reason).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Number of sitemap URLs that failed to load or returned an error
+    /// status, plus the sitemaps that were cut off at the size limit.
+    pub fn sitemap_failures(&self) -> usize {
+        let failed: HashSet<&str> = self.errors.iter().map(|error| error.url.as_str()).collect();
+        let unreachable = self.sitemap_urls
+            .iter()
+            .filter(|url| failed.contains(url.as_str()))
+            .count();
+        unreachable + self.truncated_sitemaps.len()
+    }
+
+    /// Hosts that failed to resolve, in their Unicode form, sorted.
+    pub fn unresolved_hosts(&self) -> Vec<String> {
+        self.errors
+            .iter()
+            .filter(|error| error.kind == ErrorKind::Dns)
+            .filter_map(|error| url::Url::parse(&error.url).ok()?.host_str().map(unicode_host))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+}
+
+/// Response metadata for a single fetched URL.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PageRecord {
+    pub url: String,
+    pub depth: usize,
+    /// How the URL entered the frontier.
+    pub source: UrlSource,
+    /// The page the URL was first found on.
+    pub parent_url: Option<String>,
+    /// The seed the URL was first reached from; `None` for sitemap URLs and
+    /// the pages found through them.
+    #[serde(default)]
+    pub seed: Option<String>,
+    /// Where the page is in a chain of next and previous pages, when it
+    /// was reached through one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pagination: Option<PaginationStep>,
+    pub status: u16,
+    /// URL the response came from, after in-scope redirects were followed.
+    pub final_url: String,
+    pub content_type: Option<String>,
+    /// Body size in bytes, when known.
+    pub size: Option<u64>,
+    /// The server answered `304 Not Modified` and the body came from the
+    /// HTTP cache.
+    #[serde(default)]
+    pub from_cache: bool,
+    /// Time from sending the request to the end of the body, excluding the
+    /// wait for the host's delay.
+    pub elapsed_ms: u64,
+    /// Time from sending the request to receiving the response headers.
+    #[serde(default)]
+    pub ttfb_ms: u64,
+    /// When the response was received, as an RFC 3339 timestamp.
+    pub fetched_at: String,
+    /// The page's `<title>`, with whitespace collapsed.
+    #[serde(default)]
+    pub title: Option<String>,
+    /// The page's `<meta name="description">`, with whitespace collapsed.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The text of the page's first `<h1>`, with whitespace collapsed.
+    #[serde(default)]
+    pub h1: Option<String>,
+    /// The ISO 639-1 code of the language its text was detected to be in,
+    /// when language detection is on and there was enough text to tell.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// The JSON-LD, OpenGraph, Twitter card and microdata of the page, when
+    /// structured data is extracted and it has any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub structured_data: Option<StructuredData>,
+    /// The page's `<link rel="canonical">`, resolved against its URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canonical: Option<String>,
+    /// The page asked not to be indexed.
+    #[serde(default)]
+    pub noindex: bool,
+    /// The response was a bot challenge or WAF block page instead of the
+    /// real page.
+    #[serde(default)]
+    pub challenged: bool,
+    /// The page is a directory index generated by the server.
+    #[serde(default)]
+    pub directory_listing: bool,
+    /// The body was larger than the size limit and only its start was read.
+    #[serde(default)]
+    pub truncated: bool,
+    /// Links, title and the rest were read from the DOM rendered by the
+    /// headless browser.
+    #[serde(default)]
+    pub rendered: bool,
+    /// Hash of the HTML without whitespace and volatile elements, as hex.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+    /// Simhash of the visible text as hex, when near-duplicate detection
+    /// is enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub simhash: Option<String>,
+    /// Every URL visited when redirects were followed, starting with the
+    /// original URL. Empty when there was no redirect.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redirect_chain: Vec<String>,
+    /// Off-site redirect target that was recorded but not followed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_redirect: Option<String>,
+    /// The page sends browsers elsewhere with a meta refresh or a script.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_redirect: Option<ClientRedirect>,
+    /// Response headers by lowercase name, when header capture is enabled.
+    /// With only the security audit enabled, just the audited headers.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, Vec<String>>,
+}
+
+impl PageRecord {
+    /// Whether this is a successful HTML response, the kind that links and
+    /// titles are read from.
+    pub fn is_html(&self) -> bool {
+        (200..300).contains(&self.status)
+            && self.content_type.as_deref().is_some_and(|content_type| content_type.contains("text/html"))
+    }
+
+    /// Whether this is a successful JSON response, the kind links are read
+    /// from when following JSON.
+    pub fn is_json(&self) -> bool {
+        (200..300).contains(&self.status) && self.content_type.as_deref().is_some_and(is_json_type)
+    }
+}
+
+/// A link that failed or returned an error status, and where it was found.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BrokenLink {
+    pub url: String,
+    pub status: Option<u16>,
+    pub kind: ErrorKind,
+    pub message: String,
+    /// Whether the URL is outside the crawl's scope.
+    pub external: bool,
+    /// Crawled pages that link to the URL, sorted. Empty for seeds.
+    pub referrers: Vec<String>,
+}
+
+/// A script's source map, which may expose the original source tree.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SourceMap {
+    /// Where the map was fetched from; the script's URL for inline maps.
+    pub url: String,
+    pub script: String,
+    pub origin: SourceMapOrigin,
+    /// Size in bytes, when known.
+    pub size: Option<u64>,
+    /// The original file names, as listed in the map.
+    pub sources: Vec<String>,
+    /// Whether the map embeds the original code as well.
+    pub sources_content: bool,
+    /// The map was over the size limit and left unread.
+    pub too_large: bool,
+}
+
+/// How a [`SourceMap`] was found.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceMapOrigin {
+    /// A `//# sourceMappingURL=` comment in the script.
+    Comment,
+    /// A `SourceMap` or `X-SourceMap` header on the script.
+    Header,
+    /// A `data:` URL in the script's comment.
+    Inline,
+    /// Found by trying the script's URL with `.map` appended.
+    Probe,
+}
+
+/// The structured data a page embeds.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct StructuredData {
+    /// The `<script type="application/ld+json">` blocks, parsed.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub json_ld: Vec<serde_json::Value>,
+    /// Why each JSON-LD block that couldn't be used was left out: its
+    /// parse error, or its size when over the limit.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub invalid_json_ld: Vec<String>,
+    /// `og:*` meta tags by lowercase property, the first of repeated ones.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub opengraph: BTreeMap<String, String>,
+    /// `twitter:*` meta tags by lowercase name, the first of repeated ones.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub twitter: BTreeMap<String, String>,
+    /// Top-level microdata items, each as an object with its `itemtype` as
+    /// `@type` and its properties as fields.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub microdata: Vec<serde_json::Value>,
+}
+
+/// A page reached by following next or previous links from another.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PaginationStep {
+    /// The page the chain was entered from.
+    pub first: String,
+    /// Links followed from `first` to get here, 1 for its next page.
+    pub step: usize,
+}
+
+/// A redirect done by the page instead of the server.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ClientRedirect {
+    pub kind: ClientRedirectKind,
+    /// The absolute URL the page redirects to.
+    pub target: String,
+    /// Seconds before a meta refresh fires.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay: Option<u64>,
+}
+
+/// How a page redirects in the browser.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ClientRedirectKind {
+    /// `<meta http-equiv="refresh">` with a URL.
+    MetaRefresh,
+    /// An inline script assigning a URL to `location`.
+    JsRedirect,
+}
+
+impl ClientRedirectKind {
+    /// The source of the URLs found this way.
+    pub fn source(self) -> UrlSource {
+        match self {
+            ClientRedirectKind::MetaRefresh => UrlSource::MetaRefresh,
+            ClientRedirectKind::JsRedirect => UrlSource::JsRedirect,
+        }
+    }
+}
+
+/// A `<form>` and the fields it submits.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Form {
+    /// The absolute URL the form submits to.
+    pub action: String,
+    /// `GET` or `POST`, uppercase.
+    pub method: String,
+    /// Named fields in document order.
+    pub fields: Vec<FormField>,
+    pub has_password: bool,
+    pub has_file_upload: bool,
+    /// The name of the hidden field that looks like a CSRF token, if any.
+    pub csrf_field: Option<String>,
+    /// Hash of the action, method and field names and types, identifying
+    /// the same form on different pages.
+    pub hash: String,
+    /// Other pages with the same form, sorted.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub also_on: Vec<String>,
+}
+
+/// A named `input`, `select` or `textarea` of a form.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FormField {
+    pub name: String,
+    /// The input type, lowercase, such as `text`, `hidden` or `password`,
+    /// or `select` or `textarea`.
+    pub kind: String,
+}
+
+/// Where a query parameter name was seen.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParameterStats {
+    /// URLs and GET forms using the parameter.
+    pub count: usize,
+    /// The first few of them; for forms, the form's action.
+    pub sample_urls: Vec<String>,
+}
+
+/// A wordlist path that exists, found by brute forcing a directory.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BruteForceHit {
+    pub url: String,
+    pub status: u16,
+    /// Body size in bytes, from `Content-Length` or the downloaded body.
+    pub size: Option<u64>,
+    /// Where a redirect points.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+}
+
+/// A directory index page and the files it lists.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DirectoryListing {
+    pub url: String,
+    /// Listed files other than HTML pages, sorted. They are not fetched.
+    pub files: Vec<String>,
+}
+
+/// A PDF document found by the crawl.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PdfDocument {
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub pages: usize,
+    /// Distinct URLs found in its link annotations and text.
+    pub links: usize,
+    /// Why it was not read, e.g. because it is encrypted, corrupt or over
+    /// the PDF size limit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<String>,
+}
+
+/// A well-known sensitive file that a host serves.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SensitiveFile {
+    pub url: String,
+    pub status: u16,
+    pub size: u64,
+}
+
+/// A secret exposed in a page or script.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SecretFinding {
+    /// The page or script the secret is in.
+    pub url: String,
+    /// The name of the rule that matched, such as `aws-access-key-id`.
+    pub rule: String,
+    /// The matched text, with all but the start of the secret masked.
+    pub snippet: String,
+}
+
+/// The matches of one grep pattern on one page.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GrepMatch {
+    pub url: String,
+    pub pattern: String,
+    /// Every match on the page, including those without a snippet.
+    pub count: usize,
+    /// The first matches with some text around them.
+    pub snippets: Vec<String>,
+}
+
+/// A plain-http subresource of an HTTPS page.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MixedContent {
+    pub page: String,
+    pub resource: String,
+    /// The referencing element, such as `script`, `img` or `link`.
+    pub element: String,
+}
+
+/// Which security headers the crawled HTML pages lack, and what software
+/// the site says it runs.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SecurityAudit {
+    /// Number of HTML pages audited.
+    pub pages: usize,
+    /// Pages missing each security header, by lowercase name.
+    /// `strict-transport-security` is only counted for HTTPS pages.
+    pub missing: BTreeMap<String, usize>,
+    /// The pages missing the most headers, worst first.
+    pub worst_offenders: Vec<SecurityOffender>,
+    /// Responses per `Server` value.
+    pub servers: BTreeMap<String, usize>,
+    /// Responses per `X-Powered-By` value.
+    pub powered_by: BTreeMap<String, usize>,
+}
+
+/// A host's `Strict-Transport-Security` policy.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct HstsHost {
+    pub host: String,
+    /// How long browsers should only use HTTPS, in seconds; 0 clears the
+    /// policy.
+    pub max_age: u64,
+    pub include_subdomains: bool,
+    /// Whether the host asks to be on the browsers' preload list.
+    pub preload: bool,
+}
+
+/// An HTML page without some of the security headers.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SecurityOffender {
+    pub url: String,
+    /// The missing headers, lowercase.
+    pub missing: Vec<String>,
+}
+
+/// Response times of the crawled pages.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Performance {
+    pub overall: LatencyStats,
+    /// Stats per first-level directory, like `/blog/`, with `/` for the
+    /// pages at the root.
+    pub sections: BTreeMap<String, LatencyStats>,
+}
+
+/// Response time percentiles of a set of pages, in milliseconds.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LatencyStats {
+    pub pages: usize,
+    pub p50_ms: u64,
+    pub p90_ms: u64,
+    pub p99_ms: u64,
+    /// Time-to-first-byte percentiles.
+    pub ttfb_p50_ms: u64,
+    pub ttfb_p90_ms: u64,
+    pub ttfb_p99_ms: u64,
+}
+
+/// A page that took at least the slow threshold.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SlowPage {
+    pub url: String,
+    pub elapsed_ms: u64,
+    pub ttfb_ms: u64,
+}
+
+/// A page whose `<link rel="canonical">` is suspect.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CanonicalIssue {
+    pub page: String,
+    pub canonical: String,
+    pub reason: CanonicalReason,
+    /// Status the canonical URL returned, when it was fetched.
+    pub status: Option<u16>,
+}
+
+/// A page whose `hreflang` alternates are inconsistent.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HreflangIssue {
+    pub page: String,
+    pub reason: HreflangReason,
+    /// The language and URL of the alternate at fault; `None` for a
+    /// missing `x-default`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alternate: Option<String>,
+    /// Status the alternate returned, when it is broken.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+}
+
+/// What is wrong with a page's language alternates.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum HreflangReason {
+    /// The alternate failed or returned an error status.
+    Broken,
+    /// The alternate doesn't list the page among its own alternates.
+    NotReciprocal,
+    /// The page has alternates but none for `x-default`.
+    MissingXDefault,
+}
+
+impl fmt::Display for HreflangReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            HreflangReason::Broken => "broken",
+            HreflangReason::NotReciprocal => "not reciprocal",
+            HreflangReason::MissingXDefault => "no x-default",
+        })
+    }
+}
+
+/// What is wrong with a canonical URL.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum CanonicalReason {
+    /// It is on a different host than the page.
+    OtherHost,
+    /// It redirects elsewhere.
+    Redirect,
+    /// It returned a status other than 200.
+    Status,
+    /// Fetching it failed.
+    Failed,
+}
+
+impl fmt::Display for CanonicalReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CanonicalReason::OtherHost => "other host",
+            CanonicalReason::Redirect => "redirect",
+            CanonicalReason::Status => "non-200 status",
+            CanonicalReason::Failed => "failed",
+        })
+    }
+}
+
+/// The pages of a paginated listing, found from its first page.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PaginationChain {
+    /// The page the chain was entered from.
+    pub first: String,
+    /// The other pages, in the order they were reached.
+    pub pages: Vec<String>,
+}
+
+/// URLs that returned the same content.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateGroup {
+    /// The shared content hash.
+    pub hash: String,
+    /// The URLs, sorted.
+    pub urls: Vec<String>,
+}
+
+/// A link from a crawled page.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GraphEdge {
+    pub source: String,
+    pub target: String,
+    /// Whether the target is outside the crawl scope.
+    pub external: bool,
+}
+
+/// A URL left out of the crawl by the include/exclude patterns.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SkippedUrl {
+    pub url: String,
+    /// The exclude pattern that matched, or `None` if the URL matched no
+    /// include pattern.
+    pub pattern: Option<String>,
+}
+
+/// A URL left out of the crawl because it looked like a crawler trap.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TrappedUrl {
+    pub url: String,
+    pub reason: TrapReason,
+}
+
+/// Which trap heuristic rejected a URL.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum TrapReason {
+    /// The same run of path segments repeats too often, as in
+    /// `/red/large/red/large/red/large/`.
+    RepeatedSegments,
+    /// The path has too many segments.
+    PathDepth,
+    /// The query string has too many parameters.
+    QueryParams,
+    /// A date in the path or query is implausibly far in the future or past,
+    /// as generated by endless "next month" calendar links.
+    Calendar,
+}
+
+impl fmt::Display for TrapReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TrapReason::RepeatedSegments => "repeated segments",
+            TrapReason::PathDepth => "path depth",
+            TrapReason::QueryParams => "query parameters",
+            TrapReason::Calendar => "calendar",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A directory or host whose crawl budget ran out.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BudgetHit {
+    pub kind: BudgetKind,
+    /// `host/directory/` for directory budgets, the host for subdomain budgets.
+    pub bucket: String,
+    /// URLs in the bucket that were not crawled.
+    pub skipped: usize,
+}
+
+/// Which crawl budget a [`BudgetHit`] refers to.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetKind {
+    Directory,
+    Subdomain,
+}
+
+impl fmt::Display for BudgetKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BudgetKind::Directory => "directory",
+            BudgetKind::Subdomain => "subdomain",
+        })
+    }
+}
+
+/// How one proxy of the pool fared.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProxyStats {
+    /// Proxy URL, with any password hidden.
+    pub proxy: String,
+    pub requests: usize,
+    /// Requests that failed to connect, timed out or got HTTP 407.
+    pub failures: usize,
+    /// Whether the proxy was taken out of rotation.
+    pub benched: bool,
+}
+
+impl ProxyStats {
+    /// Fraction of requests that failed, from 0.0 to 1.0.
+    pub fn failure_rate(&self) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            self.failures as f64 / self.requests as f64
+        }
+    }
+}
+
+/// How a URL was discovered.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum UrlSource {
+    /// One of the URLs the crawl started from.
+    Seed,
+    /// Found by following a link on a crawled page.
+    Link,
+    /// Listed in a sitemap.xml file.
+    Sitemap,
+    /// Found as a string in a script referenced by a crawled page.
+    Javascript,
+    /// Found in an HTML comment.
+    Comment,
+    /// Found as a string in an inline `<script>`.
+    #[serde(rename = "inline-script")]
+    InlineScript,
+    /// The target of a crawled page's meta refresh.
+    MetaRefresh,
+    /// The target of a crawled page's script redirect.
+    JsRedirect,
+    /// Found in a JSON response.
+    Json,
+    /// The next or previous page of a paginated listing, HTML or JSON,
+    /// queued at the depth of the page linking to it.
+    Pagination,
+    /// Found in a PDF document.
+    Pdf,
+}
+
+/// Broad category of a failed request.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// The response did not arrive within the request timeout, or its body
+    /// stalled for longer than the read timeout.
+    Timeout,
+    /// The connection was not established within the connect timeout.
+    ConnectTimeout,
+    /// The host name could not be resolved.
+    Dns,
+    /// The connection could not be established.
+    Connect,
+    /// The TLS handshake failed, usually because the certificate could not
+    /// be verified.
+    Tls,
+    /// The server answered with a non-success status code.
+    Status,
+    /// The URL redirected more times than the redirect limit allows.
+    TooManyRedirects,
+    /// Any other transport or body error.
+    Other,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ErrorKind::Timeout => "timeout",
+            ErrorKind::ConnectTimeout => "connect_timeout",
+            ErrorKind::Dns => "dns",
+            ErrorKind::Connect => "connect",
+            ErrorKind::Tls => "tls",
+            ErrorKind::Status => "status",
+            ErrorKind::TooManyRedirects => "too_many_redirects",
+            ErrorKind::Other => "other",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A request that failed or returned a non-success status.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CrawlError {
+    pub url: String,
+    pub kind: ErrorKind,
+    pub message: String,
+    pub status: Option<u16>,
+    /// Number of requests made before giving up.
+    #[serde(default = "default_attempts")]
+    pub attempts: u32,
+}
+
+fn default_attempts() -> u32 {
+    1
+}
+
+fn is_zero(value: &usize) -> bool {
+    *value == 0
+}
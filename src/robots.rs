@@ -0,0 +1,389 @@
+//! robots.txt parsing and matching, and the page-level robots directives.
+//!
+//! Follows the de-facto standard (RFC 9309): records are grouped by
+//! `User-agent`, the most specific matching group applies, and within that
+//! group the longest matching `Allow`/`Disallow` rule wins, with `Allow`
+//! winning ties. Rules support `*` wildcards and a trailing `$` anchor.
+
+use std::time::Duration;
+
+/// A parsed robots.txt file.
+#[derive(Debug, Clone, Default)]
+pub struct RobotsTxt {
+    groups: Vec<Group>,
+    sitemaps: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Group {
+    agents: Vec<String>,
+    rules: Vec<Rule>,
+    crawl_delay: Option<Duration>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rule {
+    allow: bool,
+    pattern: String,
+}
+
+/// The rules from a robots.txt that apply to one user agent.
+#[derive(Debug, Clone, Default)]
+pub struct RobotsRules {
+    rules: Vec<Rule>,
+    crawl_delay: Option<Duration>,
+    sitemaps: Vec<String>,
+}
+
+impl RobotsTxt {
+    /// Parses the contents of a robots.txt file. Unknown or malformed lines
+    /// are ignored.
+    pub fn parse(text: &str) -> Self {
+        let mut groups: Vec<Group> = Vec::new();
+        let mut sitemaps = Vec::new();
+        let mut current: Option<Group> = None;
+        // True once the current group has seen a rule, so that a following
+        // User-agent line starts a new group instead of extending this one
+        let mut has_rules = false;
+
+        for line in text.lines() {
+            // Strip comments and surrounding whitespace
+            let line = line.split('#').next().unwrap_or("").trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let key = key.trim().to_ascii_lowercase();
+            let value = value.trim();
+
+            match key.as_str() {
+                "user-agent" => {
+                    if has_rules || current.is_none() {
+                        if let Some(group) = current.take() {
+                            groups.push(group);
+                        }
+                        current = Some(Group::default());
+                        has_rules = false;
+                    }
+                    if let Some(group) = current.as_mut() {
+                        group.agents.push(value.to_ascii_lowercase());
+                    }
+                }
+                "allow" | "disallow" => {
+                    let Some(group) = current.as_mut() else {
+                        continue;
+                    };
+                    has_rules = true;
+
+                    // An empty Disallow means "allow everything" and adds no rule
+                    if !value.is_empty() {
+                        group.rules.push(Rule {
+                            allow: key == "allow",
+                            pattern: value.to_string(),
+                        });
+                    }
+                }
+                "crawl-delay" => {
+                    let Some(group) = current.as_mut() else {
+                        continue;
+                    };
+                    has_rules = true;
+
+                    if let Ok(seconds) = value.parse::<f64>() {
+                        if seconds.is_finite() && seconds >= 0.0 {
+                            group.crawl_delay = Some(Duration::from_secs_f64(seconds));
+                        }
+                    }
+                }
+                // Sitemap lines apply to the whole file, not to a group
+                "sitemap" if !value.is_empty() => {
+                    sitemaps.push(value.to_string());
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(group) = current {
+            groups.push(group);
+        }
+
+        RobotsTxt { groups, sitemaps }
+    }
+
+    /// Returns the rules that apply to `user_agent`.
+    ///
+    /// The group naming the longest agent token contained in the user agent
+    /// wins; groups naming the same token are merged. Falls back to the `*`
+    /// group, and to no rules at all when neither exists.
+    pub fn rules_for(&self, user_agent: &str) -> RobotsRules {
+        let user_agent = user_agent.to_ascii_lowercase();
+
+        let mut best_len = 0;
+        let mut best: Vec<&Group> = Vec::new();
+        for group in &self.groups {
+            for agent in &group.agents {
+                if agent == "*" || agent.is_empty() || !user_agent.contains(agent.as_str()) {
+                    continue;
+                }
+                if agent.len() > best_len {
+                    best_len = agent.len();
+                    best.clear();
+                }
+                if agent.len() == best_len {
+                    best.push(group);
+                }
+            }
+        }
+
+        if best.is_empty() {
+            best = self.groups
+                .iter()
+                .filter(|group| group.agents.iter().any(|agent| agent == "*"))
+                .collect();
+        }
+
+        RobotsRules {
+            rules: best.iter().flat_map(|group| group.rules.iter().cloned()).collect(),
+            crawl_delay: best.iter().filter_map(|group| group.crawl_delay).max(),
+            sitemaps: self.sitemaps.clone(),
+        }
+    }
+
+    /// `Sitemap:` URLs listed anywhere in the file.
+    pub fn sitemaps(&self) -> &[String] {
+        &self.sitemaps
+    }
+}
+
+impl RobotsRules {
+    /// The `Crawl-delay` requested by the matching group, if any.
+    pub fn crawl_delay(&self) -> Option<Duration> {
+        self.crawl_delay
+    }
+
+    /// `Sitemap:` URLs listed in the file, which apply to every user agent.
+    pub fn sitemaps(&self) -> &[String] {
+        &self.sitemaps
+    }
+
+    /// Returns true if `path` (including any query string) may be fetched.
+    pub fn is_allowed(&self, path: &str) -> bool {
+        // robots.txt itself is always allowed
+        if path == "/robots.txt" {
+            return true;
+        }
+
+        let mut best: Option<&Rule> = None;
+        for rule in &self.rules {
+            if !pattern_matches(&rule.pattern, path) {
+                continue;
+            }
+            best = match best {
+                None => Some(rule),
+                Some(current) => {
+                    let (len, current_len) = (rule.pattern.len(), current.pattern.len());
+                    if len > current_len || (len == current_len && rule.allow && !current.allow) {
+                        Some(rule)
+                    } else {
+                        Some(current)
+                    }
+                }
+            };
+        }
+
+        best.is_none_or(|rule| rule.allow)
+    }
+}
+
+/// Indexing directives for one page, from `<meta name="robots">` tags and
+/// `X-Robots-Tag` headers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RobotsDirectives {
+    /// The page asks not to be indexed.
+    pub noindex: bool,
+    /// The page asks for its links not to be followed.
+    pub nofollow: bool,
+}
+
+/// Directives that take a value after a colon, which must not be mistaken
+/// for a user agent scope.
+const VALUED_DIRECTIVES: &[&str] = &["unavailable_after", "max-snippet", "max-image-preview", "max-video-preview"];
+
+impl RobotsDirectives {
+    /// Parses a comma-separated list such as `noindex, nofollow`; `none`
+    /// means both. Directives scoped to a crawler (`googlebot: noindex`)
+    /// only apply when `user_agent` contains that crawler's name.
+    pub fn parse(value: &str, user_agent: &str) -> Self {
+        let user_agent = user_agent.to_ascii_lowercase();
+        let mut directives = RobotsDirectives::default();
+        let mut applies = true;
+        for part in value.split(',') {
+            let mut directive = part.trim().to_ascii_lowercase();
+            if let Some((scope, rest)) = directive.split_once(':') {
+                let scope = scope.trim();
+                if !VALUED_DIRECTIVES.contains(&scope) {
+                    applies = scope == "*" || user_agent.contains(scope);
+                    directive = rest.trim().to_string();
+                }
+            }
+            if !applies {
+                continue;
+            }
+            match directive.as_str() {
+                "noindex" => directives.noindex = true,
+                "nofollow" => directives.nofollow = true,
+                "none" => {
+                    directives.noindex = true;
+                    directives.nofollow = true;
+                }
+                _ => {}
+            }
+        }
+        directives
+    }
+
+    /// Combines two sets of directives; the most restrictive wins.
+    pub fn merge(self, other: RobotsDirectives) -> Self {
+        RobotsDirectives {
+            noindex: self.noindex || other.noindex,
+            nofollow: self.nofollow || other.nofollow,
+        }
+    }
+}
+
+/// Matches a robots.txt path pattern against `path`. Patterns match as a
+/// prefix unless they end in `$`; `*` matches any run of characters.
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    if !path.starts_with(first) {
+        return false;
+    }
+    let mut pos = first.len();
+
+    let rest: Vec<&str> = parts.collect();
+    if rest.is_empty() {
+        return !anchored || pos == path.len();
+    }
+
+    // Middle parts are matched at their leftmost position
+    let (last, middle) = rest.split_last().unwrap();
+    for part in middle {
+        match path[pos..].find(part) {
+            Some(index) => pos += index + part.len(),
+            None => return false,
+        }
+    }
+
+    if anchored {
+        path.len() >= pos + last.len() && path.ends_with(last)
+    } else {
+        path[pos..].contains(last)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns_match_as_prefixes() {
+        assert!(pattern_matches("/fish", "/fish"));
+        assert!(pattern_matches("/fish", "/fish.html"));
+        assert!(pattern_matches("/fish", "/fishheads/yummy.html"));
+        assert!(!pattern_matches("/fish", "/Fish.asp"));
+        assert!(!pattern_matches("/fish", "/catfish"));
+    }
+
+    #[test]
+    fn trailing_wildcards_change_nothing() {
+        assert!(pattern_matches("/fish*", "/fish"));
+        assert!(pattern_matches("/fish*", "/fish.html"));
+        assert!(pattern_matches("/fish*", "/fish/salmon.html"));
+        assert!(!pattern_matches("/fish*", "/desert/fish"));
+    }
+
+    #[test]
+    fn trailing_slashes_only_match_the_directory() {
+        assert!(pattern_matches("/fish/", "/fish/"));
+        assert!(pattern_matches("/fish/", "/fish/salmon.htm"));
+        assert!(!pattern_matches("/fish/", "/fish"));
+        assert!(!pattern_matches("/fish/", "/fish.html"));
+    }
+
+    #[test]
+    fn dollar_anchors_the_end() {
+        assert!(pattern_matches("/*.php$", "/filename.php"));
+        assert!(pattern_matches("/*.php$", "/folder/filename.php"));
+        assert!(!pattern_matches("/*.php$", "/filename.php?parameters"));
+        assert!(!pattern_matches("/*.php$", "/filename.php/"));
+        assert!(!pattern_matches("/*.php$", "/windows.PHP"));
+    }
+
+    #[test]
+    fn longest_match_wins() {
+        let rules = RobotsTxt::parse("User-agent: *\nDisallow: /shop\nAllow: /shop/public\n").rules_for("bot");
+        assert!(!rules.is_allowed("/shop/cart"));
+        assert!(rules.is_allowed("/shop/public/item"));
+        assert!(rules.is_allowed("/about"));
+    }
+
+    #[test]
+    fn allow_wins_ties() {
+        let rules = RobotsTxt::parse("User-agent: *\nDisallow: /page\nAllow: /page\n").rules_for("bot");
+        assert!(rules.is_allowed("/page"));
+        let rules = RobotsTxt::parse("User-agent: *\nAllow: /page\nDisallow: /page\n").rules_for("bot");
+        assert!(rules.is_allowed("/page"));
+    }
+
+    #[test]
+    fn empty_disallow_allows_everything() {
+        let rules = RobotsTxt::parse("User-agent: *\nDisallow:\n").rules_for("bot");
+        assert!(rules.is_allowed("/"));
+        assert!(rules.is_allowed("/anything/at/all"));
+    }
+
+    #[test]
+    fn robots_txt_itself_is_always_allowed() {
+        let rules = RobotsTxt::parse("User-agent: *\nDisallow: /\n").rules_for("bot");
+        assert!(!rules.is_allowed("/index.html"));
+        assert!(rules.is_allowed("/robots.txt"));
+    }
+
+    #[test]
+    fn the_most_specific_group_applies() {
+        let robots = RobotsTxt::parse(
+            "User-agent: *\nDisallow: /\n\nUser-agent: silent\nDisallow: /private\n\nUser-agent: silent-crawler\nDisallow: /secret\n",
+        );
+        let rules = robots.rules_for("Mozilla/5.0 (compatible; silent-crawler/1.0)");
+        assert!(rules.is_allowed("/private"));
+        assert!(!rules.is_allowed("/secret"));
+
+        let rules = robots.rules_for("OtherBot/2.0");
+        assert!(!rules.is_allowed("/index.html"));
+    }
+
+    #[test]
+    fn groups_for_the_same_agent_are_merged() {
+        let robots = RobotsTxt::parse(
+            "User-agent: silent-crawler\nDisallow: /a\nCrawl-delay: 1\n\nUser-agent: *\nDisallow: /c\n\nUser-agent: silent-crawler\nDisallow: /b\nCrawl-delay: 3\n",
+        );
+        let rules = robots.rules_for("silent-crawler/1.0");
+        assert!(!rules.is_allowed("/a"));
+        assert!(!rules.is_allowed("/b"));
+        assert!(rules.is_allowed("/c"));
+        assert_eq!(rules.crawl_delay(), Some(Duration::from_secs(3)));
+    }
+
+    #[test]
+    fn consecutive_user_agents_share_a_group() {
+        let robots = RobotsTxt::parse("User-agent: a-bot\nUser-agent: b-bot\nDisallow: /x\n");
+        assert!(!robots.rules_for("a-bot").is_allowed("/x"));
+        assert!(!robots.rules_for("b-bot").is_allowed("/x"));
+        assert!(robots.rules_for("c-bot").is_allowed("/x"));
+    }
+}
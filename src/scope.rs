@@ -0,0 +1,685 @@
+//! The domains a crawl stays on, scope files and user-supplied
+//! include/exclude patterns that fence off parts of a site, and heuristics
+//! that keep the crawl out of URL spaces that never end.
+
+use crate::builder::Config;
+use crate::error::CrawlerError;
+use crate::extract::registrable_domain;
+use crate::results::{BudgetHit, BudgetKind, SkippedUrl, TrapReason};
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use url::{Host, Position, Url};
+
+/// The URLs a crawl may request: those on the base URL's registrable
+/// domain or an allowed domain, subdomains included, on the seeds' ports;
+/// or, with a scope file, those its rules let in.
+#[derive(Debug, Clone)]
+pub(crate) struct Scope {
+    base_domain: String,
+    allowed_domains: Vec<String>,
+    seed_ports: Vec<Option<u16>>,
+    any_port: bool,
+    rules: Option<ScopeRules>,
+}
+
+impl Scope {
+    pub fn new(config: &Config) -> Result<Self, CrawlerError> {
+        let parsed_url = Url::parse(&config.base_url)?;
+        let base_host = parsed_url.host().ok_or(CrawlerError::MissingHost)?;
+        let base_domain = registrable_domain(&base_host);
+
+        let allowed_domains = config.allowed_domains
+            .iter()
+            .map(|domain| Host::parse(domain.trim().trim_start_matches('.')).map(|host| host.to_string()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut seed_ports: Vec<Option<u16>> = std::iter::once(&config.base_url)
+            .chain(&config.seeds)
+            .filter_map(|url| Url::parse(url).ok())
+            .map(|url| url.port())
+            .collect();
+        seed_ports.sort();
+        seed_ports.dedup();
+
+        let rules = match &config.scope_file {
+            Some(path) => {
+                let text = std::fs::read_to_string(path)?;
+                let rules = ScopeRules::parse(&text).map_err(|err| CrawlerError::InvalidScope(format!("{}: {}", path.display(), err)))?;
+                Some(rules)
+            }
+            None => None,
+        };
+        Ok(Scope { base_domain, allowed_domains, seed_ports, any_port: config.any_port, rules })
+    }
+
+    pub fn base_domain(&self) -> &str {
+        &self.base_domain
+    }
+
+    /// Whether a scope file decides what is in scope.
+    pub fn has_rules(&self) -> bool {
+        self.rules.is_some()
+    }
+
+    /// Returns the in-scope domain that `url` belongs to, if any. Ports
+    /// other than the seeds' are out of scope unless any port is allowed.
+    /// The scope file isn't consulted.
+    pub fn domain(&self, url: &str) -> Option<&str> {
+        let parsed_url = Url::parse(url).ok()?;
+        if !self.any_port && !self.seed_ports.contains(&parsed_url.port()) {
+            return None;
+        }
+        let url_domain = parsed_url.host_str()?;
+        // IP addresses only match exactly; names may be the domain or a subdomain
+        let is_ip = matches!(parsed_url.host(), Some(Host::Ipv4(_) | Host::Ipv6(_)));
+
+        std::iter::once(&self.base_domain)
+            .chain(&self.allowed_domains)
+            .find(|domain| {
+                url_domain == domain.as_str()
+                    || (!is_ip && url_domain.ends_with(&format!(".{}", domain)))
+            })
+            .map(String::as_str)
+    }
+
+    /// Whether `url` is in scope. A scope file's exclude rules always win;
+    /// when it has include rules they replace the domain scope, otherwise
+    /// the domain scope applies to what isn't excluded.
+    pub fn contains(&self, url: &str) -> bool {
+        let Some(rules) = &self.rules else {
+            return self.domain(url).is_some();
+        };
+        match Url::parse(url).ok().and_then(|url| rules.matching(&url)) {
+            Some((included, _)) => included,
+            None => !rules.has_includes() && self.domain(url).is_some(),
+        }
+    }
+
+    /// Returns why the scope file keeps `url` out, or `None` if it doesn't.
+    /// URLs it doesn't mention are left to the domain scope, unless it has
+    /// include rules.
+    pub fn skip(&self, url: &str) -> Option<SkippedUrl> {
+        let rules = self.rules.as_ref()?;
+        let skipped = |pattern: Option<String>| Some(SkippedUrl { url: url.to_string(), pattern });
+        match Url::parse(url).ok().and_then(|url| rules.matching(&url)) {
+            Some((false, rule)) => skipped(Some(rule.to_string())),
+            Some((true, _)) => None,
+            None if rules.has_includes() => skipped(None),
+            None => None,
+        }
+    }
+
+    /// Whether `url` would be crawled as far as the scope and `filter` go,
+    /// and why.
+    pub fn verdict(&self, filter: &UrlFilter, url: &str) -> ScopeVerdict {
+        let out = |rule: Option<String>, reason: String| ScopeVerdict { in_scope: false, rule, reason };
+        let Ok(parsed) = Url::parse(url) else {
+            return out(None, "not a valid URL".to_string());
+        };
+        let (rule, reason) = match self.rules.as_ref().map(|rules| (rules, rules.matching(&parsed))) {
+            Some((_, Some((false, rule)))) => return out(Some(rule.to_string()), "matches an exclude rule of the scope file".to_string()),
+            Some((_, Some((true, rule)))) => (Some(rule.to_string()), "matches an include rule of the scope file".to_string()),
+            Some((rules, None)) if rules.has_includes() => return out(None, "matches no include rule of the scope file".to_string()),
+            _ => match self.domain(url) {
+                Some(domain) => (None, format!("on {}", domain)),
+                None if !self.any_port && !self.seed_ports.contains(&parsed.port()) => {
+                    return out(None, format!("on port {}, which no seed is on", parsed.port_or_known_default().unwrap_or_default()));
+                }
+                None => return out(None, format!("not on {} or an allowed domain", self.base_domain)),
+            },
+        };
+        match filter.skip(url) {
+            Some(SkippedUrl { pattern: Some(pattern), .. }) => out(Some(pattern), "matches an exclude pattern".to_string()),
+            Some(SkippedUrl { pattern: None, .. }) => out(None, "matches no include pattern".to_string()),
+            None => ScopeVerdict { in_scope: true, rule, reason },
+        }
+    }
+}
+
+/// Whether a URL is in a crawl's scope, and why, as answered by
+/// [`CrawlerBuilder::scope_verdict`](crate::CrawlerBuilder::scope_verdict).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScopeVerdict {
+    pub in_scope: bool,
+    /// The scope file rule or include/exclude pattern that decided, if one
+    /// did.
+    pub rule: Option<String>,
+    /// Why, such as `on example.com` or `matches no include pattern`.
+    pub reason: String,
+}
+
+/// The include and exclude rules of a scope file.
+#[derive(Debug, Clone, Default)]
+struct ScopeRules {
+    include: Vec<ScopeRule>,
+    exclude: Vec<ScopeRule>,
+}
+
+/// One rule of a scope file.
+#[derive(Debug, Clone)]
+enum ScopeRule {
+    /// `[scheme://]host[:port][/path]`, with the path a prefix and the host
+    /// maybe `*` or `*.domain`
+    Pattern { text: String, scheme: Option<String>, host: HostPattern, port: Option<u16>, path: String },
+    /// A Burp Suite advanced-mode rule: a protocol, and regexes for the
+    /// host, the port and the file (the path and query)
+    Regex { protocol: Option<String>, host: Option<Regex>, port: Option<Regex>, file: Option<Regex> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum HostPattern {
+    Any,
+    Exact(String),
+    /// The subdomains of a domain, at any depth, but not the domain itself
+    Subdomains(String),
+}
+
+impl ScopeRules {
+    /// Parses a Burp Suite scope export (JSON), or else a file with one
+    /// rule per line: a pattern to include, or one prefixed with `-` or `!`
+    /// to exclude (`+` may mark includes). Blank lines and `#` comments are
+    /// skipped.
+    fn parse(text: &str) -> Result<Self, String> {
+        if text.trim_start().starts_with('{') {
+            return Self::parse_burp(text);
+        }
+        let mut rules = ScopeRules::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (list, pattern) = match line.strip_prefix(['-', '!']) {
+                Some(pattern) => (&mut rules.exclude, pattern),
+                None => (&mut rules.include, line.strip_prefix('+').unwrap_or(line)),
+            };
+            list.push(parse_pattern(pattern.trim()).map_err(|err| format!("line {}: {}", number + 1, err))?);
+        }
+        Ok(rules)
+    }
+
+    fn parse_burp(text: &str) -> Result<Self, String> {
+        let export: BurpExport = serde_json::from_str(text).map_err(|err| format!("not a Burp Suite scope export ({})", err))?;
+        let scope = export.target.scope;
+        let rules = |entries: Vec<BurpRule>| -> Result<Vec<ScopeRule>, String> {
+            entries.into_iter().filter(|entry| entry.enabled).map(BurpRule::into_rule).collect()
+        };
+        Ok(ScopeRules { include: rules(scope.include)?, exclude: rules(scope.exclude)? })
+    }
+
+    fn has_includes(&self) -> bool {
+        !self.include.is_empty()
+    }
+
+    /// The first exclude rule matching `url`, or else the first include
+    /// rule, with whether it includes.
+    fn matching(&self, url: &Url) -> Option<(bool, &ScopeRule)> {
+        if let Some(rule) = self.exclude.iter().find(|rule| rule.matches(url)) {
+            return Some((false, rule));
+        }
+        self.include.iter().find(|rule| rule.matches(url)).map(|rule| (true, rule))
+    }
+}
+
+fn parse_pattern(text: &str) -> Result<ScopeRule, String> {
+    let (scheme, rest) = match text.split_once("://") {
+        Some(("*", rest)) => (None, rest),
+        Some((scheme, rest)) if scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https") => {
+            (Some(scheme.to_ascii_lowercase()), rest)
+        }
+        Some((scheme, _)) => return Err(format!("unsupported scheme {:?}", scheme)),
+        None => (None, text),
+    };
+    let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    // IPv6 addresses are bracketed, so a colon after the bracket starts the port
+    let (host, port) = match authority.rfind(':') {
+        Some(colon) if !authority[colon..].contains(']') => (&authority[..colon], Some(&authority[colon + 1..])),
+        _ => (authority, None),
+    };
+    let port = match port {
+        None | Some("*") => None,
+        Some(port) => Some(port.parse::<u16>().map_err(|_| format!("invalid port {:?}", port))?),
+    };
+    let normalize = |host: &str| Host::parse(host).map(|host| host.to_string()).map_err(|err| format!("invalid host {:?}: {}", host, err));
+    let host = match host {
+        "" => return Err("no host".to_string()),
+        "*" => HostPattern::Any,
+        _ if host.starts_with("*.") => HostPattern::Subdomains(normalize(&host[2..])?),
+        _ if host.contains('*') => return Err(format!("invalid host {:?}: wildcards only go at the start, as in *.example.com", host)),
+        _ => HostPattern::Exact(normalize(host)?),
+    };
+    Ok(ScopeRule::Pattern { text: text.to_string(), scheme, host, port, path: path.to_string() })
+}
+
+impl ScopeRule {
+    fn matches(&self, url: &Url) -> bool {
+        let host = url.host_str().unwrap_or_default();
+        let port = url.port_or_known_default();
+        // Paths are matched with their query, as Burp does
+        let file = &url[Position::BeforePath..Position::AfterQuery];
+        match self {
+            ScopeRule::Pattern { scheme, host: pattern, port: wanted_port, path, .. } => {
+                scheme.as_deref().is_none_or(|scheme| scheme == url.scheme())
+                    && match pattern {
+                        HostPattern::Any => true,
+                        HostPattern::Exact(name) => host == name,
+                        HostPattern::Subdomains(domain) => host.ends_with(&format!(".{}", domain)),
+                    }
+                    && wanted_port.is_none_or(|wanted| Some(wanted) == port)
+                    && file.starts_with(path.as_str())
+            }
+            ScopeRule::Regex { protocol, host: host_pattern, port: port_pattern, file: file_pattern } => {
+                protocol.as_deref().is_none_or(|protocol| protocol == url.scheme())
+                    && host_pattern.as_ref().is_none_or(|pattern| pattern.is_match(host))
+                    && port_pattern.as_ref().is_none_or(|pattern| pattern.is_match(&port.unwrap_or_default().to_string()))
+                    && file_pattern.as_ref().is_none_or(|pattern| pattern.is_match(file))
+            }
+        }
+    }
+}
+
+impl fmt::Display for ScopeRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScopeRule::Pattern { text, .. } => f.write_str(text),
+            ScopeRule::Regex { protocol, host, port, file } => {
+                let parts: Vec<String> = [
+                    protocol.as_ref().map(|protocol| format!("protocol {}", protocol)),
+                    host.as_ref().map(|host| format!("host {}", host)),
+                    port.as_ref().map(|port| format!("port {}", port)),
+                    file.as_ref().map(|file| format!("file {}", file)),
+                ]
+                .into_iter()
+                .flatten()
+                .collect();
+                if parts.is_empty() {
+                    f.write_str("any URL")
+                } else {
+                    f.write_str(&parts.join(", "))
+                }
+            }
+        }
+    }
+}
+
+/// The parts of a Burp Suite project options export that hold the scope.
+#[derive(Deserialize)]
+struct BurpExport {
+    target: BurpTarget,
+}
+
+#[derive(Deserialize)]
+struct BurpTarget {
+    scope: BurpScope,
+}
+
+#[derive(Deserialize)]
+struct BurpScope {
+    #[serde(default)]
+    include: Vec<BurpRule>,
+    #[serde(default)]
+    exclude: Vec<BurpRule>,
+}
+
+/// A rule as Burp exports it: a URL prefix in simple mode, or a protocol
+/// and host, port and file regexes in advanced mode.
+#[derive(Deserialize)]
+struct BurpRule {
+    #[serde(default = "enabled")]
+    enabled: bool,
+    prefix: Option<String>,
+    protocol: Option<String>,
+    host: Option<String>,
+    port: Option<String>,
+    file: Option<String>,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+impl BurpRule {
+    fn into_rule(self) -> Result<ScopeRule, String> {
+        if let Some(prefix) = self.prefix.filter(|prefix| !prefix.trim().is_empty()) {
+            return parse_pattern(prefix.trim());
+        }
+        // Empty fields match anything
+        let regex = |pattern: Option<String>, case_insensitive: bool| {
+            pattern
+                .filter(|pattern| !pattern.is_empty())
+                .map(|pattern| {
+                    RegexBuilder::new(&pattern)
+                        .case_insensitive(case_insensitive)
+                        .build()
+                        .map_err(|err| format!("invalid regex {:?}: {}", pattern, err))
+                })
+                .transpose()
+        };
+        Ok(ScopeRule::Regex {
+            protocol: self.protocol.map(|protocol| protocol.to_ascii_lowercase()).filter(|protocol| !protocol.is_empty() && protocol != "any"),
+            // Hosts are compared lowercase
+            host: regex(self.host, true)?,
+            port: regex(self.port, false)?,
+            file: regex(self.file, false)?,
+        })
+    }
+}
+
+/// Regex patterns matched against full URLs before they enter the frontier.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct UrlFilter {
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+}
+
+impl UrlFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self, regex::Error> {
+        Ok(UrlFilter {
+            include: include.iter().map(|pattern| Regex::new(pattern)).collect::<Result<_, _>>()?,
+            exclude: exclude.iter().map(|pattern| Regex::new(pattern)).collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Returns why `url` is out of scope, or `None` if it may be crawled.
+    /// Exclude patterns win over include patterns; when include patterns
+    /// are given, a URL must match at least one of them.
+    pub fn skip(&self, url: &str) -> Option<SkippedUrl> {
+        if let Some(pattern) = self.exclude.iter().find(|pattern| pattern.is_match(url)) {
+            return Some(SkippedUrl {
+                url: url.to_string(),
+                pattern: Some(pattern.as_str().to_string()),
+            });
+        }
+
+        if !self.include.is_empty() && !self.include.iter().any(|pattern| pattern.is_match(url)) {
+            return Some(SkippedUrl { url: url.to_string(), pattern: None });
+        }
+
+        None
+    }
+}
+
+/// Thresholds beyond which a URL is taken to be part of a crawler trap.
+#[derive(Debug, Clone)]
+pub(crate) struct TrapRules {
+    /// How often a run of path segments may repeat back to back
+    pub max_segment_repeats: usize,
+    pub max_path_depth: usize,
+    pub max_query_params: usize,
+    /// Dates more than this many years ahead are rejected
+    pub max_future_years: i32,
+    pub current_year: i32,
+}
+
+/// Calendars before the web existed are not worth crawling either.
+const EARLIEST_CALENDAR_YEAR: i32 = 1990;
+
+impl TrapRules {
+    /// Returns why `url` looks like a trap, or `None` if it may be crawled.
+    pub fn check(&self, url: &str) -> Option<TrapReason> {
+        let parsed = Url::parse(url).ok()?;
+        let segments: Vec<&str> = parsed
+            .path_segments()
+            .map(|segments| segments.filter(|segment| !segment.is_empty()).collect())
+            .unwrap_or_default();
+
+        if segments.len() > self.max_path_depth {
+            return Some(TrapReason::PathDepth);
+        }
+        if repeated_run(&segments) > self.max_segment_repeats {
+            return Some(TrapReason::RepeatedSegments);
+        }
+        if parsed.query_pairs().count() > self.max_query_params {
+            return Some(TrapReason::QueryParams);
+        }
+
+        let far_year = |value: &str| {
+            calendar_year(value).is_some_and(|year| {
+                year < EARLIEST_CALENDAR_YEAR || year > self.current_year + self.max_future_years
+            })
+        };
+        if segments.iter().any(|segment| far_year(segment)) || parsed.query_pairs().any(|(_, value)| far_year(&value)) {
+            return Some(TrapReason::Calendar);
+        }
+
+        None
+    }
+}
+
+/// The largest number of times any run of segments repeats back to back,
+/// e.g. 3 for `red/large/red/large/red/large`.
+fn repeated_run(segments: &[&str]) -> usize {
+    let mut most = 1;
+    for length in 1..=segments.len() / 2 {
+        for start in 0..segments.len() - length {
+            let block = &segments[start..start + length];
+            let mut repeats = 1;
+            while segments
+                .get(start + repeats * length..start + (repeats + 1) * length)
+                .is_some_and(|next| next == block)
+            {
+                repeats += 1;
+            }
+            most = most.max(repeats);
+        }
+    }
+    most
+}
+
+/// The year of a date-like value: `2031`, `2031-04` or `2031-04-01`.
+fn calendar_year(value: &str) -> Option<i32> {
+    let mut parts = value.split('-');
+    let year = parts.next()?;
+    if year.len() != 4 || !year.starts_with(['1', '2']) || !year.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let rest: Vec<&str> = parts.collect();
+    if rest.len() > 2 || !rest.iter().all(|part| (1..=2).contains(&part.len()) && part.bytes().all(|b| b.is_ascii_digit())) {
+        return None;
+    }
+    year.parse().ok()
+}
+
+/// Caps on how many URLs per first-level directory and per host enter the
+/// frontier.
+#[derive(Debug, Default)]
+pub(crate) struct Budgets {
+    per_directory: Option<usize>,
+    per_subdomain: Option<usize>,
+    /// URLs admitted and URLs turned away, per bucket
+    counts: Mutex<HashMap<(BudgetKind, String), (usize, usize)>>,
+}
+
+impl Budgets {
+    pub fn new(per_directory: Option<usize>, per_subdomain: Option<usize>) -> Self {
+        Budgets { per_directory, per_subdomain, counts: Mutex::default() }
+    }
+
+    fn buckets(&self, url: &str) -> Vec<(BudgetKind, String, usize)> {
+        let Some(parsed) = Url::parse(url).ok() else {
+            return Vec::new();
+        };
+        let Some(host) = parsed.host_str() else {
+            return Vec::new();
+        };
+
+        let mut buckets = Vec::new();
+        if let Some(limit) = self.per_directory {
+            // Files directly under the root share the "/" bucket
+            let mut segments: Vec<&str> = parsed.path_segments().map(|segments| segments.collect()).unwrap_or_default();
+            segments.pop();
+            let directory = match segments.first() {
+                Some(first) => format!("{}/{}/", host, first),
+                None => format!("{}/", host),
+            };
+            buckets.push((BudgetKind::Directory, directory, limit));
+        }
+        if let Some(limit) = self.per_subdomain {
+            buckets.push((BudgetKind::Subdomain, host.to_string(), limit));
+        }
+        buckets
+    }
+
+    /// Counts `url` against its buckets, or returns the bucket whose budget
+    /// is used up without counting it anywhere.
+    pub fn admit(&self, url: &str) -> Result<(), String> {
+        let buckets = self.buckets(url);
+        let mut counts = self.counts.lock().unwrap();
+        if let Some((kind, bucket, _)) = buckets
+            .iter()
+            .find(|(kind, bucket, limit)| counts.get(&(*kind, bucket.clone())).is_some_and(|(used, _)| used >= limit))
+        {
+            counts.get_mut(&(*kind, bucket.clone())).unwrap().1 += 1;
+            return Err(bucket.clone());
+        }
+        for (kind, bucket, _) in buckets {
+            counts.entry((kind, bucket)).or_default().0 += 1;
+        }
+        Ok(())
+    }
+
+    /// Counts `url` against its buckets even if they are full, for the start
+    /// URL and URLs restored from a checkpoint.
+    pub fn add(&self, url: &str) {
+        let mut counts = self.counts.lock().unwrap();
+        for (kind, bucket, _) in self.buckets(url) {
+            counts.entry((kind, bucket)).or_default().0 += 1;
+        }
+    }
+
+    /// Buckets that turned URLs away, sorted.
+    pub fn exhausted(&self) -> Vec<BudgetHit> {
+        let mut hits: Vec<BudgetHit> = self
+            .counts
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, (_, skipped))| *skipped > 0)
+            .map(|((kind, bucket), (_, skipped))| BudgetHit { kind: *kind, bucket: bucket.clone(), skipped: *skipped })
+            .collect();
+        hits.sort_by(|a, b| (a.kind, &a.bucket).cmp(&(b.kind, &b.bucket)));
+        hits
+    }
+
+    /// Carries over the skip counts of a checkpoint.
+    pub fn restore(&self, hits: Vec<BudgetHit>) {
+        let mut counts = self.counts.lock().unwrap();
+        for hit in hits {
+            counts.entry((hit.kind, hit.bucket)).or_default().1 += hit.skipped;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CrawlerBuilder;
+
+    fn domain_of(host: &str) -> String {
+        let url = Url::parse(&format!("http://{}/", host)).unwrap();
+        registrable_domain(&url.host().unwrap())
+    }
+
+    fn in_scope(base_url: &str, url: &str) -> bool {
+        CrawlerBuilder::new(base_url).scope_verdict(url).unwrap().in_scope
+    }
+
+    #[test]
+    fn registrable_domains_follow_the_public_suffix_list() {
+        assert_eq!(domain_of("www.example.com"), "example.com");
+        assert_eq!(domain_of("shop.example.co.uk"), "example.co.uk");
+        assert_eq!(domain_of("example.co.uk"), "example.co.uk");
+        assert_eq!(domain_of("alice.github.io"), "alice.github.io");
+        assert_eq!(domain_of("docs.alice.github.io"), "alice.github.io");
+        assert_eq!(domain_of("WWW.Example.COM."), "example.com");
+    }
+
+    #[test]
+    fn ip_addresses_are_their_own_domain() {
+        assert_eq!(domain_of("192.168.1.10"), "192.168.1.10");
+        assert_eq!(domain_of("[::1]"), "[::1]");
+    }
+
+    #[test]
+    fn subdomains_share_a_co_uk_scope_but_neighbours_do_not() {
+        let base = "https://www.example.co.uk/";
+        assert!(in_scope(base, "https://example.co.uk/about"));
+        assert!(in_scope(base, "https://shop.example.co.uk/cart"));
+        assert!(!in_scope(base, "https://other.co.uk/"));
+        assert!(!in_scope(base, "https://co.uk/"));
+    }
+
+    #[test]
+    fn github_io_users_are_separate_sites() {
+        let base = "https://alice.github.io/project/";
+        assert!(in_scope(base, "https://alice.github.io/other-project/"));
+        assert!(in_scope(base, "https://docs.alice.github.io/"));
+        assert!(!in_scope(base, "https://bob.github.io/"));
+        assert!(!in_scope(base, "https://github.io/"));
+    }
+
+    #[test]
+    fn ip_addresses_only_match_exactly() {
+        let base = "http://192.168.1.10/";
+        assert!(in_scope(base, "http://192.168.1.10/admin"));
+        assert!(!in_scope(base, "http://192.168.1.11/"));
+        assert!(!in_scope(base, "http://10.192.168.1.10/"));
+        assert!(!in_scope(base, "http://192.168.1.10:8080/"));
+    }
+
+    fn traps() -> TrapRules {
+        TrapRules {
+            max_segment_repeats: 2,
+            max_path_depth: 6,
+            max_query_params: 3,
+            max_future_years: 2,
+            current_year: 2025,
+        }
+    }
+
+    #[test]
+    fn segments_may_repeat_up_to_the_limit() {
+        assert_eq!(traps().check("http://site.test/a/b/a/b/c"), None);
+        assert_eq!(traps().check("http://site.test/a/b/a/b/a/b"), Some(TrapReason::RepeatedSegments));
+        assert_eq!(traps().check("http://site.test/x/x"), None);
+        assert_eq!(traps().check("http://site.test/x/x/x"), Some(TrapReason::RepeatedSegments));
+    }
+
+    #[test]
+    fn paths_may_nest_up_to_the_limit() {
+        assert_eq!(traps().check("http://site.test/a/b/c/d/e/f"), None);
+        assert_eq!(traps().check("http://site.test/a/b/c/d/e/f/g"), Some(TrapReason::PathDepth));
+        // Empty segments from doubled or trailing slashes don't count
+        assert_eq!(traps().check("http://site.test/a//b/c/d/e/f/"), None);
+    }
+
+    #[test]
+    fn queries_may_have_up_to_the_limit_of_parameters() {
+        assert_eq!(traps().check("http://site.test/search?a=1&b=2&c=3"), None);
+        assert_eq!(traps().check("http://site.test/search?a=1&b=2&c=3&d=4"), Some(TrapReason::QueryParams));
+    }
+
+    #[test]
+    fn calendars_may_run_up_to_the_limit_of_future_years() {
+        assert_eq!(traps().check("http://site.test/calendar/2027/01"), None);
+        assert_eq!(traps().check("http://site.test/calendar/2028/01"), Some(TrapReason::Calendar));
+        assert_eq!(traps().check("http://site.test/events?date=2027-12-31"), None);
+        assert_eq!(traps().check("http://site.test/events?date=2028-01-01"), Some(TrapReason::Calendar));
+    }
+
+    #[test]
+    fn calendars_may_not_predate_the_web() {
+        assert_eq!(traps().check("http://site.test/archive/1990"), None);
+        assert_eq!(traps().check("http://site.test/archive/1989"), Some(TrapReason::Calendar));
+    }
+
+    #[test]
+    fn numbers_that_are_not_dates_are_not_calendars() {
+        assert_eq!(traps().check("http://site.test/products/9999"), None);
+        assert_eq!(traps().check("http://site.test/products/29999"), None);
+        assert_eq!(traps().check("http://site.test/items?id=2099-x"), None);
+    }
+}
@@ -0,0 +1,118 @@
+//! Secret scanning: patterns for API keys, tokens and private keys exposed
+//! in pages and scripts.
+//!
+//! The built-in rules match formats distinctive enough to be reported as
+//! they are. The generic `api_key = "..."` rule would also match
+//! placeholders and identifiers, so its values must look random: long
+//! enough, and with enough Shannon entropy per character.
+
+use crate::results::SecretFinding;
+use regex::Regex;
+use std::collections::{BTreeSet, HashSet};
+
+/// Minimum bits of entropy per character of a generic secret.
+const MIN_ENTROPY: f64 = 3.5;
+
+/// Characters of a secret left readable in a finding.
+const VISIBLE_CHARS: usize = 4;
+
+/// The built-in rules as (name, regex, whether to check entropy). A rule's
+/// first capture group, if it has one, is the secret; otherwise the whole
+/// match is.
+const BUILTIN_RULES: &[(&str, &str, bool)] = &[
+    ("aws-access-key-id", r"\b((?:AKIA|ASIA)[0-9A-Z]{16})\b", false),
+    ("google-api-key", r"\b(AIza[0-9A-Za-z_\-]{35})", false),
+    ("slack-token", r"\b(xox[abposr]-[0-9A-Za-z\-]{10,})", false),
+    ("slack-webhook", r"https://hooks\.slack\.com/services/(T[0-9A-Z]+/B[0-9A-Z]+/[0-9A-Za-z]+)", false),
+    ("github-token", r"\b(gh[pousr]_[0-9A-Za-z]{36})\b", false),
+    ("stripe-secret-key", r"\b(sk_live_[0-9A-Za-z]{24,})\b", false),
+    (
+        "private-key",
+        r"-----BEGIN (?:RSA |EC |DSA |OPENSSH |PGP |ENCRYPTED )?PRIVATE KEY(?: BLOCK)?-----\s+([0-9A-Za-z+/=]{16,})",
+        false,
+    ),
+    (
+        "generic-api-key",
+        r#"(?i)\b(?:api[_\-]?key|api[_\-]?secret|secret[_\-]?key|client[_\-]?secret|access[_\-]?token|auth[_\-]?token)["']?\s*[:=]\s*["']([0-9A-Za-z_\-+/=.]{16,128})["']"#,
+        true,
+    ),
+];
+
+/// A named pattern for one kind of secret.
+pub(crate) struct SecretRule {
+    name: String,
+    pattern: Regex,
+    check_entropy: bool,
+}
+
+impl SecretRule {
+    /// A user-supplied rule, taken as high-signal like the specific
+    /// built-in ones.
+    pub fn new(name: &str, pattern: &str) -> Result<Self, regex::Error> {
+        Ok(SecretRule { name: name.to_string(), pattern: Regex::new(pattern)?, check_entropy: false })
+    }
+}
+
+/// The built-in rules.
+pub(crate) fn builtin_rules() -> Vec<SecretRule> {
+    BUILTIN_RULES
+        .iter()
+        .map(|&(name, pattern, check_entropy)| SecretRule {
+            name: name.to_string(),
+            pattern: Regex::new(pattern).unwrap(),
+            check_entropy,
+        })
+        .collect()
+}
+
+/// Applies `rules` to the body of the page or script at `url`. Each finding
+/// holds the match with the secret redacted. Secrets a specific rule found
+/// aren't reported again by the generic one.
+pub(crate) fn scan_secrets(url: &str, body: &str, rules: &[SecretRule]) -> BTreeSet<SecretFinding> {
+    let mut findings = BTreeSet::new();
+    let mut specific = HashSet::new();
+    for rule in rules.iter().filter(|rule| !rule.check_entropy).chain(rules.iter().filter(|rule| rule.check_entropy)) {
+        for captures in rule.pattern.captures_iter(body) {
+            let whole = captures.get(0).unwrap();
+            let secret = captures.get(1).unwrap_or(whole);
+            if !rule.check_entropy {
+                specific.insert(secret.as_str());
+            } else if specific.contains(secret.as_str()) || entropy(secret.as_str()) < MIN_ENTROPY {
+                continue;
+            }
+            let snippet = format!(
+                "{}{}{}",
+                &body[whole.start()..secret.start()],
+                redact(secret.as_str()),
+                &body[secret.end()..whole.end()],
+            );
+            findings.insert(SecretFinding { url: url.to_string(), rule: rule.name.clone(), snippet });
+        }
+    }
+    findings
+}
+
+/// `secret` with all but its first [`VISIBLE_CHARS`] characters masked,
+/// keeping its length. Secrets of up to twice that are masked entirely.
+fn redact(secret: &str) -> String {
+    let length = secret.chars().count();
+    let visible = if length > VISIBLE_CHARS * 2 { VISIBLE_CHARS } else { 0 };
+    secret.chars().take(visible).chain(std::iter::repeat_n('*', length - visible)).collect()
+}
+
+/// Shannon entropy of `value` in bits per character.
+fn entropy(value: &str) -> f64 {
+    let mut counts = [0usize; 256];
+    for byte in value.bytes() {
+        counts[byte as usize] += 1;
+    }
+    let length = value.len() as f64;
+    counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / length;
+            -p * p.log2()
+        })
+        .sum()
+}
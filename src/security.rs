@@ -0,0 +1,112 @@
+//! Response header capture and the security header audit.
+//!
+//! The audit covers successful HTML pages only, since the headers protect
+//! documents rather than the images and scripts they load.
+//! `Strict-Transport-Security` is only expected over HTTPS, where browsers
+//! honour it.
+
+use crate::results::{HstsHost, PageRecord, SecurityAudit, SecurityOffender};
+use reqwest::header::HeaderMap;
+use std::collections::BTreeMap;
+
+/// Headers every HTML page is expected to send, lowercase.
+pub(crate) const SECURITY_HEADERS: [&str; 4] =
+    ["content-security-policy", "x-frame-options", "strict-transport-security", "x-content-type-options"];
+
+/// Headers that reveal the server software, lowercase.
+const FINGERPRINT_HEADERS: [&str; 2] = ["server", "x-powered-by"];
+
+/// Pages listed as worst offenders.
+const WORST_OFFENDERS: usize = 10;
+
+/// The headers of a response by lowercase name, every value kept. Unless
+/// `all` is set, only the headers the audit looks at are kept.
+pub(crate) fn captured_headers(headers: &HeaderMap, all: bool) -> BTreeMap<String, Vec<String>> {
+    let mut captured: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (name, value) in headers {
+        let name = name.as_str();
+        if all || SECURITY_HEADERS.contains(&name) || FINGERPRINT_HEADERS.contains(&name) {
+            captured
+                .entry(name.to_string())
+                .or_default()
+                .push(String::from_utf8_lossy(value.as_bytes()).into_owned());
+        }
+    }
+    captured
+}
+
+/// Parses a `Strict-Transport-Security` value sent by `host`. Values
+/// without a valid `max-age` are ignored, as browsers do.
+pub(crate) fn parse_hsts(host: &str, value: &str) -> Option<HstsHost> {
+    let mut hsts = HstsHost { host: host.to_string(), max_age: 0, include_subdomains: false, preload: false };
+    let mut max_age = None;
+    for directive in value.split(';') {
+        let (name, argument) = directive.split_once('=').unwrap_or((directive, ""));
+        match name.trim().to_ascii_lowercase().as_str() {
+            "max-age" => max_age = Some(argument.trim().trim_matches('"').parse().ok()?),
+            "includesubdomains" => hsts.include_subdomains = true,
+            "preload" => hsts.preload = true,
+            _ => {}
+        }
+    }
+    hsts.max_age = max_age?;
+    Some(hsts)
+}
+
+/// Counts the security headers missing from `pages`, sorted by URL, and
+/// tallies their `Server` and `X-Powered-By` values.
+pub(crate) fn security_audit(pages: &[PageRecord]) -> SecurityAudit {
+    let mut audit = SecurityAudit {
+        missing: SECURITY_HEADERS.iter().map(|name| (name.to_string(), 0)).collect(),
+        ..SecurityAudit::default()
+    };
+    let mut offenders = Vec::new();
+    for page in pages {
+        for value in page.headers.get("server").into_iter().flatten() {
+            *audit.servers.entry(value.clone()).or_insert(0) += 1;
+        }
+        for value in page.headers.get("x-powered-by").into_iter().flatten() {
+            *audit.powered_by.entry(value.clone()).or_insert(0) += 1;
+        }
+        if !page.is_html() {
+            continue;
+        }
+
+        audit.pages += 1;
+        let missing: Vec<String> = SECURITY_HEADERS
+            .iter()
+            .filter(|name| !has_protection(page, name))
+            .map(|name| name.to_string())
+            .collect();
+        for name in &missing {
+            *audit.missing.entry(name.clone()).or_insert(0) += 1;
+        }
+        if !missing.is_empty() {
+            offenders.push(SecurityOffender { url: page.url.clone(), missing });
+        }
+    }
+
+    // Pages keep their URL order among those missing as many headers
+    offenders.sort_by_key(|offender| std::cmp::Reverse(offender.missing.len()));
+    offenders.truncate(WORST_OFFENDERS);
+    audit.worst_offenders = offenders;
+    audit
+}
+
+/// Whether `page` has the protection the header `name` gives.
+fn has_protection(page: &PageRecord, name: &str) -> bool {
+    if page.headers.contains_key(name) {
+        return true;
+    }
+    match name {
+        "strict-transport-security" => !page.final_url.starts_with("https:"),
+        // A CSP with frame-ancestors supersedes X-Frame-Options
+        "x-frame-options" => page
+            .headers
+            .get("content-security-policy")
+            .into_iter()
+            .flatten()
+            .any(|policy| policy.to_ascii_lowercase().contains("frame-ancestors")),
+        _ => false,
+    }
+}
@@ -0,0 +1,43 @@
+//! Well-known files that shouldn't be public, or that reveal how a site is
+//! run, and how to tell them from a soft 404 page.
+
+/// Paths requested on every host by the common files check.
+pub(crate) const COMMON_FILES: [&str; 7] = [
+    "/.git/HEAD",
+    "/.env",
+    "/backup.zip",
+    "/.DS_Store",
+    "/server-status",
+    "/.well-known/security.txt",
+    "/crossdomain.xml",
+];
+
+/// Whether `body` is what the file at `path` holds, rather than an error
+/// page served with a success status.
+pub(crate) fn is_genuine(path: &str, body: &[u8]) -> bool {
+    let text = String::from_utf8_lossy(&body[..body.len().min(4096)]);
+    let trimmed = text.trim_start();
+    match path {
+        // A branch reference, or a commit hash for a detached HEAD
+        "/.git/HEAD" => {
+            trimmed.starts_with("ref: ")
+                || (trimmed.len() >= 40 && trimmed.as_bytes()[..40].iter().all(u8::is_ascii_hexdigit))
+        }
+        // KEY=value lines, and not an HTML page
+        "/.env" => {
+            !trimmed.starts_with('<')
+                && text.lines().any(|line| {
+                    let line = line.trim_start().trim_start_matches("export ");
+                    line.split_once('=').is_some_and(|(key, _)| {
+                        !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                    })
+                })
+        }
+        "/backup.zip" => body.starts_with(b"PK\x03\x04"),
+        "/.DS_Store" => body.get(4..8) == Some(b"Bud1"),
+        "/server-status" => text.contains("Apache Server Status") || text.contains("Server uptime"),
+        "/.well-known/security.txt" => !trimmed.starts_with('<') && text.to_ascii_lowercase().contains("contact:"),
+        "/crossdomain.xml" => text.contains("<cross-domain-policy"),
+        _ => true,
+    }
+}
@@ -0,0 +1,134 @@
+//! sitemap.xml parsing for `<urlset>` and `<sitemapindex>` documents.
+
+use flate2::read::GzDecoder;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::io::Read;
+
+/// The locations listed in one sitemap document.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sitemap {
+    /// Page URLs from `<url><loc>` entries.
+    pub urls: Vec<String>,
+    /// Nested sitemap URLs from `<sitemap><loc>` entries of a sitemap index.
+    pub sitemaps: Vec<String>,
+    /// True when a gzipped sitemap decompressed to more than the limit and
+    /// was cut there.
+    pub truncated: bool,
+}
+
+/// Parses a sitemap, transparently decompressing gzip (`.xml.gz`) bodies
+/// up to `limit` bytes. Malformed or cut off XML yields whatever locations
+/// were read before the error.
+pub fn parse_sitemap(body: &[u8], limit: u64) -> Sitemap {
+    // gzip magic bytes; reqwest only decodes Content-Encoding, not .gz files
+    if body.starts_with(&[0x1f, 0x8b]) {
+        // One byte past the limit tells a cut document from one that fits exactly
+        let mut decoded = Vec::new();
+        if GzDecoder::new(body).take(limit.saturating_add(1)).read_to_end(&mut decoded).is_err() {
+            return Sitemap::default();
+        }
+        let truncated = decoded.len() as u64 > limit;
+        if truncated {
+            decoded.truncate(limit as usize);
+        }
+        return Sitemap { truncated, ..parse_xml(&decoded) };
+    }
+
+    parse_xml(body)
+}
+
+fn parse_xml(xml: &[u8]) -> Sitemap {
+    let mut sitemap = Sitemap::default();
+    let mut reader = Reader::from_reader(xml);
+    reader.config_mut().trim_text(true);
+
+    // Which entry type we're inside, and whether we're inside its <loc>
+    let mut in_url = false;
+    let mut in_sitemap = false;
+    let mut in_loc = false;
+    let mut loc = String::new();
+    let mut buf = Vec::new();
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(element)) => match element.local_name().as_ref() {
+                b"url" => in_url = true,
+                b"sitemap" => in_sitemap = true,
+                b"loc" => {
+                    in_loc = true;
+                    loc.clear();
+                }
+                _ => {}
+            },
+            Ok(Event::Text(text)) if in_loc => {
+                if let Ok(text) = text.unescape() {
+                    loc.push_str(&text);
+                }
+            }
+            Ok(Event::CData(data)) if in_loc => {
+                loc.push_str(&String::from_utf8_lossy(&data));
+            }
+            Ok(Event::End(element)) => match element.local_name().as_ref() {
+                b"url" => in_url = false,
+                b"sitemap" => in_sitemap = false,
+                b"loc" => {
+                    in_loc = false;
+                    let value = loc.trim().to_string();
+                    if !value.is_empty() {
+                        if in_url {
+                            sitemap.urls.push(value);
+                        } else if in_sitemap {
+                            sitemap.sitemaps.push(value);
+                        }
+                    }
+                }
+                _ => {}
+            },
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    sitemap
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    const URLSET: &str = "<urlset><url><loc>http://site.test/a</loc></url><url><loc>http://site.test/b</loc></url></urlset>";
+
+    #[test]
+    fn gzipped_sitemaps_are_decompressed() {
+        let sitemap = parse_sitemap(&gzip(URLSET.as_bytes()), 1024);
+        assert_eq!(sitemap.urls, ["http://site.test/a", "http://site.test/b"]);
+        assert!(!sitemap.truncated);
+
+        let sitemap = parse_sitemap(&gzip(URLSET.as_bytes()), URLSET.len() as u64);
+        assert_eq!(sitemap.urls.len(), 2);
+        assert!(!sitemap.truncated);
+    }
+
+    #[test]
+    fn gzipped_sitemaps_stop_at_the_limit() {
+        let first_url = URLSET.find("<url><loc>http://site.test/b").unwrap();
+        let sitemap = parse_sitemap(&gzip(URLSET.as_bytes()), first_url as u64);
+        assert_eq!(sitemap.urls, ["http://site.test/a"]);
+        assert!(sitemap.truncated);
+
+        // A small body that inflates to far more than the limit
+        let bomb = gzip(&vec![b' '; 16 * 1024 * 1024]);
+        assert!(parse_sitemap(&bomb, 1024 * 1024).truncated);
+    }
+}
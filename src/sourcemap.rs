@@ -0,0 +1,73 @@
+//! JavaScript source maps: finding a script's map and reading the names of
+//! the original files it was built from.
+
+use base64::Engine;
+use regex::Regex;
+use serde::Deserialize;
+
+/// The part of a source map that matters here. Index maps nest whole maps
+/// in `sections` instead of listing `sources` themselves.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawMap {
+    #[serde(default)]
+    version: Option<u32>,
+    #[serde(default)]
+    sources: Vec<Option<String>>,
+    #[serde(default)]
+    sources_content: Vec<Option<String>>,
+    #[serde(default)]
+    sections: Vec<Section>,
+}
+
+#[derive(Deserialize)]
+struct Section {
+    map: Option<RawMap>,
+}
+
+/// The original files a source map names.
+pub(crate) struct MapSources {
+    pub sources: Vec<String>,
+    /// Whether the map embeds the original code, not just file names
+    pub has_content: bool,
+}
+
+/// The value of the last `//# sourceMappingURL=` (or the older `//@`)
+/// comment of `script`.
+pub(crate) fn mapping_url(script: &str) -> Option<&str> {
+    let comment = Regex::new(r"(?m)^\s*//[#@]\s*sourceMappingURL=(\S+)\s*$").unwrap();
+    comment.captures_iter(script).last().map(|captures| captures.get(1).unwrap().as_str())
+}
+
+/// The JSON of a `data:` URL mapping, which inlines the map in the script.
+pub(crate) fn inline_map(url: &str) -> Option<Vec<u8>> {
+    let data = url.strip_prefix("data:")?;
+    let (media_type, payload) = data.split_once(',')?;
+    if media_type.ends_with(";base64") {
+        base64::engine::general_purpose::STANDARD.decode(payload).ok()
+    } else {
+        Some(payload.as_bytes().to_vec())
+    }
+}
+
+/// Reads the source file names of a source map, or `None` if `json` isn't
+/// one, as when a missing `.map` is answered with an HTML error page.
+pub(crate) fn map_sources(json: &[u8]) -> Option<MapSources> {
+    let map: RawMap = serde_json::from_slice(json).ok()?;
+    if map.version != Some(3) {
+        return None;
+    }
+    let mut sources = MapSources { sources: Vec::new(), has_content: false };
+    collect(map, &mut sources);
+    Some(sources)
+}
+
+fn collect(map: RawMap, sources: &mut MapSources) {
+    sources.has_content |= map.sources_content.iter().any(Option::is_some);
+    sources.sources.extend(map.sources.into_iter().flatten());
+    for section in map.sections {
+        if let Some(map) = section.map {
+            collect(map, sources);
+        }
+    }
+}
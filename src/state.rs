@@ -0,0 +1,104 @@
+//! Crawl checkpoints for resuming interrupted crawls.
+
+use crate::crawler::FrontierEntry;
+use crate::error::CrawlerError;
+use crate::results::{BudgetHit, CrawlError, Form, GraphEdge, GrepMatch, MixedContent, PageRecord, ParameterStats, PdfDocument, SecretFinding, SkippedUrl, TrappedUrl};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+/// Everything needed to continue a crawl where it left off.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub(crate) struct CrawlState {
+    /// Base URL and depth limit the state was recorded with; resuming with
+    /// different values is rejected.
+    pub base_url: String,
+    pub max_depth: usize,
+    /// Every claimed URL with its discovery depth.
+    pub visited: HashMap<String, usize>,
+    /// URLs that were queued or in flight and still need fetching.
+    pub frontier: Vec<FrontierEntry>,
+    pub directories: Vec<String>,
+    pub subdomains: Vec<String>,
+    pub sitemap_urls: Vec<String>,
+    #[serde(default)]
+    pub truncated_sitemaps: Vec<String>,
+    #[serde(default)]
+    pub feeds: Vec<String>,
+    pub pages: Vec<PageRecord>,
+    pub errors: Vec<CrawlError>,
+    #[serde(default)]
+    pub skipped: Vec<SkippedUrl>,
+    #[serde(default)]
+    pub traps: Vec<TrappedUrl>,
+    #[serde(default)]
+    pub budgets: Vec<BudgetHit>,
+    #[serde(default)]
+    pub external_links: Vec<String>,
+    #[serde(default)]
+    pub graph: Vec<GraphEdge>,
+    #[serde(default)]
+    pub assets: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    pub forms: HashMap<String, Vec<Form>>,
+    #[serde(default)]
+    pub directory_listings: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    pub documents: Vec<PdfDocument>,
+    #[serde(default)]
+    pub hreflang: HashMap<String, BTreeMap<String, String>>,
+    /// Dedup keys of the known URLs found so far
+    #[serde(default)]
+    pub known_urls_skipped: Vec<String>,
+    #[serde(default)]
+    pub parameters: HashMap<String, ParameterStats>,
+    #[serde(default)]
+    pub parameters_dropped: usize,
+    #[serde(default)]
+    pub mixed_content: Vec<MixedContent>,
+    #[serde(default)]
+    pub scanned_scripts: Vec<String>,
+    #[serde(default)]
+    pub emails: Vec<String>,
+    #[serde(default)]
+    pub matches: Vec<GrepMatch>,
+    #[serde(default)]
+    pub secrets: Vec<SecretFinding>,
+    /// Pages linking to each URL, by dedup key.
+    #[serde(default)]
+    pub referrers: HashMap<String, Vec<String>>,
+}
+
+impl CrawlState {
+    /// Loads a state file and checks that it belongs to the same crawl.
+    pub fn load(path: &Path, base_url: &str, max_depth: usize) -> Result<Self, CrawlerError> {
+        let data = fs::read(path)?;
+        let state: CrawlState = serde_json::from_slice(&data)
+            .map_err(|err| CrawlerError::InvalidState(format!("{}: {}", path.display(), err)))?;
+
+        if state.base_url != base_url {
+            return Err(CrawlerError::InvalidState(format!(
+                "state file was recorded for {} but this crawl starts at {}",
+                state.base_url, base_url
+            )));
+        }
+        if state.max_depth != max_depth {
+            return Err(CrawlerError::InvalidState(format!(
+                "state file was recorded with depth {} but this crawl uses depth {}",
+                state.max_depth, max_depth
+            )));
+        }
+
+        Ok(state)
+    }
+
+    /// Writes the state to `path`, via a temporary file so an interruption
+    /// never leaves a truncated checkpoint behind.
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_vec(self).map_err(std::io::Error::other)?;
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, json)?;
+        fs::rename(&tmp_path, path)
+    }
+}
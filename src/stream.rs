@@ -0,0 +1,94 @@
+//! Incremental JSON Lines output, written while the crawl is running.
+
+use crate::results::{CrawlError, PageRecord, UrlSource};
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Number of lines buffered before the file is flushed.
+const FLUSH_EVERY: usize = 20;
+
+/// One line of the stream: a fetched page or a failed request.
+#[derive(Serialize)]
+struct StreamLine<'a> {
+    url: &'a str,
+    depth: usize,
+    source: UrlSource,
+    status: Option<u16>,
+    content_type: Option<&'a str>,
+    size: Option<u64>,
+    timestamp: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'a str>,
+}
+
+struct State {
+    writer: BufWriter<File>,
+    unflushed: usize,
+}
+
+/// Appends one JSON object per finished URL to a file.
+pub(crate) struct StreamWriter {
+    state: Mutex<State>,
+}
+
+impl StreamWriter {
+    /// Opens `path` for appending, creating it if needed.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(StreamWriter {
+            state: Mutex::new(State {
+                writer: BufWriter::new(file),
+                unflushed: 0,
+            }),
+        })
+    }
+
+    pub fn write_page(&self, page: &PageRecord) {
+        self.write(&StreamLine {
+            url: &page.url,
+            depth: page.depth,
+            source: page.source,
+            status: Some(page.status),
+            content_type: page.content_type.as_deref(),
+            size: page.size,
+            timestamp: &page.fetched_at,
+            error: None,
+        });
+    }
+
+    /// Writes a line for a request that produced no response at all.
+    pub fn write_failure(&self, error: &CrawlError, depth: usize, source: UrlSource, timestamp: &str) {
+        self.write(&StreamLine {
+            url: &error.url,
+            depth,
+            source,
+            status: error.status,
+            content_type: None,
+            size: None,
+            timestamp,
+            error: Some(&error.message),
+        });
+    }
+
+    fn write(&self, line: &StreamLine<'_>) {
+        let mut state = self.state.lock().unwrap();
+        // Output problems must not interrupt the crawl, so write errors are dropped
+        if serde_json::to_writer(&mut state.writer, line).is_ok() {
+            let _ = state.writer.write_all(b"\n");
+        }
+        state.unflushed += 1;
+        if state.unflushed >= FLUSH_EVERY {
+            let _ = state.writer.flush();
+            state.unflushed = 0;
+        }
+    }
+
+    pub fn flush(&self) {
+        let mut state = self.state.lock().unwrap();
+        let _ = state.writer.flush();
+        state.unflushed = 0;
+    }
+}
@@ -0,0 +1,185 @@
+//! Structured data embedded in pages: JSON-LD blocks, OpenGraph and
+//! Twitter card meta tags, and microdata items.
+
+use crate::extract::{document_base, normalize_url};
+use crate::results::{PageRecord, StructuredData};
+use scraper::{ElementRef, Html, Selector};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The structured data of `html_content`, or `None` when it has none.
+/// JSON-LD blocks larger than `max_json_ld_size` bytes aren't parsed, and
+/// are flagged like invalid ones. Microdata URLs are resolved against
+/// `source_url`.
+pub(crate) fn extract_structured_data(html_content: &str, source_url: &str, max_json_ld_size: usize) -> Option<StructuredData> {
+    let document = Html::parse_document(html_content);
+    let mut data = StructuredData::default();
+
+    let scripts = Selector::parse("script[type]").unwrap();
+    for script in document.select(&scripts) {
+        let is_json_ld = script.value().attr("type").is_some_and(|kind| {
+            kind.split(';').next().unwrap_or_default().trim().eq_ignore_ascii_case("application/ld+json")
+        });
+        if !is_json_ld {
+            continue;
+        }
+        let text: String = script.text().collect();
+        let text = text.trim();
+        if text.len() > max_json_ld_size {
+            data.invalid_json_ld.push(format!("{} bytes, over the {} byte limit", text.len(), max_json_ld_size));
+            continue;
+        }
+        match serde_json::from_str::<Value>(text) {
+            Ok(block) => data.json_ld.push(block),
+            Err(err) => data.invalid_json_ld.push(err.to_string()),
+        }
+    }
+
+    let metas = Selector::parse("meta[content]").unwrap();
+    for meta in document.select(&metas) {
+        let element = meta.value();
+        let Some(name) = element.attr("property").or_else(|| element.attr("name")) else {
+            continue;
+        };
+        let name = name.trim().to_ascii_lowercase();
+        let content = element.attr("content").unwrap_or_default().trim().to_string();
+        // The first of repeated tags, like og:image, is the main one
+        if name.starts_with("og:") {
+            data.opengraph.entry(name).or_insert(content);
+        } else if name.starts_with("twitter:") {
+            data.twitter.entry(name).or_insert(content);
+        }
+    }
+
+    let base_url = document_base(&document, source_url);
+    let items = Selector::parse("[itemscope]").unwrap();
+    for item in document.select(&items).filter(|item| item.value().attr("itemprop").is_none()) {
+        data.microdata.push(microdata_item(item, &base_url));
+    }
+
+    let empty = data.json_ld.is_empty()
+        && data.invalid_json_ld.is_empty()
+        && data.opengraph.is_empty()
+        && data.twitter.is_empty()
+        && data.microdata.is_empty();
+    (!empty).then_some(data)
+}
+
+/// A microdata item as JSON-LD-like JSON: its `itemtype` as `@type` and
+/// its properties as fields, repeated ones as arrays.
+fn microdata_item(item: ElementRef, base_url: &str) -> Value {
+    let mut fields = Map::new();
+    if let Some(types) = item.value().attr("itemtype") {
+        let mut types: Vec<Value> = types.split_whitespace().map(|kind| Value::String(kind.to_string())).collect();
+        if types.len() == 1 {
+            fields.insert("@type".to_string(), types.remove(0));
+        } else if !types.is_empty() {
+            fields.insert("@type".to_string(), Value::Array(types));
+        }
+    }
+    let mut properties = Vec::new();
+    collect_properties(item, &mut properties);
+    for property in properties {
+        let value = if property.value().attr("itemscope").is_some() {
+            microdata_item(property, base_url)
+        } else {
+            Value::String(property_value(property, base_url))
+        };
+        for name in property.value().attr("itemprop").unwrap_or_default().split_whitespace() {
+            match fields.get_mut(name) {
+                Some(Value::Array(values)) => values.push(value.clone()),
+                Some(existing) => *existing = Value::Array(vec![existing.take(), value.clone()]),
+                None => {
+                    fields.insert(name.to_string(), value.clone());
+                }
+            }
+        }
+    }
+    Value::Object(fields)
+}
+
+/// The elements with `itemprop` under `element` that belong to it, not to
+/// an item nested inside it.
+fn collect_properties<'a>(element: ElementRef<'a>, properties: &mut Vec<ElementRef<'a>>) {
+    for child in element.children().filter_map(ElementRef::wrap) {
+        if child.value().attr("itemprop").is_some() {
+            properties.push(child);
+        }
+        if child.value().attr("itemscope").is_none() {
+            collect_properties(child, properties);
+        }
+    }
+}
+
+/// The value of a microdata property, which depends on its element: a
+/// link's URL, a `<meta>`'s content, a `<time>`'s datetime, or the text.
+fn property_value(property: ElementRef, base_url: &str) -> String {
+    let element = property.value();
+    let url = |attr: &str| {
+        let value = element.attr(attr).unwrap_or_default().trim();
+        normalize_url(value, base_url).unwrap_or_else(|_| value.to_string())
+    };
+    match element.name() {
+        "meta" => element.attr("content").unwrap_or_default().trim().to_string(),
+        "a" | "area" | "link" => url("href"),
+        "audio" | "embed" | "iframe" | "img" | "source" | "track" | "video" => url("src"),
+        "object" => url("data"),
+        "data" | "meter" => element.attr("value").unwrap_or_default().trim().to_string(),
+        "time" if element.attr("datetime").is_some() => element.attr("datetime").unwrap_or_default().trim().to_string(),
+        _ => property.text().collect::<Vec<_>>().join(" ").split_whitespace().collect::<Vec<_>>().join(" "),
+    }
+}
+
+/// How many of `pages` use each schema.org type, without its
+/// `https://schema.org/` prefix: the `@type` of every JSON-LD object,
+/// nested ones and `@graph` members included, and the `itemtype` of every
+/// microdata item.
+pub(crate) fn schema_types(pages: &[PageRecord]) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for data in pages.iter().filter_map(|page| page.structured_data.as_ref()) {
+        let mut types = BTreeSet::new();
+        for block in data.json_ld.iter().chain(&data.microdata) {
+            collect_types(block, &mut types);
+        }
+        for kind in types {
+            *counts.entry(kind).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+fn collect_types(value: &Value, types: &mut BTreeSet<String>) {
+    match value {
+        Value::Object(fields) => {
+            match fields.get("@type") {
+                Some(Value::String(kind)) => {
+                    types.insert(short_type(kind));
+                }
+                Some(Value::Array(kinds)) => {
+                    types.extend(kinds.iter().filter_map(Value::as_str).map(short_type));
+                }
+                _ => {}
+            }
+            for (name, child) in fields {
+                if name != "@type" {
+                    collect_types(child, types);
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_types(item, types);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn short_type(kind: &str) -> String {
+    let kind = kind.trim();
+    ["https://schema.org/", "http://schema.org/", "schema:"]
+        .iter()
+        .find_map(|prefix| kind.strip_prefix(prefix))
+        .unwrap_or(kind)
+        .to_string()
+}
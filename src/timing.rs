@@ -0,0 +1,76 @@
+//! Response time statistics over the crawled pages.
+//!
+//! Percentiles use the nearest-rank method, so each is the time of an
+//! actual request. Times are measured from sending the request, after the
+//! host's delay was waited out, so they reflect the server and not the
+//! crawler's own pacing.
+
+use crate::results::{LatencyStats, PageRecord, Performance, SlowPage};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use url::Url;
+
+/// Response time percentiles of `pages`, overall and per first-level
+/// directory.
+pub(crate) fn performance(pages: &[PageRecord]) -> Performance {
+    let mut sections: BTreeMap<String, Vec<&PageRecord>> = BTreeMap::new();
+    for page in pages {
+        sections.entry(section(&page.url)).or_default().push(page);
+    }
+    Performance {
+        overall: latency_stats(pages.iter()),
+        sections: sections
+            .into_iter()
+            .map(|(section, pages)| (section, latency_stats(pages.into_iter())))
+            .collect(),
+    }
+}
+
+/// The pages that took at least `threshold`, slowest first.
+pub(crate) fn slow_pages(pages: &[PageRecord], threshold: Duration) -> Vec<SlowPage> {
+    let threshold = threshold.as_millis() as u64;
+    let mut slow: Vec<SlowPage> = pages
+        .iter()
+        .filter(|page| page.elapsed_ms >= threshold)
+        .map(|page| SlowPage { url: page.url.clone(), elapsed_ms: page.elapsed_ms, ttfb_ms: page.ttfb_ms })
+        .collect();
+    // Pages keep their URL order among equally slow ones
+    slow.sort_by_key(|page| std::cmp::Reverse(page.elapsed_ms));
+    slow
+}
+
+/// The first-level directory of `url`, like `/blog/`, or `/` for pages at
+/// the root.
+fn section(url: &str) -> String {
+    let Ok(url) = Url::parse(url) else {
+        return "/".to_string();
+    };
+    match url.path().trim_start_matches('/').split_once('/') {
+        Some((first, _)) => format!("/{}/", first),
+        None => "/".to_string(),
+    }
+}
+
+fn latency_stats<'a>(pages: impl Iterator<Item = &'a PageRecord>) -> LatencyStats {
+    let (mut elapsed, mut ttfb): (Vec<u64>, Vec<u64>) = pages.map(|page| (page.elapsed_ms, page.ttfb_ms)).unzip();
+    elapsed.sort_unstable();
+    ttfb.sort_unstable();
+    LatencyStats {
+        pages: elapsed.len(),
+        p50_ms: percentile(&elapsed, 50),
+        p90_ms: percentile(&elapsed, 90),
+        p99_ms: percentile(&elapsed, 99),
+        ttfb_p50_ms: percentile(&ttfb, 50),
+        ttfb_p90_ms: percentile(&ttfb, 90),
+        ttfb_p99_ms: percentile(&ttfb, 99),
+    }
+}
+
+/// The nearest-rank `percent`th percentile of the ascending `values`.
+fn percentile(values: &[u64], percent: usize) -> u64 {
+    if values.is_empty() {
+        return 0;
+    }
+    let rank = (values.len() * percent).div_ceil(100).max(1);
+    values[rank - 1]
+}
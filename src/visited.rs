@@ -0,0 +1,288 @@
+//! The set of URLs already claimed for crawling, used for deduplication.
+
+use crate::error::CrawlerError;
+use dashmap::DashSet;
+use std::fmt;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Where the visited set is kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VisitedBackend {
+    /// An exact in-memory set.
+    #[default]
+    Memory,
+    /// A fixed-size bloom filter. Uses far less memory, but a small fraction
+    /// of genuinely new URLs is mistaken for visited ones and skipped.
+    Bloom,
+    /// An exact set in a temporary SQLite database (requires the `sqlite`
+    /// feature).
+    Disk,
+}
+
+impl fmt::Display for VisitedBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            VisitedBackend::Memory => "memory",
+            VisitedBackend::Bloom => "bloom",
+            VisitedBackend::Disk => "disk",
+        })
+    }
+}
+
+impl FromStr for VisitedBackend {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "memory" => Ok(VisitedBackend::Memory),
+            "bloom" => Ok(VisitedBackend::Bloom),
+            "disk" => Ok(VisitedBackend::Disk),
+            _ => Err(format!("unknown visited backend {:?} (expected memory, bloom or disk)", value)),
+        }
+    }
+}
+
+pub(crate) enum VisitedSet {
+    Memory {
+        keys: DashSet<String>,
+        /// Bytes of key data, for the memory estimate
+        bytes: AtomicUsize,
+    },
+    Bloom(BloomFilter),
+    #[cfg(feature = "sqlite")]
+    Disk(DiskSet),
+}
+
+/// Rough per-entry overhead of the in-memory set: the `String` header and
+/// the hash table slot.
+const MEMORY_ENTRY_OVERHEAD: usize = 40;
+
+impl VisitedSet {
+    /// `capacity` and `false_positive_rate` size the bloom filter and are
+    /// ignored by the other backends.
+    pub fn new(backend: VisitedBackend, capacity: usize, false_positive_rate: f64) -> Result<Self, CrawlerError> {
+        match backend {
+            VisitedBackend::Memory => Ok(VisitedSet::Memory { keys: DashSet::new(), bytes: AtomicUsize::new(0) }),
+            VisitedBackend::Bloom => Ok(VisitedSet::Bloom(BloomFilter::new(capacity, false_positive_rate))),
+            #[cfg(feature = "sqlite")]
+            VisitedBackend::Disk => Ok(VisitedSet::Disk(DiskSet::new()?)),
+            #[cfg(not(feature = "sqlite"))]
+            VisitedBackend::Disk => Err(CrawlerError::Storage(
+                "the disk visited backend needs the `sqlite` feature".to_string(),
+            )),
+        }
+    }
+
+    /// Adds `key` and returns true if it wasn't in the set yet. Atomic: of
+    /// several callers inserting the same key, exactly one gets true.
+    pub fn insert(&self, key: String) -> bool {
+        match self {
+            VisitedSet::Memory { keys, bytes } => {
+                let len = key.len();
+                let inserted = keys.insert(key);
+                if inserted {
+                    bytes.fetch_add(len, Ordering::Relaxed);
+                }
+                inserted
+            }
+            VisitedSet::Bloom(filter) => filter.insert(&key),
+            #[cfg(feature = "sqlite")]
+            VisitedSet::Disk(set) => set.insert(&key),
+        }
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        match self {
+            VisitedSet::Memory { keys, .. } => keys.contains(key),
+            VisitedSet::Bloom(filter) => filter.contains(key),
+            #[cfg(feature = "sqlite")]
+            VisitedSet::Disk(set) => set.contains(key),
+        }
+    }
+
+    /// Removes `key`. A bloom filter can't forget keys, so there it stays.
+    pub fn remove(&self, key: &str) {
+        match self {
+            VisitedSet::Memory { keys, bytes } => {
+                if keys.remove(key).is_some() {
+                    bytes.fetch_sub(key.len(), Ordering::Relaxed);
+                }
+            }
+            VisitedSet::Bloom(_) => {}
+            #[cfg(feature = "sqlite")]
+            VisitedSet::Disk(set) => set.remove(key),
+        }
+    }
+
+    /// Approximate memory used by the set, in bytes.
+    pub fn memory_bytes(&self) -> u64 {
+        match self {
+            VisitedSet::Memory { keys, bytes } => {
+                (bytes.load(Ordering::Relaxed) + keys.len() * MEMORY_ENTRY_OVERHEAD) as u64
+            }
+            VisitedSet::Bloom(filter) => filter.memory_bytes(),
+            #[cfg(feature = "sqlite")]
+            VisitedSet::Disk(set) => set.memory_bytes(),
+        }
+    }
+}
+
+/// A bloom filter with lock-striped inserts, so a key is reported as new
+/// to only one of several concurrent inserters.
+pub(crate) struct BloomFilter {
+    bits: Vec<AtomicU64>,
+    hashes: u32,
+    stripes: Vec<Mutex<()>>,
+}
+
+impl BloomFilter {
+    /// Sizes the filter for `capacity` keys at the given false-positive rate.
+    fn new(capacity: usize, false_positive_rate: f64) -> Self {
+        let capacity = capacity.max(1) as f64;
+        let rate = false_positive_rate.clamp(1e-9, 0.5);
+        let ln2 = std::f64::consts::LN_2;
+        let bit_count = (-capacity * rate.ln() / (ln2 * ln2)).ceil().max(64.0);
+        let hashes = ((bit_count / capacity) * ln2).round().clamp(1.0, 32.0) as u32;
+        let words = (bit_count as usize).div_ceil(64);
+        BloomFilter {
+            bits: (0..words).map(|_| AtomicU64::new(0)).collect(),
+            hashes,
+            stripes: (0..64).map(|_| Mutex::new(())).collect(),
+        }
+    }
+
+    /// Bit positions of `key`, by double hashing.
+    fn positions(&self, key: &str) -> impl Iterator<Item = usize> {
+        let hash = |seed: u8| {
+            let mut hasher = DefaultHasher::new();
+            seed.hash(&mut hasher);
+            key.hash(&mut hasher);
+            hasher.finish()
+        };
+        let (first, second) = (hash(0), hash(1) | 1);
+        let bit_count = (self.bits.len() * 64) as u64;
+        (0..self.hashes as u64).map(move |i| (first.wrapping_add(i.wrapping_mul(second)) % bit_count) as usize)
+    }
+
+    fn insert(&self, key: &str) -> bool {
+        let first = self.positions(key).next().unwrap_or_default();
+        let _stripe = self.stripes[first % self.stripes.len()].lock().unwrap();
+        let mut new = false;
+        for position in self.positions(key) {
+            let mask = 1 << (position % 64);
+            if self.bits[position / 64].fetch_or(mask, Ordering::Relaxed) & mask == 0 {
+                new = true;
+            }
+        }
+        new
+    }
+
+    fn contains(&self, key: &str) -> bool {
+        self.positions(key)
+            .all(|position| self.bits[position / 64].load(Ordering::Relaxed) & (1 << (position % 64)) != 0)
+    }
+
+    fn memory_bytes(&self) -> u64 {
+        (self.bits.len() * 8) as u64
+    }
+}
+
+/// SQLite page cache size for the disk set.
+#[cfg(feature = "sqlite")]
+const DISK_CACHE_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Disk sets created so far, so that crawlers in one process each get
+/// their own database file.
+#[cfg(feature = "sqlite")]
+static DISK_SETS: AtomicUsize = AtomicUsize::new(0);
+
+/// An exact set in a temporary SQLite database, deleted when dropped.
+#[cfg(feature = "sqlite")]
+pub(crate) struct DiskSet {
+    connection: Mutex<rusqlite::Connection>,
+    path: std::path::PathBuf,
+}
+
+#[cfg(feature = "sqlite")]
+impl DiskSet {
+    fn new() -> Result<Self, CrawlerError> {
+        let failed = |err: rusqlite::Error| CrawlerError::Storage(format!("visited set: {}", err));
+        let instance = DISK_SETS.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("silent-crawler-visited-{}-{}.sqlite", std::process::id(), instance));
+        let _ = std::fs::remove_file(&path);
+        let connection = rusqlite::Connection::open(&path).map_err(failed)?;
+        connection
+            .execute_batch(&format!(
+                "PRAGMA journal_mode = OFF; PRAGMA synchronous = OFF; PRAGMA cache_size = -{};
+                 CREATE TABLE visited (key TEXT PRIMARY KEY) WITHOUT ROWID;",
+                DISK_CACHE_BYTES / 1024
+            ))
+            .map_err(failed)?;
+        Ok(DiskSet { connection: Mutex::new(connection), path })
+    }
+
+    fn insert(&self, key: &str) -> bool {
+        let connection = self.connection.lock().unwrap();
+        connection
+            .prepare_cached("INSERT OR IGNORE INTO visited (key) VALUES (?1)")
+            .and_then(|mut statement| statement.execute([key]))
+            .is_ok_and(|inserted| inserted == 1)
+    }
+
+    fn contains(&self, key: &str) -> bool {
+        let connection = self.connection.lock().unwrap();
+        connection
+            .prepare_cached("SELECT 1 FROM visited WHERE key = ?1")
+            .and_then(|mut statement| statement.exists([key]))
+            .unwrap_or(false)
+    }
+
+    /// The page cache can hold at most the whole database, up to its limit.
+    fn memory_bytes(&self) -> u64 {
+        let connection = self.connection.lock().unwrap();
+        let size: i64 = connection
+            .query_row("SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()", [], |row| row.get(0))
+            .unwrap_or(0);
+        (size.max(0) as u64).min(DISK_CACHE_BYTES)
+    }
+
+    fn remove(&self, key: &str) {
+        let connection = self.connection.lock().unwrap();
+        let _ = connection
+            .prepare_cached("DELETE FROM visited WHERE key = ?1")
+            .and_then(|mut statement| statement.execute([key]));
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl Drop for DiskSet {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disk_sets_in_one_process_are_independent() {
+        let first = DiskSet::new().unwrap();
+        let second = DiskSet::new().unwrap();
+        assert_ne!(first.path, second.path);
+
+        assert!(first.insert("http://site.test/a"));
+        assert!(!second.contains("http://site.test/a"));
+        assert!(second.insert("http://site.test/b"));
+
+        let second_path = second.path.clone();
+        drop(second);
+        assert!(!second_path.exists());
+        assert!(first.path.exists());
+        assert!(first.contains("http://site.test/a"));
+        assert!(!first.insert("http://site.test/a"));
+    }
+}
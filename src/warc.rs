@@ -0,0 +1,249 @@
+//! WARC/1.1 output: a request and a response record for every fetch, each
+//! gzipped on its own so the file can be read from any record boundary.
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use reqwest::header::HeaderMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Number of fetches buffered before the file is flushed.
+const FLUSH_EVERY: usize = 20;
+
+/// One HTTP exchange, as sent and received.
+pub(crate) struct WarcExchange<'a> {
+    pub url: &'a str,
+    pub method: &'a str,
+    /// Request headers, including the client defaults
+    pub request_headers: &'a HeaderMap,
+    /// Status line of the response, e.g. `HTTP/1.1 200 OK`
+    pub status_line: &'a str,
+    pub response_headers: &'a HeaderMap,
+    pub body: &'a [u8],
+    pub remote_addr: Option<SocketAddr>,
+}
+
+struct State {
+    writer: BufWriter<File>,
+    unflushed: usize,
+}
+
+/// Appends WARC records to a file.
+pub(crate) struct WarcWriter {
+    state: Mutex<State>,
+}
+
+impl WarcWriter {
+    /// Opens `path` for appending, creating it if needed, and writes a
+    /// `warcinfo` record with the given fields.
+    pub fn open(path: &Path, info: &[(&str, String)]) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let writer = WarcWriter {
+            state: Mutex::new(State { writer: BufWriter::new(file), unflushed: 0 }),
+        };
+
+        let filename = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        let mut fields = Vec::new();
+        for (name, value) in info {
+            fields.extend_from_slice(format!("{}: {}\r\n", name, value).as_bytes());
+        }
+        let record = record(
+            "warcinfo",
+            &[("WARC-Filename", filename), ("Content-Type", "application/warc-fields".to_string())],
+            &fields,
+        );
+        let mut state = writer.state.lock().unwrap();
+        state.writer.write_all(&gzip(&record)?)?;
+        state.writer.flush()?;
+        drop(state);
+        Ok(writer)
+    }
+
+    /// Writes a `response` record for `exchange` and a `request` record
+    /// pointing at it.
+    pub fn write(&self, exchange: &WarcExchange<'_>) {
+        let response_id = record_id();
+        let mut response_block = Vec::with_capacity(exchange.body.len() + 512);
+        response_block.extend_from_slice(exchange.status_line.as_bytes());
+        response_block.extend_from_slice(b"\r\n");
+        write_headers(&mut response_block, exchange.response_headers);
+        response_block.extend_from_slice(exchange.body);
+
+        let mut response_fields = vec![
+            ("WARC-Record-ID", response_id.clone()),
+            ("WARC-Target-URI", exchange.url.to_string()),
+            ("Content-Type", "application/http;msgtype=response".to_string()),
+        ];
+        if let Some(addr) = exchange.remote_addr {
+            response_fields.push(("WARC-IP-Address", addr.ip().to_string()));
+        }
+
+        let request_block = request_block(exchange.method, exchange.url, exchange.request_headers);
+        let request_fields = [
+            ("WARC-Target-URI", exchange.url.to_string()),
+            ("WARC-Concurrent-To", response_id),
+            ("Content-Type", "application/http;msgtype=request".to_string()),
+        ];
+
+        let mut state = self.state.lock().unwrap();
+        // Output problems must not interrupt the crawl, so write errors are dropped
+        for record in [record("response", &response_fields, &response_block), record("request", &request_fields, &request_block)] {
+            if let Ok(compressed) = gzip(&record) {
+                let _ = state.writer.write_all(&compressed);
+            }
+        }
+        state.unflushed += 1;
+        if state.unflushed >= FLUSH_EVERY {
+            let _ = state.writer.flush();
+            state.unflushed = 0;
+        }
+    }
+
+    pub fn flush(&self) {
+        let mut state = self.state.lock().unwrap();
+        let _ = state.writer.flush();
+        state.unflushed = 0;
+    }
+}
+
+/// A complete record: version line, named fields, then the block. A
+/// `WARC-Record-ID` is generated unless `fields` has one.
+fn record(kind: &str, fields: &[(&str, String)], block: &[u8]) -> Vec<u8> {
+    let mut record = Vec::with_capacity(block.len() + 512);
+    record.extend_from_slice(b"WARC/1.1\r\n");
+    record.extend_from_slice(format!("WARC-Type: {}\r\n", kind).as_bytes());
+    record.extend_from_slice(
+        format!("WARC-Date: {}\r\n", humantime::format_rfc3339_seconds(SystemTime::now())).as_bytes(),
+    );
+    if !fields.iter().any(|(name, _)| *name == "WARC-Record-ID") {
+        record.extend_from_slice(format!("WARC-Record-ID: {}\r\n", record_id()).as_bytes());
+    }
+    for (name, value) in fields {
+        record.extend_from_slice(format!("{}: {}\r\n", name, value).as_bytes());
+    }
+    record.extend_from_slice(format!("Content-Length: {}\r\n\r\n", block.len()).as_bytes());
+    record.extend_from_slice(block);
+    record.extend_from_slice(b"\r\n\r\n");
+    record
+}
+
+/// The HTTP request for `url` as it was sent.
+fn request_block(method: &str, url: &str, headers: &HeaderMap) -> Vec<u8> {
+    let mut block = Vec::new();
+    let (target, host) = match url::Url::parse(url) {
+        Ok(parsed) => {
+            let mut target = parsed.path().to_string();
+            if let Some(query) = parsed.query() {
+                target.push('?');
+                target.push_str(query);
+            }
+            let host = match (parsed.host_str(), parsed.port()) {
+                (Some(host), Some(port)) => format!("{}:{}", host, port),
+                (Some(host), None) => host.to_string(),
+                (None, _) => String::new(),
+            };
+            (target, host)
+        }
+        Err(_) => (url.to_string(), String::new()),
+    };
+    block.extend_from_slice(format!("{} {} HTTP/1.1\r\nHost: {}\r\n", method, target, host).as_bytes());
+    write_headers(&mut block, headers);
+    block
+}
+
+/// Writes `headers` and the blank line that ends them.
+fn write_headers(block: &mut Vec<u8>, headers: &HeaderMap) {
+    for (name, value) in headers {
+        block.extend_from_slice(name.as_str().as_bytes());
+        block.extend_from_slice(b": ");
+        block.extend_from_slice(value.as_bytes());
+        block.extend_from_slice(b"\r\n");
+    }
+    block.extend_from_slice(b"\r\n");
+}
+
+fn gzip(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::with_capacity(data.len() / 4), Compression::default());
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
+/// A random (version 4) UUID URN.
+fn record_id() -> String {
+    let mut bytes: [u8; 16] = rand::random();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!(
+        "<urn:uuid:{}-{}-{}-{}-{}>",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::MultiGzDecoder;
+    use reqwest::header::{HeaderValue, CONTENT_TYPE, USER_AGENT};
+    use std::io::BufReader;
+    use warc::{RecordType, WarcHeader, WarcReader};
+
+    #[test]
+    fn records_read_back_with_a_warc_reader() {
+        // The test name keeps the file apart from other tests running in this process
+        let path = std::env::temp_dir().join(format!("silent-crawler-{}-read-back.warc.gz", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut request_headers = HeaderMap::new();
+        request_headers.insert(USER_AGENT, HeaderValue::from_static("silent-crawler"));
+        let mut response_headers = HeaderMap::new();
+        response_headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/html"));
+        let body = b"<html><a href=\"/next\">next</a></html>";
+
+        let writer = WarcWriter::open(&path, &[("software", "silent-crawler".to_string())]).unwrap();
+        writer.write(&WarcExchange {
+            url: "http://site.test/page?q=1",
+            method: "GET",
+            request_headers: &request_headers,
+            status_line: "HTTP/1.1 200 OK",
+            response_headers: &response_headers,
+            body,
+            remote_addr: Some("127.0.0.1:80".parse().unwrap()),
+        });
+        writer.flush();
+
+        // Every record is its own gzip member
+        let file = File::open(&path).unwrap();
+        let reader = WarcReader::new(BufReader::new(MultiGzDecoder::new(file)));
+        let records: Vec<_> = reader.iter_records().map(|record| record.unwrap()).collect();
+        std::fs::remove_file(&path).unwrap();
+
+        let types: Vec<&RecordType> = records.iter().map(|record| record.warc_type()).collect();
+        assert_eq!(types, [&RecordType::WarcInfo, &RecordType::Response, &RecordType::Request]);
+        for record in &records {
+            assert_eq!(record.content_length(), record.body().len() as u64);
+        }
+
+        let (response, request) = (&records[1], &records[2]);
+        for record in [response, request] {
+            assert_eq!(record.header(WarcHeader::TargetURI).as_deref(), Some("http://site.test/page?q=1"));
+        }
+        assert_eq!(request.header(WarcHeader::ConcurrentTo).as_deref(), Some(response.warc_id()));
+        assert_eq!(response.header(WarcHeader::IPAddress).as_deref(), Some("127.0.0.1"));
+
+        assert!(response.body().starts_with(b"HTTP/1.1 200 OK\r\ncontent-type: text/html\r\n\r\n"));
+        assert!(response.body().ends_with(body));
+        assert_eq!(
+            request.body(),
+            b"GET /page?q=1 HTTP/1.1\r\nHost: site.test\r\nuser-agent: silent-crawler\r\n\r\n"
+        );
+    }
+}
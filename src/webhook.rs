@@ -0,0 +1,241 @@
+//! Webhook notifications: small JSON payloads POSTed when the crawl
+//! finishes or finds something.
+//!
+//! Events are delivered by a background task. Findings of one kind are
+//! collected for [`DEBOUNCE`] after the first and sent as a single payload,
+//! so a site with hundreds of matches sends a handful of requests. Each
+//! payload has a `text` line, which Slack incoming webhooks display as is.
+//! Delivery failures are logged and otherwise ignored.
+
+use crate::results::Results;
+use reqwest::header::CONTENT_TYPE;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep_until, Instant};
+use tracing::{debug, warn};
+
+/// How long findings are collected before they are sent.
+const DEBOUNCE: Duration = Duration::from_secs(10);
+
+/// Findings listed in one payload; `count` gives the full number.
+const MAX_ITEMS: usize = 20;
+
+/// Time allowed for each delivery.
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What a webhook is notified of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WebhookEvent {
+    /// The crawl finished, with counts of what it found.
+    Finish,
+    /// Requests failed or returned an error status.
+    Error,
+    /// Pages matched a grep pattern.
+    Match,
+    /// Exposed sensitive files were found.
+    SensitiveFile,
+}
+
+impl fmt::Display for WebhookEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            WebhookEvent::Finish => "finish",
+            WebhookEvent::Error => "error",
+            WebhookEvent::Match => "match",
+            WebhookEvent::SensitiveFile => "sensitive-file",
+        })
+    }
+}
+
+impl FromStr for WebhookEvent {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "finish" => Ok(WebhookEvent::Finish),
+            "error" => Ok(WebhookEvent::Error),
+            "match" => Ok(WebhookEvent::Match),
+            "sensitive-file" => Ok(WebhookEvent::SensitiveFile),
+            _ => Err(format!(
+                "unknown webhook event {:?} (expected finish, error, match or sensitive-file)",
+                value
+            )),
+        }
+    }
+}
+
+/// One finding in a payload.
+#[derive(Serialize, Debug, Clone, Default)]
+pub(crate) struct WebhookItem {
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// The error kind, for error events.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+}
+
+#[derive(Serialize, Debug)]
+struct Payload {
+    event: String,
+    target: String,
+    text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    count: Option<usize>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    items: Vec<WebhookItem>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    counts: BTreeMap<&'static str, usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    completed: Option<bool>,
+}
+
+enum Notice {
+    Found(WebhookEvent, WebhookItem),
+    /// The final payload, sent after the pending findings
+    Finish(Option<Payload>),
+}
+
+/// Sends the notifications for one crawl.
+pub(crate) struct Webhook {
+    target: String,
+    events: Vec<WebhookEvent>,
+    sender: mpsc::UnboundedSender<Notice>,
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Webhook {
+    /// Starts delivering `events` about the crawl of `target` to `url`.
+    pub fn start(url: &str, target: &str, events: Vec<WebhookEvent>) -> Result<Self, reqwest::Error> {
+        let client = reqwest::Client::builder().timeout(DELIVERY_TIMEOUT).build()?;
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let task = tokio::spawn(deliver(client, url.to_string(), target.to_string(), receiver));
+        Ok(Webhook { target: target.to_string(), events, sender, task: Mutex::new(Some(task)) })
+    }
+
+    /// Queues a finding, if its event is one the webhook wants.
+    pub fn notify(&self, event: WebhookEvent, item: WebhookItem) {
+        if self.events.contains(&event) {
+            let _ = self.sender.send(Notice::Found(event, item));
+        }
+    }
+
+    /// Sends the pending findings and, if wanted, the finish event, and
+    /// waits for them to be delivered.
+    pub async fn finish(&self, results: &Results) {
+        let payload = self.events.contains(&WebhookEvent::Finish).then(|| self.finish_payload(results));
+        let _ = self.sender.send(Notice::Finish(payload));
+        let task = self.task.lock().unwrap().take();
+        if let Some(task) = task {
+            let _ = task.await;
+        }
+    }
+
+    fn finish_payload(&self, results: &Results) -> Payload {
+        let counts = BTreeMap::from([
+            ("pages", results.pages.len()),
+            ("errors", results.errors.len()),
+            ("matches", results.matches.len()),
+            ("sensitive_files", results.sensitive_files.len()),
+        ]);
+        let verb = if results.completed { "finished" } else { "stopped" };
+        let text = format!(
+            "silent-crawler {} crawling {}: {} pages, {} errors, {} matches, {} sensitive files",
+            verb, self.target, counts["pages"], counts["errors"], counts["matches"], counts["sensitive_files"],
+        );
+        Payload {
+            event: WebhookEvent::Finish.to_string(),
+            target: self.target.clone(),
+            text,
+            count: None,
+            items: Vec::new(),
+            counts,
+            completed: Some(results.completed),
+        }
+    }
+}
+
+/// Findings of one event waiting to be sent.
+#[derive(Default)]
+struct Batch {
+    count: usize,
+    items: Vec<WebhookItem>,
+}
+
+async fn deliver(client: reqwest::Client, url: String, target: String, mut receiver: mpsc::UnboundedReceiver<Notice>) {
+    let mut batches: BTreeMap<WebhookEvent, Batch> = BTreeMap::new();
+    let mut flush_at: Option<Instant> = None;
+    loop {
+        let flush = async move {
+            match flush_at {
+                Some(at) => sleep_until(at).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            notice = receiver.recv() => match notice {
+                Some(Notice::Found(event, item)) => {
+                    let batch = batches.entry(event).or_default();
+                    batch.count += 1;
+                    if batch.items.len() < MAX_ITEMS {
+                        batch.items.push(item);
+                    }
+                    flush_at.get_or_insert_with(|| Instant::now() + DEBOUNCE);
+                }
+                Some(Notice::Finish(payload)) => {
+                    for (event, batch) in std::mem::take(&mut batches) {
+                        post(&client, &url, &batch_payload(&target, event, batch)).await;
+                    }
+                    if let Some(payload) = payload {
+                        post(&client, &url, &payload).await;
+                    }
+                    return;
+                }
+                None => return,
+            },
+            _ = flush => {
+                for (event, batch) in std::mem::take(&mut batches) {
+                    post(&client, &url, &batch_payload(&target, event, batch)).await;
+                }
+                flush_at = None;
+            }
+        }
+    }
+}
+
+fn batch_payload(target: &str, event: WebhookEvent, batch: Batch) -> Payload {
+    let found = match event {
+        WebhookEvent::Error => "errors",
+        WebhookEvent::Match => "grep matches",
+        WebhookEvent::SensitiveFile => "sensitive files",
+        WebhookEvent::Finish => "events",
+    };
+    Payload {
+        event: event.to_string(),
+        target: target.to_string(),
+        text: format!("silent-crawler found {} {} crawling {}", batch.count, found, target),
+        count: Some(batch.count),
+        items: batch.items,
+        counts: BTreeMap::new(),
+        completed: None,
+    }
+}
+
+async fn post(client: &reqwest::Client, url: &str, payload: &Payload) {
+    let body = serde_json::to_vec(payload).unwrap_or_default();
+    match client.post(url).header(CONTENT_TYPE, "application/json").body(body).send().await {
+        Ok(response) if response.status().is_success() => {
+            debug!(event = %payload.event, "webhook delivered");
+        }
+        Ok(response) => warn!(event = %payload.event, status = response.status().as_u16(), "webhook rejected"),
+        Err(err) => warn!(event = %payload.event, error = %err, "webhook delivery failed"),
+    }
+}
@@ -0,0 +1,121 @@
+//! A local HTTP server for the integration tests, serving fixed responses
+//! and recording the requests it gets.
+
+// Each test file uses a different part of it
+#![allow(dead_code)]
+
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::header::HeaderMap;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+
+/// A response the server sends for one path.
+#[derive(Clone)]
+pub struct Page {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Page {
+    pub fn html(body: &str) -> Self {
+        Page::new("text/html", body.as_bytes().to_vec())
+    }
+
+    pub fn new(content_type: &str, body: Vec<u8>) -> Self {
+        Page { status: 200, headers: vec![("content-type".to_string(), content_type.to_string())], body }
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+/// A request the server got: its path with the query, and its headers.
+#[derive(Clone)]
+pub struct Hit {
+    pub path: String,
+    pub headers: HeaderMap,
+}
+
+/// A server on a free local port. Paths without a page are 404s.
+pub struct Site {
+    pub addr: SocketAddr,
+    pages: Arc<Mutex<HashMap<String, Page>>>,
+    hits: Arc<Mutex<Vec<Hit>>>,
+}
+
+impl Site {
+    pub async fn serve<P: Into<String>>(pages: impl IntoIterator<Item = (P, Page)>) -> Self {
+        let pages: HashMap<String, Page> = pages.into_iter().map(|(path, page)| (path.into(), page)).collect();
+        let pages = Arc::new(Mutex::new(pages));
+        let hits = Arc::new(Mutex::new(Vec::new()));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (served, recorded) = (pages.clone(), hits.clone());
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let (pages, hits) = (served.clone(), recorded.clone());
+                let service = service_fn(move |request: Request<Incoming>| {
+                    let path = request.uri().path_and_query().map_or("/", |path| path.as_str()).to_string();
+                    hits.lock().unwrap().push(Hit { path: path.clone(), headers: request.headers().clone() });
+                    let response = respond(pages.lock().unwrap().get(&path));
+                    async move { Ok::<_, Infallible>(response) }
+                });
+                tokio::spawn(http1::Builder::new().serve_connection(TokioIo::new(stream), service));
+            }
+        });
+        Site { addr, pages, hits }
+    }
+
+    /// Serves `page` at `path` from now on, for pages that link to the
+    /// server's own address.
+    pub fn add(&self, path: impl Into<String>, page: Page) {
+        self.pages.lock().unwrap().insert(path.into(), page);
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{}", self.addr, path)
+    }
+
+    /// Every request so far, in the order they came in.
+    pub fn hits(&self) -> Vec<Hit> {
+        self.hits.lock().unwrap().clone()
+    }
+
+    /// How many requests each path got, by `host:port` and path.
+    pub fn hit_counts(&self) -> HashMap<String, usize> {
+        let mut counts = HashMap::new();
+        for hit in self.hits() {
+            let host = hit.headers.get("host").and_then(|host| host.to_str().ok()).unwrap_or_default().to_string();
+            *counts.entry(format!("{}{}", host, hit.path)).or_insert(0) += 1;
+        }
+        counts
+    }
+}
+
+fn respond(page: Option<&Page>) -> Response<Full<Bytes>> {
+    let Some(page) = page else {
+        let mut response = Response::new(Full::new(Bytes::from_static(b"not found")));
+        *response.status_mut() = StatusCode::NOT_FOUND;
+        return response;
+    };
+    let mut response = Response::new(Full::new(Bytes::from(page.body.clone())));
+    *response.status_mut() = StatusCode::from_u16(page.status).unwrap();
+    for (name, value) in &page.headers {
+        response.headers_mut().append(
+            hyper::header::HeaderName::from_bytes(name.as_bytes()).unwrap(),
+            value.parse().unwrap(),
+        );
+    }
+    response
+}
@@ -0,0 +1,54 @@
+//! Compressed responses, and what the crawler asks for.
+
+mod common;
+
+use common::{Page, Site};
+use silent_crawler::CrawlerBuilder;
+use std::io::Write;
+use std::time::Duration;
+
+fn brotli(data: &[u8]) -> Vec<u8> {
+    let mut compressed = Vec::new();
+    let mut writer = brotli::CompressorWriter::new(&mut compressed, 4096, 5, 22);
+    writer.write_all(data).unwrap();
+    drop(writer);
+    compressed
+}
+
+/// A site whose index is brotli compressed and links to two pages.
+async fn brotli_site() -> Site {
+    let index = brotli(br#"<html><body><a href="/one">one</a><a href="/two">two</a></body></html>"#);
+    Site::serve([
+        ("/", Page::new("text/html", index).header("content-encoding", "br")),
+        ("/one", Page::html("one")),
+        ("/two", Page::html("two")),
+    ])
+    .await
+}
+
+async fn crawl(builder: CrawlerBuilder) -> Vec<String> {
+    let results = builder.delay(Duration::ZERO).build().await.unwrap().crawl().await;
+    results.pages.into_iter().map(|page| page.url).collect()
+}
+
+#[tokio::test]
+async fn brotli_bodies_are_decoded() {
+    let site = brotli_site().await;
+    let crawled = crawl(CrawlerBuilder::new(site.url("/"))).await;
+    assert_eq!(crawled, [site.url("/"), site.url("/one"), site.url("/two")]);
+
+    let accept = site.hits().into_iter().find(|hit| hit.path == "/").unwrap().headers["accept-encoding"].clone();
+    assert!(accept.to_str().unwrap().contains("br"), "Accept-Encoding: {:?}", accept);
+}
+
+#[tokio::test]
+async fn without_compression_identity_is_asked_for() {
+    let site = brotli_site().await;
+    let crawled = crawl(CrawlerBuilder::new(site.url("/")).compression(false)).await;
+    // A server that compresses anyway is still understood
+    assert_eq!(crawled, [site.url("/"), site.url("/one"), site.url("/two")]);
+
+    for hit in site.hits() {
+        assert_eq!(hit.headers["accept-encoding"], "identity", "{} asked for {:?}", hit.path, hit.headers["accept-encoding"]);
+    }
+}
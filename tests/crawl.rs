@@ -0,0 +1,150 @@
+//! End-to-end crawls of sites served on a local port.
+
+mod common;
+
+use common::{Page, Site};
+use silent_crawler::{CrawlerBuilder, ErrorKind};
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::Duration;
+
+const LOCALHOST: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+#[tokio::test(flavor = "multi_thread")]
+async fn cross_linked_pages_are_fetched_once_each() {
+    // The pages are spread over several hosts, so that the per-host rate
+    // limit doesn't line their fetches up one after the other
+    const HOSTS: usize = 10;
+    const PAGES: usize = 5;
+    let site = Site::serve(Vec::<(String, Page)>::new()).await;
+    let port = site.addr.port();
+    let host = |host: usize| format!("s{}.site.test", host);
+    // Every page links to every page, spelled a few different ways
+    let links: String = (0..HOSTS)
+        .flat_map(|h| (0..PAGES).map(move |page| (format!("{}:{}", host(h), port), page)))
+        .map(|(authority, page)| {
+            let shouting = authority.to_uppercase();
+            format!(r#"<a href="http://{authority}/p{page}">a</a><a href="http://{authority}/p{page}#top">b</a><a href="HTTP://{shouting}/x/../p{page}">c</a>"#)
+        })
+        .collect();
+    for page in 0..PAGES {
+        site.add(format!("/p{}", page), Page::html(&links));
+    }
+
+    let mut builder = CrawlerBuilder::new(format!("http://{}:{}/p0", host(0), port))
+        .delay(Duration::ZERO)
+        .depth(5)
+        .concurrency(10)
+        .per_host_concurrency(10);
+    for h in 0..HOSTS {
+        builder = builder.resolve(host(h), LOCALHOST);
+    }
+    let results = builder.build().await.unwrap().crawl().await;
+
+    assert_eq!(results.pages.len(), HOSTS * PAGES);
+    let counts = site.hit_counts();
+    for h in 0..HOSTS {
+        for page in 0..PAGES {
+            let path = format!("{}:{}/p{}", host(h), port, page);
+            assert_eq!(counts.get(&path), Some(&1), "{} was fetched {:?} times", path, counts.get(&path));
+        }
+    }
+    assert!(counts.values().all(|&count| count == 1), "some paths were requested more than once: {:?}", counts);
+}
+
+#[tokio::test]
+async fn depth_limits_how_far_links_are_followed() {
+    let site = Site::serve([
+        ("/a", Page::html(r#"<a href="/b">b</a>"#)),
+        ("/b", Page::html(r#"<a href="/c">c</a>"#)),
+        ("/c", Page::html(r#"<a href="/d">d</a>"#)),
+        ("/d", Page::html("the end")),
+    ])
+    .await;
+
+    let results = CrawlerBuilder::new(site.url("/a"))
+        .delay(Duration::ZERO)
+        .depth(2)
+        .build()
+        .await
+        .unwrap()
+        .crawl()
+        .await;
+
+    let crawled: Vec<&str> = results.pages.iter().map(|page| page.url.as_str()).collect();
+    assert_eq!(crawled, [site.url("/a"), site.url("/b"), site.url("/c")]);
+    assert_eq!(results.depths.get(&site.url("/c")), Some(&2));
+    let paths: Vec<String> = site.hits().into_iter().map(|hit| hit.path).collect();
+    assert!(!paths.iter().any(|path| path == "/d"), "/d was fetched: {:?}", paths);
+}
+
+/// Crawls a two-page IDN site from `seed_host`, with every link written
+/// with `link_host`, and checks each page was fetched once under its
+/// ASCII name.
+async fn crawl_idn_site(seed_host: &str, link_host: &str) {
+    let site = Site::serve(Vec::<(String, Page)>::new()).await;
+    let port = site.addr.port();
+    let links = format!(
+        r#"<a href="http://{link_host}:{port}/">home</a><a href="http://{link_host}:{port}/straße">street</a><a href="/stra%C3%9Fe">street</a>"#
+    );
+    site.add("/", Page::html(&links));
+    site.add("/stra%C3%9Fe", Page::html(&links));
+
+    let results = CrawlerBuilder::new(format!("http://{}:{}/", seed_host, port))
+        .delay(Duration::ZERO)
+        .resolve("xn--mnchen-3ya.test", LOCALHOST)
+        .build()
+        .await
+        .unwrap()
+        .crawl()
+        .await;
+
+    let crawled: Vec<&str> = results.pages.iter().map(|page| page.url.as_str()).collect();
+    let home = format!("http://xn--mnchen-3ya.test:{}/", port);
+    assert_eq!(crawled, [home.clone(), format!("{}stra%C3%9Fe", home)]);
+    assert!(results.external_links.is_empty(), "out of scope: {:?}", results.external_links);
+    let counts = site.hit_counts();
+    for path in ["/", "/stra%C3%9Fe"] {
+        let key = format!("xn--mnchen-3ya.test:{}{}", port, path);
+        assert_eq!(counts.get(&key), Some(&1), "{} was fetched {:?} times", key, counts.get(&key));
+    }
+}
+
+#[tokio::test]
+async fn unicode_links_from_a_punycode_seed_stay_in_scope() {
+    crawl_idn_site("xn--mnchen-3ya.test", "münchen.test").await;
+}
+
+#[tokio::test]
+async fn punycode_links_from_a_unicode_seed_stay_in_scope() {
+    crawl_idn_site("münchen.test", "xn--mnchen-3ya.test").await;
+}
+
+#[tokio::test]
+async fn too_many_redirects_is_an_error_and_not_a_page() {
+    let redirect = |to: &str| Page { status: 301, ..Page::html("") }.header("location", to);
+    let site = Site::serve([
+        ("/", Page::html(r#"<a href="/r0">start</a>"#)),
+        ("/r0", redirect("/r1")),
+        ("/r1", redirect("/r2")),
+        ("/r2", redirect("/r3")),
+        ("/r3", redirect("/r4")),
+        ("/r4", Page::html("finally")),
+    ])
+    .await;
+
+    let results = CrawlerBuilder::new(site.url("/"))
+        .delay(Duration::ZERO)
+        .max_redirects(2)
+        .build()
+        .await
+        .unwrap()
+        .crawl()
+        .await;
+
+    let crawled: Vec<&str> = results.pages.iter().map(|page| page.url.as_str()).collect();
+    assert_eq!(crawled, [site.url("/")]);
+    assert_eq!(results.errors.len(), 1, "{:?}", results.errors);
+    assert_eq!(results.errors[0].url, site.url("/r0"));
+    assert_eq!(results.errors[0].kind, ErrorKind::TooManyRedirects);
+    assert_eq!(results.error_counts().get(&ErrorKind::TooManyRedirects), Some(&1));
+}